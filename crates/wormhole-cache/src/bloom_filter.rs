@@ -17,6 +17,27 @@
 //! - `set_url()` adds the code to the Bloom filter and the underlying cache
 //! - `del()` only removes from the underlying cache (Bloom filters don't support deletion)
 //!
+//! [`CountingBloomFilter`](crate::CountingBloomFilter) supports deletion at
+//! the cost of more memory.
+//!
+//! # Negative Answers
+//!
+//! A record held by any layer below the filter always wins over a negative
+//! answer. The filter only knows about codes written through it (or added
+//! with [`BloomFilter::warm_from`]), so "definitely not present" is only
+//! trustworthy when nothing else writes to the underlying cache. That is
+//! what [`FilterCoverage`] declares:
+//!
+//! - [`FilterCoverage::Complete`]: the filter's "absent" is final and the
+//!   underlying cache is not consulted.
+//! - [`FilterCoverage::Partial`]: the underlying cache may hold codes the
+//!   filter never saw, e.g. a Redis layer shared with other instances. An
+//!   "absent" answer falls through to the underlying cache, and codes found
+//!   there are added to the filter.
+//!
+//! Tombstones cached by `get_or_compute` in the underlying cache are misses
+//! to `get_url`, so they never hide a record a deeper layer still holds.
+//!
 //! # Use Case
 //!
 //! This is useful when the underlying cache is expensive to query (e.g., network
@@ -24,6 +45,8 @@
 
 use async_trait::async_trait;
 use parking_lot::RwLock;
+use std::future::Future;
+use std::time::Duration;
 use typed_builder::TypedBuilder;
 use wormhole_core::{ShortCode, UrlRecord};
 
@@ -33,6 +56,10 @@ use crate::{CacheError, Result, UrlCache};
 ///
 /// The Bloom filter is a probabilistic data structure that trades a small
 /// false positive rate for significant memory savings.
+///
+/// The same configuration sizes a [`CountingBloomFilter`](crate::CountingBloomFilter),
+/// which stores a 4-bit counter per bit and so needs roughly 4x the memory
+/// of a [`BloomFilter`] for the same accuracy.
 #[derive(Debug, TypedBuilder)]
 pub struct BloomFilterConfig {
     /// Expected number of items to be inserted into the filter.
@@ -50,11 +77,29 @@ pub struct BloomFilterConfig {
     pub false_positive_rate: f64,
 }
 
+/// Whether a Bloom filter has seen every code held by the cache it guards.
+///
+/// See the [module documentation](self) for how this decides between the
+/// filter and the underlying cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FilterCoverage {
+    /// Every code in the underlying cache was written through the filter or
+    /// added with [`BloomFilter::warm_from`]. "Absent" answers are final.
+    #[default]
+    Complete,
+    /// The underlying cache may hold codes the filter never saw. "Absent"
+    /// answers defer to the underlying cache.
+    Partial,
+}
+
 /// A cache decorator that uses a Bloom filter for fast negative lookups.
 ///
 /// This wrapper adds a Bloom filter in front of an existing cache implementation.
 /// It provides O(1) negative lookups with no false negatives - if the filter
-/// says a code is not present, it's definitely not in the cache.
+/// says a code is not present, it's definitely not in the cache. That
+/// guarantee only holds with [`FilterCoverage::Complete`], the default; use
+/// [`BloomFilter::with_coverage`] when other writers share the underlying
+/// cache.
 ///
 /// # Type Parameters
 ///
@@ -78,6 +123,7 @@ pub struct BloomFilter<C: UrlCache> {
     bloom: RwLock<bloomfilter::Bloom<ShortCode>>,
     /// The underlying cache that stores actual URL records.
     cache: C,
+    coverage: FilterCoverage,
 }
 
 impl<C: UrlCache> BloomFilter<C> {
@@ -96,7 +142,48 @@ impl<C: UrlCache> BloomFilter<C> {
             bloomfilter::Bloom::new_for_fp_rate(config.expected_items, config.false_positive_rate)
                 .map_err(|e| CacheError::Initialization(e.to_string()))?;
         let bloom = RwLock::new(bloom);
-        Ok(Self { bloom, cache })
+        Ok(Self {
+            bloom,
+            cache,
+            coverage: FilterCoverage::default(),
+        })
+    }
+
+    /// Declares whether the filter sees every write to the underlying cache.
+    pub fn with_coverage(mut self, coverage: FilterCoverage) -> Self {
+        self.coverage = coverage;
+        self
+    }
+
+    /// Returns the configured coverage.
+    pub fn coverage(&self) -> FilterCoverage {
+        self.coverage
+    }
+
+    /// Adds `codes` to the filter without touching the underlying cache.
+    ///
+    /// Use this to teach the filter about codes written behind its back,
+    /// e.g. after a bulk import into the database, typically by feeding it
+    /// the codes from a repository scan. Without it those codes would be
+    /// reported as definitely absent.
+    ///
+    /// Since the filter only ever gains codes, warming is safe to run while
+    /// the cache is serving traffic. The write lock is taken per code so
+    /// concurrent lookups are not stalled for the whole warm-up.
+    ///
+    /// Returns the number of codes added.
+    pub fn warm_from(&self, codes: impl IntoIterator<Item = ShortCode>) -> usize {
+        let mut added = 0;
+        for code in codes {
+            self.bloom.write().set(&code);
+            added += 1;
+        }
+        added
+    }
+
+    /// Returns `false` if `code` is definitely not in the cache.
+    pub fn might_contain(&self, code: &ShortCode) -> bool {
+        self.bloom.read().check(code)
     }
 }
 
@@ -105,23 +192,33 @@ impl<C: UrlCache> UrlCache for BloomFilter<C> {
     /// Retrieves a URL record from the cache.
     ///
     /// First checks the Bloom filter for a quick negative lookup. If the filter
-    /// indicates the code is definitely not present, returns `None` immediately
-    /// without querying the underlying cache.
+    /// indicates the code is definitely not present and its coverage is
+    /// [`FilterCoverage::Complete`], returns `None` immediately without
+    /// querying the underlying cache.
     ///
-    /// If the filter indicates the code might be present (including false
-    /// positives), delegates to the underlying cache for the actual lookup.
+    /// Otherwise (including false positives), delegates to the underlying
+    /// cache for the actual lookup.
     async fn get_url(&self, code: &ShortCode) -> Result<Option<UrlRecord>> {
-        {
-            let guard = self.bloom.read();
-            if !guard.check(code) {
-                // Bloom filter guarantees no false negatives - if it says not present,
-                // the code is definitely not in the cache
-                return Ok(None);
+        if self.might_contain(code) {
+            // Bloom filter indicates the code might be present (could be a false positive)
+            // Delegate to underlying cache to verify
+            return self.cache.get_url(code).await;
+        }
+
+        match self.coverage {
+            // Bloom filter guarantees no false negatives - if it says not present,
+            // the code is definitely not in the cache
+            FilterCoverage::Complete => Ok(None),
+            // Someone else may have written the code; only the underlying
+            // cache can tell.
+            FilterCoverage::Partial => {
+                let record = self.cache.get_url(code).await?;
+                if record.is_some() {
+                    self.bloom.write().set(code);
+                }
+                Ok(record)
             }
         }
-        // Bloom filter indicates the code might be present (could be a false positive)
-        // Delegate to underlying cache to verify
-        self.cache.get_url(code).await
     }
 
     /// Stores a URL record in the cache.
@@ -137,6 +234,17 @@ impl<C: UrlCache> UrlCache for BloomFilter<C> {
         self.cache.set_url(code, record).await
     }
 
+    /// Like [`BloomFilter::set_url`], passing `ttl` to the underlying cache.
+    async fn set_url_with_ttl(
+        &self,
+        code: &ShortCode,
+        record: &UrlRecord,
+        ttl: Option<Duration>,
+    ) -> Result<()> {
+        self.bloom.write().set(code);
+        self.cache.set_url_with_ttl(code, record, ttl).await
+    }
+
     /// Deletes a URL record from the cache.
     ///
     /// # Limitations
@@ -148,12 +256,212 @@ impl<C: UrlCache> UrlCache for BloomFilter<C> {
     /// - The underlying cache will correctly return `None` for deleted codes
     /// - The false positive rate for deleted codes will gradually increase over time
     ///
-    /// For workloads with frequent deletions, consider using a different caching
-    /// strategy or periodically rebuilding the Bloom filter.
+    /// For workloads with frequent deletions, consider
+    /// [`CountingBloomFilter`](crate::CountingBloomFilter) or periodically
+    /// rebuilding the Bloom filter.
     async fn del(&self, code: &ShortCode) -> Result<()> {
         // Note: Standard Bloom filters don't support deletion. Counting Bloom filters
         // could be used instead, but that would require additional memory overhead.
         // For now, we accept that deleted items may still trigger cache lookups.
         self.cache.del(code).await
     }
+
+    /// Same as [`BloomFilter::del`]: the codes stay in the filter.
+    async fn del_many(&self, codes: &[ShortCode]) -> Result<()> {
+        self.cache.del_many(codes).await
+    }
+
+    /// Answers from the filter like [`BloomFilter::get_url`], asking the
+    /// underlying cache's `exists` when the code might be present.
+    async fn exists(&self, code: &ShortCode) -> Result<bool> {
+        if !self.might_contain(code) && self.coverage == FilterCoverage::Complete {
+            return Ok(false);
+        }
+        let exists = self.cache.exists(code).await?;
+        if exists {
+            self.bloom.write().set(code);
+        }
+        Ok(exists)
+    }
+
+    /// Clears the underlying cache, then the filter.
+    ///
+    /// This is the only way codes leave the filter. The filter is left
+    /// untouched if the underlying cache cannot be cleared.
+    async fn clear(&self) -> Result<()> {
+        self.cache.clear().await?;
+        self.bloom.write().clear();
+        Ok(())
+    }
+
+    /// Delegates to the underlying cache's `get_or_compute`, keeping its
+    /// single-flight behaviour, and adds computed codes to the filter.
+    ///
+    /// The code is added once the underlying cache has stored it, so a
+    /// concurrent [`BloomFilter::clear`] cannot leave a stored code outside
+    /// the filter.
+    async fn get_or_compute<F, Fut>(&self, code: &ShortCode, fetch: F) -> Result<Option<UrlRecord>>
+    where
+        F: FnOnce(&ShortCode) -> Fut + Send,
+        Fut: Future<Output = Result<Option<UrlRecord>>> + Send,
+    {
+        let record = self.cache.get_or_compute(code, fetch).await?;
+        if record.is_some() {
+            self.bloom.write().set(code);
+        }
+        Ok(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MokaUrlCache;
+
+    #[test]
+    fn warm_from_lets_imported_codes_pass_the_filter() {
+        let config = BloomFilterConfig::builder()
+            .expected_items(1_000)
+            .false_positive_rate(0.01)
+            .build();
+        let cache = BloomFilter::new(config, MokaUrlCache::new()).unwrap();
+        let imported: Vec<_> = (0..100)
+            .map(|i| ShortCode::new_unchecked(format!("import{i}")))
+            .collect();
+
+        let absent_before = imported
+            .iter()
+            .filter(|code| !cache.might_contain(code))
+            .count();
+        assert!(absent_before > 90);
+
+        let added = cache.warm_from(imported.iter().cloned());
+
+        assert_eq!(added, imported.len());
+        assert!(imported.iter().all(|code| cache.might_contain(code)));
+    }
+
+    #[tokio::test]
+    async fn set_url_with_ttl_is_passed_to_the_underlying_cache() {
+        let config = BloomFilterConfig::builder()
+            .expected_items(1_000)
+            .false_positive_rate(0.01)
+            .build();
+        let cache = BloomFilter::new(config, MokaUrlCache::new()).unwrap();
+        let code = ShortCode::new_unchecked("abc123");
+        let record = UrlRecord {
+            original_url: "https://example.com".to_string(),
+            expire_at: None,
+            redirect_kind: Default::default(),
+            created_at: jiff::Timestamp::now(),
+            internal_only: false,
+            no_store: false,
+            owner_id: None,
+            metadata: Default::default(),
+        };
+
+        cache
+            .set_url_with_ttl(&code, &record, Some(Duration::from_millis(50)))
+            .await
+            .unwrap();
+        assert!(cache.might_contain(&code));
+        assert_eq!(cache.get_url(&code).await.unwrap(), Some(record));
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        assert!(cache.get_url(&code).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn get_or_compute_adds_computed_codes_and_clear_forgets_them() {
+        let config = BloomFilterConfig::builder()
+            .expected_items(1_000)
+            .false_positive_rate(0.01)
+            .build();
+        let cache = BloomFilter::new(config, MokaUrlCache::new()).unwrap();
+        let code = ShortCode::new_unchecked("computed");
+        let expected = layered_record();
+
+        let computed = expected.clone();
+        let record = cache
+            .get_or_compute(&code, |_| async move { Ok(Some(computed)) })
+            .await
+            .unwrap();
+        assert_eq!(record, Some(expected));
+        assert!(cache.might_contain(&code));
+        assert!(cache.exists(&code).await.unwrap());
+
+        cache.clear().await.unwrap();
+        assert!(!cache.might_contain(&code));
+        assert!(!cache.exists(&code).await.unwrap());
+    }
+
+    /// Fixed, so records built by separate calls compare equal.
+    fn layered_record() -> UrlRecord {
+        UrlRecord {
+            original_url: "https://example.com/l2".to_string(),
+            expire_at: None,
+            redirect_kind: Default::default(),
+            created_at: jiff::Timestamp::UNIX_EPOCH,
+            internal_only: false,
+            no_store: false,
+            owner_id: None,
+            metadata: Default::default(),
+        }
+    }
+
+    /// A filter over two layers, where L2 holds a code written by another
+    /// instance and L1 holds a tombstone for it.
+    async fn filter_over_shared_layer(
+        coverage: FilterCoverage,
+    ) -> (
+        BloomFilter<crate::LayeredCache<MokaUrlCache, MokaUrlCache>>,
+        ShortCode,
+    ) {
+        let config = BloomFilterConfig::builder()
+            .expected_items(1_000)
+            .false_positive_rate(0.01)
+            .build();
+        let (l1, l2) = (MokaUrlCache::new(), MokaUrlCache::new());
+        let code = ShortCode::new_unchecked("elsewhere");
+
+        l1.get_or_compute(&code, |_| async { Ok(None) })
+            .await
+            .unwrap();
+        l2.set_url(&code, &layered_record()).await.unwrap();
+
+        let cache = BloomFilter::new(config, crate::LayeredCache::new(l1, l2))
+            .unwrap()
+            .with_coverage(coverage);
+        assert!(!cache.might_contain(&code));
+        (cache, code)
+    }
+
+    #[tokio::test]
+    async fn partial_coverage_defers_absent_answers_to_lower_layers() {
+        let (cache, code) = filter_over_shared_layer(FilterCoverage::Partial).await;
+
+        assert_eq!(cache.get_url(&code).await.unwrap(), Some(layered_record()));
+        // Found codes are learned, so the next lookup takes the normal path.
+        assert!(cache.might_contain(&code));
+        assert_eq!(cache.get_url(&code).await.unwrap(), Some(layered_record()));
+    }
+
+    #[tokio::test]
+    async fn partial_coverage_still_answers_misses() {
+        let (cache, _) = filter_over_shared_layer(FilterCoverage::Partial).await;
+        let missing = ShortCode::new_unchecked("missing");
+
+        assert_eq!(cache.get_url(&missing).await.unwrap(), None);
+        assert!(!cache.might_contain(&missing));
+    }
+
+    #[tokio::test]
+    async fn complete_coverage_trusts_the_filter() {
+        let (cache, code) = filter_over_shared_layer(FilterCoverage::Complete).await;
+
+        // The caller promised every write goes through the filter, so the
+        // lower layer is never asked.
+        assert_eq!(cache.get_url(&code).await.unwrap(), None);
+    }
 }
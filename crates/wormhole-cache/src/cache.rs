@@ -1,6 +1,7 @@
-use crate::Result;
+use crate::{CacheError, Result};
 use async_trait::async_trait;
 use std::future::Future;
+use std::time::Duration;
 use wormhole_core::{ShortCode, UrlRecord};
 
 /// A cache for URL records.
@@ -14,12 +15,59 @@ pub trait UrlCache: Send + Sync + 'static {
     /// Returns `Ok(None)` if the key is not in the cache.
     async fn get_url(&self, code: &ShortCode) -> Result<Option<UrlRecord>>;
 
+    /// Check whether a URL record is cached for `code`.
+    ///
+    /// The default implementation calls [`UrlCache::get_url`]; caches that
+    /// can answer without fetching and decoding the record override it.
+    async fn exists(&self, code: &ShortCode) -> Result<bool> {
+        self.get_url(code).await.map(|record| record.is_some())
+    }
+
     /// Store URL record in cache.
     async fn set_url(&self, code: &ShortCode, record: &UrlRecord) -> Result<()>;
 
+    /// Store URL record in cache, expiring it after `ttl`.
+    ///
+    /// `None` stores the record without a per-entry expiry; any cache-wide
+    /// expiry still applies. The default implementation ignores `ttl` and
+    /// calls [`UrlCache::set_url`]; caches that can expire individual
+    /// entries override it.
+    async fn set_url_with_ttl(
+        &self,
+        code: &ShortCode,
+        record: &UrlRecord,
+        ttl: Option<Duration>,
+    ) -> Result<()> {
+        let _ = ttl;
+        self.set_url(code, record).await
+    }
+
     /// Remove URL record from cache.
     async fn del(&self, code: &ShortCode) -> Result<()>;
 
+    /// Remove several URL records from cache.
+    ///
+    /// The default implementation calls [`UrlCache::del`] for each code and
+    /// stops at the first error. Caches with a batched delete should override
+    /// it.
+    async fn del_many(&self, codes: &[ShortCode]) -> Result<()> {
+        for code in codes {
+            self.del(code).await?;
+        }
+        Ok(())
+    }
+
+    /// Remove every URL record from cache.
+    ///
+    /// Meant for admin tooling and tests. The default implementation fails
+    /// with [`CacheError::Operation`]; caches that can enumerate their
+    /// entries override it.
+    async fn clear(&self) -> Result<()> {
+        Err(CacheError::Operation(
+            "clear is not supported by this cache".to_string(),
+        ))
+    }
+
     /// Get URL record from cache, computing it if not present.
     async fn get_or_compute<F, Fut>(&self, code: &ShortCode, fetch: F) -> Result<Option<UrlRecord>>
     where
@@ -42,10 +90,12 @@ pub trait UrlCache: Send + Sync + 'static {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use jiff::Timestamp;
     use std::collections::HashMap;
     use std::sync::atomic::{AtomicUsize, Ordering};
     use std::sync::Arc;
     use tokio::sync::Mutex;
+    use wormhole_core::RedirectKind;
 
     #[derive(Default)]
     struct TestCache {
@@ -76,6 +126,12 @@ mod tests {
         UrlRecord {
             original_url: url.to_string(),
             expire_at: None,
+            redirect_kind: RedirectKind::default(),
+            created_at: Timestamp::now(),
+            internal_only: false,
+            no_store: false,
+            owner_id: None,
+            metadata: Default::default(),
         }
     }
 
@@ -116,4 +172,10 @@ mod tests {
         assert_eq!(result, Some(fetched.clone()));
         assert_eq!(cache.get_url(&code).await.unwrap(), Some(fetched));
     }
+
+    #[tokio::test]
+    async fn clear_is_unsupported_by_default() {
+        let err = TestCache::default().clear().await.unwrap_err();
+        assert!(matches!(err, CacheError::Operation(_)));
+    }
 }
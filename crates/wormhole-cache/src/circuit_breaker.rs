@@ -0,0 +1,479 @@
+//! A circuit breaker that stops calling a failing cache.
+//!
+//! While a remote cache such as Redis is down, every read still waits for a
+//! connection error or a timeout before the caller falls back to the
+//! database. [`CircuitBreakerCache`] counts consecutive backend failures and,
+//! past a threshold, *opens*: calls fail fast without touching the inner
+//! cache, and `get_or_compute` goes straight to its `fetch`. After a cooldown
+//! one call is let through as a probe (*half-open*); its outcome closes the
+//! breaker again or restarts the cooldown.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::time::Instant;
+use tracing::{debug, warn};
+use typed_builder::TypedBuilder;
+use wormhole_core::{ShortCode, UrlRecord};
+
+use crate::{CacheError, Result, UrlCache};
+
+/// Settings for [`CircuitBreakerCache`].
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failures that open the breaker.
+    #[builder(default = 5)]
+    pub failure_threshold: u32,
+
+    /// How long the breaker stays open before probing the cache again.
+    #[builder(default = Duration::from_secs(10))]
+    pub cooldown: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// The state of a [`CircuitBreakerCache`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    /// Calls reach the inner cache.
+    Closed,
+    /// Calls fail fast until the cooldown ends.
+    Open,
+    /// The cooldown ended; the next call probes the inner cache.
+    HalfOpen,
+}
+
+/// Marks the breaker as closed in [`Breaker::opened_at`].
+const CLOSED: u64 = 0;
+
+#[derive(Debug)]
+struct Breaker {
+    config: CircuitBreakerConfig,
+    /// Reference point for `opened_at`.
+    epoch: Instant,
+    consecutive_failures: AtomicU32,
+    /// Milliseconds since `epoch` at which the breaker opened, plus one, or
+    /// [`CLOSED`].
+    opened_at: AtomicU64,
+    /// Whether a half-open probe is in flight.
+    probing: AtomicBool,
+}
+
+/// Whether a call may reach the inner cache.
+enum Admission {
+    Allowed,
+    Probe,
+    Rejected,
+}
+
+impl Breaker {
+    fn now(&self) -> u64 {
+        u64::try_from(self.epoch.elapsed().as_millis()).unwrap_or(u64::MAX - 1) + 1
+    }
+
+    fn state(&self) -> BreakerState {
+        match self.opened_at.load(Ordering::Acquire) {
+            CLOSED => BreakerState::Closed,
+            opened_at if self.now() - opened_at < self.cooldown_millis() => BreakerState::Open,
+            _ => BreakerState::HalfOpen,
+        }
+    }
+
+    fn cooldown_millis(&self) -> u64 {
+        u64::try_from(self.config.cooldown.as_millis()).unwrap_or(u64::MAX)
+    }
+
+    fn admit(&self) -> Admission {
+        match self.state() {
+            BreakerState::Closed => Admission::Allowed,
+            BreakerState::Open => Admission::Rejected,
+            // Only one caller gets to probe; the rest keep failing fast.
+            BreakerState::HalfOpen => match self.probing.compare_exchange(
+                false,
+                true,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => Admission::Probe,
+                Err(_) => Admission::Rejected,
+            },
+        }
+    }
+
+    fn on_success(&self) {
+        self.consecutive_failures.store(0, Ordering::Release);
+        if self.opened_at.swap(CLOSED, Ordering::AcqRel) != CLOSED {
+            debug!("Cache circuit breaker closed");
+        }
+        self.probing.store(false, Ordering::Release);
+    }
+
+    fn on_failure(&self, probe: bool, error: &CacheError) {
+        let failures = self.consecutive_failures.fetch_add(1, Ordering::AcqRel) + 1;
+        if probe || failures >= self.config.failure_threshold {
+            if self.opened_at.swap(self.now(), Ordering::AcqRel) == CLOSED {
+                warn!(failures, error = %error, "Cache circuit breaker opened");
+            }
+            self.probing.store(false, Ordering::Release);
+        }
+    }
+
+    /// Updates the breaker with the outcome of an admitted call.
+    fn record<T>(&self, admission: &Admission, result: &Result<T>) {
+        let probe = matches!(admission, Admission::Probe);
+        match result {
+            Err(error) if counts_as_failure(error) => self.on_failure(probe, error),
+            _ => self.on_success(),
+        }
+    }
+}
+
+/// Only errors that say the backend is unhealthy trip the breaker; bad data
+/// in one entry does not.
+fn counts_as_failure(error: &CacheError) -> bool {
+    matches!(error, CacheError::Unavailable(_) | CacheError::Timeout(_))
+}
+
+fn open_error() -> CacheError {
+    CacheError::Unavailable("cache circuit breaker is open".to_string())
+}
+
+/// A decorator that short-circuits the wrapped cache while it keeps failing.
+///
+/// After [`CircuitBreakerConfig::failure_threshold`] consecutive
+/// `Unavailable` or `Timeout` errors the breaker opens for
+/// [`CircuitBreakerConfig::cooldown`]. While open, reads and writes return
+/// `CacheError::Unavailable` immediately, and `get_or_compute` runs `fetch`
+/// without caching the result. Callers such as `CachedRepository` already
+/// treat a failing cache as a miss, so redirects keep working at database
+/// latency instead of paying for a dead cache first.
+///
+/// Clones share the breaker.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use wormhole_cache::{CircuitBreakerCache, CircuitBreakerConfig, RedisUrlCache};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let client = redis::Client::open("redis://127.0.0.1:6379")?;
+/// let redis = RedisUrlCache::new(client.get_multiplexed_async_connection().await?);
+/// let cache = CircuitBreakerCache::new(redis, CircuitBreakerConfig::default());
+/// # let _ = cache;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerCache<C> {
+    inner: C,
+    breaker: Arc<Breaker>,
+}
+
+impl<C> CircuitBreakerCache<C> {
+    pub fn new(inner: C, config: CircuitBreakerConfig) -> Self {
+        Self {
+            inner,
+            breaker: Arc::new(Breaker {
+                config,
+                epoch: Instant::now(),
+                consecutive_failures: AtomicU32::new(0),
+                opened_at: AtomicU64::new(CLOSED),
+                probing: AtomicBool::new(false),
+            }),
+        }
+    }
+
+    /// Returns the current state of the breaker.
+    pub fn state(&self) -> BreakerState {
+        self.breaker.state()
+    }
+
+    /// Returns a reference to the wrapped cache.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Runs `call` against the inner cache unless the breaker rejects it.
+    async fn guard<T, Fut>(&self, call: impl FnOnce() -> Fut) -> Result<T>
+    where
+        Fut: Future<Output = Result<T>>,
+    {
+        let admission = self.breaker.admit();
+        if matches!(admission, Admission::Rejected) {
+            return Err(open_error());
+        }
+        let result = call().await;
+        self.breaker.record(&admission, &result);
+        result
+    }
+}
+
+#[async_trait]
+impl<C: UrlCache> UrlCache for CircuitBreakerCache<C> {
+    async fn get_url(&self, code: &ShortCode) -> Result<Option<UrlRecord>> {
+        self.guard(|| self.inner.get_url(code)).await
+    }
+
+    async fn exists(&self, code: &ShortCode) -> Result<bool> {
+        self.guard(|| self.inner.exists(code)).await
+    }
+
+    async fn set_url(&self, code: &ShortCode, record: &UrlRecord) -> Result<()> {
+        self.guard(|| self.inner.set_url(code, record)).await
+    }
+
+    async fn set_url_with_ttl(
+        &self,
+        code: &ShortCode,
+        record: &UrlRecord,
+        ttl: Option<Duration>,
+    ) -> Result<()> {
+        self.guard(|| self.inner.set_url_with_ttl(code, record, ttl))
+            .await
+    }
+
+    async fn del(&self, code: &ShortCode) -> Result<()> {
+        self.guard(|| self.inner.del(code)).await
+    }
+
+    async fn del_many(&self, codes: &[ShortCode]) -> Result<()> {
+        self.guard(|| self.inner.del_many(codes)).await
+    }
+
+    async fn clear(&self) -> Result<()> {
+        self.guard(|| self.inner.clear()).await
+    }
+
+    async fn get_or_compute<F, Fut>(&self, code: &ShortCode, fetch: F) -> Result<Option<UrlRecord>>
+    where
+        F: FnOnce(&ShortCode) -> Fut + Send,
+        Fut: Future<Output = Result<Option<UrlRecord>>> + Send,
+    {
+        let admission = self.breaker.admit();
+        if matches!(admission, Admission::Rejected) {
+            debug!(code = %code, "Cache circuit breaker open, fetching directly");
+            return fetch(code).await;
+        }
+
+        // Errors from `fetch` come back through the cache, but say nothing
+        // about the cache's health.
+        let fetch_failed = AtomicBool::new(false);
+        let fetch_failed_ref = &fetch_failed;
+        let result = self
+            .inner
+            .get_or_compute(code, move |c| {
+                let fetched = fetch(c);
+                async move {
+                    let result = fetched.await;
+                    fetch_failed_ref.store(result.is_err(), Ordering::Relaxed);
+                    result
+                }
+            })
+            .await;
+
+        if fetch_failed.load(Ordering::Relaxed) {
+            self.breaker.record(&admission, &Ok(()));
+        } else {
+            self.breaker.record(&admission, &result);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MokaUrlCache;
+    use jiff::Timestamp;
+    use std::sync::atomic::AtomicUsize;
+    use wormhole_core::RedirectKind;
+
+    fn test_record(url: &str) -> UrlRecord {
+        UrlRecord {
+            original_url: url.to_string(),
+            expire_at: None,
+            redirect_kind: RedirectKind::default(),
+            created_at: Timestamp::now(),
+            internal_only: false,
+            no_store: false,
+            owner_id: None,
+            metadata: Default::default(),
+        }
+    }
+
+    fn code(s: &str) -> ShortCode {
+        ShortCode::new_unchecked(s)
+    }
+
+    /// A Moka cache that fails with `Unavailable` while `down` is set, and
+    /// counts the calls that reached it.
+    #[derive(Debug, Clone, Default)]
+    struct FlakyCache {
+        inner: MokaUrlCache,
+        down: Arc<AtomicBool>,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl FlakyCache {
+        fn check(&self) -> Result<()> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.down.load(Ordering::SeqCst) {
+                return Err(CacheError::Unavailable("connection refused".to_string()));
+            }
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl UrlCache for FlakyCache {
+        async fn get_url(&self, code: &ShortCode) -> Result<Option<UrlRecord>> {
+            self.check()?;
+            self.inner.get_url(code).await
+        }
+
+        async fn set_url(&self, code: &ShortCode, record: &UrlRecord) -> Result<()> {
+            self.check()?;
+            self.inner.set_url(code, record).await
+        }
+
+        async fn del(&self, code: &ShortCode) -> Result<()> {
+            self.check()?;
+            self.inner.del(code).await
+        }
+
+        async fn clear(&self) -> Result<()> {
+            self.check()?;
+            self.inner.clear().await
+        }
+
+        async fn get_or_compute<F, Fut>(
+            &self,
+            code: &ShortCode,
+            fetch: F,
+        ) -> Result<Option<UrlRecord>>
+        where
+            F: FnOnce(&ShortCode) -> Fut + Send,
+            Fut: Future<Output = Result<Option<UrlRecord>>> + Send,
+        {
+            self.check()?;
+            self.inner.get_or_compute(code, fetch).await
+        }
+    }
+
+    fn breaker(flaky: &FlakyCache) -> CircuitBreakerCache<FlakyCache> {
+        CircuitBreakerCache::new(
+            flaky.clone(),
+            CircuitBreakerConfig::builder()
+                .failure_threshold(3)
+                .cooldown(Duration::from_secs(10))
+                .build(),
+        )
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn consecutive_failures_open_the_breaker() {
+        let flaky = FlakyCache::default();
+        let cache = breaker(&flaky);
+        flaky.down.store(true, Ordering::SeqCst);
+
+        for _ in 0..3 {
+            assert!(cache.get_url(&code("abc")).await.is_err());
+        }
+        assert_eq!(cache.state(), BreakerState::Open);
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 3);
+
+        // Open: calls fail fast without reaching the inner cache.
+        let err = cache.get_url(&code("abc")).await.unwrap_err();
+        assert!(matches!(err, CacheError::Unavailable(_)));
+        assert!(cache
+            .set_url(&code("abc"), &test_record("https://example.com"))
+            .await
+            .is_err());
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn successes_reset_the_failure_count() {
+        let flaky = FlakyCache::default();
+        let cache = breaker(&flaky);
+
+        for _ in 0..5 {
+            flaky.down.store(true, Ordering::SeqCst);
+            assert!(cache.get_url(&code("abc")).await.is_err());
+            assert!(cache.get_url(&code("abc")).await.is_err());
+            flaky.down.store(false, Ordering::SeqCst);
+            cache.get_url(&code("abc")).await.unwrap();
+        }
+
+        assert_eq!(cache.state(), BreakerState::Closed);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn open_breaker_fetches_directly_in_get_or_compute() {
+        let flaky = FlakyCache::default();
+        let cache = breaker(&flaky);
+        flaky.down.store(true, Ordering::SeqCst);
+        for _ in 0..3 {
+            let _ = cache.get_url(&code("abc")).await;
+        }
+
+        let record = cache
+            .get_or_compute(&code("abc"), |_| async {
+                Ok(Some(test_record("https://origin.example")))
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(record.unwrap().original_url, "https://origin.example");
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn half_open_probe_closes_the_breaker_on_recovery() {
+        let flaky = FlakyCache::default();
+        let cache = breaker(&flaky);
+        flaky.down.store(true, Ordering::SeqCst);
+        for _ in 0..3 {
+            let _ = cache.get_url(&code("abc")).await;
+        }
+
+        // A failed probe restarts the cooldown.
+        tokio::time::advance(Duration::from_secs(11)).await;
+        assert_eq!(cache.state(), BreakerState::HalfOpen);
+        assert!(cache.get_url(&code("abc")).await.is_err());
+        assert_eq!(cache.state(), BreakerState::Open);
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 4);
+
+        // Once the cache is back, the next probe closes the breaker.
+        flaky.down.store(false, Ordering::SeqCst);
+        tokio::time::advance(Duration::from_secs(11)).await;
+        cache.get_url(&code("abc")).await.unwrap();
+        assert_eq!(cache.state(), BreakerState::Closed);
+        cache.get_url(&code("abc")).await.unwrap();
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 6);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn fetch_errors_do_not_trip_the_breaker() {
+        let flaky = FlakyCache::default();
+        let cache = breaker(&flaky);
+
+        for i in 0..5 {
+            let result = cache
+                .get_or_compute(&code(&format!("code{i}")), |_| async {
+                    Err(CacheError::Unavailable("database is down".to_string()))
+                })
+                .await;
+            assert!(result.is_err());
+        }
+
+        assert_eq!(cache.state(), BreakerState::Closed);
+    }
+}
@@ -0,0 +1,124 @@
+//! Serialization formats for cache values stored out of process.
+//!
+//! Codecs are not self-describing on the wire: a cache configured with one
+//! codec cannot read values written by another. When switching codecs on a
+//! live deployment, also switch the key prefix (e.g. `wh:url:` to
+//! `wh:url:v2:`) so readers never see values in the old format; the old keys
+//! simply age out.
+
+use std::fmt::Debug;
+use wormhole_core::UrlRecord;
+
+use crate::{CacheError, Result};
+
+/// Converts [`UrlRecord`]s to and from the bytes stored in a cache backend.
+pub trait CacheCodec: Debug + Send + Sync + 'static {
+    /// Encodes a record for storage.
+    fn encode(&self, record: &UrlRecord) -> Result<Vec<u8>>;
+
+    /// Decodes a stored value.
+    ///
+    /// Returns [`CacheError::InvalidData`] if the bytes are not a valid
+    /// record in this codec's format.
+    fn decode(&self, bytes: &[u8]) -> Result<UrlRecord>;
+}
+
+/// Stores records as JSON. This is the default and matches values written
+/// before codecs were configurable.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonCodec;
+
+impl CacheCodec for JsonCodec {
+    fn encode(&self, record: &UrlRecord) -> Result<Vec<u8>> {
+        serde_json::to_vec(record)
+            .map_err(|e| CacheError::Serialization(format!("failed to serialize cache value: {e}")))
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<UrlRecord> {
+        serde_json::from_slice(bytes)
+            .map_err(|e| CacheError::InvalidData(format!("invalid JSON cache value: {e}")))
+    }
+}
+
+/// Stores records as MessagePack, which is noticeably smaller than JSON.
+///
+/// Fields are encoded by name so that records written before a field was
+/// added still decode, relying on the same `#[serde(default)]` attributes
+/// as the JSON codec.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MsgPackCodec;
+
+impl CacheCodec for MsgPackCodec {
+    fn encode(&self, record: &UrlRecord) -> Result<Vec<u8>> {
+        rmp_serde::to_vec_named(record)
+            .map_err(|e| CacheError::Serialization(format!("failed to serialize cache value: {e}")))
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<UrlRecord> {
+        rmp_serde::from_slice(bytes)
+            .map_err(|e| CacheError::InvalidData(format!("invalid MessagePack cache value: {e}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jiff::Timestamp;
+    use wormhole_core::RedirectKind;
+
+    fn test_record() -> UrlRecord {
+        UrlRecord {
+            original_url: "https://example.com".to_string(),
+            expire_at: Some(Timestamp::from_second(2_000_000_000).unwrap()),
+            redirect_kind: RedirectKind::Permanent301,
+            created_at: Timestamp::from_second(1_700_000_000).unwrap(),
+            internal_only: false,
+            no_store: false,
+            owner_id: None,
+            metadata: Default::default(),
+        }
+    }
+
+    #[test]
+    fn codecs_round_trip() {
+        let record = test_record();
+        let codecs: [&dyn CacheCodec; 2] = [&JsonCodec, &MsgPackCodec];
+
+        for codec in codecs {
+            let bytes = codec.encode(&record).unwrap();
+            assert_eq!(codec.decode(&bytes).unwrap(), record, "{codec:?}");
+        }
+    }
+
+    #[test]
+    fn codecs_round_trip_owner_and_metadata() {
+        let mut record = test_record();
+        record.owner_id = Some("tenant-a".to_string());
+        record
+            .metadata
+            .insert("campaign".to_string(), "spring".to_string());
+        let codecs: [&dyn CacheCodec; 2] = [&JsonCodec, &MsgPackCodec];
+
+        for codec in codecs {
+            let bytes = codec.encode(&record).unwrap();
+            assert_eq!(codec.decode(&bytes).unwrap(), record, "{codec:?}");
+        }
+    }
+
+    #[test]
+    fn msgpack_is_smaller_than_json() {
+        let record = test_record();
+        let json = JsonCodec.encode(&record).unwrap();
+        let msgpack = MsgPackCodec.encode(&record).unwrap();
+
+        assert!(msgpack.len() < json.len());
+    }
+
+    #[test]
+    fn decoding_another_codecs_output_is_invalid_data() {
+        let json = JsonCodec.encode(&test_record()).unwrap();
+
+        let err = MsgPackCodec.decode(&json).unwrap_err();
+        assert!(matches!(err, CacheError::InvalidData(_)));
+    }
+}
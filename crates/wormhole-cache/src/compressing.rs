@@ -0,0 +1,292 @@
+use std::future::Future;
+use std::io::{Read, Write};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD_NO_PAD;
+use base64::Engine;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use tracing::trace;
+use wormhole_core::{ShortCode, UrlRecord};
+
+use crate::{CacheError, Result, UrlCache};
+
+/// Header marking a URL stored as-is.
+const RAW: char = '\u{0}';
+/// Header marking a gzip-compressed, base64-encoded URL.
+const GZIP: char = '\u{1}';
+
+/// A decorator that compresses large URLs before handing records to the
+/// wrapped cache.
+///
+/// The inner cache still stores [`UrlRecord`]s, so only `original_url` is
+/// rewritten: it becomes a one-character header followed by either the raw
+/// URL or its gzip-compressed bytes in base64. The remaining fields are
+/// passed through untouched, which keeps expiry handling in the inner cache
+/// working.
+///
+/// Header characters are ASCII control characters, which the shortener
+/// rejects in URLs, so values written to the inner cache without this
+/// decorator are read back as raw URLs.
+///
+/// # Example
+///
+/// ```rust
+/// use wormhole_cache::{CompressingCache, MokaUrlCache};
+///
+/// let cache = CompressingCache::new(MokaUrlCache::with_capacity(10_000)).with_threshold(512);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CompressingCache<C> {
+    inner: C,
+    threshold: usize,
+}
+
+impl<C> CompressingCache<C> {
+    /// Default size in bytes above which URLs are compressed.
+    pub const DEFAULT_THRESHOLD: usize = 1024;
+
+    /// Wraps `inner`, compressing URLs longer than
+    /// [`CompressingCache::DEFAULT_THRESHOLD`] bytes.
+    pub fn new(inner: C) -> Self {
+        Self {
+            inner,
+            threshold: Self::DEFAULT_THRESHOLD,
+        }
+    }
+
+    /// Sets the size in bytes above which URLs are compressed.
+    ///
+    /// Short URLs rarely shrink enough to pay for the base64 overhead, so
+    /// this should stay well above typical URL lengths.
+    pub fn with_threshold(mut self, threshold: usize) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Returns the compression threshold in bytes.
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// Returns a reference to the wrapped cache.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    fn compress(&self, record: &UrlRecord) -> Result<UrlRecord> {
+        let url = &record.original_url;
+        let payload = if url.len() > self.threshold {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            let compressed = encoder
+                .write_all(url.as_bytes())
+                .and_then(|()| encoder.finish())
+                .map_err(|e| CacheError::Serialization(format!("failed to compress URL: {e}")))?;
+            trace!(
+                raw = url.len(),
+                compressed = compressed.len(),
+                "Compressed URL"
+            );
+            format!("{GZIP}{}", STANDARD_NO_PAD.encode(compressed))
+        } else {
+            format!("{RAW}{url}")
+        };
+
+        Ok(UrlRecord {
+            original_url: payload,
+            ..record.clone()
+        })
+    }
+
+    fn decompress(&self, mut record: UrlRecord) -> Result<UrlRecord> {
+        let mut chars = record.original_url.chars();
+        match chars.next() {
+            Some(RAW) => {
+                record.original_url = chars.as_str().to_string();
+            }
+            Some(GZIP) => {
+                let compressed = STANDARD_NO_PAD.decode(chars.as_str()).map_err(|e| {
+                    CacheError::InvalidData(format!("invalid compressed URL encoding: {e}"))
+                })?;
+                let mut url = String::new();
+                GzDecoder::new(compressed.as_slice())
+                    .read_to_string(&mut url)
+                    .map_err(|e| {
+                        CacheError::InvalidData(format!("failed to decompress URL: {e}"))
+                    })?;
+                record.original_url = url;
+            }
+            // Written without this decorator.
+            _ => {}
+        }
+        Ok(record)
+    }
+}
+
+#[async_trait]
+impl<C: UrlCache> UrlCache for CompressingCache<C> {
+    async fn get_url(&self, code: &ShortCode) -> Result<Option<UrlRecord>> {
+        match self.inner.get_url(code).await? {
+            Some(record) => self.decompress(record).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    async fn set_url(&self, code: &ShortCode, record: &UrlRecord) -> Result<()> {
+        let stored = self.compress(record)?;
+        self.inner.set_url(code, &stored).await
+    }
+
+    async fn set_url_with_ttl(
+        &self,
+        code: &ShortCode,
+        record: &UrlRecord,
+        ttl: Option<Duration>,
+    ) -> Result<()> {
+        let stored = self.compress(record)?;
+        self.inner.set_url_with_ttl(code, &stored, ttl).await
+    }
+
+    async fn del(&self, code: &ShortCode) -> Result<()> {
+        self.inner.del(code).await
+    }
+
+    async fn del_many(&self, codes: &[ShortCode]) -> Result<()> {
+        self.inner.del_many(codes).await
+    }
+
+    async fn clear(&self) -> Result<()> {
+        self.inner.clear().await
+    }
+
+    async fn exists(&self, code: &ShortCode) -> Result<bool> {
+        self.inner.exists(code).await
+    }
+
+    /// Delegates to the inner cache's `get_or_compute`, compressing what
+    /// `fetch` returns before the inner cache stores it.
+    async fn get_or_compute<F, Fut>(&self, code: &ShortCode, fetch: F) -> Result<Option<UrlRecord>>
+    where
+        F: FnOnce(&ShortCode) -> Fut + Send,
+        Fut: Future<Output = Result<Option<UrlRecord>>> + Send,
+    {
+        let record = self
+            .inner
+            .get_or_compute(code, |code| {
+                let fetched = fetch(code);
+                async move {
+                    fetched
+                        .await?
+                        .map(|record| self.compress(&record))
+                        .transpose()
+                }
+            })
+            .await?;
+        record.map(|record| self.decompress(record)).transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MokaUrlCache;
+    use jiff::Timestamp;
+    use wormhole_core::RedirectKind;
+
+    fn test_record(url: String) -> UrlRecord {
+        UrlRecord {
+            original_url: url,
+            expire_at: None,
+            redirect_kind: RedirectKind::default(),
+            created_at: Timestamp::now(),
+            internal_only: false,
+            no_store: false,
+            owner_id: None,
+            metadata: Default::default(),
+        }
+    }
+
+    fn large_url() -> String {
+        let signature = "0123456789abcdef".repeat(256);
+        format!("https://bucket.s3.amazonaws.com/object?X-Amz-Signature={signature}")
+    }
+
+    #[tokio::test]
+    async fn small_record_is_stored_raw() {
+        let cache = CompressingCache::new(MokaUrlCache::new());
+        let code = ShortCode::new_unchecked("small");
+        let record = test_record("https://example.com".to_string());
+
+        cache.set_url(&code, &record).await.unwrap();
+
+        let stored = cache.inner().get_url(&code).await.unwrap().unwrap();
+        assert_eq!(stored.original_url, format!("{RAW}https://example.com"));
+        assert_eq!(cache.get_url(&code).await.unwrap(), Some(record));
+    }
+
+    #[tokio::test]
+    async fn large_record_is_stored_compressed() {
+        let cache = CompressingCache::new(MokaUrlCache::new());
+        let code = ShortCode::new_unchecked("large");
+        let record = test_record(large_url());
+
+        cache.set_url(&code, &record).await.unwrap();
+
+        let stored = cache.inner().get_url(&code).await.unwrap().unwrap();
+        assert!(stored.original_url.starts_with(GZIP));
+        assert!(stored.original_url.len() < record.original_url.len());
+        assert_eq!(stored.created_at, record.created_at);
+        assert_eq!(cache.get_url(&code).await.unwrap(), Some(record));
+    }
+
+    #[tokio::test]
+    async fn get_or_compute_stores_compressed_and_returns_raw() {
+        let cache = CompressingCache::new(MokaUrlCache::new());
+        let code = ShortCode::new_unchecked("computed");
+        let record = test_record(large_url());
+
+        let computed = cache
+            .get_or_compute(&code, |_| {
+                let record = record.clone();
+                async move { Ok(Some(record)) }
+            })
+            .await
+            .unwrap();
+        assert_eq!(computed.as_ref(), Some(&record));
+
+        let stored = cache.inner().get_url(&code).await.unwrap().unwrap();
+        assert!(stored.original_url.starts_with(GZIP));
+        assert!(cache.exists(&code).await.unwrap());
+
+        let cached = cache
+            .get_or_compute(&code, |_| async { Ok(None) })
+            .await
+            .unwrap();
+        assert_eq!(cached, Some(record));
+    }
+
+    #[tokio::test]
+    async fn untagged_values_are_read_as_raw() {
+        let cache = CompressingCache::new(MokaUrlCache::new());
+        let code = ShortCode::new_unchecked("legacy");
+        let record = test_record("https://example.com".to_string());
+
+        cache.inner().set_url(&code, &record).await.unwrap();
+
+        assert_eq!(cache.get_url(&code).await.unwrap(), Some(record));
+    }
+
+    #[tokio::test]
+    async fn corrupt_compressed_value_is_invalid_data() {
+        let cache = CompressingCache::new(MokaUrlCache::new());
+        let code = ShortCode::new_unchecked("corrupt");
+        let record = test_record(format!("{GZIP}not-gzip"));
+
+        cache.inner().set_url(&code, &record).await.unwrap();
+
+        let err = cache.get_url(&code).await.unwrap_err();
+        assert!(matches!(err, CacheError::InvalidData(_)));
+    }
+}
@@ -0,0 +1,432 @@
+//! Counting Bloom filter cache decorator that supports deletions.
+//!
+//! [`BloomFilter`](crate::BloomFilter) cannot forget a code once it has been
+//! added, so with enough churn every lookup ends up passing the filter. This
+//! variant replaces each bit with a small counter: `set_url()` increments the
+//! counters for a code and `del()` decrements them, so a deleted code goes
+//! back to being reported as definitely absent.
+//!
+//! Counters are 4 bits wide and saturate at 15. A saturated counter is never
+//! decremented again, since it may be shared by more codes than it can count;
+//! this can only cause false positives, never false negatives.
+
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use parking_lot::RwLock;
+use tokio::sync::Mutex;
+use wormhole_core::{ShortCode, UrlRecord};
+
+use crate::{BloomFilterConfig, CacheError, Result, UrlCache};
+
+const COUNTER_MAX: u8 = 0x0f;
+
+/// Fixed-size array of 4-bit counters indexed by double hashing.
+struct Counters {
+    /// Two counters per byte, low nibble first.
+    nibbles: Vec<u8>,
+    len: usize,
+    num_hashes: u64,
+    hashers: (RandomState, RandomState),
+}
+
+impl Counters {
+    fn new(config: &BloomFilterConfig) -> Result<Self> {
+        let n = config.expected_items;
+        let p = config.false_positive_rate;
+        if n == 0 {
+            return Err(CacheError::Initialization(
+                "expected_items must be greater than zero".to_string(),
+            ));
+        }
+        if !(p > 0.0 && p < 1.0) {
+            return Err(CacheError::Initialization(format!(
+                "false_positive_rate must be between 0 and 1, got {p}"
+            )));
+        }
+
+        let ln2 = std::f64::consts::LN_2;
+        let len = (-(n as f64) * p.ln() / (ln2 * ln2)).ceil().max(1.0) as usize;
+        let num_hashes = ((len as f64 / n as f64) * ln2).round().max(1.0) as u64;
+
+        Ok(Self {
+            nibbles: vec![0; len.div_ceil(2)],
+            len,
+            num_hashes,
+            hashers: (RandomState::new(), RandomState::new()),
+        })
+    }
+
+    fn indices(&self, code: &ShortCode) -> impl Iterator<Item = usize> {
+        let h1 = self.hashers.0.hash_one(code);
+        // An odd step visits distinct slots even when `len` is a power of two.
+        let h2 = self.hashers.1.hash_one(code) | 1;
+        let len = self.len as u64;
+        (0..self.num_hashes).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % len) as usize)
+    }
+
+    fn get(&self, index: usize) -> u8 {
+        let byte = self.nibbles[index / 2];
+        if index.is_multiple_of(2) {
+            byte & 0x0f
+        } else {
+            byte >> 4
+        }
+    }
+
+    fn put(&mut self, index: usize, value: u8) {
+        let byte = &mut self.nibbles[index / 2];
+        if index.is_multiple_of(2) {
+            *byte = (*byte & 0xf0) | value;
+        } else {
+            *byte = (*byte & 0x0f) | (value << 4);
+        }
+    }
+
+    fn contains(&self, code: &ShortCode) -> bool {
+        self.indices(code).all(|i| self.get(i) > 0)
+    }
+
+    fn increment(&mut self, code: &ShortCode) {
+        let indices: Vec<_> = self.indices(code).collect();
+        for i in indices {
+            let count = self.get(i);
+            if count < COUNTER_MAX {
+                self.put(i, count + 1);
+            }
+        }
+    }
+
+    fn decrement(&mut self, code: &ShortCode) {
+        let indices: Vec<_> = self.indices(code).collect();
+        for i in indices {
+            let count = self.get(i);
+            if count > 0 && count < COUNTER_MAX {
+                self.put(i, count - 1);
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.nibbles.fill(0);
+    }
+}
+
+/// A cache decorator like [`BloomFilter`](crate::BloomFilter) whose filter
+/// also reflects deletions.
+///
+/// To keep counts accurate, `set_url()` and `del()` consult the underlying
+/// cache to learn whether the code is already stored, and are serialized
+/// against each other. Reads are unaffected and take the same fast-negative
+/// path as [`BloomFilter`](crate::BloomFilter).
+///
+/// Codes evicted or expired by the underlying cache keep their counts until
+/// they are deleted through this decorator, which only costs extra lookups.
+///
+/// Its "absent" answers are always final, as with
+/// [`FilterCoverage::Complete`](crate::FilterCoverage::Complete): every write
+/// to the underlying cache must go through this decorator.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use wormhole_cache::{BloomFilterConfig, CountingBloomFilter};
+///
+/// let config = BloomFilterConfig::builder()
+///     .expected_items(1_000_000)
+///     .false_positive_rate(0.01)
+///     .build();
+///
+/// let cache = CountingBloomFilter::new(config, underlying_cache)?;
+/// ```
+pub struct CountingBloomFilter<C: UrlCache> {
+    counters: RwLock<Counters>,
+    /// Serializes writes so the presence check and the counter update happen
+    /// atomically with respect to other writes.
+    write_lock: Mutex<()>,
+    cache: C,
+}
+
+impl<C: UrlCache> CountingBloomFilter<C> {
+    /// Creates a new counting Bloom filter cache decorator.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CacheError::Initialization` if the configuration is invalid.
+    pub fn new(config: BloomFilterConfig, cache: C) -> Result<Self> {
+        Ok(Self {
+            counters: RwLock::new(Counters::new(&config)?),
+            write_lock: Mutex::new(()),
+            cache,
+        })
+    }
+
+    /// Returns `false` if `code` is definitely not in the cache.
+    pub fn might_contain(&self, code: &ShortCode) -> bool {
+        self.counters.read().contains(code)
+    }
+
+    /// Whether the underlying cache currently holds `code`. Lookup errors
+    /// report `false`, which is the safe answer for both writes and deletes.
+    async fn is_cached(&self, code: &ShortCode) -> bool {
+        if !self.might_contain(code) {
+            return false;
+        }
+        match self.cache.get_url(code).await {
+            Ok(record) => record.is_some(),
+            Err(_) => false,
+        }
+    }
+}
+
+#[async_trait]
+impl<C: UrlCache> UrlCache for CountingBloomFilter<C> {
+    async fn get_url(&self, code: &ShortCode) -> Result<Option<UrlRecord>> {
+        if !self.might_contain(code) {
+            return Ok(None);
+        }
+        self.cache.get_url(code).await
+    }
+
+    async fn set_url(&self, code: &ShortCode, record: &UrlRecord) -> Result<()> {
+        let _guard = self.write_lock.lock().await;
+        // Overwrites must not be counted twice. When unsure, count again: an
+        // extra count only costs false positives.
+        if !self.is_cached(code).await {
+            self.counters.write().increment(code);
+        }
+        self.cache.set_url(code, record).await
+    }
+
+    async fn set_url_with_ttl(
+        &self,
+        code: &ShortCode,
+        record: &UrlRecord,
+        ttl: Option<Duration>,
+    ) -> Result<()> {
+        let _guard = self.write_lock.lock().await;
+        if !self.is_cached(code).await {
+            self.counters.write().increment(code);
+        }
+        self.cache.set_url_with_ttl(code, record, ttl).await
+    }
+
+    async fn del(&self, code: &ShortCode) -> Result<()> {
+        let _guard = self.write_lock.lock().await;
+        // Only codes known to be stored may be decremented, otherwise the
+        // counts of colliding codes would drop and cause false negatives.
+        let cached = self.is_cached(code).await;
+        self.cache.del(code).await?;
+        if cached {
+            self.counters.write().decrement(code);
+        }
+        Ok(())
+    }
+
+    async fn exists(&self, code: &ShortCode) -> Result<bool> {
+        if !self.might_contain(code) {
+            return Ok(false);
+        }
+        self.cache.exists(code).await
+    }
+
+    /// Clears the underlying cache, then resets every counter. The counters
+    /// are left untouched if the underlying cache cannot be cleared.
+    async fn clear(&self) -> Result<()> {
+        let _guard = self.write_lock.lock().await;
+        self.cache.clear().await?;
+        self.counters.write().reset();
+        Ok(())
+    }
+
+    /// Delegates to the underlying cache's `get_or_compute`, keeping its
+    /// single-flight behaviour.
+    ///
+    /// A code is counted only when this call's `fetch` ran, i.e. when the
+    /// underlying cache did not hold it yet, and only after it was stored.
+    /// Until then concurrent reads may miss it, which is never wrong for a
+    /// cache; counting it early could leave a counted code that a racing
+    /// `clear()` already wiped, or an uncounted one it did not.
+    async fn get_or_compute<F, Fut>(&self, code: &ShortCode, fetch: F) -> Result<Option<UrlRecord>>
+    where
+        F: FnOnce(&ShortCode) -> Fut + Send,
+        Fut: Future<Output = Result<Option<UrlRecord>>> + Send,
+    {
+        if let Some(record) = self.get_url(code).await? {
+            return Ok(Some(record));
+        }
+
+        let fetched = AtomicBool::new(false);
+        let record = self
+            .cache
+            .get_or_compute(code, |code| {
+                fetched.store(true, Ordering::Relaxed);
+                fetch(code)
+            })
+            .await?;
+        if record.is_some() && fetched.load(Ordering::Relaxed) {
+            let _guard = self.write_lock.lock().await;
+            // A racing `set_url()` may have counted it as well; an extra
+            // count only costs false positives.
+            self.counters.write().increment(code);
+        }
+        Ok(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MokaUrlCache;
+    use jiff::Timestamp;
+    use wormhole_core::RedirectKind;
+
+    fn test_filter() -> CountingBloomFilter<MokaUrlCache> {
+        let config = BloomFilterConfig::builder()
+            .expected_items(1_000)
+            .false_positive_rate(0.01)
+            .build();
+        CountingBloomFilter::new(config, MokaUrlCache::new()).unwrap()
+    }
+
+    fn test_record(url: &str) -> UrlRecord {
+        UrlRecord {
+            original_url: url.to_string(),
+            expire_at: None,
+            redirect_kind: RedirectKind::default(),
+            created_at: Timestamp::now(),
+            internal_only: false,
+            no_store: false,
+            owner_id: None,
+            metadata: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn delete_restores_definite_absence() {
+        let cache = test_filter();
+        let code = ShortCode::new_unchecked("abc123");
+
+        assert!(!cache.might_contain(&code));
+        cache
+            .set_url(&code, &test_record("https://example.com"))
+            .await
+            .unwrap();
+        assert!(cache.might_contain(&code));
+
+        cache.del(&code).await.unwrap();
+        assert!(!cache.might_contain(&code));
+        assert!(cache.get_url(&code).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn computed_codes_are_counted_once_and_cleared() {
+        let cache = test_filter();
+        let code = ShortCode::new_unchecked("abc123");
+
+        for _ in 0..2 {
+            let record = cache
+                .get_or_compute(&code, |_| async {
+                    Ok(Some(test_record("https://example.com")))
+                })
+                .await
+                .unwrap();
+            assert!(record.is_some());
+        }
+        assert!(cache.exists(&code).await.unwrap());
+
+        // The second call was a hit, so a single delete undoes the count.
+        cache.del(&code).await.unwrap();
+        assert!(!cache.might_contain(&code));
+
+        cache
+            .get_or_compute(&code, |_| async {
+                Ok(Some(test_record("https://example.com")))
+            })
+            .await
+            .unwrap();
+        cache.clear().await.unwrap();
+        assert!(!cache.might_contain(&code));
+        assert!(!cache.exists(&code).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn overwrite_is_counted_once() {
+        let cache = test_filter();
+        let code = ShortCode::new_unchecked("abc123");
+
+        cache
+            .set_url(&code, &test_record("https://one.example"))
+            .await
+            .unwrap();
+        cache
+            .set_url(&code, &test_record("https://two.example"))
+            .await
+            .unwrap();
+        cache.del(&code).await.unwrap();
+
+        assert!(!cache.might_contain(&code));
+    }
+
+    #[tokio::test]
+    async fn deleting_unknown_code_keeps_others() {
+        let cache = test_filter();
+        let codes: Vec<_> = (0..100)
+            .map(|i| ShortCode::new_unchecked(format!("code{i}")))
+            .collect();
+        for code in &codes {
+            cache
+                .set_url(code, &test_record("https://example.com"))
+                .await
+                .unwrap();
+        }
+
+        for i in 0..1_000 {
+            let unknown = ShortCode::new_unchecked(format!("unknown{i}"));
+            cache.del(&unknown).await.unwrap();
+        }
+
+        for code in &codes {
+            assert!(cache.might_contain(code), "{code} was lost");
+            assert!(cache.get_url(code).await.unwrap().is_some());
+        }
+    }
+
+    #[test]
+    fn saturated_counters_are_never_decremented() {
+        let config = BloomFilterConfig::builder()
+            .expected_items(1)
+            .false_positive_rate(0.5)
+            .build();
+        let mut counters = Counters::new(&config).unwrap();
+        let code = ShortCode::new_unchecked("abc123");
+
+        for _ in 0..20 {
+            counters.increment(&code);
+        }
+        for _ in 0..20 {
+            counters.decrement(&code);
+        }
+
+        assert!(counters.contains(&code));
+    }
+
+    #[test]
+    fn rejects_invalid_config() {
+        let config = BloomFilterConfig::builder()
+            .expected_items(0)
+            .false_positive_rate(0.01)
+            .build();
+        assert!(Counters::new(&config).is_err());
+
+        let config = BloomFilterConfig::builder()
+            .expected_items(10)
+            .false_positive_rate(1.0)
+            .build();
+        assert!(Counters::new(&config).is_err());
+    }
+}
@@ -1,4 +1,5 @@
 use thiserror::Error;
+use wormhole_core::UrlRecord;
 
 /// Type alias for cache results.
 pub type Result<T> = std::result::Result<T, CacheError>;
@@ -17,4 +18,9 @@ pub enum CacheError {
     Initialization(String),
     #[error("cache operation failed: {0}")]
     Operation(String),
+    /// Returned by a `get_or_compute` fetch to hand a record back without
+    /// caching it. Caches pass it through to the caller unchanged, and it
+    /// says nothing about the cache's health.
+    #[error("record must not be cached")]
+    Uncacheable(Box<UrlRecord>),
 }
@@ -0,0 +1,193 @@
+use moka::future::Cache;
+use moka::Expiry;
+use std::time::{Duration, Instant};
+use tracing::{debug, trace};
+use wormhole_core::ShortCode;
+
+/// A lightweight in-memory cache of short code existence checks.
+///
+/// Conflict checks during shortening probe many codes that are never read
+/// back. Keeping those answers here, as plain booleans, stops them from
+/// competing for capacity with the full [`UrlRecord`](wormhole_core::UrlRecord)
+/// values held by a [`UrlCache`](crate::UrlCache).
+///
+/// Both positive and negative answers are cached, so a TTL should be set when
+/// codes can be deleted behind the cache's back. Negative answers always
+/// expire, after [`MokaExistenceCache::DEFAULT_NEGATIVE_TTL`] unless set
+/// otherwise, so a code created since it was checked is soon seen.
+#[derive(Debug, Clone)]
+pub struct MokaExistenceCache {
+    cache: Cache<String, bool>,
+    max_capacity: u64,
+    ttl: Option<Duration>,
+}
+
+/// Expires negative answers after `negative_ttl`, on top of any cache-wide
+/// TTL.
+struct NegativeExpiry {
+    negative_ttl: Duration,
+}
+
+impl NegativeExpiry {
+    fn ttl_for(&self, exists: bool) -> Option<Duration> {
+        (!exists).then_some(self.negative_ttl)
+    }
+}
+
+impl Expiry<String, bool> for NegativeExpiry {
+    fn expire_after_create(
+        &self,
+        _key: &String,
+        exists: &bool,
+        _created_at: Instant,
+    ) -> Option<Duration> {
+        self.ttl_for(*exists)
+    }
+
+    fn expire_after_update(
+        &self,
+        _key: &String,
+        exists: &bool,
+        _updated_at: Instant,
+        _duration_until_expiry: Option<Duration>,
+    ) -> Option<Duration> {
+        self.ttl_for(*exists)
+    }
+}
+
+fn build_cache(
+    max_capacity: u64,
+    ttl: Option<Duration>,
+    negative_ttl: Duration,
+) -> Cache<String, bool> {
+    let builder = Cache::builder()
+        .max_capacity(max_capacity)
+        .expire_after(NegativeExpiry { negative_ttl });
+    match ttl {
+        Some(ttl) => builder.time_to_live(ttl).build(),
+        None => builder.build(),
+    }
+}
+
+impl MokaExistenceCache {
+    /// How long a negative answer is cached unless set with
+    /// [`MokaExistenceCache::with_negative_ttl`].
+    pub const DEFAULT_NEGATIVE_TTL: Duration = Duration::from_secs(5);
+
+    /// Creates a new existence cache with a default maximum capacity of 100,000 entries.
+    ///
+    /// Entries are a key and a bool, so the default is larger than that of
+    /// [`MokaUrlCache`](crate::MokaUrlCache).
+    pub fn new() -> Self {
+        Self::with_capacity(100_000)
+    }
+
+    /// Creates a new existence cache with a custom maximum capacity.
+    pub fn with_capacity(max_capacity: u64) -> Self {
+        Self {
+            cache: build_cache(max_capacity, None, Self::DEFAULT_NEGATIVE_TTL),
+            max_capacity,
+            ttl: None,
+        }
+    }
+
+    /// Creates a new existence cache whose entries expire after `ttl`.
+    pub fn with_ttl(max_capacity: u64, ttl: Duration) -> Self {
+        Self {
+            cache: build_cache(max_capacity, Some(ttl), Self::DEFAULT_NEGATIVE_TTL),
+            max_capacity,
+            ttl: Some(ttl),
+        }
+    }
+
+    /// Expires negative answers after `negative_ttl`, or the cache-wide TTL
+    /// if that is shorter.
+    ///
+    /// Call this before the cache is used: answers cached so far are
+    /// dropped.
+    pub fn with_negative_ttl(self, negative_ttl: Duration) -> Self {
+        Self {
+            cache: build_cache(self.max_capacity, self.ttl, negative_ttl),
+            ..self
+        }
+    }
+
+    /// Returns the cached existence of `code`, or `None` on a cache miss.
+    pub async fn get(&self, code: &ShortCode) -> Option<bool> {
+        let exists = self.cache.get(code.as_str()).await;
+        match exists {
+            Some(exists) => debug!(code = %code, exists, "Existence cache hit"),
+            None => trace!(code = %code, "Existence cache miss"),
+        }
+        exists
+    }
+
+    /// Records whether `code` exists.
+    pub async fn insert(&self, code: &ShortCode, exists: bool) {
+        trace!(code = %code, exists, "Caching existence check");
+        self.cache.insert(code.as_str().to_string(), exists).await;
+    }
+
+    /// Removes the cached existence of `code`, if any.
+    pub async fn invalidate(&self, code: &ShortCode) {
+        trace!(code = %code, "Invalidating existence cache entry");
+        self.cache.invalidate(code.as_str()).await;
+    }
+}
+
+impl Default for MokaExistenceCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn caches_positive_and_negative_answers() {
+        let cache = MokaExistenceCache::new();
+        let present = ShortCode::new_unchecked("present");
+        let absent = ShortCode::new_unchecked("absent");
+
+        assert_eq!(cache.get(&present).await, None);
+
+        cache.insert(&present, true).await;
+        cache.insert(&absent, false).await;
+
+        assert_eq!(cache.get(&present).await, Some(true));
+        assert_eq!(cache.get(&absent).await, Some(false));
+    }
+
+    #[tokio::test]
+    async fn negative_answers_expire_after_the_negative_ttl() {
+        let cache = MokaExistenceCache::new().with_negative_ttl(Duration::from_millis(50));
+        let present = ShortCode::new_unchecked("present");
+        let created_later = ShortCode::new_unchecked("later");
+
+        cache.insert(&present, true).await;
+        cache.insert(&created_later, false).await;
+        assert_eq!(cache.get(&created_later).await, Some(false));
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(cache.get(&created_later).await, None);
+        assert_eq!(cache.get(&present).await, Some(true));
+
+        // Turning a positive answer negative gives it the negative TTL.
+        cache.insert(&present, false).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(cache.get(&present).await, None);
+    }
+
+    #[tokio::test]
+    async fn invalidate_removes_entry() {
+        let cache = MokaExistenceCache::new();
+        let code = ShortCode::new_unchecked("abc123");
+
+        cache.insert(&code, true).await;
+        cache.invalidate(&code).await;
+
+        assert_eq!(cache.get(&code).await, None);
+    }
+}
@@ -0,0 +1,198 @@
+//! Cross-service cache invalidation over Redis Pub/Sub.
+//!
+//! The shortener publishes the code of every link it deletes; each
+//! redirector node runs an [`InvalidationSubscriber`] that drops the code
+//! from its cache, so a deleted link stops resolving without waiting for the
+//! cached entry to expire.
+//!
+//! Pub/Sub is fire-and-forget: a node that is disconnected when a message is
+//! published never sees it. Keep a TTL on cached entries to bound how long
+//! such a node can serve a stale record.
+
+use std::fmt::Debug;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use redis::AsyncCommands;
+use tokio::task::JoinHandle;
+use tracing::{debug, trace, warn};
+use wormhole_core::ShortCode;
+
+use crate::{CacheError, Result, UrlCache};
+
+/// Channel used unless another one is configured.
+pub const DEFAULT_INVALIDATION_CHANNEL: &str = "wh:invalidate";
+
+/// How long [`InvalidationSubscriber`] waits before resubscribing after its
+/// connection dropped.
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(1);
+
+/// Tells other services that the cached record of a code is stale.
+#[async_trait]
+pub trait InvalidationPublisher: Debug + Send + Sync + 'static {
+    /// Announces that `code` changed or was deleted.
+    async fn publish_invalidation(&self, code: &ShortCode) -> Result<()>;
+}
+
+/// Publishes invalidations to a Redis Pub/Sub channel.
+#[derive(Clone)]
+pub struct RedisInvalidationPublisher {
+    conn: redis::aio::MultiplexedConnection,
+    channel: String,
+}
+
+impl Debug for RedisInvalidationPublisher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisInvalidationPublisher")
+            .field("channel", &self.channel)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RedisInvalidationPublisher {
+    /// Publishes to [`DEFAULT_INVALIDATION_CHANNEL`].
+    pub fn new(conn: redis::aio::MultiplexedConnection) -> Self {
+        Self::with_channel(conn, DEFAULT_INVALIDATION_CHANNEL)
+    }
+
+    /// Publishes to `channel`, e.g. to keep environments sharing a Redis
+    /// instance apart.
+    pub fn with_channel(
+        conn: redis::aio::MultiplexedConnection,
+        channel: impl Into<String>,
+    ) -> Self {
+        Self {
+            conn,
+            channel: channel.into(),
+        }
+    }
+
+    pub fn channel(&self) -> &str {
+        &self.channel
+    }
+}
+
+#[async_trait]
+impl InvalidationPublisher for RedisInvalidationPublisher {
+    async fn publish_invalidation(&self, code: &ShortCode) -> Result<()> {
+        trace!(code = %code, channel = %self.channel, "Publishing cache invalidation");
+
+        let mut conn = self.conn.clone();
+        let receivers: u64 = conn
+            .publish(&self.channel, code.as_str())
+            .await
+            .map_err(|e| CacheError::Operation(format!("failed to publish invalidation: {e}")))?;
+
+        debug!(code = %code, receivers, "Published cache invalidation");
+        Ok(())
+    }
+}
+
+/// Listens for published invalidations and deletes the codes from `cache`.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use wormhole_cache::invalidation::InvalidationSubscriber;
+/// use wormhole_cache::MokaUrlCache;
+///
+/// # async fn example() -> wormhole_cache::Result<()> {
+/// let client = redis::Client::open("redis://127.0.0.1:6379").unwrap();
+/// let cache = MokaUrlCache::new();
+///
+/// // Returns once subscribed; the listener keeps running in the background.
+/// let listener = InvalidationSubscriber::new(client, cache.clone())
+///     .start()
+///     .await?;
+/// # listener.abort();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct InvalidationSubscriber<C> {
+    client: redis::Client,
+    channel: String,
+    cache: C,
+}
+
+impl<C: UrlCache> InvalidationSubscriber<C> {
+    /// Subscribes to [`DEFAULT_INVALIDATION_CHANNEL`].
+    pub fn new(client: redis::Client, cache: C) -> Self {
+        Self::with_channel(client, cache, DEFAULT_INVALIDATION_CHANNEL)
+    }
+
+    /// Subscribes to `channel`.
+    pub fn with_channel(client: redis::Client, cache: C, channel: impl Into<String>) -> Self {
+        Self {
+            client,
+            channel: channel.into(),
+            cache,
+        }
+    }
+
+    /// Subscribes, then keeps applying invalidations on a background task.
+    ///
+    /// Returns once the subscription is in place, so anything published
+    /// afterwards is seen. If the connection drops, the task resubscribes
+    /// after a short delay; invalidations published in between are lost.
+    /// Abort the returned handle to stop listening.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CacheError::Unavailable` if the first subscription fails.
+    pub async fn start(self) -> Result<JoinHandle<()>> {
+        let first = self.subscribe().await?;
+        Ok(tokio::spawn(async move {
+            let mut pubsub = first;
+            loop {
+                self.listen(pubsub).await;
+                warn!(channel = %self.channel, "Invalidation subscription ended, resubscribing");
+                pubsub = loop {
+                    tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+                    match self.subscribe().await {
+                        Ok(pubsub) => break pubsub,
+                        Err(e) => {
+                            warn!(channel = %self.channel, error = %e, "Failed to resubscribe")
+                        }
+                    }
+                };
+            }
+        }))
+    }
+
+    async fn subscribe(&self) -> Result<redis::aio::PubSub> {
+        let mut pubsub = self.client.get_async_pubsub().await.map_err(|e| {
+            CacheError::Unavailable(format!("failed to open Pub/Sub connection: {e}"))
+        })?;
+        pubsub.subscribe(&self.channel).await.map_err(|e| {
+            CacheError::Unavailable(format!("failed to subscribe to invalidations: {e}"))
+        })?;
+        debug!(channel = %self.channel, "Subscribed to cache invalidations");
+        Ok(pubsub)
+    }
+
+    /// Applies invalidations until the connection drops.
+    async fn listen(&self, pubsub: redis::aio::PubSub) {
+        let mut messages = pubsub.into_on_message();
+        while let Some(message) = messages.next().await {
+            let payload: String = match message.get_payload() {
+                Ok(payload) => payload,
+                Err(e) => {
+                    warn!(error = %e, "Ignoring unreadable invalidation message");
+                    continue;
+                }
+            };
+            let code = ShortCode::new_unchecked(payload);
+            if !code.is_valid() {
+                warn!(code = %code, "Ignoring invalidation for a malformed code");
+                continue;
+            }
+
+            match self.cache.del(&code).await {
+                Ok(()) => debug!(code = %code, "Applied cache invalidation"),
+                Err(e) => warn!(code = %code, error = %e, "Failed to apply cache invalidation"),
+            }
+        }
+    }
+}
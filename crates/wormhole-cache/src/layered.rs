@@ -1,11 +1,68 @@
 use crate::Result;
 use async_trait::async_trait;
+use jiff::Timestamp;
+use std::fmt::{self, Display};
 use std::future::Future;
-use tracing::{debug, trace};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, trace, warn, Instrument, Span};
 use wormhole_core::{ShortCode, UrlRecord};
 
+use crate::metrics;
+use crate::spans::{self, compute_outcome, read_outcome};
 use crate::UrlCache;
 
+/// How [`LayeredCache`] reacts when one of its layers returns an error.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LayerErrorPolicy {
+    /// Any layer error fails the whole operation, except a failed L1
+    /// backfill: once L2 has answered a read, the answer is returned.
+    Strict,
+    /// A failing layer is logged and skipped, so the other layer keeps
+    /// serving.
+    ///
+    /// Reads treat a failed layer as a miss, and a failed L1 backfill is
+    /// ignored. Writes and deletes are attempted on both layers and only fail
+    /// when both layers fail; note that a delete that only reached one layer
+    /// can leave a stale entry in the other until it expires.
+    #[default]
+    Tolerant,
+}
+
+/// How long [`LayeredCache`] keeps a record it copied from L2 into L1.
+///
+/// The TTL is passed to L1's [`UrlCache::set_url_with_ttl`], so it only takes
+/// effect if L1 supports per-entry expiry (e.g. `MokaUrlCache`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BackfillTtl {
+    /// Expire the L1 copy when the record expires (`expire_at`).
+    ///
+    /// Records without an expiry are stored without a TTL, and records that
+    /// have already expired are not backfilled at all.
+    #[default]
+    MirrorExpiry,
+    /// Expire the L1 copy after a fixed duration.
+    Fixed(Duration),
+    /// Store the L1 copy without a TTL.
+    None,
+}
+
+impl BackfillTtl {
+    /// Returns the TTL for the L1 copy of `record`. `Some(Duration::ZERO)`
+    /// means the record must not be backfilled.
+    fn ttl_for(self, record: &UrlRecord) -> Option<Duration> {
+        match self {
+            BackfillTtl::MirrorExpiry => record.expire_at.map(|expire_at| {
+                Duration::try_from(expire_at.duration_since(Timestamp::now()))
+                    .unwrap_or(Duration::ZERO)
+            }),
+            BackfillTtl::Fixed(ttl) => Some(ttl),
+            BackfillTtl::None => None,
+        }
+    }
+}
+
 /// A multi-layer cache that composes two cache implementations.
 ///
 /// This cache implementation provides a two-level caching strategy where
@@ -15,10 +72,18 @@ use crate::UrlCache;
 /// # Operation Strategy
 ///
 /// - **Get**: Try L1 first, if miss try L2. If L2 has the value, populate L1
-///   with it (cache-aside pattern with backfill).
+///   with it (cache-aside pattern with backfill). The L1 copy expires with
+///   the record by default; see [`LayeredCache::with_backfill_ttl`].
 /// - **Set**: Write to both L1 and L2 (write-through pattern).
 /// - **Delete**: Remove from both L1 and L2.
 ///
+/// A single failing layer does not fail the operation by default; see
+/// [`LayerErrorPolicy`] and [`LayeredCache::with_error_policy`].
+///
+/// For more than two layers, use [`MultiLayerCache`](crate::MultiLayerCache)
+/// rather than nesting `LayeredCache`s: nesting works, but every level
+/// reports its lookups as `l1`/`l2` and applies its own error policy.
+///
 /// # Type Parameters
 ///
 /// * `L1` - The primary/faster cache (e.g., `MokaUrlCache`)
@@ -42,6 +107,19 @@ use crate::UrlCache;
 pub struct LayeredCache<L1, L2> {
     l1: L1,
     l2: L2,
+    error_policy: LayerErrorPolicy,
+    backfill_ttl: BackfillTtl,
+    write_behind: Option<WriteBehind>,
+}
+
+/// Spawns L2 writes in the background, see [`LayeredCache::with_write_behind`].
+#[derive(Clone)]
+struct WriteBehind(Arc<dyn Fn(ShortCode, UrlRecord, Option<Duration>) + Send + Sync>);
+
+impl fmt::Debug for WriteBehind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("WriteBehind")
+    }
 }
 
 impl<L1, L2> LayeredCache<L1, L2> {
@@ -52,7 +130,40 @@ impl<L1, L2> LayeredCache<L1, L2> {
     /// * `l1` - The primary/faster cache
     /// * `l2` - The secondary/slower cache
     pub fn new(l1: L1, l2: L2) -> Self {
-        Self { l1, l2 }
+        Self {
+            l1,
+            l2,
+            error_policy: LayerErrorPolicy::default(),
+            backfill_ttl: BackfillTtl::default(),
+            write_behind: None,
+        }
+    }
+
+    /// Sets how errors from an individual layer are handled.
+    pub fn with_error_policy(mut self, error_policy: LayerErrorPolicy) -> Self {
+        self.error_policy = error_policy;
+        self
+    }
+
+    /// Returns the configured layer error policy.
+    pub fn error_policy(&self) -> LayerErrorPolicy {
+        self.error_policy
+    }
+
+    /// Sets how long records backfilled from L2 stay in L1.
+    pub fn with_backfill_ttl(mut self, backfill_ttl: BackfillTtl) -> Self {
+        self.backfill_ttl = backfill_ttl;
+        self
+    }
+
+    /// Returns the configured backfill TTL policy.
+    pub fn backfill_ttl(&self) -> BackfillTtl {
+        self.backfill_ttl
+    }
+
+    /// Returns `true` if L2 writes happen in the background.
+    pub fn is_write_behind(&self) -> bool {
+        self.write_behind.is_some()
     }
 
     /// Returns a reference to the L1 cache.
@@ -69,6 +180,85 @@ impl<L1, L2> LayeredCache<L1, L2> {
     pub fn into_inner(self) -> (L1, L2) {
         (self.l1, self.l2)
     }
+
+    /// Applies the error policy to the result of a single-layer read.
+    fn tolerate_read(
+        &self,
+        layer: &str,
+        code: &ShortCode,
+        result: Result<Option<UrlRecord>>,
+    ) -> Result<Option<UrlRecord>> {
+        match result {
+            Err(e) if self.error_policy == LayerErrorPolicy::Tolerant => {
+                warn!(code = %code, layer, error = %e, "Cache layer read failed, treating as miss");
+                Ok(None)
+            }
+            other => other,
+        }
+    }
+
+    /// Returns the TTL for the L1 copy of a record written with `ttl`: the
+    /// shorter of `ttl` and the backfill policy's TTL, so L1 never keeps a
+    /// record longer than a backfilled copy of it would stay.
+    fn l1_ttl(&self, record: &UrlRecord, ttl: Option<Duration>) -> Option<Duration> {
+        match (ttl, self.backfill_ttl.ttl_for(record)) {
+            (Some(ttl), Some(backfill)) => Some(ttl.min(backfill)),
+            (ttl, backfill) => ttl.or(backfill),
+        }
+    }
+
+    /// Combines the results of applying a write to both layers.
+    fn combine_writes(
+        &self,
+        operation: &str,
+        code: &dyn Display,
+        l1: Result<()>,
+        l2: Result<()>,
+    ) -> Result<()> {
+        match (l1, l2) {
+            (Ok(()), Ok(())) => Ok(()),
+            (Err(e), Ok(())) | (Ok(()), Err(e))
+                if self.error_policy == LayerErrorPolicy::Tolerant =>
+            {
+                warn!(code = %code, operation, error = %e, "Cache layer write failed, other layer succeeded");
+                Ok(())
+            }
+            // Under the strict policy, or when both layers failed, surface the
+            // L2 error first since it is the shared layer other nodes rely on.
+            (_, Err(e)) | (Err(e), _) => Err(e),
+        }
+    }
+}
+
+impl<L1, L2> LayeredCache<L1, L2>
+where
+    L2: UrlCache + Clone,
+{
+    /// Makes `set_url` write L1 synchronously and L2 in a spawned task.
+    ///
+    /// This takes L2 latency off the write path, at the cost of durability:
+    ///
+    /// - Until the background write lands, other nodes reading L2 miss the
+    ///   new value, and it is lost entirely if the process exits first.
+    /// - A failed L2 write is only logged; `set_url` has already succeeded.
+    /// - Background writes are not ordered against later operations, so a
+    ///   write still in flight can land after a subsequent `set_url` or
+    ///   `del` for the same code and leave a stale L2 entry until it expires.
+    ///
+    /// `del` stays synchronous on both layers. Must be used from within a
+    /// Tokio runtime.
+    pub fn with_write_behind(mut self) -> Self {
+        let l2 = self.l2.clone();
+        self.write_behind = Some(WriteBehind(Arc::new(move |code, record, ttl| {
+            let l2 = l2.clone();
+            tokio::spawn(async move {
+                if let Err(e) = l2.set_url_with_ttl(&code, &record, ttl).await {
+                    warn!(code = %code, error = %e, "Write-behind to L2 cache failed");
+                }
+            });
+        })));
+        self
+    }
 }
 
 impl<L1, L2> LayeredCache<L1, L2>
@@ -98,15 +288,42 @@ where
         // Clone for move closure
         let code = code.clone();
         let l2 = &self.l2;
+        let l1_missed = AtomicBool::new(false);
+        let l1_missed_ref = &l1_missed;
+        let l1_span = spans::layer_span("l1", &code);
 
         // Chain single-flight: L1.get_or_compute wraps L2.get_or_compute wraps fetch
         // This ensures both layers' single-flight semantics are respected
-        self.l1
+        let result = self
+            .l1
             .get_or_compute(&code, move |c| {
+                l1_missed_ref.store(true, Ordering::Relaxed);
                 let c = c.clone();
-                async move { l2.get_or_compute(&c, fetch).await }
+                let l2_span = spans::layer_span("l2", &c);
+                async move {
+                    let l2_missed = AtomicBool::new(false);
+                    let l2_missed_ref = &l2_missed;
+                    let result = l2
+                        .get_or_compute(&c, move |c| {
+                            l2_missed_ref.store(true, Ordering::Relaxed);
+                            fetch(c)
+                        })
+                        .await;
+                    Span::current().record(
+                        "outcome",
+                        compute_outcome(&result, l2_missed.load(Ordering::Relaxed)),
+                    );
+                    result
+                }
+                .instrument(l2_span)
             })
-            .await
+            .instrument(l1_span.clone())
+            .await;
+        l1_span.record(
+            "outcome",
+            compute_outcome(&result, l1_missed.load(Ordering::Relaxed)),
+        );
+        result
     }
 }
 
@@ -120,26 +337,43 @@ where
         trace!(code = %code, "Fetching URL record from layered cache");
 
         // Try L1 first
-        match self.l1.get_url(code).await? {
+        let l1_span = spans::layer_span("l1", code);
+        let l1 = self.l1.get_url(code).instrument(l1_span.clone()).await;
+        l1_span.record("outcome", read_outcome(&l1));
+        match self.tolerate_read("l1", code, l1)? {
             Some(record) => {
                 debug!(code = %code, "L1 cache hit");
+                metrics::record_lookup("l1", true);
                 return Ok(Some(record));
             }
             None => {
                 trace!(code = %code, "L1 cache miss, trying L2");
+                metrics::record_lookup("l1", false);
             }
         }
 
         // L1 miss, try L2
-        match self.l2.get_url(code).await? {
+        let l2_span = spans::layer_span("l2", code);
+        let l2 = self.l2.get_url(code).instrument(l2_span.clone()).await;
+        l2_span.record("outcome", read_outcome(&l2));
+        match self.tolerate_read("l2", code, l2)? {
             Some(record) => {
                 debug!(code = %code, "L2 cache hit, backfilling L1");
+                metrics::record_lookup("l2", true);
                 // Backfill L1 with the record from L2 so subsequent reads stay local.
-                self.l1.set_url(code, &record).await?;
+                let ttl = self.backfill_ttl.ttl_for(&record);
+                // The backfill only saves the next read a trip to L2, so its
+                // failure never costs the caller the record.
+                if ttl == Some(Duration::ZERO) {
+                    trace!(code = %code, "Record already expired, skipping L1 backfill");
+                } else if let Err(e) = self.l1.set_url_with_ttl(code, &record, ttl).await {
+                    warn!(code = %code, error = %e, "Failed to backfill L1 cache");
+                }
                 Ok(Some(record))
             }
             None => {
                 trace!(code = %code, "L2 cache miss");
+                metrics::record_lookup("l2", false);
                 Ok(None)
             }
         }
@@ -148,15 +382,64 @@ where
     async fn set_url(&self, code: &ShortCode, record: &UrlRecord) -> Result<()> {
         trace!(code = %code, "Storing URL record in layered cache");
 
+        if let Some(write_behind) = &self.write_behind {
+            self.l1.set_url(code, record).await?;
+            (write_behind.0)(code.clone(), record.clone(), None);
+            debug!(code = %code, "Stored in L1, L2 write scheduled");
+            return Ok(());
+        }
+
         // Write to L2 first (slower, more durable), then L1
-        self.l2.set_url(code, record).await?;
-        debug!(code = %code, "Stored in L2 cache");
+        let l2 = self.l2.set_url(code, record).await;
+        if l2.is_err() && self.error_policy == LayerErrorPolicy::Strict {
+            return l2;
+        }
 
         // Also write to L1
-        self.l1.set_url(code, record).await?;
-        debug!(code = %code, "Stored in L1 cache");
+        let l1 = self.l1.set_url(code, record).await;
+        debug!(code = %code, "Stored in layered cache");
 
-        Ok(())
+        self.combine_writes("set", code, l1, l2)
+    }
+
+    /// Stores the record in L2 with `ttl`, and in L1 with the shorter of
+    /// `ttl` and the [`BackfillTtl`] policy's TTL.
+    ///
+    /// A record the policy says not to keep in L1 at all (it has already
+    /// expired) is removed from L1 instead, so no older copy lingers there.
+    async fn set_url_with_ttl(
+        &self,
+        code: &ShortCode,
+        record: &UrlRecord,
+        ttl: Option<Duration>,
+    ) -> Result<()> {
+        trace!(code = %code, ?ttl, "Storing URL record in layered cache with TTL");
+
+        let l1_ttl = self.l1_ttl(record, ttl);
+        let write_l1 = || async move {
+            if l1_ttl == Some(Duration::ZERO) {
+                self.l1.del(code).await
+            } else {
+                self.l1.set_url_with_ttl(code, record, l1_ttl).await
+            }
+        };
+
+        if let Some(write_behind) = &self.write_behind {
+            write_l1().await?;
+            (write_behind.0)(code.clone(), record.clone(), ttl);
+            debug!(code = %code, "Stored in L1, L2 write scheduled");
+            return Ok(());
+        }
+
+        let l2 = self.l2.set_url_with_ttl(code, record, ttl).await;
+        if l2.is_err() && self.error_policy == LayerErrorPolicy::Strict {
+            return l2;
+        }
+
+        let l1 = write_l1().await;
+        debug!(code = %code, "Stored in layered cache");
+
+        self.combine_writes("set", code, l1, l2)
     }
 
     async fn del(&self, code: &ShortCode) -> Result<()> {
@@ -164,13 +447,47 @@ where
 
         // Delete from both caches
         // We delete from L1 first (fast), then L2
-        self.l1.del(code).await?;
-        debug!(code = %code, "Removed from L1 cache");
+        let l1 = self.l1.del(code).await;
+        if l1.is_err() && self.error_policy == LayerErrorPolicy::Strict {
+            return l1;
+        }
 
-        self.l2.del(code).await?;
-        debug!(code = %code, "Removed from L2 cache");
+        let l2 = self.l2.del(code).await;
+        debug!(code = %code, "Removed from layered cache");
 
-        Ok(())
+        self.combine_writes("del", code, l1, l2)
+    }
+
+    async fn del_many(&self, codes: &[ShortCode]) -> Result<()> {
+        trace!(
+            count = codes.len(),
+            "Removing URL records from layered cache"
+        );
+
+        let l1 = self.l1.del_many(codes).await;
+        if l1.is_err() && self.error_policy == LayerErrorPolicy::Strict {
+            return l1;
+        }
+
+        let l2 = self.l2.del_many(codes).await;
+        debug!(count = codes.len(), "Removed from layered cache");
+
+        let target = format!("{} codes", codes.len());
+        self.combine_writes("del_many", &target, l1, l2)
+    }
+
+    async fn clear(&self) -> Result<()> {
+        trace!("Clearing layered cache");
+
+        let l1 = self.l1.clear().await;
+        if l1.is_err() && self.error_policy == LayerErrorPolicy::Strict {
+            return l1;
+        }
+
+        let l2 = self.l2.clear().await;
+        debug!("Cleared layered cache");
+
+        self.combine_writes("clear", &"all codes", l1, l2)
     }
 
     async fn get_or_compute<F, Fut>(&self, code: &ShortCode, fetch: F) -> Result<Option<UrlRecord>>
@@ -186,13 +503,20 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::CacheError;
     use crate::MokaUrlCache;
-    use jiff::Timestamp;
+    use wormhole_core::RedirectKind;
 
     fn test_record(url: &str) -> UrlRecord {
         UrlRecord {
             original_url: url.to_string(),
             expire_at: None,
+            redirect_kind: RedirectKind::default(),
+            created_at: Timestamp::now(),
+            internal_only: false,
+            no_store: false,
+            owner_id: None,
+            metadata: Default::default(),
         }
     }
 
@@ -200,6 +524,25 @@ mod tests {
         ShortCode::new_unchecked(s)
     }
 
+    /// A cache layer whose every operation fails.
+    #[derive(Debug, Clone)]
+    struct FailingCache;
+
+    #[async_trait]
+    impl UrlCache for FailingCache {
+        async fn get_url(&self, _code: &ShortCode) -> Result<Option<UrlRecord>> {
+            Err(CacheError::Unavailable("layer down".to_string()))
+        }
+
+        async fn set_url(&self, _code: &ShortCode, _record: &UrlRecord) -> Result<()> {
+            Err(CacheError::Unavailable("layer down".to_string()))
+        }
+
+        async fn del(&self, _code: &ShortCode) -> Result<()> {
+            Err(CacheError::Unavailable("layer down".to_string()))
+        }
+    }
+
     fn create_test_cache() -> LayeredCache<MokaUrlCache, MokaUrlCache> {
         let l1 = MokaUrlCache::with_capacity(100);
         let l2 = MokaUrlCache::with_capacity(100);
@@ -272,6 +615,114 @@ mod tests {
         assert!(cache.l2.get_url(&c).await.unwrap().is_none());
     }
 
+    #[tokio::test]
+    async fn failing_l1_falls_through_to_l2() {
+        let l2 = MokaUrlCache::with_capacity(100);
+        let cache = LayeredCache::new(FailingCache, l2.clone());
+        let c = code("abc123");
+        let record = test_record("https://example.com");
+
+        l2.set_url(&c, &record).await.unwrap();
+
+        assert_eq!(cache.get_url(&c).await.unwrap(), Some(record.clone()));
+        // Writes and deletes still reach the healthy layer.
+        cache.set_url(&c, &record).await.unwrap();
+        cache.del(&c).await.unwrap();
+        assert!(l2.get_url(&c).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn failing_l2_does_not_break_l1() {
+        let l1 = MokaUrlCache::with_capacity(100);
+        let cache = LayeredCache::new(l1.clone(), FailingCache);
+        let c = code("abc123");
+        let record = test_record("https://example.com");
+
+        cache.set_url(&c, &record).await.unwrap();
+        assert_eq!(cache.get_url(&c).await.unwrap(), Some(record));
+
+        // An L1 miss with L2 down reads as a miss rather than an error.
+        assert!(cache.get_url(&code("missing")).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn strict_policy_propagates_layer_errors() {
+        let cache = LayeredCache::new(FailingCache, MokaUrlCache::with_capacity(100))
+            .with_error_policy(LayerErrorPolicy::Strict);
+        let c = code("abc123");
+
+        assert!(cache.get_url(&c).await.is_err());
+        assert!(cache
+            .set_url(&c, &test_record("https://example.com"))
+            .await
+            .is_err());
+        assert!(cache.del(&c).await.is_err());
+    }
+
+    /// An L1 that reads as empty but rejects every write.
+    #[derive(Debug, Clone)]
+    struct ReadOnlyCache;
+
+    #[async_trait]
+    impl UrlCache for ReadOnlyCache {
+        async fn get_url(&self, _code: &ShortCode) -> Result<Option<UrlRecord>> {
+            Ok(None)
+        }
+
+        async fn set_url(&self, _code: &ShortCode, _record: &UrlRecord) -> Result<()> {
+            Err(CacheError::Operation("read-only".to_string()))
+        }
+
+        async fn del(&self, _code: &ShortCode) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_l1_backfill_still_returns_the_l2_record() {
+        let l2 = MokaUrlCache::with_capacity(100);
+        let c = code("abc123");
+        let record = test_record("https://example.com");
+        l2.set_url(&c, &record).await.unwrap();
+
+        for policy in [LayerErrorPolicy::Tolerant, LayerErrorPolicy::Strict] {
+            let cache = LayeredCache::new(ReadOnlyCache, l2.clone()).with_error_policy(policy);
+
+            assert_eq!(
+                cache.get_url(&c).await.unwrap(),
+                Some(record.clone()),
+                "{policy:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_l1_write_after_l2_write_follows_the_policy() {
+        let l2 = MokaUrlCache::with_capacity(100);
+        let c = code("abc123");
+        let record = test_record("https://example.com");
+
+        let tolerant = LayeredCache::new(ReadOnlyCache, l2.clone());
+        tolerant.set_url(&c, &record).await.unwrap();
+        assert_eq!(l2.get_url(&c).await.unwrap(), Some(record.clone()));
+
+        let strict = LayeredCache::new(ReadOnlyCache, l2.clone())
+            .with_error_policy(LayerErrorPolicy::Strict);
+        assert!(strict.set_url(&c, &record).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn tolerant_policy_fails_when_both_layers_fail() {
+        let cache = LayeredCache::new(FailingCache, FailingCache);
+        let c = code("abc123");
+
+        assert!(cache
+            .set_url(&c, &test_record("https://example.com"))
+            .await
+            .is_err());
+        assert!(cache.del(&c).await.is_err());
+    }
+
     #[tokio::test]
     async fn layered_cache_miss_when_both_empty() {
         let cache = create_test_cache();
@@ -292,6 +743,12 @@ mod tests {
         let record = UrlRecord {
             original_url: "https://example.com".to_string(),
             expire_at: Some(future_time),
+            redirect_kind: RedirectKind::default(),
+            created_at: Timestamp::now(),
+            internal_only: false,
+            no_store: false,
+            owner_id: None,
+            metadata: Default::default(),
         };
 
         // Insert only into L2
@@ -425,4 +882,238 @@ mod tests {
             "https://example.com"
         );
     }
+
+    /// An L2 whose writes take a while to land.
+    #[derive(Debug, Clone)]
+    struct SlowCache {
+        inner: MokaUrlCache,
+        delay: std::time::Duration,
+    }
+
+    #[async_trait]
+    impl UrlCache for SlowCache {
+        async fn get_url(&self, code: &ShortCode) -> Result<Option<UrlRecord>> {
+            self.inner.get_url(code).await
+        }
+
+        async fn set_url(&self, code: &ShortCode, record: &UrlRecord) -> Result<()> {
+            tokio::time::sleep(self.delay).await;
+            self.inner.set_url(code, record).await
+        }
+
+        async fn del(&self, code: &ShortCode) -> Result<()> {
+            self.inner.del(code).await
+        }
+    }
+
+    #[tokio::test]
+    async fn write_behind_populates_l2_after_returning() {
+        let l1 = MokaUrlCache::new();
+        let l2 = MokaUrlCache::new();
+        let slow = SlowCache {
+            inner: l2.clone(),
+            delay: std::time::Duration::from_millis(50),
+        };
+        let cache = LayeredCache::new(l1.clone(), slow).with_write_behind();
+        assert!(cache.is_write_behind());
+        let c = code("behind");
+        let record = test_record("https://example.com");
+
+        cache.set_url(&c, &record).await.unwrap();
+
+        assert_eq!(l1.get_url(&c).await.unwrap(), Some(record.clone()));
+        assert!(l2.get_url(&c).await.unwrap().is_none());
+
+        let mut landed = false;
+        for _ in 0..50 {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            if l2.get_url(&c).await.unwrap().is_some() {
+                landed = true;
+                break;
+            }
+        }
+        assert!(landed, "L2 write never landed");
+    }
+
+    #[tokio::test]
+    async fn write_behind_ignores_l2_failures_and_keeps_del_synchronous() {
+        let l1 = MokaUrlCache::new();
+        let cache = LayeredCache::new(l1.clone(), FailingCache).with_write_behind();
+        let c = code("behind");
+
+        cache
+            .set_url(&c, &test_record("https://example.com"))
+            .await
+            .unwrap();
+        assert!(l1.get_url(&c).await.unwrap().is_some());
+
+        // Deletes still reach L2 synchronously, and its failure is tolerated.
+        cache.del(&c).await.unwrap();
+        assert!(l1.get_url(&c).await.unwrap().is_none());
+    }
+
+    /// Records the TTL of every `set_url_with_ttl` call.
+    #[derive(Debug, Clone, Default)]
+    struct TtlRecordingCache {
+        inner: MokaUrlCache,
+        ttls: Arc<parking_lot::Mutex<Vec<Option<Duration>>>>,
+    }
+
+    #[async_trait]
+    impl UrlCache for TtlRecordingCache {
+        async fn get_url(&self, code: &ShortCode) -> Result<Option<UrlRecord>> {
+            self.inner.get_url(code).await
+        }
+
+        async fn set_url(&self, code: &ShortCode, record: &UrlRecord) -> Result<()> {
+            self.set_url_with_ttl(code, record, None).await
+        }
+
+        async fn set_url_with_ttl(
+            &self,
+            code: &ShortCode,
+            record: &UrlRecord,
+            ttl: Option<Duration>,
+        ) -> Result<()> {
+            self.ttls.lock().push(ttl);
+            self.inner.set_url(code, record).await
+        }
+
+        async fn del(&self, code: &ShortCode) -> Result<()> {
+            self.inner.del(code).await
+        }
+    }
+
+    async fn backfill_ttls(
+        policy: BackfillTtl,
+        record: &UrlRecord,
+    ) -> (Vec<Option<Duration>>, bool) {
+        let l1 = TtlRecordingCache::default();
+        let cache = LayeredCache::new(l1.clone(), MokaUrlCache::with_capacity(100))
+            .with_backfill_ttl(policy);
+        let c = code("abc123");
+        // Without a TTL, so L2 keeps the record even if it has already
+        // expired; plain `set_url` would drop it.
+        cache.l2.set_url_with_ttl(&c, record, None).await.unwrap();
+
+        assert_eq!(cache.get_url(&c).await.unwrap().as_ref(), Some(record));
+        let backfilled = l1.inner.get_url(&c).await.unwrap().is_some();
+        let ttls = l1.ttls.lock().clone();
+        (ttls, backfilled)
+    }
+
+    #[tokio::test]
+    async fn mirror_expiry_backfill_ttl_follows_expire_at() {
+        let mut record = test_record("https://example.com");
+        record.expire_at = Some(Timestamp::now() + jiff::SignedDuration::from_secs(60));
+
+        let (ttls, backfilled) = backfill_ttls(BackfillTtl::MirrorExpiry, &record).await;
+        assert!(backfilled);
+        let ttl = ttls[0].expect("expiring record should get a TTL");
+        assert!(ttl > Duration::from_secs(55) && ttl <= Duration::from_secs(60));
+
+        let (ttls, _) =
+            backfill_ttls(BackfillTtl::MirrorExpiry, &test_record("https://a.example")).await;
+        assert_eq!(ttls, vec![None]);
+    }
+
+    #[tokio::test]
+    async fn mirror_expiry_skips_backfill_of_expired_records() {
+        let mut record = test_record("https://example.com");
+        record.expire_at = Some(Timestamp::now() - jiff::SignedDuration::from_secs(1));
+
+        let (ttls, backfilled) = backfill_ttls(BackfillTtl::MirrorExpiry, &record).await;
+        assert!(ttls.is_empty());
+        assert!(!backfilled);
+    }
+
+    #[tokio::test]
+    async fn fixed_backfill_ttl_ignores_expire_at() {
+        let mut record = test_record("https://example.com");
+        record.expire_at = Some(Timestamp::now() + jiff::SignedDuration::from_secs(3600));
+
+        let ttl = Duration::from_secs(30);
+        let (ttls, backfilled) = backfill_ttls(BackfillTtl::Fixed(ttl), &record).await;
+        assert!(backfilled);
+        assert_eq!(ttls, vec![Some(ttl)]);
+    }
+
+    #[tokio::test]
+    async fn no_backfill_ttl_stores_without_ttl() {
+        let mut record = test_record("https://example.com");
+        record.expire_at = Some(Timestamp::now() + jiff::SignedDuration::from_secs(60));
+
+        let (ttls, backfilled) = backfill_ttls(BackfillTtl::None, &record).await;
+        assert!(backfilled);
+        assert_eq!(ttls, vec![None]);
+    }
+
+    #[tokio::test]
+    async fn set_url_with_ttl_reaches_both_layers() {
+        let l1 = TtlRecordingCache::default();
+        let l2 = TtlRecordingCache::default();
+        let cache = LayeredCache::new(l1.clone(), l2.clone())
+            .with_backfill_ttl(BackfillTtl::Fixed(Duration::from_secs(30)));
+        let record = test_record("https://example.com");
+
+        let ttl = Duration::from_secs(60);
+        cache
+            .set_url_with_ttl(&code("long"), &record, Some(ttl))
+            .await
+            .unwrap();
+        // L1 keeps the record no longer than the backfill policy allows.
+        assert_eq!(*l2.ttls.lock(), vec![Some(ttl)]);
+        assert_eq!(*l1.ttls.lock(), vec![Some(Duration::from_secs(30))]);
+
+        let ttl = Duration::from_secs(10);
+        cache
+            .set_url_with_ttl(&code("short"), &record, Some(ttl))
+            .await
+            .unwrap();
+        assert_eq!(l2.ttls.lock()[1], Some(ttl));
+        assert_eq!(l1.ttls.lock()[1], Some(ttl));
+    }
+
+    #[tokio::test]
+    async fn set_url_with_ttl_keeps_expired_records_out_of_l1() {
+        let l1 = TtlRecordingCache::default();
+        let cache = LayeredCache::new(l1.clone(), MokaUrlCache::with_capacity(100));
+        let c = code("abc123");
+        let mut record = test_record("https://example.com");
+        l1.inner.set_url(&c, &record).await.unwrap();
+
+        record.expire_at = Some(Timestamp::now() - jiff::SignedDuration::from_secs(1));
+        cache
+            .set_url_with_ttl(&c, &record, Some(Duration::from_secs(60)))
+            .await
+            .unwrap();
+        assert!(l1.ttls.lock().is_empty());
+        assert!(l1.inner.get_url(&c).await.unwrap().is_none());
+        assert!(cache.l2.get_url(&c).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn clear_empties_both_layers() {
+        let cache = create_test_cache();
+        let c = code("abc123");
+        cache
+            .set_url(&c, &test_record("https://example.com"))
+            .await
+            .unwrap();
+
+        cache.clear().await.unwrap();
+
+        assert!(cache.l1.get_url(&c).await.unwrap().is_none());
+        assert!(cache.l2.get_url(&c).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn clear_fails_only_when_both_layers_fail() {
+        // `FailingCache` keeps the default `clear`, which is unsupported.
+        let cache = LayeredCache::new(MokaUrlCache::with_capacity(100), FailingCache);
+        cache.clear().await.unwrap();
+
+        let strict = cache.with_error_policy(LayerErrorPolicy::Strict);
+        assert!(strict.clear().await.is_err());
+    }
 }
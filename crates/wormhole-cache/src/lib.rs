@@ -2,16 +2,42 @@
 
 pub mod bloom_filter;
 pub mod cache;
+pub mod circuit_breaker;
+pub mod codec;
+pub mod compressing;
+pub mod counting_bloom_filter;
 pub mod error;
+pub mod existence;
+pub mod invalidation;
 pub mod layered;
+mod metrics;
 pub mod moka;
+pub mod multi_layer;
+pub mod namespaced;
+pub mod partitioned;
 pub mod redis;
+pub mod redis_cluster;
 pub mod redis_ha;
+pub mod redis_pooled;
+mod spans;
+pub mod ttl;
 
-pub use bloom_filter::{BloomFilter, BloomFilterConfig};
+pub use bloom_filter::{BloomFilter, BloomFilterConfig, FilterCoverage};
 pub use cache::UrlCache;
+pub use circuit_breaker::{BreakerState, CircuitBreakerCache, CircuitBreakerConfig};
+pub use codec::{CacheCodec, JsonCodec, MsgPackCodec};
+pub use compressing::CompressingCache;
+pub use counting_bloom_filter::CountingBloomFilter;
 pub use error::{CacheError, Result};
-pub use layered::LayeredCache;
-pub use moka::MokaUrlCache;
-pub use redis::RedisUrlCache;
+pub use existence::MokaExistenceCache;
+pub use invalidation::{InvalidationPublisher, InvalidationSubscriber, RedisInvalidationPublisher};
+pub use layered::{BackfillTtl, LayerErrorPolicy, LayeredCache};
+pub use moka::{CacheLookup, EvictionCause, EvictionListener, MokaUrlCache};
+pub use multi_layer::{DynUrlCache, MultiLayerCache, MultiLayerCacheBuilder};
+pub use namespaced::NamespacedCache;
+pub use partitioned::{PartitionedCache, PartitionedCacheConfig};
+pub use redis::{RedisCacheConfig, RedisUrlCache, SingleFlightConfig};
+pub use redis_cluster::RedisClusterUrlCache;
 pub use redis_ha::RedisHAUrlCache;
+pub use redis_pooled::{PooledRedisConfig, PooledRedisUrlCache};
+pub use ttl::TtlPolicy;
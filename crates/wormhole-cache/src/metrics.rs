@@ -0,0 +1,10 @@
+//! Hooks into `wormhole-metrics` that compile to nothing unless the `metrics`
+//! feature is enabled.
+
+#[cfg(feature = "metrics")]
+pub(crate) fn record_lookup(layer: &str, hit: bool) {
+    wormhole_metrics::metrics().record_cache_lookup(layer, hit);
+}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) fn record_lookup(_layer: &str, _hit: bool) {}
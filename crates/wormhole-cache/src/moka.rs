@@ -1,13 +1,133 @@
 use async_trait::async_trait;
-use moka::future::Cache;
+use moka::future::{Cache, CacheBuilder};
+use moka::notification::RemovalCause;
+use moka::Expiry;
+use std::fmt;
 use std::future::Future;
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tracing::{debug, trace};
 use typed_builder::TypedBuilder;
 use wormhole_core::{ShortCode, UrlRecord};
 
+use crate::ttl::remaining_lifetime;
 use crate::{Result, UrlCache};
 
+/// Why an entry left a [`MokaUrlCache`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvictionCause {
+    /// The entry's time-to-live or time-to-idle elapsed.
+    Expired,
+    /// The entry was evicted, or never admitted, to stay within capacity.
+    Size,
+    /// The entry was removed by [`UrlCache::del`] or
+    /// [`MokaUrlCache::invalidate_all`].
+    Explicit,
+}
+
+/// Callback invoked when an entry is evicted from a [`MokaUrlCache`].
+///
+/// Entries overwritten by [`UrlCache::set_url`] are not reported. The
+/// callback runs on the thread that triggered the eviction, so it should be
+/// cheap, e.g. bumping a metric.
+#[derive(Clone)]
+pub struct EvictionListener(Arc<dyn Fn(ShortCode, EvictionCause) + Send + Sync + 'static>);
+
+impl EvictionListener {
+    /// Wraps `listener` for use with a [`MokaUrlCache`].
+    pub fn new(listener: impl Fn(ShortCode, EvictionCause) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(listener))
+    }
+
+    fn attach(self, builder: MokaBuilder) -> MokaBuilder {
+        builder.eviction_listener(move |key: Arc<String>, _, cause| {
+            let cause = match cause {
+                RemovalCause::Expired => EvictionCause::Expired,
+                RemovalCause::Size => EvictionCause::Size,
+                RemovalCause::Explicit => EvictionCause::Explicit,
+                RemovalCause::Replaced => return,
+            };
+            (self.0)(ShortCode::new_unchecked(key.as_str()), cause);
+        })
+    }
+}
+
+impl fmt::Debug for EvictionListener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EvictionListener").finish_non_exhaustive()
+    }
+}
+
+/// A cached value.
+#[derive(Debug, Clone)]
+struct Entry {
+    /// `None` caches a miss from `get_or_compute`.
+    record: Option<UrlRecord>,
+    /// Per-entry time-to-live, on top of any cache-wide TTL/TTI.
+    ttl: Option<Duration>,
+}
+
+/// Expires entries after their own [`Entry::ttl`], if any.
+struct EntryExpiry;
+
+impl Expiry<String, Entry> for EntryExpiry {
+    fn expire_after_create(
+        &self,
+        _key: &String,
+        value: &Entry,
+        _created_at: Instant,
+    ) -> Option<Duration> {
+        value.ttl
+    }
+
+    fn expire_after_update(
+        &self,
+        _key: &String,
+        value: &Entry,
+        _updated_at: Instant,
+        _duration_until_expiry: Option<Duration>,
+    ) -> Option<Duration> {
+        // An overwrite replaces the previous entry's TTL, including removing it.
+        value.ttl
+    }
+}
+
+type MokaBuilder = CacheBuilder<String, Entry, Cache<String, Entry>>;
+
+/// Returns a cache builder that honors per-entry TTLs.
+fn cache_builder() -> MokaBuilder {
+    Cache::builder().expire_after(EntryExpiry)
+}
+
+/// Weighs an entry by its key plus the JSON size of its record.
+///
+/// Cached misses weigh just their key. Moka weights are `u32`, so a record
+/// larger than 4 GiB is counted as 4 GiB, and the JSON size only
+/// approximates the entry's real footprint in memory.
+#[allow(
+    clippy::ptr_arg,
+    reason = "passed to `weigher`, which requires `Fn(&String, &Entry) -> u32`"
+)]
+fn entry_weight(key: &String, entry: &Entry) -> u32 {
+    let record = entry
+        .record
+        .as_ref()
+        .and_then(|record| serde_json::to_vec(record).ok())
+        .map_or(0, |json| json.len());
+    u32::try_from(key.len() + record).unwrap_or(u32::MAX).max(1)
+}
+
+/// What [`MokaUrlCache::lookup`] found for a code.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CacheLookup {
+    /// Nothing is cached; the code has to be looked up elsewhere.
+    NotCached,
+    /// The code is cached as known to be absent.
+    Absent,
+    /// The record is cached.
+    Found(UrlRecord),
+}
+
 /// An in-memory cache implementation using Moka.
 ///
 /// This implementation stores URL records in a concurrent, high-performance
@@ -15,8 +135,7 @@ use crate::{Result, UrlCache};
 /// in front of Redis.
 #[derive(Debug, Clone)]
 pub struct MokaUrlCache {
-    // Use Option<UrlRecord> to properly handle "not found" cases in single-flight
-    cache: Cache<String, Option<UrlRecord>>,
+    cache: Cache<String, Entry>,
 }
 
 impl MokaUrlCache {
@@ -24,7 +143,7 @@ impl MokaUrlCache {
     ///
     /// The cache will have a default maximum capacity of 10,000 entries.
     pub fn new() -> Self {
-        let cache = Cache::builder().max_capacity(10_000).build();
+        let cache = cache_builder().max_capacity(10_000).build();
         Self { cache }
     }
 
@@ -34,7 +153,26 @@ impl MokaUrlCache {
     ///
     /// * `max_capacity` - Maximum number of entries the cache can hold
     pub fn with_capacity(max_capacity: u64) -> Self {
-        let cache = Cache::builder().max_capacity(max_capacity).build();
+        let cache = cache_builder().max_capacity(max_capacity).build();
+        Self { cache }
+    }
+
+    /// Creates a new Moka URL cache bounded by size in bytes rather than
+    /// entry count.
+    ///
+    /// Each entry weighs its key plus its record serialized as JSON, so a
+    /// few kilobyte-long URLs take the room of many short ones. The weight
+    /// is best-effort: Moka caps weights at `u32::MAX`, and JSON size is only
+    /// a proxy for memory use. Weighing serializes each record on insert.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_weight` - Maximum total weight, in bytes
+    pub fn with_weigher(max_weight: u64) -> Self {
+        let cache = cache_builder()
+            .weigher(entry_weight)
+            .max_capacity(max_weight)
+            .build();
         Self { cache }
     }
 
@@ -47,7 +185,7 @@ impl MokaUrlCache {
     /// * `max_capacity` - Maximum number of entries the cache can hold
     /// * `ttl` - Time-to-live for cache entries
     pub fn with_ttl(max_capacity: u64, ttl: Duration) -> Self {
-        let cache = Cache::builder()
+        let cache = cache_builder()
             .max_capacity(max_capacity)
             .time_to_live(ttl)
             .build();
@@ -63,17 +201,92 @@ impl MokaUrlCache {
     /// * `max_capacity` - Maximum number of entries the cache can hold
     /// * `tti` - Time-to-idle for cache entries
     pub fn with_tti(max_capacity: u64, tti: Duration) -> Self {
-        let cache = Cache::builder()
+        let cache = cache_builder()
             .max_capacity(max_capacity)
             .time_to_idle(tti)
             .build();
         Self { cache }
     }
 
+    /// Creates a new Moka URL cache that reports evictions to `listener`.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_capacity` - Maximum number of entries the cache can hold
+    /// * `listener` - Called with the evicted code and the [`EvictionCause`]
+    pub fn with_eviction_listener(
+        max_capacity: u64,
+        listener: impl Fn(ShortCode, EvictionCause) + Send + Sync + 'static,
+    ) -> Self {
+        let builder = cache_builder().max_capacity(max_capacity);
+        let cache = EvictionListener::new(listener).attach(builder).build();
+        Self { cache }
+    }
+
     /// Returns a builder for creating a custom cache configuration.
     pub fn builder() -> CacheConfigBuilder {
         CacheConfig::builder()
     }
+
+    /// Applies pending evictions so that capacity assertions are deterministic.
+    #[cfg(test)]
+    pub(crate) async fn run_pending_tasks(&self) {
+        self.cache.run_pending_tasks().await;
+    }
+
+    /// Returns the number of entries in the cache.
+    ///
+    /// Moka applies inserts and evictions lazily, so its raw count is only
+    /// approximate until pending tasks are flushed. This flushes them first;
+    /// the result can still drift under concurrent writes.
+    pub async fn entry_count(&self) -> u64 {
+        self.cache.run_pending_tasks().await;
+        self.cache.entry_count()
+    }
+
+    /// Returns the total weight of all entries, after flushing pending tasks.
+    ///
+    /// Without a weigher every entry weighs 1, so this equals
+    /// [`MokaUrlCache::entry_count`].
+    pub async fn weighted_size(&self) -> u64 {
+        self.cache.run_pending_tasks().await;
+        self.cache.weighted_size()
+    }
+
+    /// Returns the configured maximum capacity, or `None` if unbounded.
+    pub fn max_capacity(&self) -> Option<u64> {
+        self.cache.policy().max_capacity()
+    }
+
+    /// Caches `code` as known to be absent, for `ttl` if given.
+    ///
+    /// [`UrlCache::get_url`] reads such a tombstone as a miss, and
+    /// `get_or_compute` returns `None` for it without fetching; use
+    /// [`MokaUrlCache::lookup`] to tell it apart from an uncached code.
+    pub async fn set_absent(&self, code: &ShortCode, ttl: Option<Duration>) {
+        trace!(code = %code, ?ttl, "Caching absent code in Moka");
+        let entry = Entry { record: None, ttl };
+        self.cache.insert(code.as_str().to_string(), entry).await;
+    }
+
+    /// Looks up `code`, telling an uncached code apart from one cached as
+    /// absent.
+    pub async fn lookup(&self, code: &ShortCode) -> CacheLookup {
+        match self.cache.get(code.as_str()).await {
+            Some(Entry {
+                record: Some(record),
+                ..
+            }) => CacheLookup::Found(record),
+            Some(Entry { record: None, .. }) => CacheLookup::Absent,
+            None => CacheLookup::NotCached,
+        }
+    }
+
+    /// Removes every entry from the cache.
+    pub async fn invalidate_all(&self) {
+        self.cache.invalidate_all();
+        self.cache.run_pending_tasks().await;
+    }
 }
 
 impl Default for MokaUrlCache {
@@ -89,9 +302,9 @@ impl UrlCache for MokaUrlCache {
 
         let key = code.as_str().to_string();
         match self.cache.get(&key).await {
-            Some(record) => {
+            Some(entry) => {
                 debug!(code = %code, "Cache hit in Moka");
-                Ok(record)
+                Ok(entry.record)
             }
             None => {
                 trace!(code = %code, "Cache miss in Moka");
@@ -100,11 +313,45 @@ impl UrlCache for MokaUrlCache {
         }
     }
 
+    /// Reports whether a record is cached for `code`.
+    ///
+    /// A bare `contains_key` would also count cached misses, so the entry is
+    /// read to tell a record apart from a tombstone.
+    async fn exists(&self, code: &ShortCode) -> Result<bool> {
+        let key = code.as_str();
+        if !self.cache.contains_key(key) {
+            return Ok(false);
+        }
+        Ok(self
+            .cache
+            .get(key)
+            .await
+            .is_some_and(|entry| entry.record.is_some()))
+    }
+
+    /// Keeps the entry no longer than the record's `expire_at`, on top of
+    /// any cache-wide TTL. Records that already expired are not written.
     async fn set_url(&self, code: &ShortCode, record: &UrlRecord) -> Result<()> {
-        trace!(code = %code, "Storing URL record in Moka cache");
+        match remaining_lifetime(record) {
+            Some(Duration::ZERO) => self.del(code).await,
+            ttl => self.set_url_with_ttl(code, record, ttl).await,
+        }
+    }
+
+    async fn set_url_with_ttl(
+        &self,
+        code: &ShortCode,
+        record: &UrlRecord,
+        ttl: Option<Duration>,
+    ) -> Result<()> {
+        trace!(code = %code, ?ttl, "Storing URL record in Moka cache");
 
         let key = code.as_str().to_string();
-        self.cache.insert(key, Some(record.clone())).await;
+        let entry = Entry {
+            record: Some(record.clone()),
+            ttl,
+        };
+        self.cache.insert(key, entry).await;
         debug!(code = %code, "Cached record in Moka");
         Ok(())
     }
@@ -118,6 +365,12 @@ impl UrlCache for MokaUrlCache {
         Ok(())
     }
 
+    async fn clear(&self) -> Result<()> {
+        self.invalidate_all().await;
+        debug!("Cleared Moka cache");
+        Ok(())
+    }
+
     async fn get_or_compute<F, Fut>(&self, code: &ShortCode, fetch: F) -> Result<Option<UrlRecord>>
     where
         F: FnOnce(&ShortCode) -> Fut + Send,
@@ -129,17 +382,20 @@ impl UrlCache for MokaUrlCache {
 
         // Moka's try_get_with provides single-flight semantics:
         // concurrent requests for the same key will coalesce into a single fetch
-        let result = self
+        let entry = self
             .cache
             .try_get_with(key, async {
                 trace!(code = %code, "Cache miss, performing single-flight fetch");
-                fetch(code).await
+                let record = fetch(code).await?;
+                // Like `set_url`, never keep a record past its expiry.
+                let ttl = record.as_ref().and_then(remaining_lifetime);
+                Ok::<_, crate::CacheError>(Entry { record, ttl })
             })
             .await
             .map_err(|e| e.as_ref().clone())?;
 
         debug!(code = %code, "Single-flight fetch completed");
-        Ok(result)
+        Ok(entry.record)
     }
 }
 
@@ -149,19 +405,38 @@ pub struct CacheConfig {
     /// Maximum number of entries the cache can hold.
     #[builder(default, setter(strip_option))]
     max_capacity: Option<u64>,
+    /// Maximum total size of the entries in bytes, see
+    /// [`MokaUrlCache::with_weigher`]. Takes precedence over `max_capacity`.
+    #[builder(default, setter(strip_option))]
+    max_weight: Option<u64>,
     /// Time-to-live for cache entries.
     #[builder(default, setter(strip_option))]
     ttl: Option<Duration>,
     /// Time-to-idle for cache entries.
     #[builder(default, setter(strip_option))]
     tti: Option<Duration>,
+    /// Callback for evicted entries, see [`MokaUrlCache::with_eviction_listener`].
+    #[builder(
+        default,
+        setter(
+            fn transform<F>(listener: F) -> Option<EvictionListener>
+            where
+                F: Fn(ShortCode, EvictionCause) + Send + Sync + 'static,
+            {
+                Some(EvictionListener::new(listener))
+            }
+        )
+    )]
+    eviction_listener: Option<EvictionListener>,
 }
 
 impl From<CacheConfig> for MokaUrlCache {
     fn from(config: CacheConfig) -> Self {
-        let mut builder = Cache::builder();
+        let mut builder = cache_builder();
 
-        if let Some(capacity) = config.max_capacity {
+        if let Some(max_weight) = config.max_weight {
+            builder = builder.weigher(entry_weight).max_capacity(max_weight);
+        } else if let Some(capacity) = config.max_capacity {
             builder = builder.max_capacity(capacity);
         }
 
@@ -173,6 +448,10 @@ impl From<CacheConfig> for MokaUrlCache {
             builder = builder.time_to_idle(tti);
         }
 
+        if let Some(listener) = config.eviction_listener {
+            builder = listener.attach(builder);
+        }
+
         MokaUrlCache {
             cache: builder.build(),
         }
@@ -184,11 +463,18 @@ mod tests {
     use super::*;
     use crate::CacheError;
     use jiff::Timestamp;
+    use wormhole_core::RedirectKind;
 
     fn test_record(url: &str) -> UrlRecord {
         UrlRecord {
             original_url: url.to_string(),
             expire_at: None,
+            redirect_kind: RedirectKind::default(),
+            created_at: Timestamp::now(),
+            internal_only: false,
+            no_store: false,
+            owner_id: None,
+            metadata: Default::default(),
         }
     }
 
@@ -196,6 +482,142 @@ mod tests {
         ShortCode::new_unchecked(s)
     }
 
+    #[tokio::test]
+    async fn eviction_listener_reports_size_evictions() {
+        let evicted = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let cache = MokaUrlCache::with_eviction_listener(2, {
+            let evicted = Arc::clone(&evicted);
+            move |code, cause| evicted.lock().push((code, cause))
+        });
+
+        for i in 0..10 {
+            cache
+                .set_url(
+                    &code(&format!("code{i}")),
+                    &test_record("https://example.com"),
+                )
+                .await
+                .unwrap();
+        }
+        cache.run_pending_tasks().await;
+
+        let evicted = evicted.lock();
+        assert!(!evicted.is_empty());
+        assert!(evicted
+            .iter()
+            .all(|(_, cause)| *cause == EvictionCause::Size));
+    }
+
+    #[tokio::test]
+    async fn eviction_listener_from_config_reports_explicit_removals() {
+        let evicted = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let cache: MokaUrlCache = MokaUrlCache::builder()
+            .max_capacity(100)
+            .eviction_listener({
+                let evicted = Arc::clone(&evicted);
+                move |code, cause| evicted.lock().push((code, cause))
+            })
+            .build()
+            .into();
+        let c = code("abc123");
+
+        cache
+            .set_url(&c, &test_record("https://example.com"))
+            .await
+            .unwrap();
+        // Overwrites are not evictions.
+        cache
+            .set_url(&c, &test_record("https://example.org"))
+            .await
+            .unwrap();
+        cache.del(&c).await.unwrap();
+        cache.run_pending_tasks().await;
+
+        assert_eq!(*evicted.lock(), vec![(c, EvictionCause::Explicit)]);
+    }
+
+    #[tokio::test]
+    async fn capacity_introspection() {
+        let cache = MokaUrlCache::with_capacity(100);
+        assert_eq!(cache.max_capacity(), Some(100));
+        assert_eq!(
+            MokaUrlCache::from(CacheConfig::default()).max_capacity(),
+            None
+        );
+
+        for i in 0..10 {
+            cache
+                .set_url(
+                    &code(&format!("code{i}")),
+                    &test_record("https://example.com"),
+                )
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(cache.entry_count().await, 10);
+        assert_eq!(cache.weighted_size().await, 10);
+    }
+
+    #[tokio::test]
+    async fn weighted_cache_evicts_by_size_not_count() {
+        const MAX_WEIGHT: u64 = 20_000;
+        let cache: MokaUrlCache = MokaUrlCache::builder()
+            .max_weight(MAX_WEIGHT)
+            .build()
+            .into();
+        assert_eq!(cache.max_capacity(), Some(MAX_WEIGHT));
+
+        let long_url = format!("https://example.com/{}", "x".repeat(4_000));
+        for i in 0..4 {
+            cache
+                .set_url(&code(&format!("large{i}")), &test_record(&long_url))
+                .await
+                .unwrap();
+        }
+        for i in 0..100 {
+            cache
+                .set_url(
+                    &code(&format!("small{i:03}")),
+                    &test_record("https://example.com"),
+                )
+                .await
+                .unwrap();
+        }
+
+        // About 31 KB went in, so something was evicted to fit 20 KB...
+        let weight = cache.weighted_size().await;
+        assert!(weight <= MAX_WEIGHT, "{weight}");
+        assert!(weight > MAX_WEIGHT / 2, "{weight}");
+        // ...yet far more entries fit than the 5 large ones 20 KB could hold.
+        assert!(cache.entry_count().await > 20);
+    }
+
+    #[tokio::test]
+    async fn with_weigher_counts_bytes() {
+        let cache = MokaUrlCache::with_weigher(1_000_000);
+        let record = test_record("https://example.com");
+        cache.set_url(&code("abc123"), &record).await.unwrap();
+
+        let expected = "abc123".len() + serde_json::to_vec(&record).unwrap().len();
+        assert_eq!(cache.weighted_size().await, expected as u64);
+    }
+
+    #[tokio::test]
+    async fn invalidate_all_empties_the_cache() {
+        let cache = MokaUrlCache::new();
+        let c = code("abc123");
+        cache
+            .set_url(&c, &test_record("https://example.com"))
+            .await
+            .unwrap();
+
+        cache.invalidate_all().await;
+
+        assert_eq!(cache.entry_count().await, 0);
+        assert!(cache.get_url(&c).await.unwrap().is_none());
+    }
+
     #[tokio::test]
     async fn cache_get_and_set() {
         let cache = MokaUrlCache::new();
@@ -316,7 +738,14 @@ mod tests {
         let c = code("abc123");
         let record = UrlRecord {
             original_url: "https://example.com".to_string(),
-            expire_at: Some(Timestamp::now()),
+            // `set_url` drops records that have already expired.
+            expire_at: Some(Timestamp::now() + jiff::SignedDuration::from_hours(1)),
+            redirect_kind: RedirectKind::default(),
+            created_at: Timestamp::now(),
+            internal_only: false,
+            no_store: false,
+            owner_id: None,
+            metadata: Default::default(),
         };
 
         cache.set_url(&c, &record).await.unwrap();
@@ -420,4 +849,189 @@ mod tests {
 
         assert!(matches!(err, CacheError::Timeout(_)));
     }
+
+    #[tokio::test]
+    async fn set_url_with_ttl_expires_only_that_entry() {
+        let cache = MokaUrlCache::with_capacity(100);
+        let short = code("short");
+        let long = code("long");
+
+        cache
+            .set_url_with_ttl(
+                &short,
+                &test_record("https://a.example"),
+                Some(Duration::from_millis(50)),
+            )
+            .await
+            .unwrap();
+        cache
+            .set_url(&long, &test_record("https://b.example"))
+            .await
+            .unwrap();
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        assert!(cache.get_url(&short).await.unwrap().is_none());
+        assert!(cache.get_url(&long).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn entries_never_outlive_the_record() {
+        let cache = MokaUrlCache::with_ttl(100, Duration::from_secs(3600));
+        let with_expiry = |millis: i64| {
+            let mut record = test_record("https://example.com");
+            record.expire_at = Some(Timestamp::now() + jiff::SignedDuration::from_millis(millis));
+            record
+        };
+
+        // Expiring before the cache-wide TTL: the record's expiry wins.
+        cache
+            .set_url(&code("soon"), &with_expiry(50))
+            .await
+            .unwrap();
+        cache
+            .get_or_compute(&code("computed"), |_| async { Ok(Some(with_expiry(50))) })
+            .await
+            .unwrap();
+        // Expiring after it, or never: the cache-wide TTL applies.
+        cache
+            .set_url(&code("later"), &with_expiry(24 * 3600 * 1000))
+            .await
+            .unwrap();
+        cache
+            .set_url(&code("forever"), &test_record("https://example.com"))
+            .await
+            .unwrap();
+        // Already expired: not cached at all.
+        cache
+            .set_url(&code("gone"), &with_expiry(-1_000))
+            .await
+            .unwrap();
+        assert!(cache.get_url(&code("gone")).await.unwrap().is_none());
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        assert!(cache.get_url(&code("soon")).await.unwrap().is_none());
+        assert!(cache.get_url(&code("computed")).await.unwrap().is_none());
+        assert!(cache.get_url(&code("later")).await.unwrap().is_some());
+        assert!(cache.get_url(&code("forever")).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn overwriting_without_ttl_clears_the_entry_ttl() {
+        let cache = MokaUrlCache::with_capacity(100);
+        let c = code("abc123");
+
+        cache
+            .set_url_with_ttl(
+                &c,
+                &test_record("https://a.example"),
+                Some(Duration::from_millis(50)),
+            )
+            .await
+            .unwrap();
+        cache
+            .set_url(&c, &test_record("https://b.example"))
+            .await
+            .unwrap();
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        assert!(cache.get_url(&c).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn clear_removes_every_entry() {
+        let cache = MokaUrlCache::with_capacity(100);
+        for i in 0..5 {
+            cache
+                .set_url(
+                    &code(&format!("code{i}")),
+                    &test_record("https://example.com"),
+                )
+                .await
+                .unwrap();
+        }
+
+        cache.clear().await.unwrap();
+
+        assert_eq!(cache.entry_count().await, 0);
+        assert!(cache.get_url(&code("code0")).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn exists_reports_present_and_absent_codes() {
+        let cache = MokaUrlCache::new();
+        let c = code("present");
+        cache
+            .set_url(&c, &test_record("https://example.com"))
+            .await
+            .unwrap();
+
+        assert!(cache.exists(&c).await.unwrap());
+        assert!(!cache.exists(&code("absent")).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn exists_treats_cached_misses_as_absent() {
+        let cache = MokaUrlCache::new();
+        let c = code("tombstone");
+
+        // get_or_compute caches a `None` answer from the origin.
+        let record = cache.get_or_compute(&c, |_| async { Ok(None) }).await;
+        assert!(record.unwrap().is_none());
+        cache.run_pending_tasks().await;
+        assert_eq!(cache.entry_count().await, 1);
+
+        assert!(!cache.exists(&c).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn set_absent_is_told_apart_from_not_cached() {
+        let cache = MokaUrlCache::new();
+        let c = code("gone");
+
+        assert_eq!(cache.lookup(&c).await, CacheLookup::NotCached);
+        cache.set_absent(&c, None).await;
+        assert_eq!(cache.lookup(&c).await, CacheLookup::Absent);
+
+        // Through the trait, a tombstone still reads as a plain miss.
+        assert_eq!(cache.get_url(&c).await.unwrap(), None);
+        assert!(!cache.exists(&c).await.unwrap());
+
+        let record = test_record("https://example.com");
+        cache.set_url(&c, &record).await.unwrap();
+        assert_eq!(cache.lookup(&c).await, CacheLookup::Found(record));
+    }
+
+    #[tokio::test]
+    async fn tombstones_short_circuit_get_or_compute() {
+        let cache = MokaUrlCache::new();
+        let c = code("gone");
+        cache.set_absent(&c, None).await;
+
+        let fetched = std::sync::atomic::AtomicBool::new(false);
+        let result = cache
+            .get_or_compute(&c, |_| async {
+                fetched.store(true, std::sync::atomic::Ordering::SeqCst);
+                Ok(Some(test_record("https://example.com")))
+            })
+            .await
+            .unwrap();
+
+        assert!(result.is_none());
+        assert!(!fetched.load(std::sync::atomic::Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn tombstones_expire_after_their_ttl() {
+        let cache = MokaUrlCache::new();
+        let c = code("gone");
+
+        cache.set_absent(&c, Some(Duration::from_millis(50))).await;
+        assert_eq!(cache.lookup(&c).await, CacheLookup::Absent);
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(cache.lookup(&c).await, CacheLookup::NotCached);
+    }
 }
@@ -0,0 +1,717 @@
+//! A cache composed of any number of layers.
+//!
+//! [`MultiLayerCache`] generalizes [`LayeredCache`](crate::LayeredCache) to
+//! L1..Ln: reads go from the shallowest layer to the deepest and backfill
+//! every layer above the one that hit, while writes and deletes reach every
+//! layer.
+//!
+//! [`UrlCache`] has a generic `get_or_compute`, so it cannot be used as a
+//! trait object. Layers are stored as [`DynUrlCache`], an object-safe view
+//! implemented for every [`UrlCache`].
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tracing::{debug, trace, warn, Instrument};
+use wormhole_core::{ShortCode, UrlRecord};
+
+use crate::spans::{self, compute_outcome, read_outcome};
+use crate::{metrics, CacheError, LayerErrorPolicy, Result, UrlCache};
+
+/// A boxed, sendable future.
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A type-erased `fetch` callback for [`DynUrlCache::dyn_get_or_compute`].
+pub type BoxedFetch<'a> =
+    Box<dyn FnOnce(ShortCode) -> BoxFuture<'a, Result<Option<UrlRecord>>> + Send + 'a>;
+
+/// Object-safe counterpart of [`UrlCache`].
+///
+/// Every [`UrlCache`] implements this trait, so any cache can be boxed as a
+/// `Box<dyn DynUrlCache>`. The methods are prefixed with `dyn_` so they never
+/// clash with the [`UrlCache`] methods when both traits are in scope.
+#[async_trait]
+pub trait DynUrlCache: Send + Sync + 'static {
+    /// See [`UrlCache::get_url`].
+    async fn dyn_get_url(&self, code: &ShortCode) -> Result<Option<UrlRecord>>;
+
+    /// See [`UrlCache::set_url`].
+    async fn dyn_set_url(&self, code: &ShortCode, record: &UrlRecord) -> Result<()>;
+
+    /// See [`UrlCache::set_url_with_ttl`].
+    async fn dyn_set_url_with_ttl(
+        &self,
+        code: &ShortCode,
+        record: &UrlRecord,
+        ttl: Option<Duration>,
+    ) -> Result<()>;
+
+    /// See [`UrlCache::del`].
+    async fn dyn_del(&self, code: &ShortCode) -> Result<()>;
+
+    /// See [`UrlCache::del_many`].
+    async fn dyn_del_many(&self, codes: &[ShortCode]) -> Result<()>;
+
+    /// See [`UrlCache::clear`].
+    async fn dyn_clear(&self) -> Result<()>;
+
+    /// See [`UrlCache::get_or_compute`].
+    async fn dyn_get_or_compute<'a>(
+        &'a self,
+        code: &'a ShortCode,
+        fetch: BoxedFetch<'a>,
+    ) -> Result<Option<UrlRecord>>;
+}
+
+#[async_trait]
+impl<C: UrlCache> DynUrlCache for C {
+    async fn dyn_get_url(&self, code: &ShortCode) -> Result<Option<UrlRecord>> {
+        self.get_url(code).await
+    }
+
+    async fn dyn_set_url(&self, code: &ShortCode, record: &UrlRecord) -> Result<()> {
+        self.set_url(code, record).await
+    }
+
+    async fn dyn_set_url_with_ttl(
+        &self,
+        code: &ShortCode,
+        record: &UrlRecord,
+        ttl: Option<Duration>,
+    ) -> Result<()> {
+        self.set_url_with_ttl(code, record, ttl).await
+    }
+
+    async fn dyn_del(&self, code: &ShortCode) -> Result<()> {
+        self.del(code).await
+    }
+
+    async fn dyn_del_many(&self, codes: &[ShortCode]) -> Result<()> {
+        self.del_many(codes).await
+    }
+
+    async fn dyn_clear(&self) -> Result<()> {
+        self.clear().await
+    }
+
+    async fn dyn_get_or_compute<'a>(
+        &'a self,
+        code: &'a ShortCode,
+        fetch: BoxedFetch<'a>,
+    ) -> Result<Option<UrlRecord>> {
+        self.get_or_compute(code, move |c| fetch(c.clone())).await
+    }
+}
+
+/// A cache made of an ordered list of layers, L1 first.
+///
+/// # Operation Strategy
+///
+/// - **Get**: Try each layer in order. On a hit in layer `n`, backfill
+///   layers `1..n` with the record.
+/// - **Get or compute**: Chain every layer's own single-flight, so each
+///   layer coalesces concurrent misses before asking the next one, and only
+///   a miss in all layers runs `fetch`.
+/// - **Set**: Write to every layer, deepest first.
+/// - **Delete**: Remove from every layer, shallowest first.
+///
+/// Layer errors are handled per [`LayerErrorPolicy`]. Under the tolerant
+/// policy a write or delete succeeds as long as one layer accepted it.
+///
+/// # Example
+///
+/// ```rust
+/// use wormhole_cache::{MokaUrlCache, MultiLayerCache};
+///
+/// let cache = MultiLayerCache::builder()
+///     .layer(MokaUrlCache::with_capacity(1_000))
+///     .layer(MokaUrlCache::with_capacity(100_000))
+///     // .layer(RedisUrlCache::new(conn))
+///     .build()
+///     .unwrap();
+/// assert_eq!(cache.depth(), 2);
+/// ```
+pub struct MultiLayerCache {
+    layers: Vec<Box<dyn DynUrlCache>>,
+    names: Vec<String>,
+    error_policy: LayerErrorPolicy,
+}
+
+impl fmt::Debug for MultiLayerCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MultiLayerCache")
+            .field("layers", &self.names)
+            .field("error_policy", &self.error_policy)
+            .finish()
+    }
+}
+
+impl MultiLayerCache {
+    /// Creates a cache from `layers`, shallowest first, with the default
+    /// [`LayerErrorPolicy`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `CacheError::Initialization` if `layers` is empty.
+    pub fn new(layers: Vec<Box<dyn DynUrlCache>>) -> Result<Self> {
+        layers
+            .into_iter()
+            .fold(Self::builder(), MultiLayerCacheBuilder::boxed_layer)
+            .build()
+    }
+
+    /// Returns a builder to add layers to, shallowest first.
+    pub fn builder() -> MultiLayerCacheBuilder {
+        MultiLayerCacheBuilder::default()
+    }
+
+    /// Sets how errors from an individual layer are handled.
+    pub fn with_error_policy(mut self, error_policy: LayerErrorPolicy) -> Self {
+        self.error_policy = error_policy;
+        self
+    }
+
+    /// Returns the number of layers.
+    pub fn depth(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Returns the configured layer error policy.
+    pub fn error_policy(&self) -> LayerErrorPolicy {
+        self.error_policy
+    }
+
+    /// Applies the error policy to the result of a single-layer read.
+    fn tolerate_read(
+        &self,
+        layer: &str,
+        code: &ShortCode,
+        result: Result<Option<UrlRecord>>,
+    ) -> Result<Option<UrlRecord>> {
+        match result {
+            Err(e) if self.error_policy == LayerErrorPolicy::Tolerant => {
+                warn!(code = %code, layer, error = %e, "Cache layer read failed, treating as miss");
+                Ok(None)
+            }
+            other => other,
+        }
+    }
+
+    /// Applies a write to every layer in `order`, honoring the error policy.
+    async fn write_all<'a, W>(
+        &'a self,
+        operation: &str,
+        code: &(dyn fmt::Display + Sync),
+        order: impl Iterator<Item = usize>,
+        write: W,
+    ) -> Result<()>
+    where
+        W: Fn(&'a dyn DynUrlCache) -> BoxFuture<'a, Result<()>>,
+    {
+        let mut succeeded = false;
+        let mut last_error = None;
+
+        for i in order {
+            match write(self.layers[i].as_ref()).await {
+                Ok(()) => succeeded = true,
+                Err(e) if self.error_policy == LayerErrorPolicy::Strict => return Err(e),
+                Err(e) => {
+                    warn!(code = %code, operation, layer = %self.names[i], error = %e, "Cache layer write failed");
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        match last_error {
+            Some(e) if !succeeded => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Get URL record from cache, computing it if not present.
+    ///
+    /// Each layer's [`get_or_compute`](UrlCache::get_or_compute) wraps the
+    /// next one's, so every layer's single-flight semantics are respected
+    /// and each layer is backfilled on the way back up.
+    pub async fn get_or_compute<F, Fut>(
+        &self,
+        code: &ShortCode,
+        fetch: F,
+    ) -> Result<Option<UrlRecord>>
+    where
+        F: FnOnce(&ShortCode) -> Fut + Send,
+        Fut: Future<Output = Result<Option<UrlRecord>>> + Send,
+    {
+        trace!(code = %code, "Fetching URL record from multi-layer cache with single-flight");
+
+        compute_through(&self.layers, &self.names, code, box_fetch(fetch)).await
+    }
+}
+
+/// Runs `fetch` behind the single-flight of every layer in `layers`.
+///
+/// Each layer's part runs in its own span, nested in the span of the layer
+/// above it.
+fn compute_through<'a, 'c>(
+    layers: &'a [Box<dyn DynUrlCache>],
+    names: &'a [String],
+    code: &'c ShortCode,
+    fetch: BoxedFetch<'a>,
+) -> BoxFuture<'c, Result<Option<UrlRecord>>>
+where
+    'a: 'c,
+{
+    let (Some((layer, deeper)), Some((name, deeper_names))) =
+        (layers.split_first(), names.split_first())
+    else {
+        return fetch(code.clone());
+    };
+
+    let span = spans::layer_span(name, code);
+    Box::pin(
+        async move {
+            let missed = AtomicBool::new(false);
+            let result = layer
+                .dyn_get_or_compute(code, next_fetch(deeper, deeper_names, fetch, &missed))
+                .await;
+            tracing::Span::current().record(
+                "outcome",
+                compute_outcome(&result, missed.load(Ordering::Relaxed)),
+            );
+            result
+        }
+        .instrument(span),
+    )
+}
+
+/// Erases the type of a caller's `fetch`.
+fn box_fetch<'a, F, Fut>(fetch: F) -> BoxedFetch<'a>
+where
+    F: FnOnce(&ShortCode) -> Fut + Send + 'a,
+    Fut: Future<Output = Result<Option<UrlRecord>>> + Send + 'a,
+{
+    Box::new(
+        move |c: ShortCode| -> BoxFuture<'a, Result<Option<UrlRecord>>> {
+            Box::pin(async move { fetch(&c).await })
+        },
+    )
+}
+
+/// Wraps the layers below one layer as that layer's `fetch`, noting in
+/// `missed` whether the layer had to call it.
+fn next_fetch<'a, 'm>(
+    layers: &'a [Box<dyn DynUrlCache>],
+    names: &'a [String],
+    fetch: BoxedFetch<'a>,
+    missed: &'m AtomicBool,
+) -> BoxedFetch<'m>
+where
+    'a: 'm,
+{
+    Box::new(
+        move |c: ShortCode| -> BoxFuture<'m, Result<Option<UrlRecord>>> {
+            missed.store(true, Ordering::Relaxed);
+            Box::pin(async move { compute_through(layers, names, &c, fetch).await })
+        },
+    )
+}
+
+#[async_trait]
+impl UrlCache for MultiLayerCache {
+    async fn get_url(&self, code: &ShortCode) -> Result<Option<UrlRecord>> {
+        trace!(code = %code, "Fetching URL record from multi-layer cache");
+
+        for (i, layer) in self.layers.iter().enumerate() {
+            let name = self.names[i].as_str();
+            let span = spans::layer_span(name, code);
+            let result = layer.dyn_get_url(code).instrument(span.clone()).await;
+            span.record("outcome", read_outcome(&result));
+            let Some(record) = self.tolerate_read(name, code, result)? else {
+                trace!(code = %code, layer = name, "Cache layer miss");
+                metrics::record_lookup(name, false);
+                continue;
+            };
+
+            debug!(code = %code, layer = name, "Cache layer hit, backfilling shallower layers");
+            metrics::record_lookup(name, true);
+            for (shallower, shallower_name) in self.layers[..i].iter().zip(&self.names) {
+                if let Err(e) = shallower.dyn_set_url(code, &record).await {
+                    warn!(code = %code, layer = %shallower_name, error = %e, "Failed to backfill cache layer");
+                }
+            }
+            return Ok(Some(record));
+        }
+
+        Ok(None)
+    }
+
+    async fn set_url(&self, code: &ShortCode, record: &UrlRecord) -> Result<()> {
+        trace!(code = %code, "Storing URL record in multi-layer cache");
+
+        // Deepest first, like `LayeredCache`, so shallow layers never hold a
+        // value the shared layers below have not seen.
+        self.write_all("set", code, (0..self.layers.len()).rev(), |layer| {
+            layer.dyn_set_url(code, record)
+        })
+        .await
+    }
+
+    async fn set_url_with_ttl(
+        &self,
+        code: &ShortCode,
+        record: &UrlRecord,
+        ttl: Option<Duration>,
+    ) -> Result<()> {
+        trace!(code = %code, ?ttl, "Storing URL record in multi-layer cache");
+
+        self.write_all("set", code, (0..self.layers.len()).rev(), |layer| {
+            layer.dyn_set_url_with_ttl(code, record, ttl)
+        })
+        .await
+    }
+
+    async fn del(&self, code: &ShortCode) -> Result<()> {
+        trace!(code = %code, "Removing URL record from multi-layer cache");
+
+        self.write_all("del", code, 0..self.layers.len(), |layer| {
+            layer.dyn_del(code)
+        })
+        .await
+    }
+
+    async fn del_many(&self, codes: &[ShortCode]) -> Result<()> {
+        trace!(
+            count = codes.len(),
+            "Removing URL records from multi-layer cache"
+        );
+
+        let target = format!("{} codes", codes.len());
+        self.write_all("del_many", &target, 0..self.layers.len(), |layer| {
+            layer.dyn_del_many(codes)
+        })
+        .await
+    }
+
+    async fn clear(&self) -> Result<()> {
+        trace!("Clearing multi-layer cache");
+
+        self.write_all("clear", &"all codes", 0..self.layers.len(), |layer| {
+            layer.dyn_clear()
+        })
+        .await
+    }
+
+    async fn get_or_compute<F, Fut>(&self, code: &ShortCode, fetch: F) -> Result<Option<UrlRecord>>
+    where
+        F: FnOnce(&ShortCode) -> Fut + Send,
+        Fut: Future<Output = Result<Option<UrlRecord>>> + Send,
+    {
+        // Delegate to the inherent method to share the implementation
+        self.get_or_compute(code, fetch).await
+    }
+}
+
+/// Builder for [`MultiLayerCache`].
+#[derive(Default)]
+pub struct MultiLayerCacheBuilder {
+    layers: Vec<Box<dyn DynUrlCache>>,
+    error_policy: LayerErrorPolicy,
+}
+
+impl MultiLayerCacheBuilder {
+    /// Appends `cache` below the layers added so far.
+    pub fn layer(self, cache: impl UrlCache) -> Self {
+        self.boxed_layer(Box::new(cache))
+    }
+
+    /// Appends an already boxed layer below the layers added so far.
+    pub fn boxed_layer(mut self, cache: Box<dyn DynUrlCache>) -> Self {
+        self.layers.push(cache);
+        self
+    }
+
+    /// Sets how errors from an individual layer are handled.
+    pub fn error_policy(mut self, error_policy: LayerErrorPolicy) -> Self {
+        self.error_policy = error_policy;
+        self
+    }
+
+    /// Builds the cache.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CacheError::Initialization` if no layer was added.
+    pub fn build(self) -> Result<MultiLayerCache> {
+        if self.layers.is_empty() {
+            return Err(CacheError::Initialization(
+                "a multi-layer cache needs at least one layer".to_string(),
+            ));
+        }
+
+        let names = (1..=self.layers.len()).map(|n| format!("l{n}")).collect();
+        Ok(MultiLayerCache {
+            layers: self.layers,
+            names,
+            error_policy: self.error_policy,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MokaUrlCache;
+    use jiff::Timestamp;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use wormhole_core::RedirectKind;
+
+    fn test_record(url: &str) -> UrlRecord {
+        UrlRecord {
+            original_url: url.to_string(),
+            expire_at: None,
+            redirect_kind: RedirectKind::default(),
+            created_at: Timestamp::now(),
+            internal_only: false,
+            no_store: false,
+            owner_id: None,
+            metadata: Default::default(),
+        }
+    }
+
+    fn code(s: &str) -> ShortCode {
+        ShortCode::new_unchecked(s)
+    }
+
+    /// A cache layer whose every operation fails.
+    struct FailingCache;
+
+    #[async_trait]
+    impl UrlCache for FailingCache {
+        async fn get_url(&self, _code: &ShortCode) -> Result<Option<UrlRecord>> {
+            Err(CacheError::Unavailable("layer down".to_string()))
+        }
+
+        async fn set_url(&self, _code: &ShortCode, _record: &UrlRecord) -> Result<()> {
+            Err(CacheError::Unavailable("layer down".to_string()))
+        }
+
+        async fn del(&self, _code: &ShortCode) -> Result<()> {
+            Err(CacheError::Unavailable("layer down".to_string()))
+        }
+    }
+
+    fn three_layers() -> (MultiLayerCache, [MokaUrlCache; 3]) {
+        let layers = [
+            MokaUrlCache::new(),
+            MokaUrlCache::new(),
+            MokaUrlCache::new(),
+        ];
+        let cache = MultiLayerCache::builder()
+            .layer(layers[0].clone())
+            .layer(layers[1].clone())
+            .layer(layers[2].clone())
+            .build()
+            .unwrap();
+        (cache, layers)
+    }
+
+    #[test]
+    fn build_requires_a_layer() {
+        let err = MultiLayerCache::builder().build().unwrap_err();
+        assert!(matches!(err, CacheError::Initialization(_)));
+        assert!(MultiLayerCache::new(Vec::new()).is_err());
+    }
+
+    #[tokio::test]
+    async fn boxed_layers_backfill_from_layer_three() {
+        let (l1, l2, l3) = (
+            MokaUrlCache::new(),
+            MokaUrlCache::new(),
+            MokaUrlCache::new(),
+        );
+        let cache = MultiLayerCache::new(vec![
+            Box::new(l1.clone()),
+            Box::new(l2.clone()),
+            Box::new(l3.clone()),
+        ])
+        .unwrap();
+        assert_eq!(cache.depth(), 3);
+
+        let c = code("layer3");
+        let record = test_record("https://example.com/l3");
+        l3.set_url(&c, &record).await.unwrap();
+
+        assert_eq!(cache.get_url(&c).await.unwrap(), Some(record.clone()));
+        assert_eq!(l1.get_url(&c).await.unwrap(), Some(record.clone()));
+        assert_eq!(l2.get_url(&c).await.unwrap(), Some(record));
+    }
+
+    #[tokio::test]
+    async fn tolerant_policy_skips_a_failing_layer() {
+        let l1 = MokaUrlCache::new();
+        let l3 = MokaUrlCache::new();
+        let cache = MultiLayerCache::builder()
+            .layer(l1.clone())
+            .layer(FailingCache)
+            .layer(l3.clone())
+            .build()
+            .unwrap();
+        let c = code("tolerant");
+        let record = test_record("https://example.com");
+
+        cache.set_url(&c, &record).await.unwrap();
+        assert_eq!(l3.get_url(&c).await.unwrap(), Some(record.clone()));
+
+        l1.del(&c).await.unwrap();
+        assert_eq!(cache.get_url(&c).await.unwrap(), Some(record.clone()));
+        assert_eq!(l1.get_url(&c).await.unwrap(), Some(record));
+    }
+
+    #[tokio::test]
+    async fn strict_policy_surfaces_a_failing_layer() {
+        let cache = MultiLayerCache::builder()
+            .layer(MokaUrlCache::new())
+            .layer(FailingCache)
+            .build()
+            .unwrap()
+            .with_error_policy(LayerErrorPolicy::Strict);
+        let c = code("strict");
+
+        assert!(cache.get_url(&c).await.is_err());
+        assert!(cache
+            .set_url(&c, &test_record("https://example.com"))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn deepest_hit_backfills_every_shallower_layer() {
+        let (cache, [l1, l2, l3]) = three_layers();
+        let c = code("deep");
+        let record = test_record("https://example.com");
+        l3.set_url(&c, &record).await.unwrap();
+
+        assert_eq!(cache.get_url(&c).await.unwrap(), Some(record.clone()));
+
+        assert_eq!(l1.get_url(&c).await.unwrap(), Some(record.clone()));
+        assert_eq!(l2.get_url(&c).await.unwrap(), Some(record));
+    }
+
+    #[tokio::test]
+    async fn middle_hit_only_backfills_layers_above() {
+        let (cache, [l1, l2, l3]) = three_layers();
+        let c = code("middle");
+        let record = test_record("https://example.com");
+        l2.set_url(&c, &record).await.unwrap();
+
+        assert_eq!(cache.get_url(&c).await.unwrap(), Some(record.clone()));
+
+        assert_eq!(l1.get_url(&c).await.unwrap(), Some(record));
+        assert!(l3.get_url(&c).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn writes_and_deletes_reach_every_layer() {
+        let (cache, layers) = three_layers();
+        let c = code("write");
+        let record = test_record("https://example.com");
+
+        cache.set_url(&c, &record).await.unwrap();
+        for layer in &layers {
+            assert_eq!(layer.get_url(&c).await.unwrap(), Some(record.clone()));
+        }
+
+        cache.del(&c).await.unwrap();
+        for layer in &layers {
+            assert!(layer.get_url(&c).await.unwrap().is_none());
+        }
+
+        cache.set_url(&c, &record).await.unwrap();
+        cache.clear().await.unwrap();
+        for layer in &layers {
+            assert!(layer.get_url(&c).await.unwrap().is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn get_or_compute_fetches_once_and_fills_every_layer() {
+        let (cache, layers) = three_layers();
+        let cache = Arc::new(cache);
+        let c = code("computed");
+        let fetches = Arc::new(AtomicUsize::new(0));
+
+        let tasks: Vec<_> = (0..16)
+            .map(|_| {
+                let cache = Arc::clone(&cache);
+                let c = c.clone();
+                let fetches = Arc::clone(&fetches);
+                tokio::spawn(async move {
+                    cache
+                        .get_or_compute(&c, |_| async move {
+                            fetches.fetch_add(1, Ordering::SeqCst);
+                            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                            Ok(Some(test_record("https://example.com/computed")))
+                        })
+                        .await
+                })
+            })
+            .collect();
+        for task in tasks {
+            let record = task.await.unwrap().unwrap().unwrap();
+            assert_eq!(record.original_url, "https://example.com/computed");
+        }
+
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+        for layer in &layers {
+            assert!(layer.get_url(&c).await.unwrap().is_some());
+        }
+    }
+
+    #[tokio::test]
+    async fn get_or_compute_stops_at_the_first_layer_with_the_value() {
+        let (cache, [_, l2, l3]) = three_layers();
+        let c = code("cached");
+        let record = test_record("https://example.com");
+        l2.set_url(&c, &record).await.unwrap();
+
+        // Fails the lookup if it is ever called.
+        let result = cache
+            .get_or_compute(&c, |_| async {
+                Err(CacheError::Operation("fetch must not run".to_string()))
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(result, Some(record));
+        assert!(l3.get_url(&c).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn set_url_with_ttl_reaches_every_layer() {
+        let (cache, layers) = three_layers();
+        let c = code("ttl");
+
+        cache
+            .set_url_with_ttl(
+                &c,
+                &test_record("https://example.com"),
+                Some(Duration::from_millis(50)),
+            )
+            .await
+            .unwrap();
+        for layer in &layers {
+            assert!(layer.get_url(&c).await.unwrap().is_some());
+        }
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        for layer in &layers {
+            assert!(layer.get_url(&c).await.unwrap().is_none());
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use std::future::Future;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use wormhole_core::{ShortCode, UrlRecord};
+
+use crate::{CacheError, Result, UrlCache};
+
+/// Separates the tenant from the short code in namespaced keys.
+const SEPARATOR: char = ':';
+
+/// A decorator that scopes every key of the wrapped cache to one tenant.
+///
+/// Codes are rewritten to `<tenant_id>:<code>` before they reach the inner
+/// cache, so the inner cache's own key prefix still applies, e.g. a Redis
+/// key becomes `wh:url:acme:abc123`. Two tenants using the same code
+/// therefore never see each other's records, even when sharing one Redis.
+///
+/// Wrap a shared inner cache once per tenant; the inner cache is usually
+/// cheap to clone. [`UrlCache::clear`] clears the inner cache for every
+/// tenant sharing it, see [`NamespacedCache::clear`].
+///
+/// # Example
+///
+/// ```rust
+/// use wormhole_cache::{MokaUrlCache, NamespacedCache};
+///
+/// let shared = MokaUrlCache::with_capacity(10_000);
+/// let acme = NamespacedCache::new(shared.clone(), "acme").unwrap();
+/// let globex = NamespacedCache::new(shared, "globex").unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct NamespacedCache<C> {
+    inner: C,
+    tenant_id: String,
+}
+
+impl<C> NamespacedCache<C> {
+    /// Wraps `inner`, scoping its keys to `tenant_id`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CacheError::Initialization` if `tenant_id` is empty or
+    /// contains `:`, which would let two tenants map to the same keys.
+    pub fn new(inner: C, tenant_id: impl Into<String>) -> Result<Self> {
+        let tenant_id = tenant_id.into();
+        if tenant_id.is_empty() || tenant_id.contains(SEPARATOR) {
+            return Err(CacheError::Initialization(format!(
+                "invalid tenant id '{tenant_id}': must be non-empty and must not contain '{SEPARATOR}'"
+            )));
+        }
+        Ok(Self { inner, tenant_id })
+    }
+
+    /// Returns the tenant this cache is scoped to.
+    pub fn tenant_id(&self) -> &str {
+        &self.tenant_id
+    }
+
+    /// Returns a reference to the wrapped cache.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    fn scoped(&self, code: &ShortCode) -> ShortCode {
+        ShortCode::new_unchecked(format!("{}{SEPARATOR}{}", self.tenant_id, code.as_str()))
+    }
+}
+
+#[async_trait]
+impl<C: UrlCache> UrlCache for NamespacedCache<C> {
+    async fn get_url(&self, code: &ShortCode) -> Result<Option<UrlRecord>> {
+        self.inner.get_url(&self.scoped(code)).await
+    }
+
+    async fn exists(&self, code: &ShortCode) -> Result<bool> {
+        self.inner.exists(&self.scoped(code)).await
+    }
+
+    async fn set_url(&self, code: &ShortCode, record: &UrlRecord) -> Result<()> {
+        self.inner.set_url(&self.scoped(code), record).await
+    }
+
+    async fn set_url_with_ttl(
+        &self,
+        code: &ShortCode,
+        record: &UrlRecord,
+        ttl: Option<Duration>,
+    ) -> Result<()> {
+        self.inner
+            .set_url_with_ttl(&self.scoped(code), record, ttl)
+            .await
+    }
+
+    async fn del(&self, code: &ShortCode) -> Result<()> {
+        self.inner.del(&self.scoped(code)).await
+    }
+
+    async fn del_many(&self, codes: &[ShortCode]) -> Result<()> {
+        let scoped: Vec<ShortCode> = codes.iter().map(|code| self.scoped(code)).collect();
+        self.inner.del_many(&scoped).await
+    }
+
+    /// Clears the whole inner cache, including other tenants' entries.
+    ///
+    /// The inner cache has no way to clear only this tenant's keys. Other
+    /// tenants merely lose cached entries and read them again on their next
+    /// miss.
+    async fn clear(&self) -> Result<()> {
+        self.inner.clear().await
+    }
+
+    async fn get_or_compute<F, Fut>(&self, code: &ShortCode, fetch: F) -> Result<Option<UrlRecord>>
+    where
+        F: FnOnce(&ShortCode) -> Fut + Send,
+        Fut: Future<Output = Result<Option<UrlRecord>>> + Send,
+    {
+        // Delegate so the inner cache's single-flight still applies, but
+        // hand the caller's fetch the code it asked for.
+        self.inner
+            .get_or_compute(&self.scoped(code), move |_| fetch(code))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MokaUrlCache;
+    use jiff::Timestamp;
+    use wormhole_core::RedirectKind;
+
+    fn test_record(url: &str) -> UrlRecord {
+        UrlRecord {
+            original_url: url.to_string(),
+            expire_at: None,
+            redirect_kind: RedirectKind::default(),
+            created_at: Timestamp::now(),
+            internal_only: false,
+            no_store: false,
+            owner_id: None,
+            metadata: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn tenants_with_the_same_code_do_not_collide() {
+        let shared = MokaUrlCache::with_capacity(100);
+        let acme = NamespacedCache::new(shared.clone(), "acme").unwrap();
+        let globex = NamespacedCache::new(shared.clone(), "globex").unwrap();
+        let code = ShortCode::new_unchecked("abc123");
+
+        acme.set_url(&code, &test_record("https://acme.example"))
+            .await
+            .unwrap();
+        assert!(globex.get_url(&code).await.unwrap().is_none());
+
+        globex
+            .set_url(&code, &test_record("https://globex.example"))
+            .await
+            .unwrap();
+        assert_eq!(
+            acme.get_url(&code).await.unwrap().unwrap().original_url,
+            "https://acme.example"
+        );
+        assert_eq!(
+            globex.get_url(&code).await.unwrap().unwrap().original_url,
+            "https://globex.example"
+        );
+
+        acme.del(&code).await.unwrap();
+        assert!(acme.get_url(&code).await.unwrap().is_none());
+        assert!(globex.get_url(&code).await.unwrap().is_some());
+
+        // The unscoped key space is untouched.
+        assert!(shared.get_url(&code).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn get_or_compute_passes_the_unscoped_code_to_fetch() {
+        let cache = NamespacedCache::new(MokaUrlCache::with_capacity(100), "acme").unwrap();
+        let code = ShortCode::new_unchecked("abc123");
+
+        let record = cache
+            .get_or_compute(&code, |requested| {
+                let requested = requested.clone();
+                async move {
+                    assert_eq!(requested.as_str(), "abc123");
+                    Ok(Some(test_record("https://acme.example")))
+                }
+            })
+            .await
+            .unwrap();
+
+        assert!(record.is_some());
+        assert!(cache.get_url(&code).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn clear_reaches_the_inner_cache() {
+        let shared = MokaUrlCache::with_capacity(100);
+        let acme = NamespacedCache::new(shared.clone(), "acme").unwrap();
+        let globex = NamespacedCache::new(shared, "globex").unwrap();
+        let code = ShortCode::new_unchecked("abc123");
+        acme.set_url(&code, &test_record("https://acme.example"))
+            .await
+            .unwrap();
+        globex
+            .set_url(&code, &test_record("https://globex.example"))
+            .await
+            .unwrap();
+
+        acme.clear().await.unwrap();
+
+        assert!(acme.get_url(&code).await.unwrap().is_none());
+        assert!(globex.get_url(&code).await.unwrap().is_none());
+    }
+
+    #[test]
+    fn rejects_ambiguous_tenant_ids() {
+        let err = NamespacedCache::new(MokaUrlCache::with_capacity(1), "").unwrap_err();
+        assert!(matches!(err, CacheError::Initialization(_)));
+        let err = NamespacedCache::new(MokaUrlCache::with_capacity(1), "a:b").unwrap_err();
+        assert!(matches!(err, CacheError::Initialization(_)));
+    }
+}
@@ -0,0 +1,179 @@
+//! Tenant-partitioned in-memory caching.
+//!
+//! A single shared cache lets one busy tenant evict everyone else's entries.
+//! [`PartitionedCache`] instead gives every tenant its own [`MokaUrlCache`]
+//! with an independent capacity, so eviction pressure stays within the tenant
+//! that caused it.
+
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use typed_builder::TypedBuilder;
+
+use crate::MokaUrlCache;
+
+/// Configuration for [`PartitionedCache`].
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct PartitionedCacheConfig {
+    /// Maximum number of entries for tenants without an override.
+    #[builder(default = 10_000)]
+    pub default_capacity: u64,
+
+    /// Per-tenant maximum number of entries, keyed by tenant id.
+    #[builder(default)]
+    pub capacity_overrides: HashMap<String, u64>,
+}
+
+impl Default for PartitionedCacheConfig {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// A set of per-tenant caches, created lazily on first access.
+///
+/// Each partition is a regular [`MokaUrlCache`] and implements
+/// [`UrlCache`](crate::UrlCache), so it can be handed to anything that
+/// expects a cache once the caller knows which tenant a request belongs to.
+#[derive(Debug)]
+pub struct PartitionedCache {
+    config: PartitionedCacheConfig,
+    partitions: RwLock<HashMap<String, MokaUrlCache>>,
+}
+
+impl PartitionedCache {
+    /// Creates an empty partitioned cache.
+    pub fn new(config: PartitionedCacheConfig) -> Self {
+        Self {
+            config,
+            partitions: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the cache partition for `tenant`, creating it if needed.
+    ///
+    /// The returned handle shares storage with the partition, so entries
+    /// written through it are visible to later calls for the same tenant.
+    pub fn partition(&self, tenant: &str) -> MokaUrlCache {
+        if let Some(partition) = self.partitions.read().get(tenant) {
+            return partition.clone();
+        }
+
+        // Another caller may have created the partition between dropping the
+        // read lock and taking the write lock, so go through the entry API.
+        self.partitions
+            .write()
+            .entry(tenant.to_string())
+            .or_insert_with(|| MokaUrlCache::with_capacity(self.capacity_for(tenant)))
+            .clone()
+    }
+
+    /// Returns the capacity a partition for `tenant` is (or would be) created with.
+    pub fn capacity_for(&self, tenant: &str) -> u64 {
+        self.config
+            .capacity_overrides
+            .get(tenant)
+            .copied()
+            .unwrap_or(self.config.default_capacity)
+    }
+
+    /// Returns the number of partitions created so far.
+    pub fn partition_count(&self) -> usize {
+        self.partitions.read().len()
+    }
+}
+
+impl Default for PartitionedCache {
+    fn default() -> Self {
+        Self::new(PartitionedCacheConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UrlCache;
+    use jiff::Timestamp;
+    use wormhole_core::{RedirectKind, ShortCode, UrlRecord};
+
+    fn test_record(url: &str) -> UrlRecord {
+        UrlRecord {
+            original_url: url.to_string(),
+            expire_at: None,
+            redirect_kind: RedirectKind::default(),
+            created_at: Timestamp::now(),
+            internal_only: false,
+            no_store: false,
+            owner_id: None,
+            metadata: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn partitions_are_created_lazily_and_reused() {
+        let cache = PartitionedCache::default();
+        assert_eq!(cache.partition_count(), 0);
+
+        let code = ShortCode::new_unchecked("abc123");
+        cache
+            .partition("a")
+            .set_url(&code, &test_record("https://example.com"))
+            .await
+            .unwrap();
+
+        assert_eq!(cache.partition_count(), 1);
+        assert!(cache.partition("a").get_url(&code).await.unwrap().is_some());
+        assert!(cache.partition("b").get_url(&code).await.unwrap().is_none());
+        assert_eq!(cache.partition_count(), 2);
+    }
+
+    #[test]
+    fn capacity_overrides_take_precedence() {
+        let cache = PartitionedCache::new(
+            PartitionedCacheConfig::builder()
+                .default_capacity(100)
+                .capacity_overrides(HashMap::from([("noisy".to_string(), 5)]))
+                .build(),
+        );
+
+        assert_eq!(cache.capacity_for("noisy"), 5);
+        assert_eq!(cache.capacity_for("quiet"), 100);
+    }
+
+    #[tokio::test]
+    async fn filling_one_partition_does_not_evict_another() {
+        let cache = PartitionedCache::new(
+            PartitionedCacheConfig::builder()
+                .default_capacity(1_000)
+                .capacity_overrides(HashMap::from([("a".to_string(), 8)]))
+                .build(),
+        );
+
+        let tenant_b = cache.partition("b");
+        let b_codes: Vec<_> = (0..8)
+            .map(|i| ShortCode::new_unchecked(format!("b-{i}")))
+            .collect();
+        for code in &b_codes {
+            tenant_b
+                .set_url(code, &test_record("https://b.example"))
+                .await
+                .unwrap();
+        }
+
+        let tenant_a = cache.partition("a");
+        for i in 0..200 {
+            let code = ShortCode::new_unchecked(format!("a-{i}"));
+            tenant_a
+                .set_url(&code, &test_record("https://a.example"))
+                .await
+                .unwrap();
+        }
+
+        tenant_a.run_pending_tasks().await;
+        tenant_b.run_pending_tasks().await;
+
+        assert!(tenant_a.entry_count().await <= 8);
+        for code in &b_codes {
+            assert!(tenant_b.get_url(code).await.unwrap().is_some());
+        }
+    }
+}
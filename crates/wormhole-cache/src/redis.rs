@@ -1,24 +1,103 @@
 use async_trait::async_trait;
 use redis::AsyncCommands;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
 use tracing::{debug, trace, warn};
+use typed_builder::TypedBuilder;
 use wormhole_core::{ShortCode, UrlRecord};
 
-use crate::{CacheError, Result, UrlCache};
+use crate::ttl::remaining_lifetime;
+use crate::{CacheCodec, CacheError, JsonCodec, Result, TtlPolicy, UrlCache};
+
+/// Deletes the lock only if it still holds our token, so a fetcher whose lock
+/// already expired cannot release a lock that another instance now owns.
+pub(crate) const RELEASE_LOCK_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("DEL", KEYS[1])
+else
+    return 0
+end
+"#;
+
+/// Shortest TTL derived from a record's `expire_at`.
+const MIN_EXPIRY_TTL: Duration = Duration::from_secs(1);
+
+/// Distinguishes lock tokens created by the same process.
+static LOCK_TOKEN_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Settings for the distributed single-flight used by
+/// [`RedisUrlCache::get_or_compute`].
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct SingleFlightConfig {
+    /// How long the fetch lock is held before Redis expires it.
+    ///
+    /// This bounds how long other instances wait on a fetcher that crashed.
+    #[builder(default = Duration::from_secs(5))]
+    pub lock_ttl: Duration,
+
+    /// How long a caller that lost the lock race waits for the value before
+    /// computing it itself.
+    #[builder(default = Duration::from_secs(2))]
+    pub wait_timeout: Duration,
+
+    /// How often a waiting caller checks Redis for the value.
+    #[builder(default = Duration::from_millis(50))]
+    pub poll_interval: Duration,
+}
+
+impl Default for SingleFlightConfig {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// Connection settings for [`RedisUrlCache::connect`].
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct RedisCacheConfig {
+    /// How long a command may wait for its reply before failing with
+    /// [`CacheError::Timeout`]. `None` waits forever, so a hung Redis stalls
+    /// every lookup behind it.
+    #[builder(default = Some(Duration::from_millis(500)))]
+    pub response_timeout: Option<Duration>,
+
+    /// How long establishing the connection may take.
+    #[builder(default = Some(Duration::from_secs(2)))]
+    pub connection_timeout: Option<Duration>,
+
+    /// Prefix for cache keys.
+    #[builder(default = "wh:url:".to_string(), setter(into))]
+    pub key_prefix: String,
+}
+
+impl Default for RedisCacheConfig {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
 
 /// A Redis-based implementation of [`UrlCache`].
 ///
-/// This implementation stores URL records as JSON strings in Redis,
-/// using a configurable key prefix.
+/// This implementation stores URL records in Redis using a configurable key
+/// prefix. Values are encoded as JSON unless another [`CacheCodec`] is set
+/// with [`RedisUrlCache::with_codec`].
 #[derive(Debug, Clone)]
 pub struct RedisUrlCache {
     conn: redis::aio::MultiplexedConnection,
     key_prefix: String,
+    single_flight: SingleFlightConfig,
+    codec: Arc<dyn CacheCodec>,
+    ttl_policy: Option<TtlPolicy>,
 }
 
 fn map_redis_error(operation: &str, err: redis::RedisError) -> CacheError {
     let message = format!("{operation}: {err}");
-    if message.to_ascii_lowercase().contains("timed out") {
+    if err.is_timeout() || message.to_ascii_lowercase().contains("timed out") {
         CacheError::Timeout(message)
+    } else if err.is_io_error() || err.is_connection_dropped() || err.is_connection_refusal() {
+        CacheError::Unavailable(message)
     } else {
         CacheError::Operation(message)
     }
@@ -34,9 +113,34 @@ impl RedisUrlCache {
         Self {
             conn,
             key_prefix: "wh:url:".to_string(),
+            single_flight: SingleFlightConfig::default(),
+            codec: Arc::new(JsonCodec),
+            ttl_policy: None,
         }
     }
 
+    /// Connects to the Redis at `url` with the timeouts in `config`.
+    ///
+    /// A `rediss://` URL connects over TLS, which needs the `tls` feature.
+    ///
+    /// # Errors
+    ///
+    /// [`CacheError::Initialization`] for an invalid URL, or the mapped
+    /// connection error, e.g. [`CacheError::Timeout`] if the server does not
+    /// answer within `connection_timeout`.
+    pub async fn connect(url: &str, config: RedisCacheConfig) -> Result<Self> {
+        let client = redis::Client::open(url)
+            .map_err(|e| CacheError::Initialization(format!("invalid Redis URL: {e}")))?;
+        let conn_config = redis::AsyncConnectionConfig::new()
+            .set_response_timeout(config.response_timeout)
+            .set_connection_timeout(config.connection_timeout);
+        let conn = client
+            .get_multiplexed_async_connection_with_config(&conn_config)
+            .await
+            .map_err(|e| map_redis_error("failed to connect to Redis", e))?;
+        Ok(Self::with_prefix(conn, config.key_prefix))
+    }
+
     /// Creates a new Redis URL cache with a custom key prefix.
     ///
     /// # Arguments
@@ -50,13 +154,200 @@ impl RedisUrlCache {
         Self {
             conn,
             key_prefix: key_prefix.into(),
+            single_flight: SingleFlightConfig::default(),
+            codec: Arc::new(JsonCodec),
+            ttl_policy: None,
         }
     }
 
+    /// Sets the format used to encode cached values.
+    ///
+    /// Codecs cannot read each other's output. When changing the codec of an
+    /// existing deployment, also change the key prefix (see
+    /// [`RedisUrlCache::with_prefix`]); otherwise values written in the old
+    /// format fail to decode with [`CacheError::InvalidData`].
+    pub fn with_codec(mut self, codec: impl CacheCodec) -> Self {
+        self.codec = Arc::new(codec);
+        self
+    }
+
+    /// Overrides the lock and polling settings used by `get_or_compute`.
+    pub fn with_single_flight(mut self, config: SingleFlightConfig) -> Self {
+        self.single_flight = config;
+        self
+    }
+
+    /// Gives entries written by `set_url` a TTL from `policy`.
+    ///
+    /// Without a policy they expire with the record's `expire_at`, or never
+    /// if it has none. `set_url_with_ttl` is unaffected.
+    pub fn with_ttl_policy(mut self, policy: TtlPolicy) -> Self {
+        self.ttl_policy = Some(policy);
+        self
+    }
+
+    /// Returns how long the cached copy of `record` may live when no TTL was
+    /// given: until `expire_at`, floored at [`MIN_EXPIRY_TTL`].
+    ///
+    /// `None` means the record never expires; `Some(Duration::ZERO)` means it
+    /// already has and must not be cached.
+    fn ttl_until_expiry(record: &UrlRecord) -> Option<Duration> {
+        remaining_lifetime(record).map(|remaining| {
+            if remaining.is_zero() {
+                remaining
+            } else {
+                remaining.max(MIN_EXPIRY_TTL)
+            }
+        })
+    }
+
+    /// Fetches the record for `code` along with how long Redis will keep it.
+    ///
+    /// The duration is `None` when the key has no expiry. `GET` and `PTTL`
+    /// run in one transaction, so the TTL belongs to the value returned.
+    pub async fn get_with_ttl(
+        &self,
+        code: &ShortCode,
+    ) -> Result<Option<(UrlRecord, Option<Duration>)>> {
+        let key = self.cache_key(code);
+        trace!(code = %code, "Fetching URL record and TTL from Redis cache");
+
+        let mut conn = self.conn.clone();
+        let (cached, pttl): (Option<Vec<u8>>, i64) = redis::pipe()
+            .atomic()
+            .get(&key)
+            .pttl(&key)
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| {
+                warn!(code = %code, error = %e, "Redis error on get with TTL");
+                map_redis_error("failed to fetch value and TTL from Redis", e)
+            })?;
+
+        let Some(cached) = cached else {
+            trace!(code = %code, "Cache miss in Redis");
+            return Ok(None);
+        };
+        let record = self.decode(code, &key, &cached)?;
+        // PTTL is -1 for keys without an expiry.
+        let ttl = u64::try_from(pttl).ok().map(Duration::from_millis);
+        Ok(Some((record, ttl)))
+    }
+
+    fn decode(&self, code: &ShortCode, key: &str, cached: &[u8]) -> Result<UrlRecord> {
+        self.codec.decode(cached).map_err(|e| {
+            warn!(code = %code, error = %e, "Failed to deserialize cached record");
+            CacheError::InvalidData(format!("invalid cached value for key '{key}': {e}"))
+        })
+    }
+
     /// Generates the cache key for a short code.
     fn cache_key(&self, code: &ShortCode) -> String {
         format!("{}{}", self.key_prefix, code.as_str())
     }
+
+    /// Generates the fetch lock key for a short code.
+    ///
+    /// Short codes never contain `:`, so this cannot collide with a value key.
+    fn lock_key(&self, code: &ShortCode) -> String {
+        format!("{}lock:{}", self.key_prefix, code.as_str())
+    }
+
+    /// Tries to take the fetch lock, returning whether it was acquired.
+    async fn try_lock(&self, lock_key: &str, token: &str) -> Result<bool> {
+        let mut conn = self.conn.clone();
+        let reply: Option<String> = redis::cmd("SET")
+            .arg(lock_key)
+            .arg(token)
+            .arg("NX")
+            .arg("PX")
+            .arg(self.single_flight.lock_ttl.as_millis() as u64)
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| map_redis_error("failed to acquire fetch lock", e))?;
+        Ok(reply.is_some())
+    }
+
+    async fn unlock(&self, lock_key: &str, token: &str) -> Result<()> {
+        let mut conn = self.conn.clone();
+        redis::Script::new(RELEASE_LOCK_SCRIPT)
+            .key(lock_key)
+            .arg(token)
+            .invoke_async::<i64>(&mut conn)
+            .await
+            .map_err(|e| map_redis_error("failed to release fetch lock", e))?;
+        Ok(())
+    }
+
+    async fn is_locked(&self, lock_key: &str) -> Result<bool> {
+        let mut conn = self.conn.clone();
+        conn.exists::<_, bool>(lock_key)
+            .await
+            .map_err(|e| map_redis_error("failed to check fetch lock", e))
+    }
+
+    /// Waits for the lock holder to publish the value.
+    ///
+    /// Returns `None` when the caller should compute the value itself: either
+    /// the wait timed out, or the lock was released without a value being
+    /// cached (the holder failed or found nothing).
+    async fn wait_for_value(&self, code: &ShortCode, lock_key: &str) -> Result<Option<UrlRecord>> {
+        let deadline = Instant::now() + self.single_flight.wait_timeout;
+
+        while Instant::now() < deadline {
+            tokio::time::sleep(self.single_flight.poll_interval).await;
+
+            if let Some(record) = self.get_url(code).await? {
+                return Ok(Some(record));
+            }
+            if !self.is_locked(lock_key).await? {
+                return Ok(None);
+            }
+        }
+
+        warn!(code = %code, "Timed out waiting for fetch lock holder, computing anyway");
+        Ok(None)
+    }
+
+    async fn compute_and_backfill<F, Fut>(
+        &self,
+        code: &ShortCode,
+        fetch: F,
+    ) -> Result<Option<UrlRecord>>
+    where
+        F: FnOnce(&ShortCode) -> Fut + Send,
+        Fut: Future<Output = Result<Option<UrlRecord>>> + Send,
+    {
+        let record = fetch(code).await?;
+        if let Some(ref value) = record {
+            self.set_url(code, value).await?;
+        }
+        Ok(record)
+    }
+}
+
+pub(crate) fn lock_token() -> String {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let seq = LOCK_TOKEN_COUNTER.fetch_add(1, Ordering::Relaxed);
+    format!("{}-{nanos}-{seq}", std::process::id())
+}
+
+/// Keys requested per `SCAN` iteration when clearing the cache.
+const CLEAR_SCAN_COUNT: usize = 500;
+
+/// Escapes glob metacharacters so `prefix` matches literally in `SCAN MATCH`.
+pub(crate) fn escape_glob(prefix: &str) -> String {
+    let mut escaped = String::with_capacity(prefix.len());
+    for c in prefix.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
 }
 
 #[async_trait]
@@ -66,18 +357,10 @@ impl UrlCache for RedisUrlCache {
         trace!(code = %code, "Fetching URL record from Redis cache");
 
         let mut conn = self.conn.clone();
-        match conn.get::<_, Option<String>>(&key).await {
+        match conn.get::<_, Option<Vec<u8>>>(&key).await {
             Ok(Some(cached)) => {
                 debug!(code = %code, "Cache hit in Redis");
-                match serde_json::from_str::<UrlRecord>(&cached) {
-                    Ok(record) => Ok(Some(record)),
-                    Err(e) => {
-                        warn!(code = %code, error = %e, "Failed to deserialize cached record");
-                        Err(CacheError::InvalidData(format!(
-                            "invalid cached value for key '{key}': {e}"
-                        )))
-                    }
-                }
+                self.decode(code, &key, &cached).map(Some)
             }
             Ok(None) => {
                 trace!(code = %code, "Cache miss in Redis");
@@ -90,22 +373,67 @@ impl UrlCache for RedisUrlCache {
         }
     }
 
+    /// Checks for the key with `EXISTS`, without fetching or decoding it.
+    async fn exists(&self, code: &ShortCode) -> Result<bool> {
+        let key = self.cache_key(code);
+        trace!(code = %code, "Checking URL record in Redis cache");
+
+        let mut conn = self.conn.clone();
+        conn.exists::<_, bool>(&key).await.map_err(|e| {
+            warn!(code = %code, error = %e, "Failed to check key in Redis cache");
+            map_redis_error("failed to check key in Redis", e)
+        })
+    }
+
     async fn set_url(&self, code: &ShortCode, record: &UrlRecord) -> Result<()> {
+        match self.ttl_policy.map(|policy| policy.ttl_for(record)) {
+            // The record already expired; drop any older copy instead.
+            Some(Duration::ZERO) => self.del(code).await,
+            ttl => self.set_url_with_ttl(code, record, ttl).await,
+        }
+    }
+
+    /// Without a `ttl`, the entry expires with the record's `expire_at` (at
+    /// least one second out). Records that already expired are not written.
+    async fn set_url_with_ttl(
+        &self,
+        code: &ShortCode,
+        record: &UrlRecord,
+        ttl: Option<Duration>,
+    ) -> Result<()> {
         let key = self.cache_key(code);
-        trace!(code = %code, "Storing URL record in Redis cache");
+        let ttl = match ttl {
+            Some(ttl) => Some(ttl),
+            None => match Self::ttl_until_expiry(record) {
+                Some(Duration::ZERO) => {
+                    // The record already expired; drop any older copy rather
+                    // than caching it.
+                    debug!(code = %code, "Not caching expired record");
+                    return self.del(code).await;
+                }
+                derived => derived,
+            },
+        };
+        trace!(code = %code, ?ttl, "Storing URL record in Redis cache");
 
-        let json = match serde_json::to_string(record) {
-            Ok(json) => json,
+        let value = match self.codec.encode(record) {
+            Ok(value) => value,
             Err(e) => {
                 warn!(code = %code, error = %e, "Failed to serialize record for caching");
-                return Err(CacheError::Serialization(format!(
-                    "failed to serialize cache value: {e}"
-                )));
+                return Err(e);
             }
         };
 
         let mut conn = self.conn.clone();
-        match conn.set::<_, _, ()>(&key, json).await {
+        let result = match ttl {
+            // PX rejects zero, so round sub-millisecond TTLs up.
+            Some(ttl) => {
+                let millis = u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX).max(1);
+                conn.pset_ex::<_, _, ()>(&key, value, millis).await
+            }
+            None => conn.set::<_, _, ()>(&key, value).await,
+        };
+        match result {
             Ok(()) => {
                 debug!(code = %code, "Cached record in Redis");
                 Ok(())
@@ -133,4 +461,159 @@ impl UrlCache for RedisUrlCache {
             }
         }
     }
+
+    /// Removes all `codes` with a single multi-key `DEL`.
+    async fn del_many(&self, codes: &[ShortCode]) -> Result<()> {
+        if codes.is_empty() {
+            return Ok(());
+        }
+
+        let keys: Vec<String> = codes.iter().map(|code| self.cache_key(code)).collect();
+        trace!(count = keys.len(), "Removing URL records from Redis cache");
+
+        let mut conn = self.conn.clone();
+        match conn.del::<_, ()>(&keys).await {
+            Ok(()) => {
+                debug!(count = keys.len(), "Removed records from Redis cache");
+                Ok(())
+            }
+            Err(e) => {
+                warn!(count = keys.len(), error = %e, "Failed to remove records from Redis cache");
+                Err(map_redis_error("failed to delete values from Redis", e))
+            }
+        }
+    }
+
+    /// Deletes every key under the cache prefix, including fetch locks.
+    ///
+    /// Keys are found with `SCAN MATCH <prefix>*` and deleted in batches, so
+    /// other keys in the database are left alone (unlike `FLUSHDB`). This is
+    /// not atomic: keys written while the scan runs may survive, and readers
+    /// can see a partially cleared cache in the meantime.
+    async fn clear(&self) -> Result<()> {
+        let pattern = format!("{}*", escape_glob(&self.key_prefix));
+        trace!(pattern = %pattern, "Clearing Redis cache");
+
+        let mut conn = self.conn.clone();
+        let mut cursor: u64 = 0;
+        let mut removed = 0;
+        loop {
+            let (next, keys): (u64, Vec<String>) = redis::cmd("SCAN")
+                .arg(cursor)
+                .arg("MATCH")
+                .arg(&pattern)
+                .arg("COUNT")
+                .arg(CLEAR_SCAN_COUNT)
+                .query_async(&mut conn)
+                .await
+                .map_err(|e| map_redis_error("failed to scan keys in Redis", e))?;
+
+            if !keys.is_empty() {
+                removed += keys.len();
+                conn.del::<_, ()>(&keys)
+                    .await
+                    .map_err(|e| map_redis_error("failed to delete values from Redis", e))?;
+            }
+
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+
+        debug!(removed, "Cleared Redis cache");
+        Ok(())
+    }
+
+    /// Get URL record from cache, computing it if not present.
+    ///
+    /// Unlike the default implementation, this coordinates across every
+    /// process sharing the Redis instance: only the caller holding a
+    /// short-lived `SET NX PX` lock runs `fetch` and backfills the cache,
+    /// while the others poll for the value. Waiters give up after
+    /// [`SingleFlightConfig::wait_timeout`] and compute the value themselves,
+    /// so a stuck lock holder can delay requests but never block them.
+    async fn get_or_compute<F, Fut>(&self, code: &ShortCode, fetch: F) -> Result<Option<UrlRecord>>
+    where
+        F: FnOnce(&ShortCode) -> Fut + Send,
+        Fut: Future<Output = Result<Option<UrlRecord>>> + Send,
+    {
+        if let Some(record) = self.get_url(code).await? {
+            return Ok(Some(record));
+        }
+
+        let lock_key = self.lock_key(code);
+        let token = lock_token();
+
+        if self.try_lock(&lock_key, &token).await? {
+            trace!(code = %code, "Acquired fetch lock");
+            let result = self.compute_and_backfill(code, fetch).await;
+            if let Err(e) = self.unlock(&lock_key, &token).await {
+                // The lock expires on its own; failing to release it early only
+                // delays waiters, so don't fail the request over it.
+                warn!(code = %code, error = %e, "Failed to release fetch lock");
+            }
+            return result;
+        }
+
+        trace!(code = %code, "Fetch lock held elsewhere, waiting for value");
+        if let Some(record) = self.wait_for_value(code, &lock_key).await? {
+            return Ok(Some(record));
+        }
+
+        self.compute_and_backfill(code, fetch).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+
+    #[test]
+    fn timeouts_and_connectivity_errors_are_told_apart() {
+        let timeout = redis::RedisError::from(io::Error::new(io::ErrorKind::TimedOut, "slow"));
+        assert!(matches!(
+            map_redis_error("GET wh:abc", timeout),
+            CacheError::Timeout(_)
+        ));
+
+        let refused = redis::RedisError::from(io::Error::from(io::ErrorKind::ConnectionRefused));
+        assert!(matches!(
+            map_redis_error("GET wh:abc", refused),
+            CacheError::Unavailable(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn connect_times_out_against_a_silent_server() {
+        // Accepts connections but never answers.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let _server = tokio::spawn(async move {
+            let mut sockets = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                sockets.push(socket);
+            }
+        });
+
+        let config = RedisCacheConfig::builder()
+            .response_timeout(Some(Duration::from_millis(200)))
+            .connection_timeout(Some(Duration::from_millis(200)))
+            .build();
+        let result = tokio::time::timeout(
+            Duration::from_secs(5),
+            RedisUrlCache::connect(&format!("redis://{addr}"), config),
+        )
+        .await
+        .expect("connect should give up on its own");
+
+        assert!(matches!(result, Err(CacheError::Timeout(_))));
+    }
+
+    #[tokio::test]
+    async fn connect_rejects_invalid_urls() {
+        let result = RedisUrlCache::connect("not a url", RedisCacheConfig::default()).await;
+        assert!(matches!(result, Err(CacheError::Initialization(_))));
+    }
 }
@@ -0,0 +1,585 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use redis::cluster::ClusterClient;
+use redis::cluster_async::ClusterConnection;
+use redis::cluster_routing::{RoutingInfo, SingleNodeRoutingInfo};
+use redis::AsyncCommands;
+use tokio::time::Instant;
+use tracing::{debug, trace, warn};
+use wormhole_core::{ShortCode, UrlRecord};
+
+use crate::redis::{escape_glob, lock_token, RELEASE_LOCK_SCRIPT};
+use crate::{CacheError, Result, SingleFlightConfig, TtlPolicy, UrlCache};
+
+/// Number of hash slots in a Redis Cluster.
+const CLUSTER_SLOTS: u16 = 16384;
+
+/// Keys requested per `SCAN` iteration when clearing the cache.
+const CLEAR_SCAN_COUNT: usize = 500;
+
+/// A Redis Cluster implementation of [`UrlCache`].
+///
+/// Records are stored as JSON under the same `wh:url:` prefix as
+/// [`RedisUrlCache`](crate::RedisUrlCache). The cluster client routes every
+/// single-key command to the node owning the key's slot and follows
+/// `MOVED`/`ASK` redirects during resharding.
+///
+/// `get_or_compute` coordinates across processes with the same fetch lock
+/// as `RedisUrlCache`, see [`RedisClusterUrlCache::with_single_flight`].
+#[derive(Clone)]
+pub struct RedisClusterUrlCache {
+    conn: ClusterConnection,
+    key_prefix: String,
+    ttl_policy: Option<TtlPolicy>,
+    single_flight: SingleFlightConfig,
+}
+
+impl std::fmt::Debug for RedisClusterUrlCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisClusterUrlCache")
+            .field("key_prefix", &self.key_prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+fn map_redis_error(operation: &str, err: redis::RedisError) -> CacheError {
+    let message = format!("{operation}: {err}");
+    if err.is_timeout() || message.to_ascii_lowercase().contains("timed out") {
+        CacheError::Timeout(message)
+    } else if err.is_io_error() || err.is_connection_dropped() || err.is_connection_refusal() {
+        CacheError::Unavailable(message)
+    } else {
+        CacheError::Operation(message)
+    }
+}
+
+impl RedisClusterUrlCache {
+    /// Connects to a Redis Cluster.
+    ///
+    /// # Arguments
+    ///
+    /// * `nodes` - Startup node addresses (e.g., `["redis://10.0.0.1:6379"]`);
+    ///   the rest of the cluster is discovered from them
+    pub async fn new<T: AsRef<str>>(nodes: Vec<T>) -> Result<Self> {
+        Self::with_prefix(nodes, "wh:url:").await
+    }
+
+    /// Connects to a Redis Cluster with a custom key prefix.
+    ///
+    /// # Arguments
+    ///
+    /// * `nodes` - Startup node addresses
+    /// * `key_prefix` - Custom prefix for cache keys (e.g., "myapp:url:")
+    pub async fn with_prefix<T: AsRef<str>>(
+        nodes: Vec<T>,
+        key_prefix: impl Into<String>,
+    ) -> Result<Self> {
+        let nodes: Vec<&str> = nodes.iter().map(|node| node.as_ref()).collect();
+        let client = ClusterClient::new(nodes).map_err(|e| {
+            CacheError::Initialization(format!("invalid Redis Cluster configuration: {e}"))
+        })?;
+        let conn = client.get_async_connection().await.map_err(|e| {
+            CacheError::Unavailable(format!("failed to connect to Redis Cluster: {e}"))
+        })?;
+
+        Ok(Self::from_connection(conn, key_prefix))
+    }
+
+    /// Wraps an existing cluster connection.
+    pub fn from_connection(conn: ClusterConnection, key_prefix: impl Into<String>) -> Self {
+        Self {
+            conn,
+            key_prefix: key_prefix.into(),
+            ttl_policy: None,
+            single_flight: SingleFlightConfig::default(),
+        }
+    }
+
+    /// Configures the distributed single-flight used by `get_or_compute`.
+    pub fn with_single_flight(mut self, config: SingleFlightConfig) -> Self {
+        self.single_flight = config;
+        self
+    }
+
+    /// Gives entries written by `set_url` a TTL from `policy`.
+    ///
+    /// Without a policy they never expire. `set_url_with_ttl` is unaffected.
+    pub fn with_ttl_policy(mut self, policy: TtlPolicy) -> Self {
+        self.ttl_policy = Some(policy);
+        self
+    }
+
+    /// Generates the cache key for a short code.
+    fn cache_key(&self, code: &ShortCode) -> String {
+        format!("{}{}", self.key_prefix, code.as_str())
+    }
+
+    /// Generates the fetch lock key for a short code.
+    ///
+    /// The lock lives in its own slot; the lock and value commands are
+    /// separate, so they need not share one.
+    fn lock_key(&self, code: &ShortCode) -> String {
+        format!("{}lock:{}", self.key_prefix, code.as_str())
+    }
+
+    /// Tries to take the fetch lock, returning whether it was acquired.
+    async fn try_lock(&self, lock_key: &str, token: &str) -> Result<bool> {
+        let mut conn = self.conn.clone();
+        let reply: Option<String> = redis::cmd("SET")
+            .arg(lock_key)
+            .arg(token)
+            .arg("NX")
+            .arg("PX")
+            .arg(self.single_flight.lock_ttl.as_millis() as u64)
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| map_redis_error("failed to acquire fetch lock", e))?;
+        Ok(reply.is_some())
+    }
+
+    async fn unlock(&self, lock_key: &str, token: &str) -> Result<()> {
+        let mut conn = self.conn.clone();
+        redis::Script::new(RELEASE_LOCK_SCRIPT)
+            .key(lock_key)
+            .arg(token)
+            .invoke_async::<i64>(&mut conn)
+            .await
+            .map_err(|e| map_redis_error("failed to release fetch lock", e))?;
+        Ok(())
+    }
+
+    async fn is_locked(&self, lock_key: &str) -> Result<bool> {
+        let mut conn = self.conn.clone();
+        conn.exists::<_, bool>(lock_key)
+            .await
+            .map_err(|e| map_redis_error("failed to check fetch lock", e))
+    }
+
+    /// Waits for the lock holder to publish the value, as in
+    /// `RedisUrlCache`.
+    async fn wait_for_value(&self, code: &ShortCode, lock_key: &str) -> Result<Option<UrlRecord>> {
+        let deadline = Instant::now() + self.single_flight.wait_timeout;
+
+        while Instant::now() < deadline {
+            tokio::time::sleep(self.single_flight.poll_interval).await;
+
+            if let Some(record) = self.get_url(code).await? {
+                return Ok(Some(record));
+            }
+            if !self.is_locked(lock_key).await? {
+                return Ok(None);
+            }
+        }
+
+        warn!(code = %code, "Timed out waiting for fetch lock holder, computing anyway");
+        Ok(None)
+    }
+
+    async fn compute_and_backfill<F, Fut>(
+        &self,
+        code: &ShortCode,
+        fetch: F,
+    ) -> Result<Option<UrlRecord>>
+    where
+        F: FnOnce(&ShortCode) -> Fut + Send,
+        Fut: Future<Output = Result<Option<UrlRecord>>> + Send,
+    {
+        let record = fetch(code).await?;
+        if let Some(ref value) = record {
+            self.set_url(code, value).await?;
+        }
+        Ok(record)
+    }
+
+    /// Fetches several records, returned in the order of `codes`.
+    ///
+    /// `MGET` only works on keys sharing a slot, so the keys are grouped by
+    /// slot and one `MGET` is sent per group. Short codes spread across the
+    /// keyspace, so this usually fans out to roughly one command per code
+    /// and touches most nodes of the cluster.
+    pub async fn get_urls(&self, codes: &[ShortCode]) -> Result<Vec<Option<UrlRecord>>> {
+        let keys: Vec<String> = codes.iter().map(|code| self.cache_key(code)).collect();
+        let by_slot = group_by_slot(&keys);
+        trace!(
+            count = codes.len(),
+            slots = by_slot.len(),
+            "Fetching URL records from Redis Cluster"
+        );
+
+        let mut records = vec![None; codes.len()];
+        let mut conn = self.conn.clone();
+        for indices in by_slot.into_values() {
+            let slot_keys: Vec<&str> = indices.iter().map(|&i| keys[i].as_str()).collect();
+            let values: Vec<Option<String>> = redis::cmd("MGET")
+                .arg(&slot_keys)
+                .query_async(&mut conn)
+                .await
+                .map_err(|e| map_redis_error("failed to fetch values from Redis Cluster", e))?;
+
+            for (i, value) in indices.into_iter().zip(values) {
+                if let Some(json) = value {
+                    records[i] = Some(decode(&keys[i], &json)?);
+                }
+            }
+        }
+
+        Ok(records)
+    }
+
+    /// Deletes `keys` with one `DEL` per hash slot, as in `get_urls`.
+    async fn del_keys(&self, keys: &[String]) -> Result<()> {
+        let mut conn = self.conn.clone();
+        for indices in group_by_slot(keys).into_values() {
+            let slot_keys: Vec<&str> = indices.iter().map(|&i| keys[i].as_str()).collect();
+            conn.del::<_, ()>(&slot_keys)
+                .await
+                .map_err(|e| map_redis_error("failed to delete values from Redis Cluster", e))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the addresses of the cluster's primaries from `CLUSTER NODES`.
+    async fn primaries(&self) -> Result<Vec<(String, u16)>> {
+        let mut conn = self.conn.clone();
+        let nodes: String = redis::cmd("CLUSTER")
+            .arg("NODES")
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| map_redis_error("failed to list Redis Cluster nodes", e))?;
+        Ok(primary_addresses(&nodes))
+    }
+}
+
+/// Groups the indices of `keys` by the hash slot of the key.
+fn group_by_slot(keys: &[String]) -> BTreeMap<u16, Vec<usize>> {
+    let mut by_slot: BTreeMap<u16, Vec<usize>> = BTreeMap::new();
+    for (i, key) in keys.iter().enumerate() {
+        by_slot.entry(key_slot(key)).or_default().push(i);
+    }
+    by_slot
+}
+
+/// Parses the reachable primaries out of a `CLUSTER NODES` reply.
+///
+/// Each line reads `<id> <ip:port@cport[,hostname]> <flags> ...`.
+fn primary_addresses(nodes: &str) -> Vec<(String, u16)> {
+    nodes
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let address = fields.nth(1)?;
+            let flags = fields.next()?;
+            let usable = flags
+                .split(',')
+                .all(|flag| !matches!(flag, "fail" | "handshake" | "noaddr"));
+            if !usable || !flags.split(',').any(|flag| flag == "master") {
+                return None;
+            }
+            let (host, port) = address.split(['@', ',']).next()?.rsplit_once(':')?;
+            Some((host.to_string(), port.parse().ok()?))
+        })
+        .collect()
+}
+
+fn decode(key: &str, json: &str) -> Result<UrlRecord> {
+    serde_json::from_str(json)
+        .map_err(|e| CacheError::InvalidData(format!("invalid cached value for key '{key}': {e}")))
+}
+
+/// Returns the cluster hash slot of `key`, honoring `{hash tags}`.
+fn key_slot(key: &str) -> u16 {
+    let bytes = key.as_bytes();
+    let hashed = match bytes.iter().position(|&b| b == b'{') {
+        Some(open) => match bytes[open + 1..].iter().position(|&b| b == b'}') {
+            Some(len) if len > 0 => &bytes[open + 1..open + 1 + len],
+            _ => bytes,
+        },
+        None => bytes,
+    };
+    crc16(hashed) % CLUSTER_SLOTS
+}
+
+/// CRC16-CCITT (XMODEM), as used by Redis Cluster.
+fn crc16(bytes: &[u8]) -> u16 {
+    let mut crc: u16 = 0;
+    for &byte in bytes {
+        crc ^= u16::from(byte) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+#[async_trait]
+impl UrlCache for RedisClusterUrlCache {
+    async fn get_url(&self, code: &ShortCode) -> Result<Option<UrlRecord>> {
+        let key = self.cache_key(code);
+        trace!(code = %code, "Fetching URL record from Redis Cluster cache");
+
+        let mut conn = self.conn.clone();
+        match conn.get::<_, Option<String>>(&key).await {
+            Ok(Some(cached)) => {
+                debug!(code = %code, "Cache hit in Redis Cluster");
+                decode(&key, &cached).map(Some).inspect_err(|e| {
+                    warn!(code = %code, error = %e, "Failed to deserialize cached record");
+                })
+            }
+            Ok(None) => {
+                trace!(code = %code, "Cache miss in Redis Cluster");
+                Ok(None)
+            }
+            Err(e) => {
+                warn!(code = %code, error = %e, "Redis Cluster error on get");
+                Err(map_redis_error(
+                    "failed to fetch value from Redis Cluster",
+                    e,
+                ))
+            }
+        }
+    }
+
+    async fn set_url(&self, code: &ShortCode, record: &UrlRecord) -> Result<()> {
+        match self.ttl_policy.map(|policy| policy.ttl_for(record)) {
+            // The record already expired; drop any older copy instead.
+            Some(Duration::ZERO) => self.del(code).await,
+            ttl => self.set_url_with_ttl(code, record, ttl).await,
+        }
+    }
+
+    async fn set_url_with_ttl(
+        &self,
+        code: &ShortCode,
+        record: &UrlRecord,
+        ttl: Option<Duration>,
+    ) -> Result<()> {
+        let key = self.cache_key(code);
+        trace!(code = %code, ?ttl, "Storing URL record in Redis Cluster cache");
+
+        let json = serde_json::to_string(record).map_err(|e| {
+            warn!(code = %code, error = %e, "Failed to serialize record for caching");
+            CacheError::Serialization(format!("failed to serialize cache value: {e}"))
+        })?;
+
+        let mut conn = self.conn.clone();
+        let result = match ttl {
+            // PX rejects zero, so round sub-millisecond TTLs up.
+            Some(ttl) => {
+                let millis = u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX).max(1);
+                conn.pset_ex::<_, _, ()>(&key, json, millis).await
+            }
+            None => conn.set::<_, _, ()>(&key, json).await,
+        };
+        match result {
+            Ok(()) => {
+                debug!(code = %code, "Cached record in Redis Cluster");
+                Ok(())
+            }
+            Err(e) => {
+                warn!(code = %code, error = %e, "Failed to cache record in Redis Cluster");
+                Err(map_redis_error("failed to write value to Redis Cluster", e))
+            }
+        }
+    }
+
+    async fn del(&self, code: &ShortCode) -> Result<()> {
+        let key = self.cache_key(code);
+        trace!(code = %code, "Removing URL record from Redis Cluster cache");
+
+        let mut conn = self.conn.clone();
+        match conn.del::<_, ()>(&key).await {
+            Ok(()) => {
+                debug!(code = %code, "Removed record from Redis Cluster cache");
+                Ok(())
+            }
+            Err(e) => {
+                warn!(code = %code, error = %e, "Failed to remove record from Redis Cluster cache");
+                Err(map_redis_error(
+                    "failed to delete value from Redis Cluster",
+                    e,
+                ))
+            }
+        }
+    }
+
+    /// Deletes the keys with one `DEL` per hash slot; see
+    /// [`RedisClusterUrlCache::get_urls`] for why they are grouped.
+    async fn del_many(&self, codes: &[ShortCode]) -> Result<()> {
+        if codes.is_empty() {
+            return Ok(());
+        }
+
+        let keys: Vec<String> = codes.iter().map(|code| self.cache_key(code)).collect();
+        trace!(
+            count = keys.len(),
+            "Removing URL records from Redis Cluster cache"
+        );
+        self.del_keys(&keys).await.inspect_err(|e| {
+            warn!(count = keys.len(), error = %e, "Failed to remove records from Redis Cluster cache");
+        })
+    }
+
+    /// Checks for the key with `EXISTS`, without fetching or decoding it.
+    async fn exists(&self, code: &ShortCode) -> Result<bool> {
+        let key = self.cache_key(code);
+        trace!(code = %code, "Checking URL record in Redis Cluster cache");
+
+        let mut conn = self.conn.clone();
+        conn.exists::<_, bool>(&key).await.map_err(|e| {
+            warn!(code = %code, error = %e, "Failed to check key in Redis Cluster cache");
+            map_redis_error("failed to check key in Redis Cluster", e)
+        })
+    }
+
+    /// Deletes every key under the cache prefix by running `SCAN` on each
+    /// primary in turn, since a cluster has no cross-node cursor.
+    ///
+    /// Not atomic, see [`RedisUrlCache`](crate::RedisUrlCache)'s `clear`. A
+    /// resharding in progress can move keys to a primary that was already
+    /// scanned, leaving them behind.
+    async fn clear(&self) -> Result<()> {
+        let pattern = format!("{}*", escape_glob(&self.key_prefix));
+        let primaries = self.primaries().await?;
+        trace!(pattern = %pattern, primaries = primaries.len(), "Clearing Redis Cluster cache");
+
+        let mut conn = self.conn.clone();
+        let mut removed = 0;
+        for (host, port) in primaries {
+            let routing = RoutingInfo::SingleNode(SingleNodeRoutingInfo::ByAddress {
+                host: host.clone(),
+                port,
+            });
+            let mut cursor: u64 = 0;
+            loop {
+                let mut scan = redis::cmd("SCAN");
+                scan.arg(cursor)
+                    .arg("MATCH")
+                    .arg(&pattern)
+                    .arg("COUNT")
+                    .arg(CLEAR_SCAN_COUNT);
+                let reply = conn
+                    .route_command(scan, routing.clone())
+                    .await
+                    .map_err(|e| map_redis_error("failed to scan keys in Redis Cluster", e))?;
+                let (next, keys): (u64, Vec<String>) =
+                    redis::from_redis_value(reply).map_err(|e| {
+                        CacheError::Operation(format!(
+                            "unexpected SCAN reply from {host}:{port}: {e}"
+                        ))
+                    })?;
+
+                if !keys.is_empty() {
+                    removed += keys.len();
+                    self.del_keys(&keys).await?;
+                }
+
+                if next == 0 {
+                    break;
+                }
+                cursor = next;
+            }
+        }
+
+        debug!(removed, "Cleared Redis Cluster cache");
+        Ok(())
+    }
+
+    /// Get URL record from cache, computing it if not present.
+    ///
+    /// Only the caller holding the fetch lock runs `fetch`; the others poll
+    /// for the value, as in [`RedisUrlCache`](crate::RedisUrlCache). The lock
+    /// uses the same key and token scheme, so instances on either cache type
+    /// coordinate when they share a prefix.
+    async fn get_or_compute<F, Fut>(&self, code: &ShortCode, fetch: F) -> Result<Option<UrlRecord>>
+    where
+        F: FnOnce(&ShortCode) -> Fut + Send,
+        Fut: Future<Output = Result<Option<UrlRecord>>> + Send,
+    {
+        if let Some(record) = self.get_url(code).await? {
+            return Ok(Some(record));
+        }
+
+        let lock_key = self.lock_key(code);
+        let token = lock_token();
+
+        if self.try_lock(&lock_key, &token).await? {
+            trace!(code = %code, "Acquired fetch lock in Redis Cluster");
+            let result = self.compute_and_backfill(code, fetch).await;
+            if let Err(e) = self.unlock(&lock_key, &token).await {
+                // The lock expires on its own; failing to release it early only
+                // delays waiters, so don't fail the request over it.
+                warn!(code = %code, error = %e, "Failed to release fetch lock");
+            }
+            return result;
+        }
+
+        trace!(code = %code, "Fetch lock held elsewhere, waiting for value");
+        if let Some(record) = self.wait_for_value(code, &lock_key).await? {
+            return Ok(Some(record));
+        }
+
+        self.compute_and_backfill(code, fetch).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc16_matches_the_cluster_spec() {
+        // Reference value from the Redis Cluster specification.
+        assert_eq!(crc16(b"123456789"), 0x31C3);
+    }
+
+    #[test]
+    fn key_slot_matches_redis() {
+        // Values as reported by `CLUSTER KEYSLOT`.
+        assert_eq!(key_slot("foo"), 12182);
+        assert_eq!(key_slot("bar"), 5061);
+        assert_eq!(key_slot("somekey"), 11058);
+    }
+
+    #[test]
+    fn key_slot_hashes_only_the_hash_tag() {
+        assert_eq!(key_slot("{user1000}.following"), key_slot("user1000"));
+        assert_eq!(key_slot("wh:url:{abc}1"), key_slot("wh:url:{abc}2"));
+        // Empty or unterminated tags hash the whole key.
+        assert_eq!(key_slot("foo{}bar"), crc16(b"foo{}bar") % CLUSTER_SLOTS);
+        assert_eq!(key_slot("foo{bar"), crc16(b"foo{bar") % CLUSTER_SLOTS);
+    }
+
+    #[test]
+    fn primary_addresses_skip_replicas_and_failed_nodes() {
+        let nodes = "\
+07c37dfeb235213a872192d90877d0cd55635b91 127.0.0.1:30004@31004,node4 slave e7d1eecce10fd6bb5eb35b9f99a514335d9ba9ca 0 1426238317239 4 connected
+67ed2db8d677e59ec4a4cefb06858cf2a1a89fa1 127.0.0.1:30002@31002 master - 0 1426238316232 2 connected 5461-10922
+292f8b365bb7edb5e285caf0b7e6ddc7265d2f4f 127.0.0.1:30003@31003 master - 0 1426238318243 3 connected 10923-16383
+e7d1eecce10fd6bb5eb35b9f99a514335d9ba9ca 127.0.0.1:30001@31001,node1 myself,master - 0 0 1 connected 0-5460
+6ec23923021cf3ffec47632106199cb7f496ce01 127.0.0.1:30005@31005 master,fail - 1426238316232 0 5 disconnected
+";
+        assert_eq!(
+            primary_addresses(nodes),
+            vec![
+                ("127.0.0.1".to_string(), 30002),
+                ("127.0.0.1".to_string(), 30003),
+                ("127.0.0.1".to_string(), 30001),
+            ]
+        );
+    }
+
+    #[test]
+    fn cache_keys_spread_across_slots() {
+        let slots: std::collections::HashSet<_> = (0..100)
+            .map(|i| key_slot(&format!("wh:url:code{i}")))
+            .collect();
+        assert!(slots.len() > 90);
+    }
+}
@@ -1,25 +1,43 @@
+use std::time::Duration;
+
 use async_trait::async_trait;
 use deadpool_redis::redis::AsyncCommands;
 use tracing::{debug, trace, warn};
 use wormhole_core::{ShortCode, UrlRecord};
 
-use crate::{CacheError, Result, UrlCache};
+use crate::redis::escape_glob;
+use crate::{CacheError, Result, TtlPolicy, UrlCache};
 
 /// A Redis Sentinel-based high-availability implementation of [`UrlCache`].
 ///
 /// This implementation uses separate connection pools for master (writes)
 /// and replicas (reads), providing read scalability and automatic failover.
+///
+/// Replication is asynchronous, so a replica can briefly miss a write this
+/// cache just made. See [`RedisHAUrlCache::with_read_from_master_after_write`]
+/// to read recently written codes from the master instead.
 #[derive(Debug, Clone)]
 pub struct RedisHAUrlCache {
     master_pool: deadpool_redis::sentinel::Pool,
     replica_pool: deadpool_redis::sentinel::Pool,
     key_prefix: String,
+    ttl_policy: Option<TtlPolicy>,
+    recent_writes: Option<moka::future::Cache<String, ()>>,
 }
 
+/// Most keys [`RedisHAUrlCache`] remembers as recently written; older ones
+/// are evicted first and go back to being read from replicas.
+const RECENT_WRITES_CAPACITY: u64 = 100_000;
+
+/// Keys requested per `SCAN` iteration when clearing the cache.
+const CLEAR_SCAN_COUNT: usize = 500;
+
 fn map_redis_error(operation: &str, err: deadpool_redis::redis::RedisError) -> CacheError {
     let message = format!("{operation}: {err}");
-    if message.to_ascii_lowercase().contains("timed out") {
+    if err.is_timeout() || message.to_ascii_lowercase().contains("timed out") {
         CacheError::Timeout(message)
+    } else if err.is_io_error() || err.is_connection_dropped() || err.is_connection_refusal() {
+        CacheError::Unavailable(message)
     } else {
         CacheError::Operation(message)
     }
@@ -86,30 +104,76 @@ impl RedisHAUrlCache {
             master_pool,
             replica_pool,
             key_prefix: key_prefix.into(),
+            ttl_policy: None,
+            recent_writes: None,
         })
     }
 
+    /// Gives entries written by `set_url` a TTL from `policy`.
+    ///
+    /// Without a policy they never expire. `set_url_with_ttl` is unaffected.
+    pub fn with_ttl_policy(mut self, policy: TtlPolicy) -> Self {
+        self.ttl_policy = Some(policy);
+        self
+    }
+
+    /// Reads a code from the master for `window` after this cache wrote or
+    /// deleted it, instead of from a replica that may not have caught up.
+    ///
+    /// Without this, a read right after `set_url` can miss on a lagging
+    /// replica and send the caller to the repository, and a read right after
+    /// `del` can still see the old record. Pick a window comfortably above
+    /// the usual replication lag; writes made by other instances are not
+    /// tracked.
+    pub fn with_read_from_master_after_write(mut self, window: Duration) -> Self {
+        self.recent_writes = Some(
+            moka::future::Cache::builder()
+                .max_capacity(RECENT_WRITES_CAPACITY)
+                .time_to_live(window)
+                .build(),
+        );
+        self
+    }
+
     /// Generates the cache key for a short code.
     fn cache_key(&self, code: &ShortCode) -> String {
         format!("{}{}", self.key_prefix, code.as_str())
     }
+
+    /// Notes that `key` was just written on the master.
+    async fn mark_written(&self, key: &str) {
+        if let Some(recent_writes) = &self.recent_writes {
+            recent_writes.insert(key.to_string(), ()).await;
+        }
+    }
+
+    /// Returns `true` if `key` is still within its read-from-master window.
+    fn recently_written(&self, key: &str) -> bool {
+        self.recent_writes
+            .as_ref()
+            .is_some_and(|recent_writes| recent_writes.contains_key(key))
+    }
 }
 
 #[async_trait]
 impl UrlCache for RedisHAUrlCache {
     async fn get_url(&self, code: &ShortCode) -> Result<Option<UrlRecord>> {
         let key = self.cache_key(code);
-        trace!(code = %code, "Fetching URL record from Redis HA cache (replica)");
+        let (pool, node) = if self.recently_written(&key) {
+            (&self.master_pool, "master")
+        } else {
+            (&self.replica_pool, "replica")
+        };
+        trace!(code = %code, node, "Fetching URL record from Redis HA cache");
 
-        let mut conn = self
-            .replica_pool
+        let mut conn = pool
             .get()
             .await
-            .map_err(|e| map_pool_error("failed to get replica connection", e))?;
+            .map_err(|e| map_pool_error(&format!("failed to get {node} connection"), e))?;
 
         match conn.get::<_, Option<String>>(&key).await {
             Ok(Some(cached)) => {
-                debug!(code = %code, "Cache hit in Redis HA (replica)");
+                debug!(code = %code, node, "Cache hit in Redis HA");
                 match serde_json::from_str::<UrlRecord>(&cached) {
                     Ok(record) => Ok(Some(record)),
                     Err(e) => {
@@ -125,15 +189,32 @@ impl UrlCache for RedisHAUrlCache {
                 Ok(None)
             }
             Err(e) => {
-                warn!(code = %code, error = %e, "Redis error on get from replica");
-                Err(map_redis_error("failed to fetch value from replica", e))
+                warn!(code = %code, node, error = %e, "Redis error on get");
+                Err(map_redis_error(
+                    &format!("failed to fetch value from {node}"),
+                    e,
+                ))
             }
         }
     }
 
     async fn set_url(&self, code: &ShortCode, record: &UrlRecord) -> Result<()> {
+        match self.ttl_policy.map(|policy| policy.ttl_for(record)) {
+            // The record already expired; drop any older copy instead.
+            Some(Duration::ZERO) => self.del(code).await,
+            ttl => self.set_url_with_ttl(code, record, ttl).await,
+        }
+    }
+
+    async fn set_url_with_ttl(
+        &self,
+        code: &ShortCode,
+        record: &UrlRecord,
+        ttl: Option<Duration>,
+    ) -> Result<()> {
         let key = self.cache_key(code);
-        trace!(code = %code, "Storing URL record in Redis HA cache (master)");
+        trace!(code = %code, ?ttl, "Storing URL record in Redis HA cache (master)");
+        self.mark_written(&key).await;
 
         let json = match serde_json::to_string(record) {
             Ok(json) => json,
@@ -153,7 +234,15 @@ impl UrlCache for RedisHAUrlCache {
             }
         };
 
-        match conn.set::<_, _, ()>(&key, json).await {
+        let result = match ttl {
+            // PX rejects zero, so round sub-millisecond TTLs up.
+            Some(ttl) => {
+                let millis = u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX).max(1);
+                conn.pset_ex::<_, _, ()>(&key, json, millis).await
+            }
+            None => conn.set::<_, _, ()>(&key, json).await,
+        };
+        match result {
             Ok(()) => {
                 debug!(code = %code, "Cached record in Redis HA (master)");
                 Ok(())
@@ -168,6 +257,7 @@ impl UrlCache for RedisHAUrlCache {
     async fn del(&self, code: &ShortCode) -> Result<()> {
         let key = self.cache_key(code);
         trace!(code = %code, "Removing URL record from Redis HA cache (master)");
+        self.mark_written(&key).await;
 
         let mut conn = match self.master_pool.get().await {
             Ok(conn) => conn,
@@ -188,6 +278,108 @@ impl UrlCache for RedisHAUrlCache {
             }
         }
     }
+
+    async fn del_many(&self, codes: &[ShortCode]) -> Result<()> {
+        if codes.is_empty() {
+            return Ok(());
+        }
+
+        let keys: Vec<String> = codes.iter().map(|code| self.cache_key(code)).collect();
+        trace!(
+            count = keys.len(),
+            "Removing URL records from Redis HA cache (master)"
+        );
+        for key in &keys {
+            self.mark_written(key).await;
+        }
+
+        let mut conn = match self.master_pool.get().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                warn!(error = %e, "Failed to get connection from master pool");
+                return Err(map_pool_error("failed to get master connection", e));
+            }
+        };
+
+        match conn.del::<_, ()>(&keys).await {
+            Ok(()) => {
+                debug!(count = keys.len(), "Removed records from Redis HA cache");
+                Ok(())
+            }
+            Err(e) => {
+                warn!(count = keys.len(), error = %e, "Failed to remove records from Redis HA cache");
+                Err(map_redis_error("failed to delete values from master", e))
+            }
+        }
+    }
+
+    /// Checks for the key with `EXISTS`, on the same node `get_url` would
+    /// read from.
+    async fn exists(&self, code: &ShortCode) -> Result<bool> {
+        let key = self.cache_key(code);
+        let (pool, node) = if self.recently_written(&key) {
+            (&self.master_pool, "master")
+        } else {
+            (&self.replica_pool, "replica")
+        };
+        trace!(code = %code, node, "Checking URL record in Redis HA cache");
+
+        let mut conn = pool
+            .get()
+            .await
+            .map_err(|e| map_pool_error(&format!("failed to get {node} connection"), e))?;
+        conn.exists::<_, bool>(&key).await.map_err(|e| {
+            warn!(code = %code, node, error = %e, "Failed to check key in Redis HA cache");
+            map_redis_error(&format!("failed to check key on {node}"), e)
+        })
+    }
+
+    /// Deletes every key under the cache prefix with a batched `SCAN` +
+    /// `DEL` on the master; not atomic, see
+    /// [`RedisUrlCache`](crate::RedisUrlCache)'s `clear`.
+    ///
+    /// Replicas catch up asynchronously, so the removed keys are read from
+    /// the master for the read-from-master window, as after `del`.
+    async fn clear(&self) -> Result<()> {
+        let pattern = format!("{}*", escape_glob(&self.key_prefix));
+        trace!(pattern = %pattern, "Clearing Redis HA cache (master)");
+
+        let mut conn = self.master_pool.get().await.map_err(|e| {
+            warn!(error = %e, "Failed to get connection from master pool");
+            map_pool_error("failed to get master connection", e)
+        })?;
+        let mut cursor: u64 = 0;
+        let mut removed = 0;
+        loop {
+            let (next, keys): (u64, Vec<String>) = deadpool_redis::redis::cmd("SCAN")
+                .arg(cursor)
+                .arg("MATCH")
+                .arg(&pattern)
+                .arg("COUNT")
+                .arg(CLEAR_SCAN_COUNT)
+                .query_async(&mut conn)
+                .await
+                .map_err(|e| map_redis_error("failed to scan keys on master", e))?;
+
+            if !keys.is_empty() {
+                removed += keys.len();
+                for key in &keys {
+                    self.mark_written(key).await;
+                }
+                conn.del::<_, ()>(&keys)
+                    .await
+                    .map_err(|e| map_redis_error("failed to delete values from master", e))?;
+            }
+
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+
+        debug!(removed, "Cleared Redis HA cache");
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,440 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use deadpool_redis::redis::AsyncCommands;
+use deadpool_redis::{Connection, Pool, PoolConfig, PoolError, Runtime};
+use tokio::time::Instant;
+use tracing::{debug, trace, warn};
+use typed_builder::TypedBuilder;
+use wormhole_core::{ShortCode, UrlRecord};
+
+use crate::redis::{escape_glob, lock_token, RELEASE_LOCK_SCRIPT};
+use crate::{CacheCodec, CacheError, JsonCodec, Result, SingleFlightConfig, TtlPolicy, UrlCache};
+
+/// Keys requested per `SCAN` iteration when clearing the cache.
+const CLEAR_SCAN_COUNT: usize = 500;
+
+/// Settings for [`PooledRedisUrlCache`].
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct PooledRedisConfig {
+    /// Redis URL, e.g. "redis://localhost:6379".
+    #[builder(setter(into))]
+    pub url: String,
+    /// Maximum number of open connections.
+    #[builder(default = 16)]
+    pub max_size: usize,
+    /// How long to wait for a free connection before giving up with
+    /// [`CacheError::Timeout`].
+    #[builder(default = Duration::from_secs(1))]
+    pub wait_timeout: Duration,
+    /// Prefix for cache keys.
+    #[builder(default = "wh:url:".to_string(), setter(into))]
+    pub key_prefix: String,
+}
+
+/// A Redis implementation of [`UrlCache`] backed by a connection pool.
+///
+/// [`RedisUrlCache`](crate::RedisUrlCache) sends every command through one
+/// multiplexed connection, which can become the bottleneck under heavy
+/// concurrency. This cache spreads commands over up to
+/// [`PooledRedisConfig::max_size`] connections instead. Keys and values are
+/// the same as `RedisUrlCache`'s, so both can share a Redis instance.
+///
+/// `get_or_compute` takes the same distributed fetch lock as
+/// `RedisUrlCache`'s, so callers of both coordinate with each other.
+#[derive(Debug, Clone)]
+pub struct PooledRedisUrlCache {
+    pool: Pool,
+    key_prefix: String,
+    single_flight: SingleFlightConfig,
+    codec: Arc<dyn CacheCodec>,
+    ttl_policy: Option<TtlPolicy>,
+}
+
+fn map_redis_error(operation: &str, err: deadpool_redis::redis::RedisError) -> CacheError {
+    let message = format!("{operation}: {err}");
+    if err.is_timeout() || message.to_ascii_lowercase().contains("timed out") {
+        CacheError::Timeout(message)
+    } else if err.is_io_error() || err.is_connection_dropped() || err.is_connection_refusal() {
+        CacheError::Unavailable(message)
+    } else {
+        CacheError::Operation(message)
+    }
+}
+
+fn map_pool_error(operation: &str, err: PoolError) -> CacheError {
+    let message = format!("{operation}: {err}");
+    match err {
+        PoolError::Timeout(_) => CacheError::Timeout(message),
+        _ => CacheError::Unavailable(message),
+    }
+}
+
+impl PooledRedisUrlCache {
+    /// Creates the pool. Connections are opened lazily on first use.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CacheError::Initialization` if the URL is invalid.
+    pub fn new(config: PooledRedisConfig) -> Result<Self> {
+        let mut pool_config = PoolConfig::new(config.max_size);
+        pool_config.timeouts.wait = Some(config.wait_timeout);
+
+        let mut redis_config = deadpool_redis::Config::from_url(config.url);
+        redis_config.pool = Some(pool_config);
+
+        let pool = redis_config
+            .create_pool(Some(Runtime::Tokio1))
+            .map_err(|e| CacheError::Initialization(format!("failed to create pool: {e}")))?;
+
+        Ok(Self {
+            pool,
+            key_prefix: config.key_prefix,
+            single_flight: SingleFlightConfig::default(),
+            codec: Arc::new(JsonCodec),
+            ttl_policy: None,
+        })
+    }
+
+    /// Sets the format used to encode cached values.
+    ///
+    /// As with [`RedisUrlCache::with_codec`](crate::RedisUrlCache::with_codec),
+    /// change the key prefix along with the codec.
+    pub fn with_codec(mut self, codec: impl CacheCodec) -> Self {
+        self.codec = Arc::new(codec);
+        self
+    }
+
+    /// Overrides the lock and polling settings used by `get_or_compute`.
+    pub fn with_single_flight(mut self, config: SingleFlightConfig) -> Self {
+        self.single_flight = config;
+        self
+    }
+
+    /// Gives entries written by `set_url` a TTL from `policy`.
+    ///
+    /// Without a policy they never expire. `set_url_with_ttl` is unaffected.
+    pub fn with_ttl_policy(mut self, policy: TtlPolicy) -> Self {
+        self.ttl_policy = Some(policy);
+        self
+    }
+
+    /// Returns the current pool size and idle connection count.
+    pub fn status(&self) -> deadpool_redis::Status {
+        self.pool.status()
+    }
+
+    /// Generates the cache key for a short code.
+    fn cache_key(&self, code: &ShortCode) -> String {
+        format!("{}{}", self.key_prefix, code.as_str())
+    }
+
+    async fn connection(&self, code: &ShortCode) -> Result<Connection> {
+        self.pool.get().await.map_err(|e| {
+            warn!(code = %code, error = %e, "Failed to get connection from Redis pool");
+            map_pool_error("failed to get connection", e)
+        })
+    }
+
+    /// Generates the fetch lock key for a short code, matching
+    /// `RedisUrlCache`'s.
+    fn lock_key(&self, code: &ShortCode) -> String {
+        format!("{}lock:{}", self.key_prefix, code.as_str())
+    }
+
+    /// Tries to take the fetch lock, returning whether it was acquired.
+    async fn try_lock(&self, code: &ShortCode, lock_key: &str, token: &str) -> Result<bool> {
+        let mut conn = self.connection(code).await?;
+        let reply: Option<String> = deadpool_redis::redis::cmd("SET")
+            .arg(lock_key)
+            .arg(token)
+            .arg("NX")
+            .arg("PX")
+            .arg(self.single_flight.lock_ttl.as_millis() as u64)
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| map_redis_error("failed to acquire fetch lock", e))?;
+        Ok(reply.is_some())
+    }
+
+    async fn unlock(&self, code: &ShortCode, lock_key: &str, token: &str) -> Result<()> {
+        let mut conn = self.connection(code).await?;
+        deadpool_redis::redis::Script::new(RELEASE_LOCK_SCRIPT)
+            .key(lock_key)
+            .arg(token)
+            .invoke_async::<i64>(&mut conn)
+            .await
+            .map_err(|e| map_redis_error("failed to release fetch lock", e))?;
+        Ok(())
+    }
+
+    async fn is_locked(&self, code: &ShortCode, lock_key: &str) -> Result<bool> {
+        let mut conn = self.connection(code).await?;
+        conn.exists::<_, bool>(lock_key)
+            .await
+            .map_err(|e| map_redis_error("failed to check fetch lock", e))
+    }
+
+    /// Waits for the lock holder to publish the value, see
+    /// `RedisUrlCache`'s `get_or_compute`.
+    async fn wait_for_value(&self, code: &ShortCode, lock_key: &str) -> Result<Option<UrlRecord>> {
+        let deadline = Instant::now() + self.single_flight.wait_timeout;
+
+        while Instant::now() < deadline {
+            tokio::time::sleep(self.single_flight.poll_interval).await;
+
+            if let Some(record) = self.get_url(code).await? {
+                return Ok(Some(record));
+            }
+            if !self.is_locked(code, lock_key).await? {
+                return Ok(None);
+            }
+        }
+
+        warn!(code = %code, "Timed out waiting for fetch lock holder, computing anyway");
+        Ok(None)
+    }
+
+    async fn compute_and_backfill<F, Fut>(
+        &self,
+        code: &ShortCode,
+        fetch: F,
+    ) -> Result<Option<UrlRecord>>
+    where
+        F: FnOnce(&ShortCode) -> Fut + Send,
+        Fut: Future<Output = Result<Option<UrlRecord>>> + Send,
+    {
+        let record = fetch(code).await?;
+        if let Some(ref value) = record {
+            self.set_url(code, value).await?;
+        }
+        Ok(record)
+    }
+}
+
+#[async_trait]
+impl UrlCache for PooledRedisUrlCache {
+    async fn get_url(&self, code: &ShortCode) -> Result<Option<UrlRecord>> {
+        let key = self.cache_key(code);
+        trace!(code = %code, "Fetching URL record from pooled Redis cache");
+
+        let mut conn = self.connection(code).await?;
+        match conn.get::<_, Option<Vec<u8>>>(&key).await {
+            Ok(Some(cached)) => {
+                debug!(code = %code, "Cache hit in pooled Redis");
+                match self.codec.decode(&cached) {
+                    Ok(record) => Ok(Some(record)),
+                    Err(e) => {
+                        warn!(code = %code, error = %e, "Failed to deserialize cached record");
+                        Err(CacheError::InvalidData(format!(
+                            "invalid cached value for key '{key}': {e}"
+                        )))
+                    }
+                }
+            }
+            Ok(None) => {
+                trace!(code = %code, "Cache miss in pooled Redis");
+                Ok(None)
+            }
+            Err(e) => {
+                warn!(code = %code, error = %e, "Redis error on get");
+                Err(map_redis_error("failed to fetch value from Redis", e))
+            }
+        }
+    }
+
+    /// Checks for the key with `EXISTS`, without fetching or decoding it.
+    async fn exists(&self, code: &ShortCode) -> Result<bool> {
+        let key = self.cache_key(code);
+        trace!(code = %code, "Checking URL record in pooled Redis cache");
+
+        let mut conn = self.connection(code).await?;
+        conn.exists::<_, bool>(&key).await.map_err(|e| {
+            warn!(code = %code, error = %e, "Failed to check key in pooled Redis cache");
+            map_redis_error("failed to check key in Redis", e)
+        })
+    }
+
+    async fn set_url(&self, code: &ShortCode, record: &UrlRecord) -> Result<()> {
+        match self.ttl_policy.map(|policy| policy.ttl_for(record)) {
+            // The record already expired; drop any older copy instead.
+            Some(Duration::ZERO) => self.del(code).await,
+            ttl => self.set_url_with_ttl(code, record, ttl).await,
+        }
+    }
+
+    async fn set_url_with_ttl(
+        &self,
+        code: &ShortCode,
+        record: &UrlRecord,
+        ttl: Option<Duration>,
+    ) -> Result<()> {
+        let key = self.cache_key(code);
+        trace!(code = %code, ?ttl, "Storing URL record in pooled Redis cache");
+
+        let value = match self.codec.encode(record) {
+            Ok(value) => value,
+            Err(e) => {
+                warn!(code = %code, error = %e, "Failed to serialize record for caching");
+                return Err(e);
+            }
+        };
+
+        let mut conn = self.connection(code).await?;
+        let result = match ttl {
+            // PX rejects zero, so round sub-millisecond TTLs up.
+            Some(ttl) => {
+                let millis = u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX).max(1);
+                conn.pset_ex::<_, _, ()>(&key, value, millis).await
+            }
+            None => conn.set::<_, _, ()>(&key, value).await,
+        };
+        match result {
+            Ok(()) => {
+                debug!(code = %code, "Cached record in pooled Redis");
+                Ok(())
+            }
+            Err(e) => {
+                warn!(code = %code, error = %e, "Failed to cache record in pooled Redis");
+                Err(map_redis_error("failed to write value to Redis", e))
+            }
+        }
+    }
+
+    async fn del(&self, code: &ShortCode) -> Result<()> {
+        let key = self.cache_key(code);
+        trace!(code = %code, "Removing URL record from pooled Redis cache");
+
+        let mut conn = self.connection(code).await?;
+        match conn.del::<_, ()>(&key).await {
+            Ok(()) => {
+                debug!(code = %code, "Removed record from pooled Redis cache");
+                Ok(())
+            }
+            Err(e) => {
+                warn!(code = %code, error = %e, "Failed to remove record from pooled Redis cache");
+                Err(map_redis_error("failed to delete value from Redis", e))
+            }
+        }
+    }
+
+    async fn del_many(&self, codes: &[ShortCode]) -> Result<()> {
+        if codes.is_empty() {
+            return Ok(());
+        }
+
+        let keys: Vec<String> = codes.iter().map(|code| self.cache_key(code)).collect();
+        trace!(
+            count = keys.len(),
+            "Removing URL records from pooled Redis cache"
+        );
+
+        let mut conn = self.pool.get().await.map_err(|e| {
+            warn!(error = %e, "Failed to get connection from Redis pool");
+            map_pool_error("failed to get connection", e)
+        })?;
+        conn.del::<_, ()>(&keys).await.map_err(|e| {
+            warn!(count = keys.len(), error = %e, "Failed to remove records from pooled Redis cache");
+            map_redis_error("failed to delete values from Redis", e)
+        })
+    }
+
+    /// Deletes every key under the cache prefix with a batched `SCAN` +
+    /// `DEL`; not atomic, see [`RedisUrlCache`](crate::RedisUrlCache)'s
+    /// `clear`.
+    async fn clear(&self) -> Result<()> {
+        let pattern = format!("{}*", escape_glob(&self.key_prefix));
+        trace!(pattern = %pattern, "Clearing pooled Redis cache");
+
+        let mut conn = self.pool.get().await.map_err(|e| {
+            warn!(error = %e, "Failed to get connection from Redis pool");
+            map_pool_error("failed to get connection", e)
+        })?;
+        let mut cursor: u64 = 0;
+        let mut removed = 0;
+        loop {
+            let (next, keys): (u64, Vec<String>) = deadpool_redis::redis::cmd("SCAN")
+                .arg(cursor)
+                .arg("MATCH")
+                .arg(&pattern)
+                .arg("COUNT")
+                .arg(CLEAR_SCAN_COUNT)
+                .query_async(&mut conn)
+                .await
+                .map_err(|e| map_redis_error("failed to scan keys in Redis", e))?;
+
+            if !keys.is_empty() {
+                removed += keys.len();
+                conn.del::<_, ()>(&keys)
+                    .await
+                    .map_err(|e| map_redis_error("failed to delete values from Redis", e))?;
+            }
+
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+
+        debug!(removed, "Cleared pooled Redis cache");
+        Ok(())
+    }
+
+    /// Same distributed single-flight as
+    /// [`RedisUrlCache`](crate::RedisUrlCache)'s `get_or_compute`, using the
+    /// same lock keys.
+    async fn get_or_compute<F, Fut>(&self, code: &ShortCode, fetch: F) -> Result<Option<UrlRecord>>
+    where
+        F: FnOnce(&ShortCode) -> Fut + Send,
+        Fut: Future<Output = Result<Option<UrlRecord>>> + Send,
+    {
+        if let Some(record) = self.get_url(code).await? {
+            return Ok(Some(record));
+        }
+
+        let lock_key = self.lock_key(code);
+        let token = lock_token();
+
+        if self.try_lock(code, &lock_key, &token).await? {
+            trace!(code = %code, "Acquired fetch lock");
+            let result = self.compute_and_backfill(code, fetch).await;
+            if let Err(e) = self.unlock(code, &lock_key, &token).await {
+                warn!(code = %code, error = %e, "Failed to release fetch lock");
+            }
+            return result;
+        }
+
+        trace!(code = %code, "Fetch lock held elsewhere, waiting for value");
+        if let Some(record) = self.wait_for_value(code, &lock_key).await? {
+            return Ok(Some(record));
+        }
+
+        self.compute_and_backfill(code, fetch).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pool_timeouts_map_to_cache_timeouts() {
+        let err = map_pool_error(
+            "failed to get connection",
+            PoolError::Timeout(deadpool_redis::TimeoutType::Wait),
+        );
+        assert!(matches!(err, CacheError::Timeout(_)));
+
+        let err = map_pool_error("failed to get connection", PoolError::Closed);
+        assert!(matches!(err, CacheError::Unavailable(_)));
+    }
+
+    #[test]
+    fn invalid_url_fails_initialization() {
+        let config = PooledRedisConfig::builder().url("not a url").build();
+        let err = PooledRedisUrlCache::new(config).unwrap_err();
+        assert!(matches!(err, CacheError::Initialization(_)));
+    }
+}
@@ -0,0 +1,36 @@
+//! Tracing spans for the lookups that pass through cache layers.
+
+use tracing::field::Empty;
+use tracing::Span;
+use wormhole_core::ShortCode;
+
+use crate::{CacheError, Result};
+
+/// Opens the span covering one layer's part of a lookup.
+///
+/// The `outcome` field starts empty; fill it in with `span.record` once the
+/// layer has answered, e.g. via [`read_outcome`].
+pub(crate) fn layer_span(layer: &str, code: &ShortCode) -> Span {
+    tracing::debug_span!("cache.layer", layer, code = %code, outcome = Empty)
+}
+
+/// The `outcome` of a plain read: `hit`, `miss` or `error`.
+pub(crate) fn read_outcome<T>(result: &Result<Option<T>>) -> &'static str {
+    match result {
+        Ok(Some(_)) => "hit",
+        Ok(None) => "miss",
+        Err(_) => "error",
+    }
+}
+
+/// The `outcome` of a `get_or_compute`: `hit` unless the layer had to ask
+/// the next one (`miss`), or `error`. An [`CacheError::Uncacheable`] record
+/// was fetched, so it counts as a miss.
+pub(crate) fn compute_outcome<T>(result: &Result<T>, missed: bool) -> &'static str {
+    match result {
+        Err(CacheError::Uncacheable(_)) => "miss",
+        Err(_) => "error",
+        Ok(_) if missed => "miss",
+        Ok(_) => "hit",
+    }
+}
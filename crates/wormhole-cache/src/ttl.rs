@@ -0,0 +1,204 @@
+use jiff::Timestamp;
+use rand::Rng;
+use std::time::Duration;
+use wormhole_core::UrlRecord;
+
+/// Shortest TTL a [`TtlPolicy`] hands out.
+const MIN_TTL: Duration = Duration::from_millis(1);
+
+/// Returns how long `record` stays live: `None` if it never expires,
+/// `Duration::ZERO` if it already has.
+pub(crate) fn remaining_lifetime(record: &UrlRecord) -> Option<Duration> {
+    record.expire_at.map(|expire_at| {
+        Duration::try_from(expire_at.duration_since(Timestamp::now())).unwrap_or(Duration::ZERO)
+    })
+}
+
+/// How long a cache keeps an entry written by `set_url`.
+///
+/// Entries written with the same fixed TTL expire together, so a burst of
+/// writes (e.g. after a deploy empties the cache) turns into a burst of
+/// misses one TTL later. Jitter spreads those expirations out: each call to
+/// [`TtlPolicy::ttl`] returns a duration drawn uniformly from
+/// `base * (1 ± jitter_fraction)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TtlPolicy {
+    base: Duration,
+    jitter_fraction: f64,
+}
+
+impl TtlPolicy {
+    /// Creates a policy around `base` with the given relative jitter.
+    ///
+    /// `jitter_fraction` is clamped to `[0, 1)`, so the jittered TTL is
+    /// always positive; NaN is treated as no jitter.
+    pub fn new(base: Duration, jitter_fraction: f64) -> Self {
+        let jitter_fraction = if jitter_fraction.is_nan() {
+            0.0
+        } else {
+            jitter_fraction.clamp(0.0, 1.0 - f64::EPSILON)
+        };
+        Self {
+            base,
+            jitter_fraction,
+        }
+    }
+
+    /// Creates a policy that always returns `base`.
+    pub fn fixed(base: Duration) -> Self {
+        Self::new(base, 0.0)
+    }
+
+    pub fn base(&self) -> Duration {
+        self.base
+    }
+
+    pub fn jitter_fraction(&self) -> f64 {
+        self.jitter_fraction
+    }
+
+    /// Returns the TTL for the next write.
+    ///
+    /// Never shorter than one millisecond, even for a zero `base`.
+    pub fn ttl(&self) -> Duration {
+        let ttl = if self.jitter_fraction == 0.0 {
+            self.base
+        } else {
+            let factor =
+                rand::rng().random_range(1.0 - self.jitter_fraction..=1.0 + self.jitter_fraction);
+            self.base.mul_f64(factor)
+        };
+        ttl.max(MIN_TTL)
+    }
+
+    /// Returns the TTL for caching `record`: [`TtlPolicy::ttl`], cut short
+    /// so the entry never outlives the record's `expire_at`.
+    ///
+    /// Returns `Duration::ZERO` if the record already expired, in which case
+    /// it should not be cached at all.
+    pub fn ttl_for(&self, record: &UrlRecord) -> Duration {
+        let ttl = self.ttl();
+        match remaining_lifetime(record) {
+            Some(remaining) => ttl.min(remaining),
+            None => ttl,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jiff::SignedDuration;
+    use wormhole_core::RedirectKind;
+
+    fn expiring_in(lifetime: Option<SignedDuration>) -> UrlRecord {
+        UrlRecord {
+            original_url: "https://example.com".to_string(),
+            expire_at: lifetime.map(|lifetime| Timestamp::now() + lifetime),
+            redirect_kind: RedirectKind::default(),
+            created_at: Timestamp::now(),
+            internal_only: false,
+            no_store: false,
+            owner_id: None,
+            metadata: Default::default(),
+        }
+    }
+
+    #[test]
+    fn soon_expiring_records_get_their_remaining_lifetime() {
+        let policy = TtlPolicy::fixed(Duration::from_secs(3600));
+
+        let ttl = policy.ttl_for(&expiring_in(Some(SignedDuration::from_secs(30))));
+
+        assert!(
+            ttl <= Duration::from_secs(30) && ttl > Duration::from_secs(25),
+            "{ttl:?}"
+        );
+    }
+
+    #[test]
+    fn far_expiring_records_get_the_policy_ttl() {
+        let policy = TtlPolicy::fixed(Duration::from_secs(3600));
+
+        let ttl = policy.ttl_for(&expiring_in(Some(SignedDuration::from_hours(24))));
+
+        assert_eq!(ttl, Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn records_without_expiry_get_the_policy_ttl() {
+        let policy = TtlPolicy::fixed(Duration::from_secs(3600));
+
+        assert_eq!(
+            policy.ttl_for(&expiring_in(None)),
+            Duration::from_secs(3600)
+        );
+    }
+
+    #[test]
+    fn expired_records_get_no_ttl() {
+        let policy = TtlPolicy::fixed(Duration::from_secs(3600));
+
+        let ttl = policy.ttl_for(&expiring_in(Some(SignedDuration::from_secs(-1))));
+
+        assert_eq!(ttl, Duration::ZERO);
+    }
+
+    #[test]
+    fn jittered_ttls_stay_within_the_band_and_spread_out() {
+        let base = Duration::from_secs(300);
+        let policy = TtlPolicy::new(base, 0.1);
+        let low = base.mul_f64(0.9);
+        let high = base.mul_f64(1.1);
+
+        let ttls: Vec<Duration> = (0..10_000).map(|_| policy.ttl()).collect();
+
+        for ttl in &ttls {
+            assert!(
+                *ttl >= low && *ttl <= high,
+                "{ttl:?} outside {low:?}..={high:?}"
+            );
+        }
+        // Uniform over the band: roughly half on either side of the base,
+        // and both ends of the band are reached.
+        let below = ttls.iter().filter(|ttl| **ttl < base).count();
+        assert!(
+            (4_000..=6_000).contains(&below),
+            "{below} of 10000 below base"
+        );
+        let min = ttls.iter().min().unwrap();
+        let max = ttls.iter().max().unwrap();
+        assert!(*min < base.mul_f64(0.91), "min {min:?}");
+        assert!(*max > base.mul_f64(1.09), "max {max:?}");
+    }
+
+    #[test]
+    fn fixed_policy_returns_the_base() {
+        let policy = TtlPolicy::fixed(Duration::from_secs(60));
+
+        for _ in 0..100 {
+            assert_eq!(policy.ttl(), Duration::from_secs(60));
+        }
+    }
+
+    #[test]
+    fn jitter_never_produces_a_non_positive_ttl() {
+        for fraction in [1.0, 5.0, f64::INFINITY] {
+            let policy = TtlPolicy::new(Duration::from_millis(10), fraction);
+            assert!(policy.jitter_fraction() < 1.0);
+            for _ in 0..10_000 {
+                assert!(policy.ttl() >= MIN_TTL);
+            }
+        }
+
+        assert_eq!(TtlPolicy::fixed(Duration::ZERO).ttl(), MIN_TTL);
+        assert_eq!(
+            TtlPolicy::new(Duration::from_secs(1), -0.5).jitter_fraction(),
+            0.0
+        );
+        assert_eq!(
+            TtlPolicy::new(Duration::from_secs(1), f64::NAN).jitter_fraction(),
+            0.0
+        );
+    }
+}
@@ -1,8 +1,15 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 
+use jiff::Timestamp;
 use redis::AsyncCommands;
-use wormhole_cache::{CacheError, RedisUrlCache, UrlCache};
-use wormhole_core::{ShortCode, UrlRecord};
+use wormhole_cache::{
+    CacheError, InvalidationPublisher, InvalidationSubscriber, MokaUrlCache, MsgPackCodec,
+    PooledRedisConfig, PooledRedisUrlCache, RedisCacheConfig, RedisInvalidationPublisher,
+    RedisUrlCache, TtlPolicy, UrlCache,
+};
+use wormhole_core::{RedirectKind, ShortCode, UrlRecord};
 use wormhole_test_infra::redis::RedisMaster;
 
 /// Test fixture that manages a Redis container using test-infra.
@@ -28,6 +35,11 @@ impl RedisTestContainer {
         Self { redis, redis_url }
     }
 
+    /// Creates a client for the container.
+    pub fn client(&self) -> redis::Client {
+        redis::Client::open(self.redis_url.as_str()).expect("Failed to create Redis client")
+    }
+
     /// Creates a new Redis connection.
     pub async fn create_connection(&self) -> redis::aio::MultiplexedConnection {
         let client =
@@ -44,6 +56,12 @@ fn create_test_record(url: impl Into<String>) -> UrlRecord {
     UrlRecord {
         original_url: url.into(),
         expire_at: None,
+        redirect_kind: RedirectKind::default(),
+        created_at: Timestamp::now(),
+        internal_only: false,
+        no_store: false,
+        owner_id: None,
+        metadata: Default::default(),
     }
 }
 
@@ -97,6 +115,25 @@ async fn test_redis_cache_delete() {
     assert!(result.is_none(), "Cache should be empty after delete");
 }
 
+#[tokio::test]
+async fn test_redis_cache_exists() {
+    let fixture = RedisTestContainer::start().await;
+    let conn = fixture.create_connection().await;
+    let cache = RedisUrlCache::new(conn);
+
+    let code = ShortCode::custom("exists123").unwrap();
+    assert!(!cache.exists(&code).await.unwrap());
+
+    cache
+        .set_url(&code, &create_test_record("https://example.com/exists"))
+        .await
+        .unwrap();
+    assert!(cache.exists(&code).await.unwrap());
+
+    cache.del(&code).await.unwrap();
+    assert!(!cache.exists(&code).await.unwrap());
+}
+
 #[tokio::test]
 async fn test_redis_cache_multiple_codes() {
     let fixture = RedisTestContainer::start().await;
@@ -236,3 +273,460 @@ async fn test_redis_cache_with_ttl_via_redis() {
     let result = cache.get_url(&code).await.unwrap();
     assert!(result.is_none(), "Key should be expired after TTL");
 }
+
+#[tokio::test]
+async fn test_redis_cache_connect_times_out_on_a_stalled_server() {
+    let fixture = RedisTestContainer::start().await;
+    let config = RedisCacheConfig::builder()
+        .response_timeout(Some(Duration::from_millis(200)))
+        .build();
+    let cache = RedisUrlCache::connect(&fixture.redis_url, config)
+        .await
+        .unwrap();
+    let code = ShortCode::new_unchecked("stalled");
+    cache
+        .set_url(&code, &create_test_record("https://example.com/stalled"))
+        .await
+        .unwrap();
+
+    // Stall every client, including the cache's, for two seconds.
+    let mut admin = fixture.create_connection().await;
+    redis::cmd("CLIENT")
+        .arg("PAUSE")
+        .arg(2000)
+        .query_async::<()>(&mut admin)
+        .await
+        .unwrap();
+
+    let started = std::time::Instant::now();
+    let err = cache.get_url(&code).await.unwrap_err();
+    assert!(matches!(err, CacheError::Timeout(_)), "got {err:?}");
+    assert!(started.elapsed() < Duration::from_secs(1));
+
+    // The connection is usable again once the pause ends.
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    assert!(cache.get_url(&code).await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn test_redis_cache_ttl_policy_jitters_set_url_expiry() {
+    let fixture = RedisTestContainer::start().await;
+    let conn = fixture.create_connection().await;
+    let mut redis_conn = fixture.create_connection().await;
+
+    let cache =
+        RedisUrlCache::new(conn).with_ttl_policy(TtlPolicy::new(Duration::from_secs(100), 0.2));
+    let record = create_test_record("https://example.com/jitter");
+
+    let mut ttls = Vec::new();
+    for i in 0..50 {
+        let code = ShortCode::new_unchecked(format!("jitter{i}"));
+        cache.set_url(&code, &record).await.unwrap();
+        let pttl: i64 = redis_conn.pttl(format!("wh:url:{code}")).await.unwrap();
+        assert!(
+            (79_000..=120_000).contains(&pttl),
+            "PTTL {pttl} outside the band"
+        );
+        ttls.push(pttl);
+    }
+
+    // 50 draws from a 40s-wide band should not all land on the same second.
+    ttls.sort_unstable();
+    assert!(ttls[49] - ttls[0] > 1_000, "TTLs were not spread: {ttls:?}");
+}
+
+#[tokio::test]
+async fn test_redis_cache_set_url_expires_with_the_record() {
+    let fixture = RedisTestContainer::start().await;
+    let conn = fixture.create_connection().await;
+    let mut redis_conn = fixture.create_connection().await;
+    let cache = RedisUrlCache::new(conn);
+
+    // A future expiry becomes the key's TTL.
+    let code = ShortCode::new_unchecked("expiring");
+    let mut record = create_test_record("https://example.com/expiring");
+    record.expire_at = Some(Timestamp::now() + jiff::SignedDuration::from_secs(60));
+    cache.set_url(&code, &record).await.unwrap();
+    let pttl: i64 = redis_conn.pttl("wh:url:expiring").await.unwrap();
+    assert!((55_000..=60_000).contains(&pttl), "PTTL {pttl}");
+
+    // An expiry under a second away is floored at one second.
+    let code = ShortCode::new_unchecked("imminent");
+    record.expire_at = Some(Timestamp::now() + jiff::SignedDuration::from_millis(10));
+    cache.set_url(&code, &record).await.unwrap();
+    let pttl: i64 = redis_conn.pttl("wh:url:imminent").await.unwrap();
+    assert!((900..=1_000).contains(&pttl), "PTTL {pttl}");
+
+    // A record that already expired is not cached, and replaces nothing.
+    let code = ShortCode::new_unchecked("expired");
+    cache
+        .set_url(&code, &create_test_record("https://example.com/old"))
+        .await
+        .unwrap();
+    record.expire_at = Some(Timestamp::now() - jiff::SignedDuration::from_secs(1));
+    cache.set_url(&code, &record).await.unwrap();
+    assert!(cache.get_url(&code).await.unwrap().is_none());
+
+    // Without an expiry the key persists.
+    let code = ShortCode::new_unchecked("forever");
+    cache
+        .set_url(&code, &create_test_record("https://example.com/forever"))
+        .await
+        .unwrap();
+    let pttl: i64 = redis_conn.pttl("wh:url:forever").await.unwrap();
+    assert_eq!(pttl, -1);
+
+    // An explicit TTL still wins over the record's expiry.
+    let code = ShortCode::new_unchecked("explicit");
+    record.expire_at = Some(Timestamp::now() + jiff::SignedDuration::from_secs(3600));
+    cache
+        .set_url_with_ttl(&code, &record, Some(Duration::from_secs(30)))
+        .await
+        .unwrap();
+    let pttl: i64 = redis_conn.pttl("wh:url:explicit").await.unwrap();
+    assert!((25_000..=30_000).contains(&pttl), "PTTL {pttl}");
+}
+
+#[tokio::test]
+async fn test_redis_cache_get_with_ttl_reports_remaining_lifetime() {
+    let fixture = RedisTestContainer::start().await;
+    let conn = fixture.create_connection().await;
+    let mut redis_conn = fixture.create_connection().await;
+    let cache = RedisUrlCache::new(conn);
+    let record = create_test_record("https://example.com/ttl");
+
+    redis_conn
+        .set_ex::<_, _, ()>("wh:url:expiring", serde_json::to_vec(&record).unwrap(), 60)
+        .await
+        .unwrap();
+    let (cached, ttl) = cache
+        .get_with_ttl(&ShortCode::new_unchecked("expiring"))
+        .await
+        .unwrap()
+        .expect("key should be cached");
+    assert_eq!(cached.original_url, record.original_url);
+    let ttl = ttl.expect("key has an expiry");
+    assert!(
+        ttl > Duration::from_secs(55) && ttl <= Duration::from_secs(60),
+        "TTL {ttl:?}"
+    );
+
+    let code = ShortCode::new_unchecked("forever");
+    cache.set_url(&code, &record).await.unwrap();
+    let (_, ttl) = cache.get_with_ttl(&code).await.unwrap().unwrap();
+    assert_eq!(ttl, None);
+
+    let missing = ShortCode::new_unchecked("missing");
+    assert!(cache.get_with_ttl(&missing).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_redis_cache_get_or_compute_single_flight() {
+    let fixture = RedisTestContainer::start().await;
+    let conn = fixture.create_connection().await;
+    let cache = RedisUrlCache::new(conn);
+
+    let code = ShortCode::custom("hotkey").unwrap();
+    let fetches = Arc::new(AtomicUsize::new(0));
+
+    let tasks: Vec<_> = (0..32)
+        .map(|_| {
+            let cache = cache.clone();
+            let code = code.clone();
+            let fetches = Arc::clone(&fetches);
+            tokio::spawn(async move {
+                cache
+                    .get_or_compute(&code, |_| async move {
+                        fetches.fetch_add(1, Ordering::SeqCst);
+                        // Keep the fetch slow enough that every caller misses.
+                        tokio::time::sleep(Duration::from_millis(200)).await;
+                        Ok(Some(create_test_record("https://example.com/hot")))
+                    })
+                    .await
+            })
+        })
+        .collect();
+
+    for task in tasks {
+        let record = task.await.unwrap().unwrap();
+        assert_eq!(record.unwrap().original_url, "https://example.com/hot");
+    }
+
+    let fetches = fetches.load(Ordering::SeqCst);
+    assert!(
+        fetches <= 2,
+        "expected the fetch to be coalesced, but it ran {fetches} times"
+    );
+}
+
+#[tokio::test]
+async fn test_redis_cache_msgpack_codec_round_trip() {
+    let fixture = RedisTestContainer::start().await;
+    let conn = fixture.create_connection().await;
+    let cache = RedisUrlCache::new(conn).with_codec(MsgPackCodec);
+
+    let code = ShortCode::custom("msgpack").unwrap();
+    let record = create_test_record("https://example.com/msgpack");
+
+    cache.set_url(&code, &record).await.unwrap();
+    assert_eq!(cache.get_url(&code).await.unwrap(), Some(record));
+}
+
+#[tokio::test]
+async fn test_redis_cache_switching_codec_requires_prefix_bump() {
+    let fixture = RedisTestContainer::start().await;
+    let code = ShortCode::custom("codec_switch").unwrap();
+
+    // A value written by the default JSON codec...
+    let json_cache = RedisUrlCache::new(fixture.create_connection().await);
+    json_cache
+        .set_url(&code, &create_test_record("https://example.com/json"))
+        .await
+        .unwrap();
+
+    // ...cannot be read once the codec changes under the same prefix.
+    let same_prefix =
+        RedisUrlCache::new(fixture.create_connection().await).with_codec(MsgPackCodec);
+    let err = same_prefix.get_url(&code).await.unwrap_err();
+    assert!(matches!(err, CacheError::InvalidData(_)));
+
+    // Bumping the prefix alongside the codec turns the old value into a miss.
+    let bumped_prefix = RedisUrlCache::with_prefix(fixture.create_connection().await, "wh:url:v2:")
+        .with_codec(MsgPackCodec);
+    assert!(bumped_prefix.get_url(&code).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_redis_cache_del_many() {
+    let fixture = RedisTestContainer::start().await;
+    let cache = RedisUrlCache::new(fixture.create_connection().await);
+
+    let codes: Vec<_> = (0..5)
+        .map(|i| ShortCode::custom(format!("bulk{i}")).unwrap())
+        .collect();
+    let keep = ShortCode::custom("keep").unwrap();
+    for code in codes.iter().chain([&keep]) {
+        cache
+            .set_url(code, &create_test_record("https://example.com/bulk"))
+            .await
+            .unwrap();
+    }
+
+    cache.del_many(&codes).await.unwrap();
+
+    for code in &codes {
+        assert!(cache.get_url(code).await.unwrap().is_none());
+    }
+    assert!(cache.get_url(&keep).await.unwrap().is_some());
+
+    // An empty batch must not send a bare `DEL`, which Redis rejects.
+    cache.del_many(&[]).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_pooled_redis_cache_concurrent_gets() {
+    let fixture = RedisTestContainer::start().await;
+    let config = PooledRedisConfig::builder()
+        .url(fixture.redis_url.clone())
+        .max_size(4)
+        .build();
+    let cache = PooledRedisUrlCache::new(config).unwrap();
+
+    let codes: Vec<_> = (0..20)
+        .map(|i| ShortCode::custom(format!("pooled{i}")).unwrap())
+        .collect();
+    for (i, code) in codes.iter().enumerate() {
+        cache
+            .set_url(
+                code,
+                &create_test_record(format!("https://example.com/{i}")),
+            )
+            .await
+            .unwrap();
+    }
+
+    // Far more concurrent requests than connections: callers wait for a
+    // free connection instead of failing.
+    let handles: Vec<_> = (0..200)
+        .map(|i| {
+            let cache = cache.clone();
+            let code = codes[i % codes.len()].clone();
+            tokio::spawn(async move { (i % 20, cache.get_url(&code).await) })
+        })
+        .collect();
+
+    for handle in handles {
+        let (i, result) = handle.await.unwrap();
+        let record = result.unwrap().expect("record should be cached");
+        assert_eq!(record.original_url, format!("https://example.com/{i}"));
+    }
+    assert!(cache.status().size <= 4);
+}
+
+#[tokio::test]
+async fn test_pooled_redis_cache_shares_keys_with_redis_cache() {
+    let fixture = RedisTestContainer::start().await;
+    let plain = RedisUrlCache::new(fixture.create_connection().await);
+    let pooled = PooledRedisUrlCache::new(
+        PooledRedisConfig::builder()
+            .url(fixture.redis_url.clone())
+            .build(),
+    )
+    .unwrap();
+
+    let code = ShortCode::custom("shared").unwrap();
+    plain
+        .set_url(&code, &create_test_record("https://example.com/shared"))
+        .await
+        .unwrap();
+    assert!(pooled.get_url(&code).await.unwrap().is_some());
+
+    pooled.del_many(&[code.clone()]).await.unwrap();
+    assert!(plain.get_url(&code).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_redis_cache_clear_only_removes_prefixed_keys() {
+    let fixture = RedisTestContainer::start().await;
+    let mut conn = fixture.create_connection().await;
+    let cache = RedisUrlCache::new(conn.clone());
+    let other = RedisUrlCache::with_prefix(conn.clone(), "other:");
+
+    // More keys than one SCAN batch, to exercise the cursor loop.
+    for i in 0..1_200 {
+        let code = ShortCode::custom(format!("clear{i}")).unwrap();
+        cache
+            .set_url(&code, &create_test_record("https://example.com/clear"))
+            .await
+            .unwrap();
+    }
+    let kept = ShortCode::custom("kept").unwrap();
+    other
+        .set_url(&kept, &create_test_record("https://example.com/kept"))
+        .await
+        .unwrap();
+    let _: () = conn.set("unrelated", "value").await.unwrap();
+
+    cache.clear().await.unwrap();
+
+    let remaining: Vec<String> = conn.keys("wh:url:*").await.unwrap();
+    assert!(remaining.is_empty());
+    assert!(other.get_url(&kept).await.unwrap().is_some());
+    let unrelated: Option<String> = conn.get("unrelated").await.unwrap();
+    assert_eq!(unrelated.as_deref(), Some("value"));
+}
+
+#[tokio::test]
+async fn test_pooled_redis_cache_get_or_compute_coordinates_with_redis_cache() {
+    let fixture = RedisTestContainer::start().await;
+    let plain = RedisUrlCache::new(fixture.create_connection().await);
+    let pooled = PooledRedisUrlCache::new(
+        PooledRedisConfig::builder()
+            .url(fixture.redis_url.clone())
+            .build(),
+    )
+    .unwrap();
+
+    let code = ShortCode::custom("pooledhot").unwrap();
+    let fetches = Arc::new(AtomicUsize::new(0));
+
+    let tasks: Vec<_> = (0..32)
+        .map(|i| {
+            let (plain, pooled) = (plain.clone(), pooled.clone());
+            let code = code.clone();
+            let fetches = Arc::clone(&fetches);
+            tokio::spawn(async move {
+                let fetch = |_: &ShortCode| async move {
+                    fetches.fetch_add(1, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(200)).await;
+                    Ok(Some(create_test_record("https://example.com/pooled")))
+                };
+                // Both caches take the same fetch lock.
+                if i % 2 == 0 {
+                    pooled.get_or_compute(&code, fetch).await
+                } else {
+                    plain.get_or_compute(&code, fetch).await
+                }
+            })
+        })
+        .collect();
+
+    for task in tasks {
+        let record = task.await.unwrap().unwrap();
+        assert_eq!(record.unwrap().original_url, "https://example.com/pooled");
+    }
+
+    let fetches = fetches.load(Ordering::SeqCst);
+    assert!(
+        fetches <= 2,
+        "expected the fetch to be coalesced, but it ran {fetches} times"
+    );
+    assert!(pooled.exists(&code).await.unwrap());
+    assert!(!pooled
+        .exists(&ShortCode::custom("pooledcold").unwrap())
+        .await
+        .unwrap());
+}
+
+#[tokio::test]
+async fn test_pooled_redis_cache_clear() {
+    let fixture = RedisTestContainer::start().await;
+    let cache = PooledRedisUrlCache::new(
+        PooledRedisConfig::builder()
+            .url(fixture.redis_url.clone())
+            .build(),
+    )
+    .unwrap();
+
+    let code = ShortCode::custom("pooledclear").unwrap();
+    cache
+        .set_url(&code, &create_test_record("https://example.com"))
+        .await
+        .unwrap();
+
+    cache.clear().await.unwrap();
+
+    assert!(cache.get_url(&code).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_published_invalidation_evicts_subscriber_caches() {
+    let fixture = RedisTestContainer::start().await;
+    let code = ShortCode::custom("stale1").unwrap();
+    let other = ShortCode::custom("fresh1").unwrap();
+    let record = create_test_record("https://example.com/stale");
+
+    // A redirector node's local and shared caches, both holding the code.
+    let moka = MokaUrlCache::new();
+    let redis_cache = RedisUrlCache::with_prefix(fixture.create_connection().await, "inv:");
+    for c in [&code, &other] {
+        moka.set_url(c, &record).await.unwrap();
+        redis_cache.set_url(c, &record).await.unwrap();
+    }
+    let moka_listener = InvalidationSubscriber::new(fixture.client(), moka.clone())
+        .start()
+        .await
+        .unwrap();
+    let redis_listener = InvalidationSubscriber::new(fixture.client(), redis_cache.clone())
+        .start()
+        .await
+        .unwrap();
+
+    // The shortener side announces the deletion.
+    let publisher = RedisInvalidationPublisher::new(fixture.create_connection().await);
+    publisher.publish_invalidation(&code).await.unwrap();
+
+    awaitility::at_most(Duration::from_secs(5))
+        .poll_interval(Duration::from_millis(20))
+        .until_async(|| async {
+            moka.get_url(&code).await.unwrap().is_none()
+                && redis_cache.get_url(&code).await.unwrap().is_none()
+        })
+        .await;
+    // Other codes are left alone.
+    assert!(moka.get_url(&other).await.unwrap().is_some());
+    assert!(redis_cache.get_url(&other).await.unwrap().is_some());
+
+    moka_listener.abort();
+    redis_listener.abort();
+}
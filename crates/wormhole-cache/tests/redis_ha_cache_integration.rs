@@ -1,8 +1,9 @@
 use std::time::Duration;
 
+use jiff::Timestamp;
 use wormhole_cache::Result;
 use wormhole_cache::{RedisHAUrlCache, UrlCache};
-use wormhole_core::{ShortCode, UrlRecord};
+use wormhole_core::{RedirectKind, ShortCode, UrlRecord};
 use wormhole_test_infra::redis::{RedisHA, RedisHAConfig};
 
 /// Test fixture that manages a Redis HA environment using test-infra.
@@ -54,6 +55,12 @@ fn create_test_record(url: impl Into<String>) -> UrlRecord {
     UrlRecord {
         original_url: url.into(),
         expire_at: None,
+        redirect_kind: RedirectKind::default(),
+        created_at: Timestamp::now(),
+        internal_only: false,
+        no_store: false,
+        owner_id: None,
+        metadata: Default::default(),
     }
 }
 
@@ -84,3 +91,56 @@ async fn test_redis_ha_cache_basic_get_set() {
         })
         .await;
 }
+
+#[tokio::test]
+async fn test_redis_ha_cache_reads_own_writes_within_master_window() {
+    let fixture = RedisHATestFixture::start().await;
+    let cache = fixture
+        .create_cache_with_prefix("ryw:")
+        .unwrap()
+        .with_read_from_master_after_write(Duration::from_secs(5));
+
+    for i in 0..100 {
+        let code = ShortCode::custom(format!("fresh{i}")).unwrap();
+        let record = create_test_record(format!("https://example.com/{i}"));
+
+        cache.set_url(&code, &record).await.unwrap();
+        // Read straight back: served by the master, so replication lag
+        // cannot turn this into a miss.
+        assert_eq!(cache.get_url(&code).await.unwrap(), Some(record));
+
+        cache.del(&code).await.unwrap();
+        assert_eq!(cache.get_url(&code).await.unwrap(), None);
+    }
+}
+
+#[tokio::test]
+async fn test_redis_ha_cache_clear_removes_only_its_prefix() {
+    let fixture = RedisHATestFixture::start().await;
+    let window = Duration::from_secs(5);
+    let cleared = fixture
+        .create_cache_with_prefix("clear:a:")
+        .unwrap()
+        .with_read_from_master_after_write(window);
+    let kept = fixture
+        .create_cache_with_prefix("clear:b:")
+        .unwrap()
+        .with_read_from_master_after_write(window);
+
+    let codes: Vec<ShortCode> = (0..20)
+        .map(|i| ShortCode::custom(format!("code{i}")).unwrap())
+        .collect();
+    for code in &codes {
+        let record = create_test_record("https://example.com");
+        cleared.set_url(code, &record).await.unwrap();
+        kept.set_url(code, &record).await.unwrap();
+    }
+    assert!(cleared.exists(&codes[0]).await.unwrap());
+
+    cleared.clear().await.unwrap();
+
+    for code in &codes {
+        assert!(!cleared.exists(code).await.unwrap());
+        assert!(kept.exists(code).await.unwrap());
+    }
+}
@@ -1,8 +1,88 @@
+use crate::error::CoreError;
 use serde::{Deserialize, Serialize};
 use smol_str::SmolStr;
 use std::fmt::Display;
+use std::str::FromStr;
+use thiserror::Error;
 use wormhole_tinyflake::TinyId;
 
+/// An alphabet is not exactly 58 unique ASCII characters.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("invalid base58 alphabet: {0}")]
+pub struct AlphabetError(String);
+
+/// The 58 characters short codes are spelled with, in digit order.
+///
+/// Codes encoded with one alphabet decode to different bytes, or not at all,
+/// with another, so every component that encodes or decodes generated codes
+/// must agree on it.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Alphabet {
+    chars: [u8; 58],
+}
+
+impl Alphabet {
+    /// The Bitcoin alphabet; the default.
+    pub const BITCOIN: Self = Self {
+        chars: *b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz",
+    };
+    /// The Flickr alphabet, which orders lowercase letters before uppercase.
+    pub const FLICKR: Self = Self {
+        chars: *b"123456789abcdefghijkmnopqrstuvwxyzABCDEFGHJKLMNPQRSTUVWXYZ",
+    };
+    /// The Ripple alphabet.
+    pub const RIPPLE: Self = Self {
+        chars: *b"rpshnaf39wBUDNEGHJKLM4PQRST7VWXYZ2bcdeCg65jkm8oFqi1tuvAxyz",
+    };
+
+    /// Creates an alphabet from exactly 58 unique ASCII characters.
+    pub fn new(chars: &str) -> Result<Self, AlphabetError> {
+        let chars: [u8; 58] = chars.as_bytes().try_into().map_err(|_| {
+            AlphabetError(format!(
+                "expected 58 characters, got {}",
+                chars.chars().count()
+            ))
+        })?;
+        bs58::Alphabet::new(&chars).map_err(|e| AlphabetError(e.to_string()))?;
+        Ok(Self { chars })
+    }
+
+    /// Returns the characters in digit order.
+    pub fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.chars).expect("alphabet is ASCII")
+    }
+
+    fn table(&self) -> bs58::Alphabet {
+        bs58::Alphabet::new(&self.chars).expect("alphabet was validated on creation")
+    }
+}
+
+impl Default for Alphabet {
+    fn default() -> Self {
+        Self::BITCOIN
+    }
+}
+
+impl std::fmt::Debug for Alphabet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Alphabet").field(&self.as_str()).finish()
+    }
+}
+
+impl FromStr for Alphabet {
+    type Err = AlphabetError;
+
+    /// Parses `bitcoin`, `flickr` or `ripple`, or else 58 custom characters.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bitcoin" => Ok(Self::BITCOIN),
+            "flickr" => Ok(Self::FLICKR),
+            "ripple" => Ok(Self::RIPPLE),
+            custom => Self::new(custom),
+        }
+    }
+}
+
 /// A short code encoded as base58 string.
 #[derive(Clone, PartialEq, Eq, Hash)]
 pub struct ShortCodeBase58(SmolStr);
@@ -25,14 +105,58 @@ impl ShortCodeBase58 {
     /// let base58 = ShortCodeBase58::new(tiny_id.into_bytes());
     /// ```
     pub fn new<T: AsRef<[u8]>>(bytes: T) -> Self {
-        let encoded = bs58::encode(bytes).into_string();
+        Self::encode_with(bytes, &Alphabet::BITCOIN)
+    }
+
+    /// Like [`ShortCodeBase58::new`], but spells the code with `alphabet`.
+    pub fn encode_with<T: AsRef<[u8]>>(bytes: T, alphabet: &Alphabet) -> Self {
+        let encoded = bs58::encode(bytes)
+            .with_alphabet(&alphabet.table())
+            .into_string();
         Self(SmolStr::new(encoded))
     }
 
+    /// Wraps a code that is already encoded, e.g. one received over the
+    /// wire.
+    ///
+    /// The alphabet it was spelled with is not known here, so the code is
+    /// not checked; [`ShortCodeBase58::decode_with`] does that.
+    pub fn from_encoded(code: impl Into<SmolStr>) -> Self {
+        Self(code.into())
+    }
+
+    /// Returns the longest base58 string that `byte_len` bytes can encode to.
+    ///
+    /// Base58 output length depends on the value, so codes from a fixed-size
+    /// id vary in length; this is the upper bound, reached by all-`0xFF`
+    /// input. Leading zero bytes encode to one character each, which is
+    /// never longer.
+    pub fn max_encoded_len(byte_len: usize) -> usize {
+        bs58::encode(vec![0xFF; byte_len]).into_string().len()
+    }
+
     /// Returns the short code as a string slice.
     pub fn as_str(&self) -> &str {
         &self.0
     }
+
+    /// Decodes the base58 string back into the bytes it was encoded from.
+    ///
+    /// This is the inverse of [`ShortCodeBase58::new`] and is used to recover
+    /// the identifier embedded in a generated code.
+    pub fn decode(&self) -> Result<Vec<u8>, CoreError> {
+        self.decode_with(&Alphabet::BITCOIN)
+    }
+
+    /// Like [`ShortCodeBase58::decode`], for codes spelled with `alphabet`.
+    pub fn decode_with(&self, alphabet: &Alphabet) -> Result<Vec<u8>, CoreError> {
+        bs58::decode(self.0.as_str())
+            .with_alphabet(&alphabet.table())
+            .into_vec()
+            .map_err(|e| {
+                CoreError::InvalidShortCode(format!("failed to decode base58 short code: {e}"))
+            })
+    }
 }
 
 impl std::fmt::Debug for ShortCodeBase58 {
@@ -75,3 +199,84 @@ impl From<TinyId> for ShortCodeBase58 {
 }
 
 // TODO: test the conversion when have way to create a TinyId
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_round_trips_encoded_bytes() {
+        let bytes = [0x00, 0x12, 0x34, 0x56, 0x78];
+        let code = ShortCodeBase58::new(bytes);
+        assert_eq!(code.decode().unwrap(), bytes.to_vec());
+    }
+
+    #[test]
+    fn max_encoded_len_bounds_every_encoding() {
+        assert_eq!(ShortCodeBase58::max_encoded_len(0), 0);
+        assert_eq!(ShortCodeBase58::max_encoded_len(5), 7);
+        assert_eq!(ShortCodeBase58::max_encoded_len(8), 11);
+
+        for bytes in [
+            [0x00; 5],
+            [0x00, 0xFF, 0xFF, 0xFF, 0xFF],
+            [0x01, 0, 0, 0, 0],
+        ] {
+            assert!(ShortCodeBase58::new(bytes).as_str().len() <= 7);
+        }
+    }
+
+    #[test]
+    fn encoding_round_trips_in_every_alphabet() {
+        let custom =
+            Alphabet::new("ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz234567890").unwrap();
+        let bytes = [0x00, 0x12, 0x34, 0x56, 0x78];
+
+        for alphabet in [
+            Alphabet::BITCOIN,
+            Alphabet::FLICKR,
+            Alphabet::RIPPLE,
+            custom,
+        ] {
+            let code = ShortCodeBase58::encode_with(bytes, &alphabet);
+            assert!(code
+                .as_str()
+                .bytes()
+                .all(|c| alphabet.as_str().contains(c as char)));
+            assert_eq!(code.decode_with(&alphabet).unwrap(), bytes.to_vec());
+        }
+    }
+
+    #[test]
+    fn alphabets_spell_the_same_bytes_differently() {
+        let bytes = [0x12, 0x34, 0x56, 0x78, 0x9A];
+        let bitcoin = ShortCodeBase58::encode_with(bytes, &Alphabet::BITCOIN);
+        let flickr = ShortCodeBase58::encode_with(bytes, &Alphabet::FLICKR);
+
+        assert_eq!(bitcoin, ShortCodeBase58::new(bytes));
+        assert_ne!(bitcoin, flickr);
+        assert_ne!(
+            flickr.decode_with(&Alphabet::BITCOIN).ok(),
+            Some(bytes.to_vec())
+        );
+    }
+
+    #[test]
+    fn alphabet_must_be_58_unique_characters() {
+        let bitcoin = Alphabet::BITCOIN.as_str();
+
+        assert!(Alphabet::new(&bitcoin[..57]).is_err());
+        assert!(Alphabet::new(&format!("{bitcoin}0")).is_err());
+        assert!(Alphabet::new(&format!("{}1", &bitcoin[..57])).is_err());
+        assert!(Alphabet::new(&format!("{}\u{e9}", &bitcoin[..56])).is_err());
+        assert_eq!(Alphabet::new(bitcoin), Ok(Alphabet::BITCOIN));
+    }
+
+    #[test]
+    fn alphabet_parses_names_and_custom_characters() {
+        assert_eq!("flickr".parse(), Ok(Alphabet::FLICKR));
+        assert_eq!("ripple".parse(), Ok(Alphabet::RIPPLE));
+        assert_eq!(Alphabet::BITCOIN.as_str().parse(), Ok(Alphabet::BITCOIN));
+        assert!("base64".parse::<Alphabet>().is_err());
+    }
+}
@@ -0,0 +1,109 @@
+//! Time sources for expiry checks.
+//!
+//! Services read "now" through a [`SharedClock`] instead of calling
+//! [`Timestamp::now`] directly, so tests can move time with a
+//! [`ManualClock`] rather than sleep. The [`Clock`] trait is the one the
+//! tinyflake generator already uses.
+
+use jiff::{SignedDuration, Timestamp};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+pub use wormhole_tinyflake::{Clock, SystemClock};
+
+/// A cheaply cloneable handle to a [`Clock`], the system clock by default.
+#[derive(Clone)]
+pub struct SharedClock(Arc<dyn Clock>);
+
+impl SharedClock {
+    pub fn new(clock: impl Clock + 'static) -> Self {
+        Self(Arc::new(clock))
+    }
+
+    /// Returns the current time of the clock.
+    pub fn now(&self) -> Timestamp {
+        self.0.now()
+    }
+
+    /// Returns `true` if `expire_at` is set and has been reached.
+    pub fn is_expired(&self, expire_at: Option<Timestamp>) -> bool {
+        expire_at.is_some_and(|expire_at| self.now() >= expire_at)
+    }
+}
+
+impl Default for SharedClock {
+    fn default() -> Self {
+        Self::new(SystemClock)
+    }
+}
+
+impl fmt::Debug for SharedClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SharedClock").finish_non_exhaustive()
+    }
+}
+
+/// A clock that only moves when told to, for tests.
+///
+/// Clones share the same time.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now: Arc<Mutex<Timestamp>>,
+}
+
+impl ManualClock {
+    /// Starts the clock at `now`.
+    pub fn new(now: Timestamp) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(now)),
+        }
+    }
+
+    /// Moves the clock to `now`, which may be in the past.
+    pub fn set(&self, now: Timestamp) {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner()) = now;
+    }
+
+    /// Moves the clock forward by `duration`.
+    pub fn advance(&self, duration: SignedDuration) {
+        let mut now = self.now.lock().unwrap_or_else(|e| e.into_inner());
+        *now += duration;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Timestamp {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Jumps straight to `target` instead of blocking.
+    fn wait_until(&self, target: Timestamp) {
+        let mut now = self.now.lock().unwrap_or_else(|e| e.into_inner());
+        if target > *now {
+            *now = target;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shared_manual_clock_moves_only_when_told() {
+        let start = Timestamp::from_second(1_000).unwrap();
+        let manual = ManualClock::new(start);
+        let clock = SharedClock::new(manual.clone());
+        let expire_at = Some(start + SignedDuration::from_secs(60));
+
+        assert_eq!(clock.now(), start);
+        assert!(!clock.is_expired(expire_at));
+        assert!(!clock.is_expired(None));
+
+        manual.advance(SignedDuration::from_secs(60));
+        assert!(clock.is_expired(expire_at));
+
+        manual.set(start);
+        assert!(!clock.is_expired(expire_at));
+    }
+}
@@ -7,4 +7,10 @@ pub type Result<T> = std::result::Result<T, CoreError>;
 pub enum CoreError {
     #[error("invalid short code: {0}")]
     InvalidShortCode(String),
+    #[error("alias is reserved: {0}")]
+    ReservedAlias(String),
+    #[error("invalid base URL: {0}")]
+    InvalidBaseUrl(String),
+    #[error("invalid alias policy: {0}")]
+    InvalidAliasPolicy(String),
 }
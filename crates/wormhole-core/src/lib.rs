@@ -4,8 +4,12 @@
 //! shortener service and the redirector service.
 
 pub mod base58;
+pub mod base_url;
+pub mod clock;
 pub mod error;
 pub mod shortcode;
 
+pub use base_url::BaseUrl;
+pub use clock::{Clock, ManualClock, SharedClock};
 pub use error::CoreError;
-pub use shortcode::{ShortCode, UrlRecord};
+pub use shortcode::{merge_target, AliasPolicy, RedirectKind, ShortCode, UrlRecord};
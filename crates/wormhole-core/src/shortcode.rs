@@ -1,7 +1,9 @@
 use crate::base58::ShortCodeBase58;
+use crate::base_url::BaseUrl;
 use crate::error::CoreError;
 use jiff::Timestamp;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
 use std::fmt::Display;
 
 /// A validated short code identifier for a shortened URL.
@@ -23,11 +25,265 @@ pub struct UrlRecord {
     pub original_url: String,
     /// When the record expires, if ever.
     pub expire_at: Option<Timestamp>,
+    /// Which HTTP redirect status to answer with when the code is visited.
+    ///
+    /// Defaulted on deserialization so entries cached before this field
+    /// existed are still readable.
+    #[serde(default)]
+    pub redirect_kind: RedirectKind,
+    /// When the short code was created.
+    ///
+    /// Entries cached before this field existed fall back to the Unix epoch,
+    /// which reads as "unknown" rather than pretending they were just created.
+    #[serde(default = "unknown_created_at")]
+    pub created_at: Timestamp,
+    /// Whether the code may only be resolved by trusted (internal) callers.
+    #[serde(default)]
+    pub internal_only: bool,
+    /// Whether the record must never be cached, e.g. for one-time tokens.
+    ///
+    /// Such records are always read from the repository.
+    #[serde(default)]
+    pub no_store: bool,
+    /// The tenant or user the code belongs to, for deployments that scope
+    /// listing and deletion to the owner.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner_id: Option<String>,
+    /// Free-form labels attached by the caller, e.g. a campaign name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+}
+
+impl UrlRecord {
+    /// Returns the URL to redirect to, carrying over the query string and
+    /// fragment of the incoming request. See [`merge_target`].
+    pub fn build_target(
+        &self,
+        incoming_query: Option<&str>,
+        incoming_fragment: Option<&str>,
+    ) -> String {
+        merge_target(&self.original_url, incoming_query, incoming_fragment)
+    }
+}
+
+/// Merges the query string and fragment of an incoming request onto
+/// `original_url`.
+///
+/// The incoming query is appended after any query the stored URL already
+/// has, joined with `&`. A fragment on the stored URL wins, as it would for a
+/// browser following a `Location` that has one; otherwise the incoming
+/// fragment is used. Leading `?` and `#` on the arguments are ignored, and
+/// empty values add nothing.
+pub fn merge_target(
+    original_url: &str,
+    incoming_query: Option<&str>,
+    incoming_fragment: Option<&str>,
+) -> String {
+    let (rest, stored_fragment) = match original_url.split_once('#') {
+        Some((rest, fragment)) => (rest, Some(fragment)),
+        None => (original_url, None),
+    };
+    let (base, stored_query) = match rest.split_once('?') {
+        Some((base, query)) => (base, Some(query)),
+        None => (rest, None),
+    };
+    let incoming_query = incoming_query.map(|query| query.trim_start_matches('?'));
+    let incoming_fragment = incoming_fragment.map(|fragment| fragment.trim_start_matches('#'));
+
+    let mut target = base.to_string();
+    let queries = [stored_query, incoming_query];
+    let mut queries = queries.into_iter().flatten().filter(|q| !q.is_empty());
+    if let Some(first) = queries.next() {
+        target.push('?');
+        target.push_str(first);
+        for query in queries {
+            target.push('&');
+            target.push_str(query);
+        }
+    }
+
+    let fragment = stored_fragment
+        .filter(|fragment| !fragment.is_empty())
+        .or(incoming_fragment.filter(|fragment| !fragment.is_empty()));
+    if let Some(fragment) = fragment {
+        target.push('#');
+        target.push_str(fragment);
+    }
+
+    target
+}
+
+fn unknown_created_at() -> Timestamp {
+    Timestamp::UNIX_EPOCH
+}
+
+/// The HTTP redirect status used when resolving a short code.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RedirectKind {
+    /// `301 Moved Permanently`: clients and search engines may cache the target.
+    Permanent301,
+    /// `302 Found`: the target may change, so clients should not cache it.
+    #[default]
+    Found302,
+    /// `307 Temporary Redirect`: like 302, but the request method is preserved.
+    Temporary307,
+    /// `308 Permanent Redirect`: like 301, but the request method is preserved.
+    Permanent308,
+}
+
+impl RedirectKind {
+    /// Returns the HTTP status code for this redirect kind.
+    pub fn status_code(self) -> u16 {
+        match self {
+            RedirectKind::Permanent301 => 301,
+            RedirectKind::Found302 => 302,
+            RedirectKind::Temporary307 => 307,
+            RedirectKind::Permanent308 => 308,
+        }
+    }
+
+    /// Whether clients may remember the target instead of asking again.
+    pub fn is_permanent(self) -> bool {
+        matches!(
+            self,
+            RedirectKind::Permanent301 | RedirectKind::Permanent308
+        )
+    }
+
+    /// Maps an HTTP status code back to a redirect kind.
+    ///
+    /// Returns `None` for status codes that are not supported redirects.
+    pub fn from_status_code(code: u16) -> Option<Self> {
+        match code {
+            301 => Some(RedirectKind::Permanent301),
+            302 => Some(RedirectKind::Found302),
+            307 => Some(RedirectKind::Temporary307),
+            308 => Some(RedirectKind::Permanent308),
+            _ => None,
+        }
+    }
 }
 
 const MIN_LENGTH: usize = 3;
 const MAX_LENGTH: usize = 32;
 
+/// Deployment-specific rules for custom aliases, checked by
+/// [`ShortCode::custom_with_policy`].
+///
+/// The default policy reserves nothing, keeps aliases as typed and uses the
+/// same 3-32 character bounds as [`ShortCode::custom`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AliasPolicy {
+    /// Reserved words, stored lowercased.
+    reserved: HashSet<String>,
+    min_len: usize,
+    max_len: usize,
+    case_insensitive: bool,
+}
+
+impl Default for AliasPolicy {
+    fn default() -> Self {
+        Self {
+            reserved: HashSet::new(),
+            min_len: MIN_LENGTH,
+            max_len: MAX_LENGTH,
+            case_insensitive: false,
+        }
+    }
+}
+
+impl AliasPolicy {
+    /// Creates the default policy.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reserves `words`, e.g. route names like `admin` or `health` that an
+    /// alias must not shadow. Matching ignores ASCII case.
+    pub fn with_reserved<I, S>(mut self, words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.reserved.extend(
+            words
+                .into_iter()
+                .map(|word| word.as_ref().to_ascii_lowercase()),
+        );
+        self
+    }
+
+    /// Overrides the allowed alias length, in bytes, inclusive. The
+    /// defaults are 3 and 32.
+    ///
+    /// [`ShortCode::custom`] and [`ShortCode::is_valid`] keep the default
+    /// bounds, so whatever resolves codes must check them with
+    /// [`ShortCode::parse_with`] or [`ShortCode::is_valid_with`] under the
+    /// same policy.
+    ///
+    /// # Errors
+    ///
+    /// [`CoreError::InvalidAliasPolicy`] if `min_len` is zero or greater
+    /// than `max_len`, or `max_len` is over 32, the widest code storage
+    /// holds.
+    pub fn with_length(mut self, min_len: usize, max_len: usize) -> Result<Self, CoreError> {
+        if min_len == 0 || min_len > max_len {
+            return Err(CoreError::InvalidAliasPolicy(format!(
+                "invalid alias length bounds {min_len}..={max_len}"
+            )));
+        }
+        if max_len > MAX_LENGTH {
+            return Err(CoreError::InvalidAliasPolicy(format!(
+                "alias max length {max_len} exceeds {MAX_LENGTH}"
+            )));
+        }
+        self.min_len = min_len;
+        self.max_len = max_len;
+        Ok(self)
+    }
+
+    /// The policy allowing every length any policy can, 1 to 32 bytes.
+    ///
+    /// For checks that cannot know the deployment's policy, such as
+    /// decoding a code off the wire; the receiving service checks its own.
+    pub fn widest() -> Self {
+        Self {
+            min_len: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Lowercases aliases before validating them, so `MyLink` and `mylink`
+    /// name the same code.
+    ///
+    /// Generated codes are base58 and case-sensitive; this never touches
+    /// them.
+    pub fn with_case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive = case_insensitive;
+        self
+    }
+
+    /// Whether aliases are lowercased.
+    pub fn is_case_insensitive(&self) -> bool {
+        self.case_insensitive
+    }
+
+    /// Returns `true` if `code` is a reserved word, ignoring ASCII case.
+    pub fn is_reserved(&self, code: &str) -> bool {
+        self.reserved.contains(&code.to_ascii_lowercase())
+    }
+
+    /// The shortest allowed alias.
+    pub fn min_len(&self) -> usize {
+        self.min_len
+    }
+
+    /// The longest allowed alias.
+    pub fn max_len(&self) -> usize {
+        self.max_len
+    }
+}
+
 impl ShortCode {
     /// Creates a `ShortCode` from a value that can be converted into [`ShortCodeBase58`].
     ///
@@ -60,6 +316,43 @@ impl ShortCode {
         Ok(Self::Custom(code))
     }
 
+    /// Like [`ShortCode::custom`], but checks the code against `policy`
+    /// instead of the built-in rules.
+    ///
+    /// Reserved words fail with [`CoreError::ReservedAlias`]. With
+    /// [`AliasPolicy::with_case_insensitive`], the code is lowercased first.
+    pub fn custom_with_policy(
+        code: impl Into<String>,
+        policy: &AliasPolicy,
+    ) -> std::result::Result<Self, CoreError> {
+        let mut code = code.into();
+        if policy.case_insensitive {
+            code.make_ascii_lowercase();
+        }
+        Self::validate_with(&code, policy.min_len, policy.max_len)?;
+        if policy.is_reserved(&code) {
+            return Err(CoreError::ReservedAlias(code));
+        }
+        Ok(Self::Custom(code))
+    }
+
+    /// Parses a code that names an existing link, e.g. one taken from a
+    /// request path.
+    ///
+    /// Accepts any code that could have been created: one within the
+    /// built-in bounds, which generated codes always meet, or an alias
+    /// within `policy`'s length bounds. Unlike
+    /// [`ShortCode::custom_with_policy`], the code is neither lowercased
+    /// nor checked against reserved words.
+    pub fn parse_with(
+        code: impl Into<String>,
+        policy: &AliasPolicy,
+    ) -> std::result::Result<Self, CoreError> {
+        let code = code.into();
+        Self::validate_for(&code, policy)?;
+        Ok(Self::Custom(code))
+    }
+
     /// Creates a `ShortCode` without validation.
     ///
     /// Use this only for codes produced by trusted internal sources
@@ -68,9 +361,23 @@ impl ShortCode {
         Self::Custom(code.into())
     }
 
-    /// Generates the full shortened URL based on the provided base URL.
-    pub fn to_url(&self, base_url: &str) -> String {
-        format!("{}/{}", base_url.trim_end_matches('/'), self)
+    /// Generates the full shortened URL under `base_url`.
+    pub fn to_url(&self, base_url: &BaseUrl) -> String {
+        format!("{base_url}/{self}")
+    }
+
+    /// Returns `true` if the code passes the same checks as
+    /// [`ShortCode::custom`].
+    ///
+    /// Codes built with [`ShortCode::new_unchecked`] may fail this.
+    pub fn is_valid(&self) -> bool {
+        Self::validate(self.as_str()).is_ok()
+    }
+
+    /// Returns `true` if the code passes the same checks as
+    /// [`ShortCode::parse_with`] under `policy`.
+    pub fn is_valid_with(&self, policy: &AliasPolicy) -> bool {
+        Self::validate_for(self.as_str(), policy).is_ok()
     }
 
     /// Returns the short code as a string slice.
@@ -82,11 +389,19 @@ impl ShortCode {
     }
 
     fn validate(code: &str) -> Result<(), CoreError> {
-        if code.len() < MIN_LENGTH || code.len() > MAX_LENGTH {
+        Self::validate_with(code, MIN_LENGTH, MAX_LENGTH)
+    }
+
+    fn validate_for(code: &str, policy: &AliasPolicy) -> Result<(), CoreError> {
+        Self::validate(code).or_else(|_| Self::validate_with(code, policy.min_len, policy.max_len))
+    }
+
+    fn validate_with(code: &str, min_len: usize, max_len: usize) -> Result<(), CoreError> {
+        if code.len() < min_len || code.len() > max_len {
             return Err(CoreError::InvalidShortCode(format!(
                 "length must be between {} and {}, got {}",
-                MIN_LENGTH,
-                MAX_LENGTH,
+                min_len,
+                max_len,
                 code.len()
             )));
         }
@@ -145,6 +460,130 @@ mod tests {
         assert!(ShortCode::custom("abc!def").is_err());
     }
 
+    #[test]
+    fn is_valid_rechecks_unchecked_codes() {
+        assert!(ShortCode::custom("abc123").unwrap().is_valid());
+        assert!(ShortCode::new_unchecked("abc123").is_valid());
+        assert!(!ShortCode::new_unchecked("ab").is_valid());
+        assert!(!ShortCode::new_unchecked("abc/def").is_valid());
+    }
+
+    #[test]
+    fn reserved_aliases_are_rejected_in_any_case() {
+        let policy = AliasPolicy::new().with_reserved(["admin", "API", "health"]);
+
+        for alias in ["admin", "Admin", "ADMIN", "api", "Api", "hEaLtH"] {
+            let err = ShortCode::custom_with_policy(alias, &policy).unwrap_err();
+            assert!(
+                matches!(&err, CoreError::ReservedAlias(code) if code == alias),
+                "{alias}: {err:?}"
+            );
+        }
+
+        assert!(ShortCode::custom_with_policy("admins", &policy).is_ok());
+        assert!(ShortCode::custom("admin").is_ok());
+    }
+
+    #[test]
+    fn case_insensitive_policy_lowercases_aliases() {
+        let policy = AliasPolicy::new().with_case_insensitive(true);
+
+        let code = ShortCode::custom_with_policy("MyLink", &policy).unwrap();
+        assert_eq!(code, ShortCode::custom("mylink").unwrap());
+
+        let code = ShortCode::custom_with_policy("MyLink", &AliasPolicy::new()).unwrap();
+        assert_eq!(code.as_str(), "MyLink");
+    }
+
+    #[test]
+    fn default_policy_matches_custom() {
+        let policy = AliasPolicy::default();
+        assert_eq!((policy.min_len(), policy.max_len()), (3, 32));
+
+        for code in [
+            "ab".to_string(),
+            "abc".to_string(),
+            "a".repeat(32),
+            "a".repeat(33),
+        ] {
+            assert_eq!(
+                ShortCode::custom_with_policy(code.as_str(), &policy).is_ok(),
+                ShortCode::custom(code.as_str()).is_ok(),
+                "{code}"
+            );
+        }
+    }
+
+    #[test]
+    fn length_errors_report_the_configured_bounds() {
+        let policy = AliasPolicy::new().with_length(2, 16).unwrap();
+
+        assert!(ShortCode::custom_with_policy("ab", &policy).is_ok());
+        assert!(ShortCode::custom_with_policy("a".repeat(16), &policy).is_ok());
+
+        let err = ShortCode::custom_with_policy("a", &policy).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid short code: length must be between 2 and 16, got 1"
+        );
+        let err = ShortCode::custom_with_policy("a".repeat(17), &policy).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid short code: length must be between 2 and 16, got 17"
+        );
+
+        // The policy-free constructor keeps its own bounds.
+        assert!(ShortCode::custom("ab").is_err());
+        assert!(ShortCode::custom("a".repeat(17)).is_ok());
+    }
+
+    #[test]
+    fn invalid_length_bounds_are_rejected() {
+        for (min_len, max_len) in [(8, 4), (0, 4), (3, 33)] {
+            let err = AliasPolicy::new()
+                .with_length(min_len, max_len)
+                .unwrap_err();
+            assert!(
+                matches!(err, CoreError::InvalidAliasPolicy(_)),
+                "{min_len}..={max_len}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_with_accepts_policy_and_built_in_lengths() {
+        let policy = AliasPolicy::new()
+            .with_length(2, 4)
+            .unwrap()
+            .with_reserved(["admin"])
+            .with_case_insensitive(true);
+
+        // Short aliases the policy allows, and generated-length codes.
+        for code in ["ab", "abcdefgh", "Admin", "a".repeat(32).as_str()] {
+            let parsed = ShortCode::parse_with(code, &policy).unwrap();
+            assert_eq!(parsed.as_str(), code);
+            assert!(parsed.is_valid_with(&policy));
+        }
+        for code in ["a", "a".repeat(33).as_str(), "a/b", ""] {
+            assert!(ShortCode::parse_with(code, &policy).is_err(), "{code:?}");
+        }
+
+        assert!(!ShortCode::new_unchecked("ab").is_valid());
+        assert!(ShortCode::new_unchecked("ab").is_valid_with(&policy));
+        assert!(ShortCode::new_unchecked("a").is_valid_with(&AliasPolicy::widest()));
+    }
+
+    #[test]
+    fn policy_overrides_length_bounds() {
+        let policy = AliasPolicy::new().with_length(2, 4).unwrap();
+
+        assert!(ShortCode::custom_with_policy("ab", &policy).is_ok());
+        assert!(ShortCode::custom_with_policy("abcd", &policy).is_ok());
+        assert!(ShortCode::custom_with_policy("a", &policy).is_err());
+        assert!(ShortCode::custom_with_policy("abcde", &policy).is_err());
+        assert!(ShortCode::custom_with_policy("a/b", &policy).is_err());
+    }
+
     #[test]
     fn display_custom() {
         let code = ShortCode::custom("my-code").unwrap();
@@ -154,10 +593,144 @@ mod tests {
     #[test]
     fn to_url_custom() {
         let code = ShortCode::custom("abc123").unwrap();
-        assert_eq!(code.to_url("https://worm.hole"), "https://worm.hole/abc123");
+        let cases = [
+            ("https://worm.hole", "https://worm.hole/abc123"),
+            ("https://worm.hole/", "https://worm.hole/abc123"),
+            (
+                "https://go.brand-a.com/s",
+                "https://go.brand-a.com/s/abc123",
+            ),
+            ("https://l.brand-b.io/s//", "https://l.brand-b.io/s/abc123"),
+            ("http://localhost:8080", "http://localhost:8080/abc123"),
+        ];
+        for (base, expected) in cases {
+            let base = BaseUrl::parse(base).unwrap();
+            assert_eq!(code.to_url(&base), expected);
+        }
+    }
+
+    #[test]
+    fn redirect_kind_status_code_round_trips() {
+        for kind in [
+            RedirectKind::Permanent301,
+            RedirectKind::Found302,
+            RedirectKind::Temporary307,
+            RedirectKind::Permanent308,
+        ] {
+            assert_eq!(
+                RedirectKind::from_status_code(kind.status_code()),
+                Some(kind)
+            );
+        }
+        assert_eq!(RedirectKind::from_status_code(303), None);
+        assert_eq!(RedirectKind::from_status_code(200), None);
+    }
+
+    #[test]
+    fn url_record_without_redirect_kind_defaults_to_found() {
+        let record: UrlRecord =
+            serde_json::from_str(r#"{"original_url":"https://example.com","expire_at":null}"#)
+                .unwrap();
+        assert_eq!(record.redirect_kind, RedirectKind::Found302);
+    }
+
+    #[test]
+    fn url_record_without_created_at_defaults_to_epoch() {
+        let record: UrlRecord =
+            serde_json::from_str(r#"{"original_url":"https://example.com","expire_at":null}"#)
+                .unwrap();
+        assert_eq!(record.created_at, Timestamp::UNIX_EPOCH);
+    }
+
+    #[test]
+    fn url_record_without_internal_only_is_public() {
+        let record: UrlRecord =
+            serde_json::from_str(r#"{"original_url":"https://example.com","expire_at":null}"#)
+                .unwrap();
+        assert!(!record.internal_only);
+    }
+
+    #[test]
+    fn url_record_owner_and_metadata_round_trip() {
+        let mut owned = record("https://example.com");
+        owned.owner_id = Some("tenant-a".to_string());
+        owned
+            .metadata
+            .insert("campaign".to_string(), "spring".to_string());
+
+        let json = serde_json::to_string(&owned).unwrap();
+        let decoded: UrlRecord = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, owned);
+
+        // Records without either are serialized without them, and entries
+        // cached before the fields existed decode as unowned.
+        let json = serde_json::to_string(&record("https://example.com")).unwrap();
+        assert!(!json.contains("owner_id") && !json.contains("metadata"));
+        let decoded: UrlRecord = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.owner_id, None);
+        assert!(decoded.metadata.is_empty());
+    }
+
+    fn record(original_url: &str) -> UrlRecord {
+        UrlRecord {
+            original_url: original_url.to_string(),
+            expire_at: None,
+            redirect_kind: RedirectKind::default(),
+            created_at: Timestamp::UNIX_EPOCH,
+            internal_only: false,
+            no_store: false,
+            owner_id: None,
+            metadata: Default::default(),
+        }
+    }
+
+    #[test]
+    fn build_target_adds_query_to_url_without_one() {
+        let record = record("https://example.com/page");
+
+        assert_eq!(
+            record.build_target(Some("ref=x"), None),
+            "https://example.com/page?ref=x"
+        );
+        assert_eq!(
+            record.build_target(Some("?ref=x"), None),
+            "https://example.com/page?ref=x"
+        );
+        assert_eq!(record.build_target(None, None), "https://example.com/page");
+        assert_eq!(
+            record.build_target(Some(""), None),
+            "https://example.com/page"
+        );
+    }
+
+    #[test]
+    fn build_target_appends_to_an_existing_query() {
+        let record = record("https://example.com/page?utm_source=mail");
+
+        assert_eq!(
+            record.build_target(Some("ref=x&lang=en"), None),
+            "https://example.com/page?utm_source=mail&ref=x&lang=en"
+        );
+        assert_eq!(
+            record.build_target(None, None),
+            "https://example.com/page?utm_source=mail"
+        );
+    }
+
+    #[test]
+    fn build_target_merges_fragments() {
+        assert_eq!(
+            record("https://example.com/page").build_target(Some("ref=x"), Some("#frag")),
+            "https://example.com/page?ref=x#frag"
+        );
+        // The stored fragment stays after the merged query and wins.
+        assert_eq!(
+            record("https://example.com/page?a=1#top").build_target(Some("b=2"), Some("frag")),
+            "https://example.com/page?a=1&b=2#top"
+        );
         assert_eq!(
-            code.to_url("https://worm.hole/"),
-            "https://worm.hole/abc123"
+            record("https://example.com/page#").build_target(None, Some("frag")),
+            "https://example.com/page#frag"
         );
     }
 }
@@ -2,10 +2,16 @@
 
 use clap::Parser;
 use std::net::SocketAddr;
+use wormhole_gateway::root::RootBehavior;
 
 pub const LISTEN_ADDR_ENV: &str = "WORMHOLE_GATEWAY_LISTEN_ADDR";
 pub const SHORTENER_ADDR_ENV: &str = "WORMHOLE_GATEWAY_SHORTENER_ADDR";
 pub const REDIRECTOR_ADDR_ENV: &str = "WORMHOLE_GATEWAY_REDIRECTOR_ADDR";
+pub const ROOT_BEHAVIOR_ENV: &str = "WORMHOLE_GATEWAY_ROOT_BEHAVIOR";
+pub const NOT_FOUND_MAX_AGE_ENV: &str = "WORMHOLE_GATEWAY_NOT_FOUND_MAX_AGE";
+pub const EXPIRED_MAX_AGE_ENV: &str = "WORMHOLE_GATEWAY_EXPIRED_MAX_AGE";
+pub const ALIAS_MIN_LEN_ENV: &str = "WORMHOLE_GATEWAY_ALIAS_MIN_LEN";
+pub const ALIAS_MAX_LEN_ENV: &str = "WORMHOLE_GATEWAY_ALIAS_MAX_LEN";
 pub const DEFAULT_LISTEN_ADDR: &str = "0.0.0.0:8080";
 
 #[derive(Debug, Parser)]
@@ -23,4 +29,24 @@ pub struct CLI {
     #[arg(long, env = REDIRECTOR_ADDR_ENV)]
     /// gRPC address for the redirector service, e.g., "http://127.0.0.1:50052"
     pub redirector_addr: String,
+
+    #[arg(long, env = ROOT_BEHAVIOR_ENV, default_value = "not-found")]
+    /// What to serve at "/": "static-file:<path>", "redirect:<url>" or "not-found"
+    pub root_behavior: RootBehavior,
+
+    #[arg(long, env = NOT_FOUND_MAX_AGE_ENV, default_value_t = 0)]
+    /// Seconds a CDN may cache the 404 for an unknown short code; 0 sends "no-store"
+    pub not_found_max_age: u64,
+
+    #[arg(long, env = EXPIRED_MAX_AGE_ENV, default_value_t = 0)]
+    /// Seconds a CDN may cache the 404 for an expired short code; 0 sends "no-store"
+    pub expired_max_age: u64,
+
+    #[arg(long, env = ALIAS_MIN_LEN_ENV, default_value_t = 3, value_parser = clap::value_parser!(u8).range(1..=32))]
+    /// Shortest custom alias accepted in paths; must match the shortener's --alias-min-len
+    pub alias_min_len: u8,
+
+    #[arg(long, env = ALIAS_MAX_LEN_ENV, default_value_t = 32, value_parser = clap::value_parser!(u8).range(1..=32))]
+    /// Longest custom alias accepted in paths; must match the shortener's --alias-max-len
+    pub alias_max_len: u8,
 }
@@ -3,13 +3,16 @@ mod cli;
 
 use wormhole_gateway::adapter::grpc::GrpcUrlAdapter;
 use wormhole_gateway::app::App;
+use wormhole_gateway::not_found::NotFoundCaching;
 use wormhole_gateway::state::AppState;
 use wormhole_telemetry::init_tracing;
 
 use crate::cli::CLI;
 use clap::Parser;
+use std::time::Duration;
 use tonic::transport::Endpoint;
 use tracing::info;
+use wormhole_core::{AliasPolicy, BaseUrl};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -22,6 +25,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         listen_addr = %config.listen_addr,
         shortener_addr = %config.shortener_addr,
         redirector_addr = %config.redirector_addr,
+        root_behavior = %config.root_behavior,
+        not_found_max_age = config.not_found_max_age,
+        expired_max_age = config.expired_max_age,
         "starting gateway HTTP server"
     );
 
@@ -47,12 +53,22 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let adapter = GrpcUrlAdapter::builder()
         .shortener(shortener_client)
         .redirector(redirector_client)
+        .base_url(BaseUrl::parse("https://worm.hole")?)
         .build();
 
+    let alias_policy =
+        AliasPolicy::new().with_length(config.alias_min_len.into(), config.alias_max_len.into())?;
+
     // Create application state with the adapter
     let state = AppState::builder()
         .url_service(adapter)
         .base_url("https://worm.hole".to_string())
+        .root_behavior(config.root_behavior)
+        .not_found_caching(NotFoundCaching {
+            missing_max_age: Duration::from_secs(config.not_found_max_age),
+            expired_max_age: Duration::from_secs(config.expired_max_age),
+        })
+        .alias_policy(alias_policy)
         .build();
 
     // Build and start the Axum router
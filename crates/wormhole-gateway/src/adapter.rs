@@ -1,2 +1,2 @@
 pub mod grpc;
-mod local;
+pub mod local;
@@ -6,8 +6,12 @@
 //! protocol - it only knows about the trait methods, not how they're implemented.
 
 use async_trait::async_trait;
+use prost::Message;
 use tonic::transport::Channel;
+use tonic::Code;
 use typed_builder::TypedBuilder;
+use wormhole_core::BaseUrl;
+use wormhole_proto_schema::shortener::v1 as shortener_proto;
 use wormhole_proto_schema::v1 as proto;
 use wormhole_proto_schema::v1::redirector_service_client::RedirectorServiceClient;
 use wormhole_proto_schema::v1::shortener_service_client::ShortenerServiceClient;
@@ -36,6 +40,10 @@ pub struct GrpcUrlAdapter {
     /// gRPC client for the redirector service (read operations).
     #[builder]
     redirector: RedirectorServiceClient<Channel>,
+    /// Base to build short URLs on when the shortener's response has no
+    /// `short_url`, i.e. when the shortener has no base URL configured.
+    #[builder(default, setter(strip_option))]
+    base_url: Option<BaseUrl>,
 }
 
 impl GrpcUrlAdapter {
@@ -47,10 +55,30 @@ impl GrpcUrlAdapter {
         Self {
             shortener,
             redirector,
+            base_url: None,
         }
     }
 }
 
+/// Builds the wire short code for a code taken from a request path.
+///
+/// The gateway cannot tell a generated code from an alias, so it picks the
+/// kind the services will accept: codes no base58 alphabet could spell, e.g.
+/// with `-` or `_` or outside the built-in length bounds, go as aliases.
+fn wire_short_code(code: String) -> ShortCode {
+    let generated = wormhole_core::ShortCode::custom(code.as_str()).is_ok()
+        && code.bytes().all(|c| c.is_ascii_alphanumeric());
+    let kind = if generated {
+        ShortCodeKind::Generated
+    } else {
+        ShortCodeKind::Custom
+    };
+    ShortCode {
+        code,
+        kind: kind as i32,
+    }
+}
+
 // ==============================================================================
 // UrlWrite Implementation
 // ==============================================================================
@@ -70,6 +98,9 @@ impl UrlWrite for GrpcUrlAdapter {
             original_url: cmd.original_url,
             custom_alias: cmd.custom_alias,
             expire_at,
+            internal_only: false,
+            no_store: false,
+            base_url: None,
         };
 
         // Call the remote shortener service
@@ -86,31 +117,48 @@ impl UrlWrite for GrpcUrlAdapter {
             .short_code
             .ok_or_else(|| BackendError::Internal("missing short_code in response".to_string()))?;
 
-        let short_code_str = short_code.code.clone();
+        // Prefer the shortener's URL, which honors its configured base.
+        let short_url = match (response.short_url, &self.base_url) {
+            (short_url, _) if !short_url.is_empty() => short_url,
+            (_, Some(base_url)) => format!("{base_url}/{}", short_code.code),
+            (_, None) => {
+                return Err(BackendError::Internal(
+                    "shortener returned no short_url and no base URL is configured".to_string(),
+                ))
+            }
+        };
 
-        // Note: The gRPC response doesn't include short_url or original_url
-        // We reconstruct what we can from the request
+        // The response doesn't echo the original URL or expiry; take them
+        // from the request.
         Ok(WriteUrlResult {
-            short_code: short_code_str.clone(),
-            short_url: format!("https://worm.hole/{}", short_code_str),
+            short_code: short_code.code,
+            short_url,
             original_url,
             expire_at: cmd.expire_at,
         })
     }
 
     async fn delete(&self, cmd: DeleteUrlCmd) -> Result<()> {
-        // Build the short code for the request
-        let _short_code = ShortCode {
-            code: cmd.short_code,
-            kind: ShortCodeKind::Generated as i32,
+        let short_code = wire_short_code(cmd.short_code);
+
+        let request = shortener_proto::DeleteRequest {
+            short_code: Some(short_code),
         };
 
-        // Call the remote shortener service's delete method
-        // Note: The proto only defines Create, so we'd need to add Delete to the proto
-        // For now, we'll handle this as a not-implemented error
-        Err(BackendError::Internal(
-            "delete not implemented in gRPC adapter (requires proto extension)".to_string(),
-        ))
+        // Call the remote shortener service
+        let response = self
+            .shortener
+            .clone()
+            .delete(request)
+            .await
+            .map_err(|e| BackendError::Internal(e.to_string()))?
+            .into_inner();
+
+        if response.existed {
+            Ok(())
+        } else {
+            Err(BackendError::NotFound)
+        }
     }
 }
 
@@ -118,14 +166,27 @@ impl UrlWrite for GrpcUrlAdapter {
 // UrlRead Implementation
 // ==============================================================================
 
+/// Maps a failed `Resolve` call, using the `ResolveFailure` details to tell
+/// expired codes apart from other misses.
+fn resolve_error(status: tonic::Status) -> BackendError {
+    match status.code() {
+        Code::NotFound => {
+            let expired = proto::ResolveFailure::decode(status.details())
+                .is_ok_and(|failure| failure.reason() == proto::ResolveFailureReason::Expired);
+            if expired {
+                BackendError::Expired
+            } else {
+                BackendError::NotFound
+            }
+        }
+        _ => BackendError::Internal(status.to_string()),
+    }
+}
+
 #[async_trait]
 impl UrlRead for GrpcUrlAdapter {
     async fn get(&self, short_code: &str) -> Result<GetUrlResult> {
-        // Build the short code for the request
-        let short_code = ShortCode {
-            code: short_code.to_string(),
-            kind: ShortCodeKind::Generated as i32,
-        };
+        let short_code = wire_short_code(short_code.to_string());
 
         let request = proto::ResolveRequest {
             short_code: Some(short_code),
@@ -137,7 +198,7 @@ impl UrlRead for GrpcUrlAdapter {
             .clone()
             .resolve(request)
             .await
-            .map_err(|e| BackendError::Internal(e.to_string()))?
+            .map_err(resolve_error)?
             .into_inner();
 
         // Extract the URL record from response
@@ -148,9 +209,12 @@ impl UrlRead for GrpcUrlAdapter {
             .expire_at
             .map(|ts| jiff::Timestamp::new(ts.seconds, ts.nanos).expect("valid timestamp"));
 
+        let redirect_kind = url_record.redirect_kind().into();
+
         Ok(GetUrlResult {
             original_url: url_record.original_url,
             expire_at,
+            redirect_kind,
         })
     }
 }
@@ -1,9 +1,9 @@
 use async_trait::async_trait;
 use std::sync::Arc;
 use typed_builder::TypedBuilder;
-use wormhole_core::ShortCode;
-use wormhole_redirector::redirector::Redirector;
-use wormhole_shortener::shortener::{ExpirationPolicy, ShortenParams, Shortener};
+use wormhole_core::{AliasPolicy, BaseUrl, ShortCode};
+use wormhole_redirector::redirector::{CallerTrust, NotFoundReason, Redirector, Resolution};
+use wormhole_shortener::shortener::{ConflictPolicy, ExpirationPolicy, ShortenParams, Shortener};
 
 use crate::backend::{
     BackendError, DeleteUrlCmd, GetUrlResult, Result, UrlRead, UrlWrite, WriteUrlCmd,
@@ -28,13 +28,25 @@ pub struct LocalUrlAdapter {
         )
     )]
     redirector: Arc<dyn Redirector>,
-    #[builder(setter(into))]
-    base_url: String,
+    /// Base short URLs are built on, e.g. "https://worm.hole".
+    ///
+    /// The setter panics if this is not a valid [`BaseUrl`].
+    #[builder(
+        setter(
+            fn transform<T: AsRef<str>>(base_url: T) -> BaseUrl {
+                BaseUrl::parse(base_url.as_ref())
+                    .unwrap_or_else(|error| panic!("invalid base URL: {error}"))
+            }
+        )
+    )]
+    base_url: BaseUrl,
 }
 
 impl LocalUrlAdapter {
+    /// Accepts any code an alias policy can allow; the services behind the
+    /// adapter check their own.
     fn parse_short_code(short_code: &str) -> Result<ShortCode> {
-        ShortCode::custom(short_code)
+        ShortCode::parse_with(short_code, &AliasPolicy::widest())
             .map_err(|error| BackendError::InvalidShortCode(error.to_string()))
     }
 }
@@ -64,6 +76,10 @@ impl UrlWrite for LocalUrlAdapter {
                 original_url: original_url.clone(),
                 expiration,
                 custom_alias,
+                internal_only: false,
+                no_store: false,
+                idempotency_key: None,
+                conflict_policy: ConflictPolicy::Reject,
             })
             .await
             .map_err(BackendError::from)?;
@@ -97,27 +113,35 @@ impl UrlRead for LocalUrlAdapter {
     async fn get(&self, short_code: &str) -> Result<GetUrlResult> {
         let short_code = Self::parse_short_code(short_code)?;
 
-        let record = self
+        // The gateway serves the public internet, so internal-only codes
+        // must not resolve through it.
+        let record = match self
             .redirector
-            .resolve(&short_code)
+            .resolve_detailed(&short_code, CallerTrust::Untrusted)
             .await
             .map_err(BackendError::from)?
-            .ok_or(BackendError::NotFound)?;
+        {
+            Resolution::Found(record) => record,
+            Resolution::NotFound(NotFoundReason::Expired) => return Err(BackendError::Expired),
+            Resolution::NotFound(_) => return Err(BackendError::NotFound),
+        };
 
         Ok(GetUrlResult {
             original_url: record.original_url,
             expire_at: record.expire_at,
+            redirect_kind: record.redirect_kind,
         })
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::backend::{DeleteUrlCmd, UrlRead, UrlWrite, WriteUrlCmd};
+    use crate::backend::{BackendError, DeleteUrlCmd, UrlRead, UrlWrite, WriteUrlCmd};
+    use wormhole_core::{RedirectKind, ShortCode, UrlRecord};
     use wormhole_generator::seq::SeqGenerator;
     use wormhole_redirector::RedirectorService;
     use wormhole_shortener::service::ShortenerService;
-    use wormhole_storage::InMemoryRepository;
+    use wormhole_storage::{InMemoryRepository, Repository};
 
     #[tokio::test]
     async fn smoke_test() {
@@ -162,4 +186,37 @@ mod tests {
         let get_response = adapter.get(&code).await;
         assert!(get_response.is_err());
     }
+
+    #[tokio::test]
+    async fn internal_only_codes_are_not_found() {
+        let storage = InMemoryRepository::new();
+        storage
+            .insert(
+                &ShortCode::new_unchecked("internal"),
+                UrlRecord {
+                    original_url: "https://intranet.example".to_string(),
+                    expire_at: None,
+                    redirect_kind: RedirectKind::default(),
+                    created_at: jiff::Timestamp::now(),
+                    internal_only: true,
+                    no_store: false,
+                    owner_id: None,
+                    metadata: Default::default(),
+                },
+            )
+            .await
+            .unwrap();
+
+        let adapter = super::LocalUrlAdapter::builder()
+            .shortener(ShortenerService::new(
+                storage.clone(),
+                SeqGenerator::with_prefix("test"),
+            ))
+            .redirector(RedirectorService::new(storage))
+            .base_url("https://worm.hole")
+            .build();
+
+        let err = adapter.get("internal").await.unwrap_err();
+        assert!(matches!(err, BackendError::NotFound));
+    }
 }
@@ -1,8 +1,11 @@
-use crate::handlers::{create_url_handler, delete_url_handler, get_url_handler, health_handler};
+use crate::handlers::{
+    create_url_handler, delete_url_handler, get_url_handler, health_handler, redirect_handler,
+    root_handler,
+};
 use crate::state::AppState;
 use axum::extract::MatchedPath;
 use axum::http::Request;
-use axum::routing::{get, post};
+use axum::routing::{any, get, post};
 use axum::Router;
 use tower_http::trace::{DefaultOnRequest, DefaultOnResponse};
 use tower_http::LatencyUnit;
@@ -43,16 +46,21 @@ impl App {
                     .latency_unit(LatencyUnit::Micros),
             );
 
-        Router::new()
+        let router = Router::new()
+            .route("/", get(root_handler))
             .route("/health", get(health_handler))
+            .route("/{short_code}", any(redirect_handler))
             .nest(
                 "/v1/urls",
                 Router::new().route("/", post(create_url_handler)).route(
                     "/{short_code}",
                     get(get_url_handler).delete(delete_url_handler),
                 ),
-            )
-            .layer(trace_layer)
-            .with_state(state)
+            );
+
+        #[cfg(feature = "metrics")]
+        let router = router.route("/metrics", get(crate::handlers::metrics_handler));
+
+        router.layer(trace_layer).with_state(state)
     }
 }
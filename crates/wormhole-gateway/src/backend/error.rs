@@ -1,12 +1,16 @@
-use wormhole_redirector::RedirectorError;
+use wormhole_redirector::{NotFoundReason, RedirectorError};
 use wormhole_shortener::ShortenerError;
 
 #[derive(Debug)]
 pub enum BackendError {
     InvalidUrl(String),
     InvalidShortCode(String),
+    InvalidExpiration(String),
     NotFound,
+    /// The short code existed but has expired.
+    Expired,
     AliasConflict(String),
+    IdempotencyConflict(String),
     StorageUnavailable(String),
     StorageTimeout(String),
     Internal(String),
@@ -18,8 +22,13 @@ impl From<ShortenerError> for BackendError {
     fn from(error: ShortenerError) -> Self {
         match error {
             ShortenerError::AliasConflict(code) => Self::AliasConflict(code),
+            ShortenerError::IdempotencyConflict(key) => Self::IdempotencyConflict(key),
             ShortenerError::InvalidUrl(message) => Self::InvalidUrl(message),
             ShortenerError::InvalidShortCode(message) => Self::InvalidShortCode(message),
+            ShortenerError::ReservedAlias(code) => {
+                Self::InvalidShortCode(format!("alias is reserved: {code}"))
+            }
+            ShortenerError::InvalidExpiration(message) => Self::InvalidExpiration(message),
             ShortenerError::Storage(message) => {
                 if message.starts_with("storage backend unavailable:") {
                     Self::StorageUnavailable(message)
@@ -29,6 +38,7 @@ impl From<ShortenerError> for BackendError {
                     Self::Internal(message)
                 }
             }
+            ShortenerError::GenerationFailed(message) => Self::Internal(message),
         }
     }
 }
@@ -40,7 +50,13 @@ impl From<RedirectorError> for BackendError {
                 Self::InvalidShortCode("short code is required".to_string())
             }
             RedirectorError::ShortCodeMalformed(message) => Self::InvalidShortCode(message),
-            RedirectorError::ShortCodeNotFound => Self::NotFound,
+            RedirectorError::ShortCodeUnresolved(NotFoundReason::Expired) => Self::Expired,
+            RedirectorError::ShortCodeNotFound | RedirectorError::ShortCodeUnresolved(_) => {
+                Self::NotFound
+            }
+            RedirectorError::Maintenance { .. } => {
+                Self::StorageUnavailable("service is under maintenance".to_string())
+            }
             RedirectorError::Storage(source) => {
                 let message = source.to_string();
 
@@ -1,6 +1,7 @@
 use super::Result;
 use async_trait::async_trait;
 use jiff::Timestamp;
+use wormhole_core::RedirectKind;
 
 #[derive(Debug, Clone)]
 pub struct GetUrlCmd {
@@ -11,6 +12,7 @@ pub struct GetUrlCmd {
 pub struct GetUrlResult {
     pub original_url: String,
     pub expire_at: Option<Timestamp>,
+    pub redirect_kind: RedirectKind,
 }
 
 #[async_trait]
@@ -12,8 +12,10 @@ pub enum AppError {
     InvalidRequest(String),
     InvalidUrl(String),
     InvalidShortCode(String),
+    InvalidExpiration(String),
     NotFound,
     AliasConflict(String),
+    IdempotencyConflict(String),
     StorageUnavailable(String),
     StorageTimeout(String),
     Internal(String),
@@ -29,8 +31,10 @@ impl AppError {
             Self::InvalidRequest(_) => (StatusCode::BAD_REQUEST, "invalid_request"),
             Self::InvalidUrl(_) => (StatusCode::BAD_REQUEST, "invalid_url"),
             Self::InvalidShortCode(_) => (StatusCode::BAD_REQUEST, "invalid_short_code"),
+            Self::InvalidExpiration(_) => (StatusCode::BAD_REQUEST, "invalid_expiration"),
             Self::NotFound => (StatusCode::NOT_FOUND, "short_code_not_found"),
             Self::AliasConflict(_) => (StatusCode::CONFLICT, "alias_conflict"),
+            Self::IdempotencyConflict(_) => (StatusCode::CONFLICT, "idempotency_conflict"),
             Self::StorageUnavailable(_) => (StatusCode::SERVICE_UNAVAILABLE, "storage_unavailable"),
             Self::StorageTimeout(_) => (StatusCode::GATEWAY_TIMEOUT, "storage_timeout"),
             Self::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, "internal_error"),
@@ -42,11 +46,15 @@ impl AppError {
             Self::InvalidRequest(message)
             | Self::InvalidUrl(message)
             | Self::InvalidShortCode(message)
+            | Self::InvalidExpiration(message)
             | Self::StorageUnavailable(message)
             | Self::StorageTimeout(message)
             | Self::Internal(message) => message,
             Self::NotFound => "short code not found".to_string(),
             Self::AliasConflict(_) => "short code already exists".to_string(),
+            Self::IdempotencyConflict(_) => {
+                "idempotency key was already used for a different request".to_string()
+            }
         }
     }
 }
@@ -55,8 +63,13 @@ impl From<ShortenerError> for AppError {
     fn from(error: ShortenerError) -> Self {
         match error {
             ShortenerError::AliasConflict(code) => Self::AliasConflict(code),
+            ShortenerError::IdempotencyConflict(key) => Self::IdempotencyConflict(key),
             ShortenerError::InvalidUrl(message) => Self::InvalidUrl(message),
             ShortenerError::InvalidShortCode(message) => Self::InvalidShortCode(message),
+            ShortenerError::ReservedAlias(code) => {
+                Self::InvalidShortCode(format!("alias is reserved: {code}"))
+            }
+            ShortenerError::InvalidExpiration(message) => Self::InvalidExpiration(message),
             ShortenerError::Storage(message) => {
                 if message.starts_with("storage backend unavailable:") {
                     Self::StorageUnavailable(message)
@@ -66,6 +79,7 @@ impl From<ShortenerError> for AppError {
                     Self::Internal(message)
                 }
             }
+            ShortenerError::GenerationFailed(message) => Self::Internal(message),
         }
     }
 }
@@ -77,7 +91,12 @@ impl From<RedirectorError> for AppError {
                 Self::InvalidShortCode("short code is required".to_string())
             }
             RedirectorError::ShortCodeMalformed(message) => Self::InvalidShortCode(message),
-            RedirectorError::ShortCodeNotFound => Self::NotFound,
+            RedirectorError::ShortCodeNotFound | RedirectorError::ShortCodeUnresolved(_) => {
+                Self::NotFound
+            }
+            RedirectorError::Maintenance { .. } => {
+                Self::StorageUnavailable("service is under maintenance".to_string())
+            }
             RedirectorError::Storage(source) => {
                 let message = source.to_string();
 
@@ -98,8 +117,10 @@ impl From<BackendError> for AppError {
         match error {
             BackendError::InvalidUrl(message) => Self::InvalidUrl(message),
             BackendError::InvalidShortCode(message) => Self::InvalidShortCode(message),
-            BackendError::NotFound => Self::NotFound,
+            BackendError::InvalidExpiration(message) => Self::InvalidExpiration(message),
+            BackendError::NotFound | BackendError::Expired => Self::NotFound,
             BackendError::AliasConflict(code) => Self::AliasConflict(code),
+            BackendError::IdempotencyConflict(key) => Self::IdempotencyConflict(key),
             BackendError::StorageUnavailable(message) => Self::StorageUnavailable(message),
             BackendError::StorageTimeout(message) => Self::StorageTimeout(message),
             BackendError::Internal(message) => Self::Internal(message),
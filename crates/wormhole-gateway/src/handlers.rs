@@ -1,5 +1,13 @@
 mod health;
+#[cfg(feature = "metrics")]
+mod metrics;
+mod redirect;
+mod root;
 mod url;
 
 pub use health::*;
+#[cfg(feature = "metrics")]
+pub use metrics::*;
+pub use redirect::*;
+pub use root::*;
 pub use url::*;
@@ -0,0 +1,12 @@
+use axum::http::header::CONTENT_TYPE;
+use axum::response::IntoResponse;
+
+/// Serves every Wormhole metric recorded in this process in the Prometheus
+/// text exposition format.
+pub async fn metrics_handler() -> impl IntoResponse {
+    let metrics = wormhole_metrics::metrics();
+    (
+        [(CONTENT_TYPE, wormhole_metrics::CONTENT_TYPE)],
+        metrics.encode(),
+    )
+}
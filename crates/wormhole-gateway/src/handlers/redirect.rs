@@ -0,0 +1,420 @@
+use crate::backend::BackendError;
+use crate::error::{AppError, Result};
+use crate::not_found::cache_control;
+use crate::path::ShortCodeSegment;
+use crate::state::AppState;
+use axum::extract::{RawQuery, State};
+use axum::http::header::{CACHE_CONTROL, LOCATION};
+use axum::http::{HeaderValue, Method, StatusCode};
+use axum::response::{IntoResponse, Response};
+use jiff::Timestamp;
+use std::time::Duration;
+use tracing::instrument;
+use wormhole_core::{merge_target, RedirectKind};
+
+/// How long clients and shared caches may keep a permanent redirect.
+const PERMANENT_REDIRECT_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Picks the status for a redirect of `kind` answering a `method` request.
+///
+/// Clients may turn a 301 or 302 into a GET, which would drop the body of an
+/// API call made through the short link. Requests other than GET and HEAD
+/// therefore get the method-preserving equivalent (308 or 307) instead.
+fn redirect_status(kind: RedirectKind, method: &Method) -> StatusCode {
+    let preserve_method = !matches!(*method, Method::GET | Method::HEAD);
+    match kind {
+        RedirectKind::Permanent301 if preserve_method => StatusCode::PERMANENT_REDIRECT,
+        RedirectKind::Permanent301 => StatusCode::MOVED_PERMANENTLY,
+        RedirectKind::Found302 if preserve_method => StatusCode::TEMPORARY_REDIRECT,
+        RedirectKind::Found302 => StatusCode::FOUND,
+        RedirectKind::Temporary307 => StatusCode::TEMPORARY_REDIRECT,
+        RedirectKind::Permanent308 => StatusCode::PERMANENT_REDIRECT,
+    }
+}
+
+/// Lets permanent redirects be cached, but never past the code's expiry;
+/// temporary ones are not stored at all.
+fn redirect_cache_control(kind: RedirectKind, expire_at: Option<Timestamp>) -> HeaderValue {
+    if !kind.is_permanent() {
+        return cache_control(Duration::ZERO);
+    }
+    let max_age = match expire_at {
+        Some(expire_at) => Duration::try_from(expire_at.duration_since(Timestamp::now()))
+            .unwrap_or(Duration::ZERO)
+            .min(PERMANENT_REDIRECT_MAX_AGE),
+        None => PERMANENT_REDIRECT_MAX_AGE,
+    };
+    cache_control(max_age)
+}
+
+/// Public redirect endpoint: sends the visitor to the original URL using the
+/// redirect status stored with the short code.
+///
+/// Any method is accepted. GET and HEAD get the stored status; other methods
+/// get its method-preserving counterpart (301 becomes 308, 302 becomes
+/// 307), so clients do not replay them as a GET. Permanent
+/// redirects are cacheable for up to a day (or until the code expires),
+/// temporary ones are sent with `Cache-Control: no-store`.
+///
+/// The request's query string is carried over to the original URL, so
+/// `/{code}?ref=x` lands on `{original_url}?ref=x` (or `&ref=x` if the
+/// original already has a query). Browsers never send the fragment; they
+/// reapply it to the target themselves unless the original URL has one.
+///
+/// Codes that are malformed or percent-encoded are rejected with a 400, see
+/// [`ShortCodeSegment`]. Codes that do not resolve get the same 404 body every time, with the
+/// cache directives from [`AppState::not_found_caching`].
+#[instrument(skip(state))]
+pub async fn redirect_handler(
+    method: Method,
+    ShortCodeSegment(short_code): ShortCodeSegment,
+    RawQuery(query): RawQuery,
+    State(state): State<AppState>,
+) -> Result<Response> {
+    let result = match state.url_service().get(&short_code).await {
+        Ok(result) => result,
+        Err(BackendError::NotFound) => return Ok(state.not_found_caching().response(false)),
+        Err(BackendError::Expired) => return Ok(state.not_found_caching().response(true)),
+        Err(error) => return Err(error.into()),
+    };
+
+    let target = merge_target(&result.original_url, query.as_deref(), None);
+    let location = HeaderValue::try_from(target)
+        .map_err(|e| AppError::Internal(format!("stored URL is not a valid header value: {e}")))?;
+
+    Ok((
+        redirect_status(result.redirect_kind, &method),
+        [
+            (LOCATION, location),
+            (
+                CACHE_CONTROL,
+                redirect_cache_control(result.redirect_kind, result.expire_at),
+            ),
+        ],
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::local::LocalUrlAdapter;
+    use crate::not_found::{NotFoundCaching, NOT_FOUND_BODY};
+    use axum::body::{to_bytes, Bytes};
+    use axum::http::header::{CACHE_CONTROL, CONTENT_TYPE};
+    use axum::http::HeaderMap;
+    use std::time::Duration;
+    use wormhole_core::{ShortCode, UrlRecord};
+    use wormhole_generator::seq::SeqGenerator;
+    use wormhole_redirector::RedirectorService;
+    use wormhole_shortener::service::ShortenerService;
+    use wormhole_storage::{InMemoryRepository, Repository};
+
+    async fn state_with(code: &str, redirect_kind: RedirectKind) -> AppState {
+        let storage = InMemoryRepository::new();
+        storage
+            .insert(
+                &ShortCode::custom(code).unwrap(),
+                UrlRecord {
+                    original_url: "https://example.com".to_string(),
+                    expire_at: None,
+                    redirect_kind,
+                    created_at: jiff::Timestamp::now(),
+                    internal_only: false,
+                    no_store: false,
+                    owner_id: None,
+                    metadata: Default::default(),
+                },
+            )
+            .await
+            .unwrap();
+
+        let adapter = LocalUrlAdapter::builder()
+            .shortener(ShortenerService::new(
+                storage.clone(),
+                SeqGenerator::with_prefix("test"),
+            ))
+            .redirector(RedirectorService::new(storage))
+            .base_url("https://worm.hole")
+            .build();
+
+        AppState::builder()
+            .url_service(adapter)
+            .base_url("https://worm.hole".to_string())
+            .build()
+    }
+
+    #[tokio::test]
+    async fn redirect_uses_stored_redirect_kind() {
+        for (kind, status) in [
+            (RedirectKind::Permanent301, StatusCode::MOVED_PERMANENTLY),
+            (RedirectKind::Found302, StatusCode::FOUND),
+            (RedirectKind::Temporary307, StatusCode::TEMPORARY_REDIRECT),
+            (RedirectKind::Permanent308, StatusCode::PERMANENT_REDIRECT),
+        ] {
+            let state = state_with("abc123", kind).await;
+
+            let response = redirect_handler(
+                Method::GET,
+                ShortCodeSegment("abc123".to_string()),
+                RawQuery(None),
+                State(state),
+            )
+            .await
+            .unwrap();
+
+            assert_eq!(response.status(), status);
+            assert_eq!(response.headers()[LOCATION], "https://example.com");
+        }
+    }
+
+    #[tokio::test]
+    async fn redirect_status_and_caching_follow_kind_and_method() {
+        use RedirectKind::*;
+        let permanent = "public, max-age=86400";
+        let cases = [
+            (
+                Permanent301,
+                Method::GET,
+                StatusCode::MOVED_PERMANENTLY,
+                permanent,
+            ),
+            (
+                Permanent301,
+                Method::HEAD,
+                StatusCode::MOVED_PERMANENTLY,
+                permanent,
+            ),
+            (
+                Permanent301,
+                Method::POST,
+                StatusCode::PERMANENT_REDIRECT,
+                permanent,
+            ),
+            (
+                Permanent301,
+                Method::PUT,
+                StatusCode::PERMANENT_REDIRECT,
+                permanent,
+            ),
+            (Found302, Method::GET, StatusCode::FOUND, "no-store"),
+            (Found302, Method::HEAD, StatusCode::FOUND, "no-store"),
+            (
+                Found302,
+                Method::POST,
+                StatusCode::TEMPORARY_REDIRECT,
+                "no-store",
+            ),
+            (
+                Found302,
+                Method::PUT,
+                StatusCode::TEMPORARY_REDIRECT,
+                "no-store",
+            ),
+            (
+                Temporary307,
+                Method::GET,
+                StatusCode::TEMPORARY_REDIRECT,
+                "no-store",
+            ),
+            (
+                Temporary307,
+                Method::POST,
+                StatusCode::TEMPORARY_REDIRECT,
+                "no-store",
+            ),
+            (
+                Permanent308,
+                Method::GET,
+                StatusCode::PERMANENT_REDIRECT,
+                permanent,
+            ),
+            (
+                Permanent308,
+                Method::PUT,
+                StatusCode::PERMANENT_REDIRECT,
+                permanent,
+            ),
+        ];
+
+        for (kind, method, status, cache_control) in cases {
+            let state = state_with("abc123", kind).await;
+
+            let response = redirect_handler(
+                method.clone(),
+                ShortCodeSegment("abc123".to_string()),
+                RawQuery(None),
+                State(state),
+            )
+            .await
+            .unwrap();
+
+            assert_eq!(response.status(), status, "{kind:?} {method}");
+            assert_eq!(
+                response.headers()[CACHE_CONTROL],
+                cache_control,
+                "{kind:?} {method}"
+            );
+        }
+    }
+
+    #[test]
+    fn permanent_redirects_are_not_cached_past_expiry() {
+        let soon = Timestamp::now() + jiff::SignedDuration::from_secs(60);
+        let max_age = redirect_cache_control(RedirectKind::Permanent308, Some(soon));
+        let secs: u64 = max_age
+            .to_str()
+            .unwrap()
+            .strip_prefix("public, max-age=")
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!((55..=60).contains(&secs), "max-age {secs}");
+
+        let past = Timestamp::now() - jiff::SignedDuration::from_secs(60);
+        assert_eq!(
+            redirect_cache_control(RedirectKind::Permanent301, Some(past)),
+            "no-store"
+        );
+        let far = Timestamp::now() + jiff::SignedDuration::from_hours(24 * 30);
+        assert_eq!(
+            redirect_cache_control(RedirectKind::Permanent301, Some(far)),
+            "public, max-age=86400"
+        );
+    }
+
+    #[tokio::test]
+    async fn redirect_carries_the_query_string_over() {
+        let state = state_with("abc123", RedirectKind::Found302).await;
+
+        let response = redirect_handler(
+            Method::GET,
+            ShortCodeSegment("abc123".to_string()),
+            RawQuery(Some("ref=x&lang=en".to_string())),
+            State(state),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            response.headers()[LOCATION],
+            "https://example.com?ref=x&lang=en"
+        );
+    }
+
+    #[tokio::test]
+    async fn redirect_returns_not_found_for_unknown_code() {
+        let state = state_with("abc123", RedirectKind::Found302).await;
+
+        let response = redirect_handler(
+            Method::GET,
+            ShortCodeSegment("missing".to_string()),
+            RawQuery(None),
+            State(state),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(response.headers()[CACHE_CONTROL], "no-store");
+    }
+
+    /// A state holding one code that expired an hour ago.
+    async fn state_with_expired(caching: NotFoundCaching) -> AppState {
+        let storage = InMemoryRepository::new();
+        let now = jiff::Timestamp::now();
+        storage
+            .insert(
+                &ShortCode::custom("expired").unwrap(),
+                UrlRecord {
+                    original_url: "https://example.com".to_string(),
+                    expire_at: Some(now - jiff::SignedDuration::from_hours(1)),
+                    redirect_kind: RedirectKind::Found302,
+                    created_at: now - jiff::SignedDuration::from_hours(2),
+                    internal_only: false,
+                    no_store: false,
+                    owner_id: None,
+                    metadata: Default::default(),
+                },
+            )
+            .await
+            .unwrap();
+
+        let adapter = LocalUrlAdapter::builder()
+            .shortener(ShortenerService::new(
+                storage.clone(),
+                SeqGenerator::with_prefix("test"),
+            ))
+            .redirector(RedirectorService::new(storage))
+            .base_url("https://worm.hole")
+            .build();
+
+        AppState::builder()
+            .url_service(adapter)
+            .base_url("https://worm.hole".to_string())
+            .not_found_caching(caching)
+            .build()
+    }
+
+    async fn not_found(state: &AppState, code: &str) -> (StatusCode, HeaderMap, Bytes) {
+        let response = redirect_handler(
+            Method::GET,
+            ShortCodeSegment(code.to_string()),
+            RawQuery(None),
+            State(state.clone()),
+        )
+        .await
+        .unwrap();
+        let (parts, body) = response.into_parts();
+        let body = to_bytes(body, usize::MAX).await.unwrap();
+        (parts.status, parts.headers, body)
+    }
+
+    #[tokio::test]
+    async fn not_found_responses_are_byte_stable() {
+        let state = state_with_expired(NotFoundCaching {
+            missing_max_age: Duration::from_secs(300),
+            expired_max_age: Duration::from_secs(10),
+        })
+        .await;
+
+        let first = not_found(&state, "missing").await;
+        let again = not_found(&state, "missing").await;
+        let other = not_found(&state, "another").await;
+
+        assert_eq!(first.0, StatusCode::NOT_FOUND);
+        assert_eq!(first.1[CACHE_CONTROL], "public, max-age=300");
+        assert_eq!(first.1[CONTENT_TYPE], "application/json");
+        assert_eq!(first.2, NOT_FOUND_BODY.as_bytes());
+        assert_eq!(first, again);
+        assert_eq!(first, other);
+    }
+
+    #[tokio::test]
+    async fn expired_codes_use_the_expired_max_age() {
+        let state = state_with_expired(NotFoundCaching {
+            missing_max_age: Duration::from_secs(300),
+            expired_max_age: Duration::from_secs(10),
+        })
+        .await;
+
+        let (status, headers, body) = not_found(&state, "expired").await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(headers[CACHE_CONTROL], "public, max-age=10");
+        assert_eq!(body, NOT_FOUND_BODY.as_bytes());
+    }
+
+    #[tokio::test]
+    async fn expired_codes_are_not_stored_by_default() {
+        let state = state_with_expired(NotFoundCaching {
+            missing_max_age: Duration::from_secs(300),
+            ..NotFoundCaching::default()
+        })
+        .await;
+
+        let (_, headers, _) = not_found(&state, "expired").await;
+
+        assert_eq!(headers[CACHE_CONTROL], "no-store");
+    }
+}
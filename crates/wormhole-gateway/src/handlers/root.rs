@@ -0,0 +1,110 @@
+use crate::error::{AppError, Result};
+use crate::root::RootBehavior;
+use crate::state::AppState;
+use axum::extract::State;
+use axum::http::header::{CONTENT_TYPE, LOCATION};
+use axum::http::{HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+
+/// Landing endpoint for `/`, answered according to the configured
+/// [`RootBehavior`].
+pub async fn root_handler(State(state): State<AppState>) -> Result<Response> {
+    match state.root_behavior() {
+        RootBehavior::StaticFile(path) => {
+            let body = tokio::fs::read(path).await.map_err(|e| {
+                AppError::Internal(format!(
+                    "failed to read root page '{}': {e}",
+                    path.display()
+                ))
+            })?;
+            Ok((
+                [(
+                    CONTENT_TYPE,
+                    HeaderValue::from_static("text/html; charset=utf-8"),
+                )],
+                body,
+            )
+                .into_response())
+        }
+        RootBehavior::Redirect(url) => {
+            let location = HeaderValue::try_from(url.as_str()).map_err(|e| {
+                AppError::Internal(format!("root redirect is not a valid header value: {e}"))
+            })?;
+            Ok((StatusCode::FOUND, [(LOCATION, location)]).into_response())
+        }
+        RootBehavior::NotFound => Ok(StatusCode::NOT_FOUND.into_response()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::local::LocalUrlAdapter;
+    use axum::body::to_bytes;
+    use wormhole_generator::seq::SeqGenerator;
+    use wormhole_redirector::RedirectorService;
+    use wormhole_shortener::service::ShortenerService;
+    use wormhole_storage::InMemoryRepository;
+
+    fn state_with(root_behavior: RootBehavior) -> AppState {
+        let storage = InMemoryRepository::new();
+        let adapter = LocalUrlAdapter::builder()
+            .shortener(ShortenerService::new(
+                storage.clone(),
+                SeqGenerator::with_prefix("test"),
+            ))
+            .redirector(RedirectorService::new(storage))
+            .base_url("https://worm.hole")
+            .build();
+
+        AppState::builder()
+            .url_service(adapter)
+            .base_url("https://worm.hole".to_string())
+            .root_behavior(root_behavior)
+            .build()
+    }
+
+    #[tokio::test]
+    async fn root_serves_static_file() {
+        let path = std::env::temp_dir().join(format!("wormhole-root-{}.html", std::process::id()));
+        tokio::fs::write(&path, "<h1>wormhole</h1>").await.unwrap();
+
+        let response = root_handler(State(state_with(RootBehavior::StaticFile(path.clone()))))
+            .await
+            .unwrap();
+        tokio::fs::remove_file(&path).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CONTENT_TYPE], "text/html; charset=utf-8");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"<h1>wormhole</h1>");
+    }
+
+    #[tokio::test]
+    async fn root_redirects_to_configured_url() {
+        let behavior = RootBehavior::Redirect("https://example.com/about".to_string());
+
+        let response = root_handler(State(state_with(behavior))).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::FOUND);
+        assert_eq!(response.headers()[LOCATION], "https://example.com/about");
+    }
+
+    #[tokio::test]
+    async fn root_returns_not_found() {
+        let response = root_handler(State(state_with(RootBehavior::NotFound)))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn root_reports_missing_static_file() {
+        let behavior = RootBehavior::StaticFile("/nonexistent/wormhole/index.html".into());
+
+        let result = root_handler(State(state_with(behavior))).await;
+
+        assert!(matches!(result, Err(AppError::Internal(_))));
+    }
+}
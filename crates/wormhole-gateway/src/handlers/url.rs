@@ -1,9 +1,10 @@
 use crate::backend::{DeleteUrlCmd, WriteUrlCmd};
 use crate::error::{AppError, Result};
 use crate::model::{CreateUrlRequest, CreateUrlResponse, GetUrlResponse};
+use crate::path::ShortCodeSegment;
 use crate::state::AppState;
 use axum::extract::rejection::JsonRejection;
-use axum::extract::{Path, State};
+use axum::extract::State;
 use axum::http::StatusCode;
 use axum::Json;
 use std::result::Result as StdResult;
@@ -39,7 +40,7 @@ pub async fn create_url_handler(
 
 #[instrument(skip(state))]
 pub async fn get_url_handler(
-    Path(short_code): Path<String>,
+    ShortCodeSegment(short_code): ShortCodeSegment,
     State(state): State<AppState>,
 ) -> Result<Json<GetUrlResponse>> {
     let result = state.url_service().get(&short_code).await?;
@@ -52,7 +53,7 @@ pub async fn get_url_handler(
 
 #[instrument(skip(state))]
 pub async fn delete_url_handler(
-    Path(short_code): Path<String>,
+    ShortCodeSegment(short_code): ShortCodeSegment,
     State(state): State<AppState>,
 ) -> Result<StatusCode> {
     state
@@ -4,4 +4,7 @@ pub mod backend;
 pub mod error;
 pub mod handlers;
 pub mod model;
+pub mod not_found;
+pub mod path;
+pub mod root;
 pub mod state;
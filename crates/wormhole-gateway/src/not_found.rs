@@ -0,0 +1,86 @@
+//! The response served when a short code does not resolve.
+//!
+//! The body and headers depend only on [`NotFoundCaching`] and on whether the
+//! code expired, never on the code itself, so a CDN can cache misses and keep
+//! repeated lookups for unknown codes away from the origin.
+
+use axum::http::header::{CACHE_CONTROL, CONTENT_TYPE};
+use axum::http::{HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use std::time::Duration;
+
+/// The fixed body of every not-found redirect response.
+///
+/// Matches the JSON the API renders for `AppError::NotFound`.
+pub const NOT_FOUND_BODY: &str =
+    r#"{"error":{"code":"short_code_not_found","message":"short code not found"}}"#;
+
+/// How long shared caches may keep not-found responses.
+///
+/// A max-age of zero sends `Cache-Control: no-store`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NotFoundCaching {
+    /// Max-age for codes that do not exist.
+    pub missing_max_age: Duration,
+    /// Max-age for codes that existed but have expired. Keep this shorter
+    /// than `missing_max_age`, or zero, if expired codes can be renewed.
+    pub expired_max_age: Duration,
+}
+
+impl NotFoundCaching {
+    /// Builds the response for a code that does not exist, or that expired
+    /// if `expired` is set.
+    pub fn response(&self, expired: bool) -> Response {
+        let max_age = if expired {
+            self.expired_max_age
+        } else {
+            self.missing_max_age
+        };
+
+        (
+            StatusCode::NOT_FOUND,
+            [
+                (CONTENT_TYPE, HeaderValue::from_static("application/json")),
+                (CACHE_CONTROL, cache_control(max_age)),
+            ],
+            NOT_FOUND_BODY,
+        )
+            .into_response()
+    }
+}
+
+pub(crate) fn cache_control(max_age: Duration) -> HeaderValue {
+    match max_age.as_secs() {
+        0 => HeaderValue::from_static("no-store"),
+        secs => HeaderValue::try_from(format!("public, max-age={secs}"))
+            .expect("cache-control value is valid ASCII"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+    use axum::body::to_bytes;
+
+    #[test]
+    fn zero_max_age_disables_caching() {
+        assert_eq!(cache_control(Duration::ZERO), "no-store");
+        assert_eq!(cache_control(Duration::from_millis(999)), "no-store");
+        assert_eq!(
+            cache_control(Duration::from_secs(300)),
+            "public, max-age=300"
+        );
+    }
+
+    #[tokio::test]
+    async fn body_matches_the_api_not_found_error() {
+        let api = to_bytes(AppError::NotFound.into_response().into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let api: serde_json::Value = serde_json::from_slice(&api).unwrap();
+        let fixed: serde_json::Value = serde_json::from_str(NOT_FOUND_BODY).unwrap();
+
+        assert_eq!(api, fixed);
+    }
+}
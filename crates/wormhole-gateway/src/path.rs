@@ -0,0 +1,123 @@
+use crate::error::{AppError, Result};
+use axum::extract::{FromRef, FromRequestParts};
+use axum::http::request::Parts;
+use std::sync::Arc;
+use wormhole_core::AliasPolicy;
+use wormhole_redirector::extract::ShortCodePath;
+
+/// The `{short_code}` segment of the request path, checked against the
+/// [`ShortCode`](wormhole_core::ShortCode) rules.
+///
+/// Parsed like [`ShortCodePath`], but rejected with the gateway's JSON
+/// `400 invalid_short_code` error. Lengths are checked against the
+/// [`AliasPolicy`] in the state.
+///
+/// The code must be the last segment of the route.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShortCodeSegment(pub String);
+
+impl ShortCodeSegment {
+    /// Parses a raw, still percent-encoded path segment.
+    pub fn parse(raw: &str, policy: &AliasPolicy) -> Result<Self> {
+        let ShortCodePath(code) = ShortCodePath::parse_with(raw, policy)
+            .map_err(|e| AppError::InvalidShortCode(e.to_string()))?;
+        Ok(Self(code.as_str().to_string()))
+    }
+}
+
+impl<S> FromRequestParts<S> for ShortCodeSegment
+where
+    S: Send + Sync,
+    Arc<AliasPolicy>: FromRef<S>,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self> {
+        let raw = parts.uri.path().rsplit('/').next().unwrap_or_default();
+        Self::parse(raw, &Arc::<AliasPolicy>::from_ref(state))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::{Request, StatusCode};
+    use axum::response::IntoResponse;
+
+    async fn extract(uri: &str) -> Result<ShortCodeSegment> {
+        extract_with(uri, AliasPolicy::default()).await
+    }
+
+    async fn extract_with(uri: &str, policy: AliasPolicy) -> Result<ShortCodeSegment> {
+        let (mut parts, ()) = Request::builder().uri(uri).body(()).unwrap().into_parts();
+        ShortCodeSegment::from_request_parts(&mut parts, &Arc::new(policy)).await
+    }
+
+    fn rejected(result: Result<ShortCodeSegment>) -> StatusCode {
+        result.unwrap_err().into_response().status()
+    }
+
+    #[tokio::test]
+    async fn valid_codes_pass_through_unchanged() {
+        assert_eq!(
+            extract("/abc123").await.unwrap(),
+            ShortCodeSegment("abc123".to_string())
+        );
+        assert_eq!(
+            extract("/v1/urls/My_code-9?ref=x").await.unwrap(),
+            ShortCodeSegment("My_code-9".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn encoded_slashes_are_rejected() {
+        assert_eq!(
+            rejected(extract("/abc%2Fdef").await),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            rejected(extract("/abc%2fdef").await),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[tokio::test]
+    async fn encoded_spaces_are_rejected() {
+        assert_eq!(
+            rejected(extract("/abc%20def").await),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[tokio::test]
+    async fn encoded_forms_of_valid_codes_are_rejected() {
+        // Decodes to the valid code `abc123`, but is not how it is spelled.
+        let err = extract("/%61bc123").await.unwrap_err();
+
+        assert!(matches!(
+            err,
+            AppError::InvalidShortCode(message) if message.contains("percent-encoded")
+        ));
+    }
+
+    #[tokio::test]
+    async fn invalid_codes_are_rejected() {
+        assert_eq!(rejected(extract("/ab").await), StatusCode::BAD_REQUEST);
+        assert_eq!(rejected(extract("/abc.def").await), StatusCode::BAD_REQUEST);
+        assert_eq!(rejected(extract("/%FF%FE").await), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn lengths_follow_the_alias_policy() {
+        let policy = AliasPolicy::new().with_length(2, 32).unwrap();
+
+        assert_eq!(
+            extract_with("/ab", policy.clone()).await.unwrap(),
+            ShortCodeSegment("ab".to_string())
+        );
+        assert_eq!(
+            rejected(extract_with("/a", policy).await),
+            StatusCode::BAD_REQUEST
+        );
+    }
+}
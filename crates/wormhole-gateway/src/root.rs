@@ -0,0 +1,90 @@
+//! What the gateway serves at `/`, where there is no short code to resolve.
+
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Landing behavior for requests to the gateway root.
+///
+/// Parsed from `static-file:<path>`, `redirect:<url>` or `not-found`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum RootBehavior {
+    /// Serve the HTML file at this path.
+    StaticFile(PathBuf),
+    /// Redirect to this URL, e.g. a marketing site.
+    Redirect(String),
+    /// Respond with `404 Not Found`.
+    #[default]
+    NotFound,
+}
+
+impl FromStr for RootBehavior {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "not-found" {
+            return Ok(Self::NotFound);
+        }
+        if let Some(path) = s.strip_prefix("static-file:") {
+            if path.is_empty() {
+                return Err("static-file requires a path, e.g. static-file:./index.html".into());
+            }
+            return Ok(Self::StaticFile(PathBuf::from(path)));
+        }
+        if let Some(url) = s.strip_prefix("redirect:") {
+            if url.is_empty() {
+                return Err("redirect requires a URL, e.g. redirect:https://example.com".into());
+            }
+            return Ok(Self::Redirect(url.to_string()));
+        }
+        Err(format!(
+            "invalid root behavior '{s}', expected static-file:<path>, redirect:<url> or not-found"
+        ))
+    }
+}
+
+impl fmt::Display for RootBehavior {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::StaticFile(path) => write!(f, "static-file:{}", path.display()),
+            Self::Redirect(url) => write!(f, "redirect:{url}"),
+            Self::NotFound => f.write_str("not-found"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_each_behavior() {
+        assert_eq!(
+            "static-file:/srv/index.html".parse(),
+            Ok(RootBehavior::StaticFile(PathBuf::from("/srv/index.html")))
+        );
+        assert_eq!(
+            "redirect:https://example.com".parse(),
+            Ok(RootBehavior::Redirect("https://example.com".to_string()))
+        );
+        assert_eq!("not-found".parse(), Ok(RootBehavior::NotFound));
+    }
+
+    #[test]
+    fn rejects_unknown_or_incomplete_behaviors() {
+        assert!("landing".parse::<RootBehavior>().is_err());
+        assert!("static-file:".parse::<RootBehavior>().is_err());
+        assert!("redirect:".parse::<RootBehavior>().is_err());
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for behavior in [
+            RootBehavior::StaticFile(PathBuf::from("index.html")),
+            RootBehavior::Redirect("https://example.com".to_string()),
+            RootBehavior::NotFound,
+        ] {
+            assert_eq!(behavior.to_string().parse(), Ok(behavior));
+        }
+    }
+}
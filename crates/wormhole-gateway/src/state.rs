@@ -1,6 +1,10 @@
 use crate::backend::UrlService;
+use crate::not_found::NotFoundCaching;
+use crate::root::RootBehavior;
+use axum::extract::FromRef;
 use std::sync::Arc;
 use typed_builder::TypedBuilder;
+use wormhole_core::AliasPolicy;
 
 #[derive(Clone, TypedBuilder)]
 pub struct AppState {
@@ -16,6 +20,16 @@ pub struct AppState {
     /// The base URL for public access to the short URLs.
     #[builder]
     base_url: String,
+    /// What to serve at `/`, where there is no short code.
+    #[builder(default)]
+    root_behavior: RootBehavior,
+    /// Cache directives for redirects to codes that do not resolve.
+    #[builder(default)]
+    not_found_caching: NotFoundCaching,
+    /// Length bounds of custom aliases in request paths; match the
+    /// shortener's, or its shorter or longer aliases are rejected.
+    #[builder(default, setter(transform = |policy: AliasPolicy| Arc::new(policy)))]
+    alias_policy: Arc<AliasPolicy>,
 }
 
 impl AppState {
@@ -26,4 +40,22 @@ impl AppState {
     pub fn base_url(&self) -> &str {
         &self.base_url
     }
+
+    pub fn root_behavior(&self) -> &RootBehavior {
+        &self.root_behavior
+    }
+
+    pub fn not_found_caching(&self) -> &NotFoundCaching {
+        &self.not_found_caching
+    }
+
+    pub fn alias_policy(&self) -> &AliasPolicy {
+        &self.alias_policy
+    }
+}
+
+impl FromRef<AppState> for Arc<AliasPolicy> {
+    fn from_ref(state: &AppState) -> Self {
+        Arc::clone(&state.alias_policy)
+    }
 }
@@ -0,0 +1,167 @@
+use crate::{Generator, GeneratorError};
+use wormhole_core::ShortCode;
+
+/// Words that must not appear anywhere in a generated code, in any case.
+#[derive(Debug, Clone, Default)]
+pub struct Blocklist {
+    /// Lowercased, non-empty words.
+    words: Vec<String>,
+}
+
+impl Blocklist {
+    pub fn new<I, S>(words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let words = words
+            .into_iter()
+            .map(|word| word.as_ref().trim().to_lowercase())
+            .filter(|word| !word.is_empty())
+            .collect();
+        Self { words }
+    }
+
+    /// Returns the first blocked word `code` contains, ignoring case.
+    pub fn find(&self, code: &str) -> Option<&str> {
+        let code = code.to_lowercase();
+        self.words
+            .iter()
+            .find(|word| code.contains(word.as_str()))
+            .map(String::as_str)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+/// Wraps a [`Generator`] and regenerates codes that contain a blocked word.
+///
+/// Each regeneration consumes a code from the inner generator, so uniqueness
+/// is preserved. After `max_attempts` blocked codes in a row the last one is
+/// returned anyway, since [`Generator::generate`] cannot fail; with a
+/// reasonable blocklist that is vanishingly rare.
+#[derive(Debug, Clone)]
+pub struct FilteredGenerator<G> {
+    inner: G,
+    blocklist: Blocklist,
+    max_attempts: usize,
+}
+
+impl<G: Generator> FilteredGenerator<G> {
+    /// Creates a filter that tries up to 16 codes per call.
+    pub fn new(inner: G, blocklist: Blocklist) -> Self {
+        Self {
+            inner,
+            blocklist,
+            max_attempts: 16,
+        }
+    }
+
+    /// Sets how many codes to try per call. Values below 1 are treated as 1.
+    pub fn with_max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    pub fn inner(&self) -> &G {
+        &self.inner
+    }
+}
+
+impl<G: Generator> Generator for FilteredGenerator<G> {
+    type Output = ShortCode;
+
+    fn generate(&self) -> Self::Output {
+        let mut code: ShortCode = self.inner.generate().into();
+        for _ in 1..self.max_attempts {
+            if self.blocklist.find(code.as_str()).is_none() {
+                break;
+            }
+            code = self.inner.generate().into();
+        }
+        code
+    }
+
+    fn try_generate(&self) -> Result<Self::Output, GeneratorError> {
+        let mut code: ShortCode = self.inner.try_generate()?.into();
+        for _ in 1..self.max_attempts {
+            if self.blocklist.find(code.as_str()).is_none() {
+                break;
+            }
+            code = self.inner.try_generate()?.into();
+        }
+        Ok(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Hands out the given codes in order, then repeats the last one.
+    struct StubGenerator(Mutex<Vec<&'static str>>);
+
+    impl StubGenerator {
+        fn new(codes: &[&'static str]) -> Self {
+            let mut codes = codes.to_vec();
+            codes.reverse();
+            Self(Mutex::new(codes))
+        }
+    }
+
+    impl Generator for StubGenerator {
+        type Output = ShortCode;
+
+        fn generate(&self) -> Self::Output {
+            let mut codes = self.0.lock().unwrap();
+            let code = if codes.len() > 1 {
+                codes.pop().unwrap()
+            } else {
+                codes[0]
+            };
+            ShortCode::new_unchecked(code)
+        }
+    }
+
+    #[test]
+    fn blocked_code_is_regenerated() {
+        let generator = FilteredGenerator::new(
+            StubGenerator::new(&["xBaDx", "clean1"]),
+            Blocklist::new(["bad"]),
+        );
+
+        assert_eq!(generator.generate().as_str(), "clean1");
+    }
+
+    #[test]
+    fn blocklist_ignores_case() {
+        let blocklist = Blocklist::new(["Bad", "  ", ""]);
+
+        assert_eq!(blocklist.find("xbAdx"), Some("bad"));
+        assert_eq!(blocklist.find("BAD"), Some("bad"));
+        assert_eq!(blocklist.find("b4d"), None);
+    }
+
+    #[test]
+    fn gives_up_after_max_attempts() {
+        let generator = FilteredGenerator::new(
+            StubGenerator::new(&["bad1", "bad2", "bad3", "clean"]),
+            Blocklist::new(["bad"]),
+        )
+        .with_max_attempts(2);
+
+        assert_eq!(generator.generate().as_str(), "bad2");
+        assert_eq!(generator.generate().as_str(), "clean");
+    }
+
+    #[test]
+    fn empty_blocklist_passes_codes_through() {
+        let generator =
+            FilteredGenerator::new(StubGenerator::new(&["anything"]), Blocklist::default());
+
+        assert_eq!(generator.generate().as_str(), "anything");
+    }
+}
@@ -1,9 +1,47 @@
+pub mod filtered;
+pub mod nanoid;
 pub mod obfuscated;
 pub mod seq;
 
+use thiserror::Error;
 use wormhole_core::ShortCode;
 use wormhole_tinyflake::{Clock, Tinyflake};
 
+/// A generator can produce codes longer than the storage column holds.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error(
+    "generated short codes can be up to {max_code_len} characters, \
+     but the short code column only holds {column_width}"
+)]
+pub struct CodeWidthError {
+    pub max_code_len: usize,
+    pub column_width: usize,
+}
+
+/// Checks that codes of up to `max_code_len` characters fit a column of
+/// `column_width` characters.
+///
+/// Run it at startup so that a too-narrow column fails fast instead of
+/// truncating or rejecting inserts at request time.
+pub fn check_code_width(max_code_len: usize, column_width: usize) -> Result<(), CodeWidthError> {
+    if max_code_len > column_width {
+        return Err(CodeWidthError {
+            max_code_len,
+            column_width,
+        });
+    }
+    Ok(())
+}
+
+/// Why a [`Generator`] could not produce a code.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum GeneratorError {
+    /// The Tinyflake behind the generator failed, e.g. because it ran past
+    /// its time limit or its state lock was poisoned.
+    #[error(transparent)]
+    Tinyflake(#[from] wormhole_tinyflake::Error),
+}
+
 /// Trait for generating short codes.
 ///
 /// Implementations are pure generators that don't interact with storage.
@@ -16,25 +54,38 @@ pub trait Generator: Send + Sync + 'static {
     ///
     /// The generated code should be unique
     fn generate(&self) -> Self::Output;
+
+    /// Like [`Generator::generate`], but reports failures instead of
+    /// panicking.
+    ///
+    /// The default wraps [`Generator::generate`], for generators that cannot
+    /// fail.
+    fn try_generate(&self) -> Result<Self::Output, GeneratorError> {
+        Ok(self.generate())
+    }
 }
 
 impl<C: Clock + 'static> Generator for Tinyflake<C> {
     type Output = ShortCode;
 
+    /// # Panics
+    ///
+    /// Panics if the Tinyflake fails; use [`Generator::try_generate`] to get
+    /// the error instead.
     fn generate(&self) -> Self::Output {
-        // `Generator` is intentionally infallible. Tinyflake errors indicate an
-        // unrecoverable generator state (e.g., poisoned lock or epoch overflow).
-        let id = self
-            .next_id()
-            .expect("tinyflake generator failed to produce the next id");
-        ShortCode::generated(id)
+        self.try_generate()
+            .expect("tinyflake generator failed to produce the next id")
+    }
+
+    fn try_generate(&self) -> Result<Self::Output, GeneratorError> {
+        Ok(ShortCode::generated(self.next_id()?))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Generator;
-    use jiff::Timestamp;
+    use super::{Generator, GeneratorError};
+    use jiff::{SignedDuration, Timestamp};
     use wormhole_core::ShortCode;
     use wormhole_tinyflake::{Tinyflake, TinyflakeSettings};
 
@@ -56,4 +107,24 @@ mod tests {
         assert!(matches!(second, ShortCode::Generated(_)));
         assert_ne!(first.as_str(), second.as_str());
     }
+
+    #[test]
+    fn try_generate_reports_an_exhausted_epoch() {
+        // An 8-bit timestamp field runs out 256 seconds after the epoch.
+        let settings = TinyflakeSettings::builder()
+            .node_id(0)
+            .start_epoch(Timestamp::now() - SignedDuration::from_secs(1_000))
+            .timestamp_bits(8)
+            .sequence_bits(24)
+            .node_bits(8)
+            .build();
+        let tinyflake = Tinyflake::new(settings).unwrap();
+
+        assert_eq!(
+            tinyflake.try_generate(),
+            Err(GeneratorError::Tinyflake(
+                wormhole_tinyflake::Error::OverTimeLimit
+            ))
+        );
+    }
 }
@@ -0,0 +1,163 @@
+use crate::Generator;
+use rand::Rng;
+use std::collections::HashSet;
+use thiserror::Error;
+use wormhole_core::ShortCode;
+
+/// The 64 characters [`ShortCode`] accepts: `[A-Za-z0-9_-]`.
+pub const URL_SAFE_ALPHABET: &str =
+    "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789_-";
+
+/// Code length used by [`NanoidGenerator::default`].
+pub const DEFAULT_LENGTH: usize = 10;
+
+/// Length bounds of a valid [`ShortCode`].
+const MIN_LENGTH: usize = 3;
+const MAX_LENGTH: usize = 32;
+
+/// A [`NanoidGenerator`] configuration that would produce invalid codes.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum NanoidError {
+    #[error("length must be between {MIN_LENGTH} and {MAX_LENGTH}, got {0}")]
+    InvalidLength(usize),
+    #[error("alphabet needs at least two characters")]
+    AlphabetTooSmall,
+    #[error("alphabet character {0:?} is not allowed in short codes")]
+    InvalidCharacter(char),
+    #[error("alphabet repeats {0:?}")]
+    DuplicateCharacter(char),
+}
+
+/// Draws nanoid-style codes: `length` characters picked uniformly at random
+/// from `alphabet`.
+///
+/// Unlike the Tinyflake generators, uniqueness is only probabilistic. With
+/// `N = alphabet.len() ^ length` possible codes, the chance that any two of
+/// `k` codes collide is about `k² / 2N`. The defaults give `N = 64^10 ≈ 2^60`:
+/// roughly a one-in-a-million chance of a collision after 1.5 million codes,
+/// and even odds after 1.2 billion. A collision surfaces as a conflict when
+/// the code is inserted, so size the length for the expected volume.
+#[derive(Debug, Clone)]
+pub struct NanoidGenerator {
+    alphabet: Vec<char>,
+    length: usize,
+}
+
+impl NanoidGenerator {
+    /// Creates a generator for codes of `length` characters from `alphabet`.
+    ///
+    /// Fails unless every code it can produce is a valid [`ShortCode`]:
+    /// `length` must be 3..=32, and `alphabet` must hold at least two
+    /// distinct characters from [`URL_SAFE_ALPHABET`].
+    pub fn new(length: usize, alphabet: &str) -> Result<Self, NanoidError> {
+        if !(MIN_LENGTH..=MAX_LENGTH).contains(&length) {
+            return Err(NanoidError::InvalidLength(length));
+        }
+
+        let mut seen = HashSet::new();
+        for c in alphabet.chars() {
+            if !URL_SAFE_ALPHABET.contains(c) {
+                return Err(NanoidError::InvalidCharacter(c));
+            }
+            if !seen.insert(c) {
+                return Err(NanoidError::DuplicateCharacter(c));
+            }
+        }
+        if seen.len() < 2 {
+            return Err(NanoidError::AlphabetTooSmall);
+        }
+
+        Ok(Self {
+            alphabet: alphabet.chars().collect(),
+            length,
+        })
+    }
+
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    pub fn alphabet(&self) -> &[char] {
+        &self.alphabet
+    }
+}
+
+impl Default for NanoidGenerator {
+    fn default() -> Self {
+        Self::new(DEFAULT_LENGTH, URL_SAFE_ALPHABET).expect("default nanoid settings are valid")
+    }
+}
+
+impl Generator for NanoidGenerator {
+    type Output = ShortCode;
+
+    fn generate(&self) -> Self::Output {
+        let mut rng = rand::rng();
+        let code: String = (0..self.length)
+            .map(|_| self.alphabet[rng.random_range(0..self.alphabet.len())])
+            .collect();
+        ShortCode::new_unchecked(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_codes_are_valid_short_codes() {
+        let generator = NanoidGenerator::default();
+
+        for _ in 0..1_000 {
+            let code = generator.generate();
+            assert_eq!(code.as_str().len(), DEFAULT_LENGTH);
+            assert!(code.is_valid(), "{code}");
+        }
+    }
+
+    #[test]
+    fn codes_use_only_the_configured_alphabet() {
+        let generator = NanoidGenerator::new(16, "abc").unwrap();
+
+        for _ in 0..1_000 {
+            let code = generator.generate();
+            assert_eq!(code.as_str().len(), 16);
+            assert!(code.as_str().chars().all(|c| "abc".contains(c)), "{code}");
+        }
+    }
+
+    #[test]
+    fn codes_are_unique_across_many_draws() {
+        let generator = NanoidGenerator::default();
+
+        let codes: HashSet<_> = (0..100_000)
+            .map(|_| generator.generate().to_string())
+            .collect();
+
+        assert_eq!(codes.len(), 100_000);
+    }
+
+    #[test]
+    fn invalid_settings_are_rejected() {
+        assert_eq!(
+            NanoidGenerator::new(2, URL_SAFE_ALPHABET).unwrap_err(),
+            NanoidError::InvalidLength(2)
+        );
+        assert_eq!(
+            NanoidGenerator::new(33, URL_SAFE_ALPHABET).unwrap_err(),
+            NanoidError::InvalidLength(33)
+        );
+        assert_eq!(
+            NanoidGenerator::new(10, "ab.").unwrap_err(),
+            NanoidError::InvalidCharacter('.')
+        );
+        assert_eq!(
+            NanoidGenerator::new(10, "aba").unwrap_err(),
+            NanoidError::DuplicateCharacter('a')
+        );
+        assert_eq!(
+            NanoidGenerator::new(10, "a").unwrap_err(),
+            NanoidError::AlphabetTooSmall
+        );
+    }
+}
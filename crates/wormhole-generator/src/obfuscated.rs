@@ -1,22 +1,111 @@
-use crate::Generator;
+use crate::{Generator, GeneratorError};
+use jiff::Timestamp;
+use std::time::Duration;
+use thiserror::Error;
 use typed_builder::TypedBuilder;
-use wormhole_core::base58::ShortCodeBase58;
+use wormhole_core::base58::{Alphabet, ShortCodeBase58};
 use wormhole_core::ShortCode;
-use wormhole_tinyflake::{Clock, SystemClock, TinyId, Tinyflake, TinyflakeSettings};
+use wormhole_tinyflake::{
+    BitLayout, Clock, StateStore, SystemClock, TinyId, Tinyflake, TinyflakeSettings,
+};
 
 const LOWER_40_BITS_MASK: u64 = (1_u64 << 40) - 1;
 
-#[derive(Debug, TypedBuilder)]
+/// Computes the multiplicative inverse of `value` modulo 2^64.
+///
+/// Only odd numbers are invertible modulo a power of two, so even inputs
+/// return `None`. Each Newton iteration doubles the number of correct low
+/// bits, and an odd `value` is already its own inverse modulo 2^3, so five
+/// rounds are enough to cover all 64 bits.
+fn inverse_mod_2_64(value: u64) -> Option<u64> {
+    if value.is_multiple_of(2) {
+        return None;
+    }
+
+    let mut inverse = value;
+    for _ in 0..5 {
+        inverse = inverse.wrapping_mul(2_u64.wrapping_sub(value.wrapping_mul(inverse)));
+    }
+    Some(inverse)
+}
+
+const DEFAULT_PRIME: u64 = 3;
+const DEFAULT_MASK: u64 = 0xDEAD_BEEF_CAFE_BABE;
+
+/// The multiplier of an [`Obfuscator`] cannot be undone.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("obfuscator multiplier {prime} is even, so obfuscated ids could not be decoded")]
+pub struct NonInvertiblePrimeError {
+    pub prime: u64,
+}
+
+#[derive(Debug, Clone)]
 /// An Obfuscator that specially design for obfuscating TinyID.
 /// It uses a simple multiplicative and XOR-based obfuscation method.
+///
+/// The multiplier (`prime`) must be odd: only odd numbers are coprime with
+/// 2^40, and an even one would map different ids to the same code and make
+/// [`Obfuscator::deobfuscate`] impossible. [`ObfuscatorBuilder::build`]
+/// enforces this.
 pub struct Obfuscator {
-    #[builder(default = 3)]
     prime: u64,
-    #[builder(default = 0xDEAD_BEEF_CAFE_BABE)]
     mask: u64,
 }
 
+impl Default for Obfuscator {
+    fn default() -> Self {
+        Self {
+            prime: DEFAULT_PRIME,
+            mask: DEFAULT_MASK,
+        }
+    }
+}
+
+/// Builder for [`Obfuscator`], see [`Obfuscator::builder`].
+#[derive(Debug, Clone)]
+pub struct ObfuscatorBuilder {
+    prime: u64,
+    mask: u64,
+}
+
+impl ObfuscatorBuilder {
+    /// Sets the multiplier. Defaults to 3.
+    pub fn prime(mut self, prime: u64) -> Self {
+        self.prime = prime;
+        self
+    }
+
+    /// Sets the XOR mask. Defaults to `0xDEAD_BEEF_CAFE_BABE`.
+    pub fn mask(mut self, mask: u64) -> Self {
+        self.mask = mask;
+        self
+    }
+
+    /// Builds the obfuscator.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`NonInvertiblePrimeError`] if the multiplier is even.
+    pub fn build(self) -> Result<Obfuscator, NonInvertiblePrimeError> {
+        if inverse_mod_2_64(self.prime).is_none() {
+            return Err(NonInvertiblePrimeError { prime: self.prime });
+        }
+        Ok(Obfuscator {
+            prime: self.prime,
+            mask: self.mask,
+        })
+    }
+}
+
 impl Obfuscator {
+    /// Returns a builder starting from the default multiplier and mask.
+    pub fn builder() -> ObfuscatorBuilder {
+        ObfuscatorBuilder {
+            prime: DEFAULT_PRIME,
+            mask: DEFAULT_MASK,
+        }
+    }
+
     pub fn prime(&self) -> u64 {
         self.prime
     }
@@ -40,17 +129,78 @@ impl Obfuscator {
                 obfuscated_bytes[6],
                 obfuscated_bytes[7],
             ],
+            alphabet: Alphabet::default(),
         }
     }
+
+    /// Reverses [`Obfuscator::obfuscate`], recovering the original TinyId.
+    ///
+    /// Returns `None` if the multiplier is even, because multiplying by an even
+    /// number discards the low bit and cannot be undone modulo 2^40. Such an
+    /// obfuscator cannot be built, so this only guards the invariant.
+    pub fn deobfuscate(&self, id: &ObfuscatedTinyID) -> Option<TinyId> {
+        let inverse = inverse_mod_2_64(self.prime)?;
+        let raw = id.inner;
+        let obfuscated = u64::from_be_bytes([0, 0, 0, raw[0], raw[1], raw[2], raw[3], raw[4]]);
+
+        // XOR is its own inverse; the multiplication is undone with the modular
+        // inverse, which is also valid modulo 2^40 since 2^40 divides 2^64.
+        let source = ((obfuscated ^ self.mask) & LOWER_40_BITS_MASK).wrapping_mul(inverse)
+            & LOWER_40_BITS_MASK;
+        let source_bytes = source.to_be_bytes();
+
+        Some(TinyId::from_bytes([
+            source_bytes[3],
+            source_bytes[4],
+            source_bytes[5],
+            source_bytes[6],
+            source_bytes[7],
+        ]))
+    }
 }
 
 pub struct ObfuscatedTinyID {
     inner: [u8; 5],
+    /// The alphabet the id is spelled with once converted to a short code.
+    alphabet: Alphabet,
+}
+
+impl ObfuscatedTinyID {
+    /// Size of an obfuscated TinyId in bytes.
+    pub const BYTE_LEN: usize = 5;
+
+    /// Returns the longest short code an obfuscated TinyId can encode to.
+    pub fn max_code_len() -> usize {
+        ShortCodeBase58::max_encoded_len(Self::BYTE_LEN)
+    }
+
+    /// Reconstructs an obfuscated id from the base58 short code it was encoded into.
+    ///
+    /// Returns `None` if the code is not valid base58 or does not decode to
+    /// exactly the 5 bytes an obfuscated TinyId occupies.
+    pub fn from_base58(code: &ShortCodeBase58) -> Option<Self> {
+        Self::from_base58_with(code, &Alphabet::default())
+    }
+
+    /// Like [`ObfuscatedTinyID::from_base58`], for codes spelled with `alphabet`.
+    pub fn from_base58_with(code: &ShortCodeBase58, alphabet: &Alphabet) -> Option<Self> {
+        let inner: [u8; 5] = code.decode_with(alphabet).ok()?.try_into().ok()?;
+        Some(Self {
+            inner,
+            alphabet: *alphabet,
+        })
+    }
+
+    /// Spells the id with `alphabet` when it is converted to a short code.
+    pub fn with_alphabet(mut self, alphabet: Alphabet) -> Self {
+        self.alphabet = alphabet;
+        self
+    }
 }
 
 impl From<ObfuscatedTinyID> for ShortCodeBase58 {
     fn from(val: ObfuscatedTinyID) -> Self {
-        ShortCodeBase58::new(val.inner)
+        ShortCodeBase58::encode_with(val.inner, &val.alphabet)
     }
 }
 
@@ -63,21 +213,64 @@ impl From<ObfuscatedTinyID> for ShortCode {
 pub struct ObfuscatedTinyFlake<C: Clock> {
     inner: Tinyflake<C>,
     obfuscator: Obfuscator,
+    alphabet: Alphabet,
 }
 
 impl ObfuscatedTinyFlake<SystemClock> {
+    /// Creates a generator backed by the system clock.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the settings are invalid; see [`ObfuscatedTinyFlake::try_new`].
     pub fn new(settings: TinyflakeSettings, obfuscator: Obfuscator) -> Self {
-        Self {
-            inner: Tinyflake::new(settings).unwrap(),
+        Self::try_new(settings, obfuscator).unwrap()
+    }
+
+    /// Creates a generator backed by the system clock, failing if the node
+    /// id is out of range or the start epoch is ahead of the clock.
+    pub fn try_new(
+        settings: TinyflakeSettings,
+        obfuscator: Obfuscator,
+    ) -> Result<Self, wormhole_tinyflake::Error> {
+        Ok(Self {
+            inner: Tinyflake::new(settings)?,
             obfuscator,
-        }
+            alphabet: Alphabet::default(),
+        })
     }
 }
 
 impl<C: Clock> ObfuscatedTinyFlake<C> {
+    /// Persists the generator's high-water timestamp; see
+    /// [`Tinyflake::with_state_store`].
+    pub fn with_state_store(mut self, store: impl StateStore, interval: Duration) -> Self {
+        self.inner = self.inner.with_state_store(store, interval);
+        self
+    }
+
+    /// Spells generated codes with `alphabet` instead of the Bitcoin one.
+    pub fn with_alphabet(mut self, alphabet: Alphabet) -> Self {
+        self.alphabet = alphabet;
+        self
+    }
+
+    /// Returns the next id, obfuscated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the Tinyflake fails; see
+    /// [`ObfuscatedTinyFlake::try_next_obfuscated_id`].
     pub fn next_obfuscated_id(&self) -> ObfuscatedTinyID {
-        let id = self.inner.next_id().unwrap(); // TODO: safe unwrap?
-        self.obfuscator.obfuscate(id)
+        self.try_next_obfuscated_id()
+            .expect("tinyflake generator failed to produce the next id")
+    }
+
+    /// Returns the next id, obfuscated, or the Tinyflake's error, e.g.
+    /// [`wormhole_tinyflake::Error::OverTimeLimit`] once the epoch has run
+    /// out.
+    pub fn try_next_obfuscated_id(&self) -> Result<ObfuscatedTinyID, wormhole_tinyflake::Error> {
+        let id = self.inner.next_id()?;
+        Ok(self.obfuscator.obfuscate(id).with_alphabet(self.alphabet))
     }
 }
 
@@ -87,6 +280,49 @@ impl<C: Clock + 'static> Generator for ObfuscatedTinyFlake<C> {
     fn generate(&self) -> Self::Output {
         self.next_obfuscated_id()
     }
+
+    fn try_generate(&self) -> Result<Self::Output, GeneratorError> {
+        Ok(self.try_next_obfuscated_id()?)
+    }
+}
+
+/// Recovers the creation time embedded in codes minted by [`ObfuscatedTinyFlake`].
+///
+/// The decoder must be configured with the same start epoch, obfuscator
+/// parameters, bit layout and alphabet as the generator; otherwise the
+/// recovered time is meaningless.
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct CreationTimeDecoder {
+    /// The custom epoch the generator counts seconds from.
+    start_epoch: Timestamp,
+    /// The obfuscator the generator applies to every TinyId.
+    obfuscator: Obfuscator,
+    /// The generator's bit layout.
+    #[builder(default)]
+    layout: BitLayout,
+    /// The alphabet the generator spells codes with.
+    #[builder(default)]
+    alphabet: Alphabet,
+}
+
+impl CreationTimeDecoder {
+    /// Returns when the given code was created, if it can be determined.
+    ///
+    /// Custom aliases carry no embedded timestamp, so they always yield `None`,
+    /// as do generated codes that don't decode to an obfuscated TinyId.
+    pub fn created_at(&self, code: &ShortCode) -> Option<Timestamp> {
+        let ShortCode::Generated(base58) = code else {
+            return None;
+        };
+
+        let obfuscated = ObfuscatedTinyID::from_base58_with(base58, &self.alphabet)?;
+        let tiny_id = self.obfuscator.deobfuscate(&obfuscated)?;
+
+        Timestamp::from_second(
+            self.start_epoch.as_second() + self.layout.timestamp(&tiny_id) as i64,
+        )
+        .ok()
+    }
 }
 
 #[cfg(test)]
@@ -111,7 +347,7 @@ mod tests {
             .with_sequence(0xA5)
             .with_node_id(0b11);
 
-        let obfuscator = Obfuscator::builder().build();
+        let obfuscator = Obfuscator::default();
 
         let obfuscated = obfuscator.obfuscate(id);
 
@@ -125,6 +361,7 @@ mod tests {
     fn obfuscated_tiny_id_converts_into_base58() {
         let obfuscated = ObfuscatedTinyID {
             inner: [0x10, 0x20, 0x30, 0x40, 0x50],
+            alphabet: Alphabet::default(),
         };
 
         let code: ShortCodeBase58 = obfuscated.into();
@@ -146,11 +383,193 @@ mod tests {
 
         let tinyflake = Tinyflake::new(settings).unwrap();
 
-        let obfuscator = Obfuscator::builder().build();
+        let obfuscator = Obfuscator::default();
 
         let first: ShortCodeBase58 = obfuscator.obfuscate(tinyflake.next_id().unwrap()).into();
         let second: ShortCodeBase58 = obfuscator.obfuscate(tinyflake.next_id().unwrap()).into();
 
         assert_ne!(first.as_str(), second.as_str());
     }
+
+    #[test]
+    fn deobfuscate_round_trips_obfuscate() {
+        let id = TinyId::new()
+            .with_timestamp(0x1234_5678)
+            .with_sequence(0x42)
+            .with_node_id(0b10);
+
+        let obfuscator = Obfuscator::default();
+        let obfuscated = obfuscator.obfuscate(id);
+
+        assert_eq!(obfuscator.deobfuscate(&obfuscated), Some(id));
+    }
+
+    #[test]
+    fn deobfuscate_rejects_even_multiplier() {
+        // Bypasses the builder, which would refuse this multiplier.
+        let obfuscator = Obfuscator {
+            prime: 4,
+            mask: DEFAULT_MASK,
+        };
+        let obfuscated = ObfuscatedTinyID {
+            inner: [0; 5],
+            alphabet: Alphabet::default(),
+        };
+
+        assert_eq!(obfuscator.deobfuscate(&obfuscated), None);
+    }
+
+    #[test]
+    fn creation_time_decoder_recovers_generation_time() {
+        let start: Timestamp = "2026-01-01T00:00:00Z".parse().unwrap();
+        let id = TinyId::new().with_timestamp(3600);
+        let obfuscator = Obfuscator::default();
+        let code: ShortCode = obfuscator.obfuscate(id).into();
+
+        let decoder = CreationTimeDecoder::builder()
+            .start_epoch(start)
+            .obfuscator(obfuscator)
+            .build();
+
+        let expected: Timestamp = "2026-01-01T01:00:00Z".parse().unwrap();
+        assert_eq!(decoder.created_at(&code), Some(expected));
+    }
+
+    #[test]
+    fn creation_time_decoder_ignores_custom_codes() {
+        let decoder = CreationTimeDecoder::builder()
+            .start_epoch(Timestamp::UNIX_EPOCH)
+            .obfuscator(Obfuscator::default())
+            .build();
+
+        let code = ShortCode::custom("my-alias").unwrap();
+        assert_eq!(decoder.created_at(&code), None);
+    }
+
+    #[test]
+    fn max_code_len_fits_the_documented_column_width() {
+        // `short_code` is VARCHAR(32); see the storage DDL.
+        assert_eq!(ObfuscatedTinyID::max_code_len(), 7);
+        assert!(crate::check_code_width(ObfuscatedTinyID::max_code_len(), 32).is_ok());
+
+        let err = crate::check_code_width(ObfuscatedTinyID::max_code_len(), 6).unwrap_err();
+        assert_eq!(
+            err,
+            crate::CodeWidthError {
+                max_code_len: 7,
+                column_width: 6
+            }
+        );
+    }
+
+    #[test]
+    fn generated_codes_never_exceed_max_code_len() {
+        let obfuscator = Obfuscator::default();
+        for timestamp in [0, 1, 0xFFFF, 0x3FFF_FFFF] {
+            let id = TinyId::new().with_timestamp(timestamp).with_sequence(0xFF);
+            let code: ShortCodeBase58 = obfuscator.obfuscate(id).into();
+            assert!(code.as_str().len() <= ObfuscatedTinyID::max_code_len());
+        }
+    }
+
+    #[test]
+    fn try_generate_reports_an_exhausted_epoch() {
+        let settings = TinyflakeSettings::builder()
+            .node_id(0)
+            .start_epoch(Timestamp::now() - jiff::SignedDuration::from_secs(1_000))
+            .timestamp_bits(8)
+            .sequence_bits(24)
+            .node_bits(8)
+            .build();
+        let generator = ObfuscatedTinyFlake::new(settings, Obfuscator::default());
+
+        assert!(matches!(
+            generator.try_generate(),
+            Err(GeneratorError::Tinyflake(
+                wormhole_tinyflake::Error::OverTimeLimit
+            ))
+        ));
+    }
+
+    #[test]
+    fn try_new_rejects_an_epoch_in_the_future() {
+        let future = Timestamp::now() + jiff::SignedDuration::from_hours(24);
+        let settings = TinyflakeSettings::builder()
+            .node_id(0)
+            .start_epoch(future)
+            .build();
+
+        let result = ObfuscatedTinyFlake::try_new(settings, Obfuscator::default());
+
+        assert!(matches!(
+            result,
+            Err(wormhole_tinyflake::Error::EpochAhead { .. })
+        ));
+    }
+
+    #[test]
+    fn generator_spells_codes_with_its_alphabet() {
+        let settings = TinyflakeSettings::builder()
+            .node_id(1)
+            .start_epoch(Timestamp::now() - jiff::SignedDuration::from_secs(1_000))
+            .build();
+        let generator = ObfuscatedTinyFlake::new(settings, Obfuscator::default())
+            .with_alphabet(Alphabet::FLICKR);
+
+        let code: ShortCodeBase58 = generator.generate().into();
+
+        assert!(ObfuscatedTinyID::from_base58_with(&code, &Alphabet::FLICKR).is_some());
+        let decoded = code.decode_with(&Alphabet::FLICKR).unwrap();
+        assert_eq!(
+            code,
+            ShortCodeBase58::encode_with(decoded, &Alphabet::FLICKR)
+        );
+    }
+
+    #[test]
+    fn creation_time_decoder_uses_the_generator_alphabet() {
+        let id = TinyId::new().with_timestamp(3600);
+        let obfuscator = Obfuscator::default();
+        let code: ShortCode = obfuscator
+            .obfuscate(id)
+            .with_alphabet(Alphabet::RIPPLE)
+            .into();
+
+        let decoder = CreationTimeDecoder::builder()
+            .start_epoch(Timestamp::UNIX_EPOCH)
+            .obfuscator(obfuscator)
+            .alphabet(Alphabet::RIPPLE)
+            .build();
+
+        assert_eq!(decoder.created_at(&code), Timestamp::from_second(3600).ok());
+    }
+
+    #[test]
+    fn builder_accepts_odd_primes_and_round_trips() {
+        let id = TinyId::new()
+            .with_timestamp(0x1234)
+            .with_sequence(0x42)
+            .with_node_id(0b10);
+
+        for prime in [3, 7, 1_000_003, 0xFFFF_FFFF_FFFF_FFFF] {
+            let obfuscator = Obfuscator::builder().prime(prime).build().unwrap();
+            let obfuscated = obfuscator.obfuscate(id);
+            assert_eq!(
+                obfuscator.deobfuscate(&obfuscated),
+                Some(id),
+                "prime {prime}"
+            );
+        }
+        assert_eq!(Obfuscator::builder().build().unwrap().prime(), 3);
+    }
+
+    #[test]
+    fn builder_rejects_even_primes() {
+        for prime in [0, 2, 4, 1 << 40] {
+            assert_eq!(
+                Obfuscator::builder().prime(prime).build().unwrap_err(),
+                NonInvertiblePrimeError { prime }
+            );
+        }
+    }
 }
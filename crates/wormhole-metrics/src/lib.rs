@@ -0,0 +1,216 @@
+//! Prometheus metrics shared across Wormhole services.
+//!
+//! Library crates record into a single process-wide [`Metrics`] instance
+//! (see [`metrics`]) when their `metrics` cargo feature is enabled. Binaries
+//! serve the collected values by calling [`Metrics::encode`] from whatever
+//! endpoint they expose, typically `GET /metrics`.
+
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts,
+    Registry, TextEncoder,
+};
+use std::sync::LazyLock;
+use std::time::Duration;
+
+/// The content type of [`Metrics::encode`] output.
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+/// Returns the process-wide metrics instance.
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+/// How a redirect resolution ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedirectOutcome {
+    /// The code resolved to a live record.
+    Hit,
+    /// The code does not exist.
+    Miss,
+    /// The code exists but its record has expired.
+    Expired,
+}
+
+impl RedirectOutcome {
+    fn as_str(self) -> &'static str {
+        match self {
+            RedirectOutcome::Hit => "hit",
+            RedirectOutcome::Miss => "miss",
+            RedirectOutcome::Expired => "expired",
+        }
+    }
+}
+
+/// The metric families recorded by Wormhole services.
+pub struct Metrics {
+    registry: Registry,
+    cache_lookups: IntCounterVec,
+    redirect_resolutions: IntCounterVec,
+    shorten_requests: IntCounterVec,
+    repository_fetch_seconds: Histogram,
+    generator_wait_seconds: HistogramVec,
+    cache_key_cardinality: IntGauge,
+    degraded_reads: IntCounter,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("wormhole".to_string()), None)
+            .expect("metric namespace is valid");
+
+        let cache_lookups = IntCounterVec::new(
+            Opts::new("cache_lookups_total", "Cache lookups by layer and result."),
+            &["layer", "result"],
+        )
+        .expect("metric options are valid");
+        let redirect_resolutions = IntCounterVec::new(
+            Opts::new(
+                "redirect_resolutions_total",
+                "Short code resolutions by outcome.",
+            ),
+            &["outcome"],
+        )
+        .expect("metric options are valid");
+        let shorten_requests = IntCounterVec::new(
+            Opts::new("shorten_requests_total", "Shorten requests by result."),
+            &["result"],
+        )
+        .expect("metric options are valid");
+        let repository_fetch_seconds = Histogram::with_opts(HistogramOpts::new(
+            "repository_fetch_seconds",
+            "Latency of reads that fall through the cache to the repository.",
+        ))
+        .expect("metric options are valid");
+        let generator_wait_seconds = HistogramVec::new(
+            HistogramOpts::new(
+                "generator_wait_seconds",
+                "Time the ID generator spent blocked, by reason.",
+            ),
+            &["reason"],
+        )
+        .expect("metric options are valid");
+        let cache_key_cardinality = IntGauge::new(
+            "cache_key_cardinality",
+            "Estimated distinct short codes resolved in the last complete window.",
+        )
+        .expect("metric options are valid");
+        let degraded_reads = IntCounter::new(
+            "degraded_reads_total",
+            "Reads served from the repository because the cache failed.",
+        )
+        .expect("metric options are valid");
+
+        for collector in [
+            Box::new(cache_lookups.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(redirect_resolutions.clone()),
+            Box::new(shorten_requests.clone()),
+            Box::new(repository_fetch_seconds.clone()),
+            Box::new(generator_wait_seconds.clone()),
+            Box::new(cache_key_cardinality.clone()),
+            Box::new(degraded_reads.clone()),
+        ] {
+            registry
+                .register(collector)
+                .expect("each metric is registered exactly once");
+        }
+
+        Self {
+            registry,
+            cache_lookups,
+            redirect_resolutions,
+            shorten_requests,
+            repository_fetch_seconds,
+            generator_wait_seconds,
+            cache_key_cardinality,
+            degraded_reads,
+        }
+    }
+
+    /// Returns the registry holding every Wormhole metric.
+    ///
+    /// Useful for binaries that want to merge these metrics with their own.
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    /// Records a cache lookup against `layer` (e.g. `"l1"`, `"l2"`).
+    pub fn record_cache_lookup(&self, layer: &str, hit: bool) {
+        let result = if hit { "hit" } else { "miss" };
+        self.cache_lookups.with_label_values(&[layer, result]).inc();
+    }
+
+    /// Records the outcome of resolving a short code.
+    pub fn record_redirect(&self, outcome: RedirectOutcome) {
+        self.redirect_resolutions
+            .with_label_values(&[outcome.as_str()])
+            .inc();
+    }
+
+    /// Records the result of a shorten request (e.g. `"ok"`, `"alias_conflict"`).
+    pub fn record_shorten(&self, result: &str) {
+        self.shorten_requests.with_label_values(&[result]).inc();
+    }
+
+    /// Records how long a repository read behind the cache took.
+    pub fn observe_repository_fetch(&self, elapsed: Duration) {
+        self.repository_fetch_seconds.observe(elapsed.as_secs_f64());
+    }
+
+    /// Records how long the ID generator blocked for `reason` (e.g.
+    /// `"sequence_exhausted"`, `"clock_rollback"`).
+    pub fn observe_generator_wait(&self, reason: &str, elapsed: Duration) {
+        self.generator_wait_seconds
+            .with_label_values(&[reason])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Sets the estimated number of distinct short codes resolved in the
+    /// last complete window.
+    pub fn set_cache_key_cardinality(&self, estimate: u64) {
+        self.cache_key_cardinality
+            .set(i64::try_from(estimate).unwrap_or(i64::MAX));
+    }
+
+    /// Records a read that bypassed a failing cache.
+    pub fn record_degraded_read(&self) {
+        self.degraded_reads.inc();
+    }
+
+    /// Encodes all metrics in the Prometheus text exposition format.
+    pub fn encode(&self) -> String {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("encoding into a Vec cannot fail");
+        String::from_utf8(buffer).expect("text exposition format is UTF-8")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recorded_values_appear_in_encoded_output() {
+        let metrics = Metrics::new();
+
+        metrics.record_cache_lookup("l1", true);
+        metrics.record_redirect(RedirectOutcome::Expired);
+        metrics.record_shorten("ok");
+        metrics.observe_repository_fetch(Duration::from_millis(5));
+        metrics.observe_generator_wait("clock_rollback", Duration::from_secs(1));
+        metrics.record_degraded_read();
+
+        let output = metrics.encode();
+        assert!(output.contains(r#"wormhole_cache_lookups_total{layer="l1",result="hit"} 1"#));
+        assert!(output.contains(r#"wormhole_redirect_resolutions_total{outcome="expired"} 1"#));
+        assert!(output.contains(r#"wormhole_shorten_requests_total{result="ok"} 1"#));
+        assert!(output.contains("wormhole_repository_fetch_seconds_count 1"));
+        assert!(
+            output.contains(r#"wormhole_generator_wait_seconds_count{reason="clock_rollback"} 1"#)
+        );
+        assert!(output.contains("wormhole_degraded_reads_total 1"));
+    }
+}
@@ -15,7 +15,12 @@ fn main() -> Result<(), Box<dyn Error>> {
         .map(|entry| Ok(entry?))
         .collect::<Result<_, Box<dyn Error>>>()?;
 
-    tonic_prost_build::configure().compile_protos(&protos, &[proto_dir])?;
+    // Also emit the encoded descriptors so the servers can offer reflection.
+    let descriptor_path = PathBuf::from(std::env::var("OUT_DIR")?).join("wormhole_descriptor.bin");
+
+    tonic_prost_build::configure()
+        .file_descriptor_set_path(descriptor_path)
+        .compile_protos(&protos, &[proto_dir])?;
 
     Ok(())
 }
@@ -12,8 +12,27 @@ pub mod redirector {
     }
 }
 
+/// Encoded descriptors of every package in this crate.
+pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("wormhole_descriptor");
+
+/// Builds a gRPC reflection service describing every package in this crate,
+/// so tools like `grpcurl` work without the `.proto` files.
+pub fn reflection_service() -> Result<
+    tonic_reflection::server::v1::ServerReflectionServer<
+        impl tonic_reflection::server::v1::ServerReflection,
+    >,
+    tonic_reflection::server::Error,
+> {
+    tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+        .build_v1()
+}
+
 pub mod v1 {
     pub use crate::redirector::v1::*;
     pub use crate::shortcode::v1::*;
     pub use crate::shortener::v1::*;
+    // Both services have a `Resolve` RPC; the flat namespace keeps the
+    // redirector's messages; see `shortener::v1` for the shortener's.
+    pub use crate::redirector::v1::{ResolveRequest, ResolveResponse};
 }
@@ -21,18 +21,22 @@ impl TryInto<core::ShortCode> for &ShortCode {
             .map_err(|_| ConversionError::InvalidKind(self.kind))?;
 
         match kind {
+            // Carried as-is: the code may be spelled with any alphabet the
+            // generator is configured with, which is not known here. Generated
+            // codes always meet the built-in short code rules, so check those.
             ShortCodeKind::Generated => {
-                // We decode then re-encode to preserve the generated variant while
-                // ensuring the wire value is valid base58.
-                let decoded = bs58::decode(self.code.as_str()).into_vec().map_err(|e| {
-                    ConversionError::MalformedCode(format!(
-                        "failed to decode base58 short code: {e}"
-                    ))
-                })?;
-                Ok(core::ShortCode::generated(ShortCodeBase58::new(decoded)))
+                core::ShortCode::custom(self.code.as_str())
+                    .map_err(|_| ConversionError::MalformedCode(self.code.clone()))?;
+                Ok(core::ShortCode::generated(ShortCodeBase58::from_encoded(
+                    self.code.as_str(),
+                )))
+            }
+            // The receiving service checks the code against its own alias
+            // policy, so accept any length a policy can allow.
+            ShortCodeKind::Custom => {
+                core::ShortCode::parse_with(self.code.as_str(), &core::AliasPolicy::widest())
+                    .map_err(|_| ConversionError::MalformedCode(self.code.clone()))
             }
-            ShortCodeKind::Custom => core::ShortCode::custom(self.code.as_str())
-                .map_err(|_| ConversionError::MalformedCode(self.code.clone())),
             ShortCodeKind::Unspecified => Err(ConversionError::InvalidKind(self.kind)),
         }
     }
@@ -46,10 +50,35 @@ impl TryInto<core::ShortCode> for ShortCode {
     }
 }
 
+impl From<core::RedirectKind> for RedirectKind {
+    fn from(kind: core::RedirectKind) -> Self {
+        match kind {
+            core::RedirectKind::Permanent301 => RedirectKind::Permanent301,
+            core::RedirectKind::Found302 => RedirectKind::Found302,
+            core::RedirectKind::Temporary307 => RedirectKind::Temporary307,
+            core::RedirectKind::Permanent308 => RedirectKind::Permanent308,
+        }
+    }
+}
+
+impl From<RedirectKind> for core::RedirectKind {
+    fn from(kind: RedirectKind) -> Self {
+        match kind {
+            RedirectKind::Permanent301 => core::RedirectKind::Permanent301,
+            // Unspecified comes from peers that predate the field, which always
+            // redirected with 302.
+            RedirectKind::Found302 | RedirectKind::Unspecified => core::RedirectKind::Found302,
+            RedirectKind::Temporary307 => core::RedirectKind::Temporary307,
+            RedirectKind::Permanent308 => core::RedirectKind::Permanent308,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::v1::{ShortCode, ShortCodeKind};
     use wormhole_core as core;
+    use wormhole_core::base58::{Alphabet, ShortCodeBase58};
 
     #[test]
     fn test_short_code_try_into() {
@@ -64,12 +93,48 @@ mod tests {
     }
 
     #[test]
-    fn invalid_base58() {
+    fn generated_codes_keep_their_spelling_in_any_alphabet() {
+        let bytes = [0x12, 0x34, 0x56, 0x78, 0x9A];
+        for alphabet in [Alphabet::BITCOIN, Alphabet::FLICKR, Alphabet::RIPPLE] {
+            let encoded = ShortCodeBase58::encode_with(bytes, &alphabet);
+            let shortcode = ShortCode {
+                code: encoded.to_string(),
+                kind: ShortCodeKind::Generated as i32,
+            };
+
+            let result: core::ShortCode = shortcode.try_into().expect("generated code");
+            assert!(matches!(result, core::ShortCode::Generated(_)));
+            assert_eq!(result.as_str(), encoded.as_str());
+        }
+    }
+
+    #[test]
+    fn malformed_generated_codes_are_rejected() {
+        for code in ["", "a/b"] {
+            let shortcode = ShortCode {
+                code: code.to_string(),
+                kind: ShortCodeKind::Generated as i32,
+            };
+
+            let result: Result<core::ShortCode, _> = shortcode.try_into();
+            assert!(result.is_err(), "{code:?}");
+        }
+    }
+
+    #[test]
+    fn custom_codes_of_any_policy_length_convert() {
         let shortcode = ShortCode {
-            code: "invalid_base58".to_string(),
-            kind: ShortCodeKind::Generated as i32,
+            code: "ab".to_string(),
+            kind: ShortCodeKind::Custom as i32,
         };
 
+        let result: core::ShortCode = shortcode.try_into().expect("short alias should convert");
+        assert_eq!(result.as_str(), "ab");
+
+        let shortcode = ShortCode {
+            code: "a/b".to_string(),
+            kind: ShortCodeKind::Custom as i32,
+        };
         let result: Result<core::ShortCode, _> = shortcode.try_into();
         assert!(result.is_err());
     }
@@ -1,9 +1,23 @@
-use clap::Parser;
-use std::net::SocketAddr;
+use clap::{ArgAction, Parser};
+use jiff::Timestamp;
+use std::net::{IpAddr, SocketAddr};
+use wormhole_core::base58::Alphabet;
 
 pub const LISTEN_ADDR_ENV: &str = "WORMHOLE_REDIRECTOR_GRPC_LISTEN_ADDR";
 pub const MYSQL_DSN_ENV: &str = "WORMHOLE_REDIRECTOR_MYSQL_DSN";
 pub const REDIS_URL_ENV: &str = "WORMHOLE_REDIRECTOR_REDIS_URL";
+pub const REDIS_KEY_PREFIX_ENV: &str = "WORMHOLE_REDIRECTOR_REDIS_KEY_PREFIX";
+pub const GENERATOR_START_EPOCH_ENV: &str = "WORMHOLE_REDIRECTOR_GENERATOR_START_EPOCH";
+pub const GENERATOR_CODE_ALPHABET_ENV: &str = "WORMHOLE_REDIRECTOR_GENERATOR_CODE_ALPHABET";
+pub const GENERATOR_OBFUSCATOR_PRIME_ENV: &str = "WORMHOLE_REDIRECTOR_GENERATOR_OBFUSCATOR_PRIME";
+pub const GENERATOR_OBFUSCATOR_MASK_ENV: &str = "WORMHOLE_REDIRECTOR_GENERATOR_OBFUSCATOR_MASK";
+pub const COLLAPSE_DUPLICATE_SLASHES_ENV: &str = "WORMHOLE_REDIRECTOR_COLLAPSE_DUPLICATE_SLASHES";
+pub const TRUSTED_CALLERS_ENV: &str = "WORMHOLE_REDIRECTOR_TRUSTED_CALLERS";
+pub const COUNT_HITS_ENV: &str = "WORMHOLE_REDIRECTOR_COUNT_HITS";
+pub const TRACK_KEY_CARDINALITY_ENV: &str = "WORMHOLE_REDIRECTOR_TRACK_KEY_CARDINALITY";
+pub const ENABLE_REFLECTION_ENV: &str = "WORMHOLE_REDIRECTOR_ENABLE_REFLECTION";
+pub const ALIAS_MIN_LEN_ENV: &str = "WORMHOLE_REDIRECTOR_ALIAS_MIN_LEN";
+pub const ALIAS_MAX_LEN_ENV: &str = "WORMHOLE_REDIRECTOR_ALIAS_MAX_LEN";
 pub const DEFAULT_LISTEN_ADDR: &str = "0.0.0.0:50052";
 
 #[derive(Debug, Parser)]
@@ -19,4 +33,68 @@ pub struct CLI {
     #[arg(long, env = REDIS_URL_ENV)]
     /// Redis URL, e.g. "redis://localhost:6379"
     pub redis_url: String,
+
+    #[arg(long, env = REDIS_KEY_PREFIX_ENV, default_value = "wh:url:")]
+    /// Prefix of cached record keys; give each logical cache sharing a Redis
+    /// its own prefix
+    pub redis_key_prefix: String,
+
+    #[arg(long, env = GENERATOR_START_EPOCH_ENV)]
+    /// Start epoch of the shortener's code generator, e.g. "2026-01-01T00:00:00Z".
+    /// When set, resolve responses include the creation time of generated codes.
+    pub generator_start_epoch: Option<Timestamp>,
+
+    #[arg(long, env = GENERATOR_CODE_ALPHABET_ENV, default_value = "bitcoin")]
+    /// Alphabet the shortener spells generated codes with; must match its
+    /// --code-alphabet for creation times to decode
+    pub generator_code_alphabet: Alphabet,
+
+    #[arg(long, env = GENERATOR_OBFUSCATOR_PRIME_ENV, value_parser = parse_u64)]
+    /// Multiplier the shortener obfuscates generated ids with, decimal or
+    /// 0x-prefixed hex; must match its --obfuscator-prime
+    pub generator_obfuscator_prime: Option<u64>,
+
+    #[arg(long, env = GENERATOR_OBFUSCATOR_MASK_ENV, value_parser = parse_u64)]
+    /// XOR mask the shortener obfuscates generated ids with, decimal or
+    /// 0x-prefixed hex; must match its --obfuscator-mask
+    pub generator_obfuscator_mask: Option<u64>,
+
+    #[arg(long, env = COLLAPSE_DUPLICATE_SLASHES_ENV)]
+    /// Collapse repeated slashes in the path of resolved destinations.
+    pub collapse_duplicate_slashes: bool,
+
+    #[arg(long, env = TRUSTED_CALLERS_ENV, value_delimiter = ',')]
+    /// Comma-separated peer IPs allowed to resolve internal-only codes.
+    pub trusted_callers: Vec<IpAddr>,
+
+    #[arg(long, env = ALIAS_MIN_LEN_ENV, default_value_t = 3, value_parser = clap::value_parser!(u8).range(1..=32))]
+    /// Shortest custom alias to resolve; must match the shortener's --alias-min-len
+    pub alias_min_len: u8,
+
+    #[arg(long, env = ALIAS_MAX_LEN_ENV, default_value_t = 32, value_parser = clap::value_parser!(u8).range(1..=32))]
+    /// Longest custom alias to resolve; must match the shortener's --alias-max-len
+    pub alias_max_len: u8,
+
+    #[arg(long, env = COUNT_HITS_ENV)]
+    /// Count clicks per short code in Redis.
+    pub count_hits: bool,
+
+    #[arg(long, env = TRACK_KEY_CARDINALITY_ENV)]
+    /// Estimate distinct resolved codes per window and export them as a gauge.
+    pub track_key_cardinality: bool,
+
+    #[arg(long, env = ENABLE_REFLECTION_ENV, default_value_t = cfg!(debug_assertions), action = ArgAction::Set)]
+    /// Serve gRPC reflection for tools like grpcurl; on by default in debug builds only.
+    pub enable_reflection: bool,
+}
+
+/// Parses a decimal or `0x`-prefixed hexadecimal `u64`.
+fn parse_u64(value: &str) -> Result<u64, std::num::ParseIntError> {
+    match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => value.parse(),
+    }
 }
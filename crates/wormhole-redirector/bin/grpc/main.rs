@@ -2,16 +2,21 @@ mod cli;
 
 use crate::cli::CLI;
 use clap::Parser;
+use std::sync::Arc;
 use tonic::transport::Server;
-use tracing::info;
+use tracing::{info, warn};
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 use wormhole_cache::RedisUrlCache;
-use wormhole_proto_schema::v1::redirector_service_server::RedirectorServiceServer;
+use wormhole_core::AliasPolicy;
+use wormhole_generator::obfuscated::{CreationTimeDecoder, Obfuscator};
+use wormhole_proto_schema::v1::redirector_service_server::{self, RedirectorServiceServer};
+use wormhole_redirector::cardinality::KeyCardinality;
 use wormhole_redirector::grpc::RedirectorGrpcServer;
+use wormhole_redirector::hits::{BufferedHitCounter, RedisHitCounter};
 use wormhole_redirector::repository::CachedRepository;
 use wormhole_redirector::service::RedirectorService;
-use wormhole_storage::MySqlRepository;
+use wormhole_storage::{MySqlRepository, ReadRepository};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -35,23 +40,77 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Create Redis cache connection
     let client = redis::Client::open(config.redis_url.as_str())?;
     let conn = client.get_multiplexed_async_connection().await?;
-    let cache = RedisUrlCache::new(conn);
+    let cache = RedisUrlCache::with_prefix(conn.clone(), config.redis_key_prefix.clone());
 
     // Create MySQL repository
     let inner = MySqlRepository::connect(&config.mysql_dsn).await?;
     // do the migration before starting the server
     inner.migrate().await?;
 
+    // Report NOT_SERVING, rather than exiting, if either backend is down at
+    // startup, so readiness probes keep traffic away.
+    let (health_reporter, health_service) = tonic_health::server::health_reporter();
+    let mysql_ping = inner.ping().await.map_err(|e| e.to_string());
+    let redis_ping = redis::cmd("PING")
+        .query_async::<()>(&mut conn.clone())
+        .await
+        .map_err(|e| e.to_string());
+    let status = match mysql_ping.and(redis_ping) {
+        Ok(()) => tonic_health::ServingStatus::Serving,
+        Err(error) => {
+            warn!(error = %error, "startup health ping failed, reporting NOT_SERVING");
+            tonic_health::ServingStatus::NotServing
+        }
+    };
+    health_reporter
+        .set_service_status(redirector_service_server::SERVICE_NAME, status)
+        .await;
+
     // Wrap with caching layer
-    let repository = CachedRepository::new(inner, cache);
+    let alias_policy =
+        AliasPolicy::new().with_length(config.alias_min_len.into(), config.alias_max_len.into())?;
+    let repository = CachedRepository::new(inner, cache).with_alias_policy(alias_policy);
 
-    let service = RedirectorService::new(repository);
-    let grpc_server = RedirectorGrpcServer::new(service);
+    let mut service = RedirectorService::new(repository);
+    if config.count_hits {
+        let hits = BufferedHitCounter::new(RedisHitCounter::new(conn), Default::default());
+        hits.spawn_flusher();
+        service = service.with_hit_sink(hits);
+    }
+    if config.track_key_cardinality {
+        service = service.with_key_cardinality(Arc::new(KeyCardinality::new(Default::default())));
+    }
+    let mut grpc_server = RedirectorGrpcServer::new(service)
+        .with_collapse_duplicate_slashes(config.collapse_duplicate_slashes)
+        .with_trusted_callers(config.trusted_callers);
+    if let Some(start_epoch) = config.generator_start_epoch {
+        let mut obfuscator = Obfuscator::builder();
+        if let Some(prime) = config.generator_obfuscator_prime {
+            obfuscator = obfuscator.prime(prime);
+        }
+        if let Some(mask) = config.generator_obfuscator_mask {
+            obfuscator = obfuscator.mask(mask);
+        }
+        let obfuscator = obfuscator
+            .build()
+            .map_err(|e| format!("cannot decode creation times: {e}"))?;
+        let decoder = CreationTimeDecoder::builder()
+            .start_epoch(start_epoch)
+            .obfuscator(obfuscator)
+            .alphabet(config.generator_code_alphabet)
+            .build();
+        grpc_server = grpc_server.with_created_at_decoder(decoder);
+    }
 
-    let (_, health_service) = tonic_health::server::health_reporter();
+    let reflection = if config.enable_reflection {
+        Some(wormhole_proto_schema::reflection_service()?)
+    } else {
+        None
+    };
 
     Server::builder()
         .add_service(health_service)
+        .add_optional_service(reflection)
         .add_service(RedirectorServiceServer::new(grpc_server))
         .serve(config.listen_addr)
         .await?;
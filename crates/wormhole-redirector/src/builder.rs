@@ -0,0 +1,347 @@
+//! One-step assembly of a cached [`RedirectorService`].
+
+use std::time::Duration;
+
+use thiserror::Error;
+use wormhole_cache::{
+    CacheError, LayerErrorPolicy, MokaExistenceCache, MokaUrlCache, MultiLayerCache,
+    RedisHAUrlCache, RedisUrlCache, TtlPolicy,
+};
+use wormhole_core::AliasPolicy;
+use wormhole_storage::ReadRepository;
+
+use crate::{CachedRepository, RedirectorService};
+
+/// Key prefix of the Redis layer unless another one is configured.
+const DEFAULT_REDIS_KEY_PREFIX: &str = "wh:url:";
+
+/// The service a [`RedirectorBuilder`] produces.
+pub type CachedRedirector<R> = RedirectorService<CachedRepository<R, MultiLayerCache>>;
+
+/// Errors returned by [`RedirectorBuilder::build`].
+#[derive(Debug, Error)]
+pub enum BuildError {
+    /// Both a single Redis connection and a Sentinel deployment were set.
+    #[error("configure either a single Redis connection or Redis Sentinel, not both")]
+    ConflictingRedis,
+    /// Neither an L1 nor an L2 cache was configured.
+    #[error("at least one cache layer is required; use RedirectorService::new for no caching")]
+    NoCacheLayer,
+    /// A size or duration that must be positive was zero.
+    #[error("{0} must be positive")]
+    NotPositive(&'static str),
+    /// A cache layer could not be created.
+    #[error(transparent)]
+    Cache(#[from] CacheError),
+}
+
+/// Where the L2 layer lives.
+#[derive(Debug)]
+enum RedisLayer {
+    Single(redis::aio::MultiplexedConnection),
+    Sentinel {
+        sentinels: Vec<String>,
+        service_name: String,
+    },
+}
+
+/// Assembles a [`RedirectorService`] over a cached repository.
+///
+/// The repository is wrapped in a [`CachedRepository`] whose cache is a
+/// [`MultiLayerCache`] of an optional Moka L1 and an optional Redis L2
+/// (single node or Sentinel). At least one layer is required, and the
+/// combination is checked by [`RedirectorBuilder::build`].
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use wormhole_redirector::builder::RedirectorBuilder;
+/// use wormhole_storage::InMemoryRepository;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let client = redis::Client::open("redis://127.0.0.1:6379")?;
+/// let service = RedirectorBuilder::new(InMemoryRepository::new())
+///     .with_l1(10_000)
+///     .with_redis(client.get_multiplexed_async_connection().await?)
+///     .with_default_ttl(Duration::from_secs(3600))
+///     .build()?;
+/// # let _ = service;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct RedirectorBuilder<R> {
+    repository: R,
+    l1_capacity: Option<u64>,
+    redis: Option<RedisLayer>,
+    conflicting_redis: bool,
+    redis_key_prefix: String,
+    default_ttl: Option<Duration>,
+    negative_cache: Option<(u64, Duration)>,
+    inner_concurrency: Option<usize>,
+    error_policy: LayerErrorPolicy,
+    alias_policy: AliasPolicy,
+}
+
+impl<R: ReadRepository> RedirectorBuilder<R> {
+    /// Starts a builder around the backing store.
+    pub fn new(repository: R) -> Self {
+        Self {
+            repository,
+            l1_capacity: None,
+            redis: None,
+            conflicting_redis: false,
+            redis_key_prefix: DEFAULT_REDIS_KEY_PREFIX.to_string(),
+            default_ttl: None,
+            negative_cache: None,
+            inner_concurrency: None,
+            error_policy: LayerErrorPolicy::default(),
+            alias_policy: AliasPolicy::default(),
+        }
+    }
+
+    /// Adds an in-process Moka L1 holding up to `max_capacity` records.
+    pub fn with_l1(mut self, max_capacity: u64) -> Self {
+        self.l1_capacity = Some(max_capacity);
+        self
+    }
+
+    /// Uses a single Redis node as L2.
+    pub fn with_redis(self, conn: redis::aio::MultiplexedConnection) -> Self {
+        self.set_redis(RedisLayer::Single(conn))
+    }
+
+    /// Uses a Redis Sentinel deployment as L2, reading from replicas.
+    pub fn with_redis_sentinel<T: AsRef<str>>(self, sentinels: Vec<T>, service_name: &str) -> Self {
+        self.set_redis(RedisLayer::Sentinel {
+            sentinels: sentinels.iter().map(|s| s.as_ref().to_string()).collect(),
+            service_name: service_name.to_string(),
+        })
+    }
+
+    fn set_redis(mut self, layer: RedisLayer) -> Self {
+        // Reported by `build`, so a misconfiguration is not silently resolved
+        // by whichever call came last.
+        self.conflicting_redis |= self.redis.is_some();
+        self.redis = Some(layer);
+        self
+    }
+
+    /// Sets the key prefix of the Redis L2. Defaults to `wh:url:`.
+    pub fn with_redis_key_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.redis_key_prefix = prefix.into();
+        self
+    }
+
+    /// Expires cached records after `ttl` in every layer.
+    ///
+    /// Either way, no entry outlives the record's own `expire_at`; without a
+    /// default TTL, that is the only expiry.
+    pub fn with_default_ttl(mut self, ttl: Duration) -> Self {
+        self.default_ttl = Some(ttl);
+        self
+    }
+
+    /// Caches the answers of existence checks, including negative ones, for
+    /// `ttl` in a separate cache of up to `max_capacity` codes. Keep `ttl`
+    /// short: a code created after a negative answer stays invisible to
+    /// existence checks until it expires.
+    ///
+    /// Resolves already remember misses in the L1; this covers
+    /// [`ReadRepository::exists`], see [`CachedRepository::with_exists_cache`].
+    pub fn with_negative_caching(mut self, max_capacity: u64, ttl: Duration) -> Self {
+        self.negative_cache = Some((max_capacity, ttl));
+        self
+    }
+
+    /// See [`CachedRepository::with_inner_concurrency`].
+    pub fn with_inner_concurrency(mut self, limit: usize) -> Self {
+        self.inner_concurrency = Some(limit);
+        self
+    }
+
+    /// Sets how a failing cache layer is handled.
+    pub fn with_error_policy(mut self, error_policy: LayerErrorPolicy) -> Self {
+        self.error_policy = error_policy;
+        self
+    }
+
+    /// See [`CachedRepository::with_alias_policy`].
+    pub fn with_alias_policy(mut self, policy: AliasPolicy) -> Self {
+        self.alias_policy = policy;
+        self
+    }
+
+    /// Validates the configuration and assembles the service.
+    ///
+    /// # Errors
+    ///
+    /// - [`BuildError::ConflictingRedis`] if more than one Redis L2 was set.
+    /// - [`BuildError::NoCacheLayer`] if neither L1 nor L2 was set.
+    /// - [`BuildError::NotPositive`] for a zero capacity, TTL or limit.
+    /// - [`BuildError::Cache`] if the Sentinel pools cannot be created.
+    pub fn build(self) -> Result<CachedRedirector<R>, BuildError> {
+        if self.conflicting_redis {
+            return Err(BuildError::ConflictingRedis);
+        }
+        if self.l1_capacity.is_none() && self.redis.is_none() {
+            return Err(BuildError::NoCacheLayer);
+        }
+        check_positive("L1 capacity", self.l1_capacity)?;
+        check_positive("default TTL", self.default_ttl.map(|ttl| ttl.as_nanos()))?;
+        check_positive(
+            "negative cache capacity",
+            self.negative_cache.map(|(capacity, _)| capacity),
+        )?;
+        check_positive(
+            "negative cache TTL",
+            self.negative_cache.map(|(_, ttl)| ttl.as_nanos()),
+        )?;
+        check_positive("inner concurrency", self.inner_concurrency)?;
+
+        let mut layers = MultiLayerCache::builder().error_policy(self.error_policy);
+        if let Some(capacity) = self.l1_capacity {
+            layers = layers.layer(match self.default_ttl {
+                Some(ttl) => MokaUrlCache::with_ttl(capacity, ttl),
+                None => MokaUrlCache::with_capacity(capacity),
+            });
+        }
+        let ttl_policy = self.default_ttl.map(TtlPolicy::fixed);
+        match self.redis {
+            Some(RedisLayer::Single(conn)) => {
+                let mut cache = RedisUrlCache::with_prefix(conn, self.redis_key_prefix);
+                if let Some(policy) = ttl_policy {
+                    cache = cache.with_ttl_policy(policy);
+                }
+                layers = layers.layer(cache);
+            }
+            Some(RedisLayer::Sentinel {
+                sentinels,
+                service_name,
+            }) => {
+                let mut cache =
+                    RedisHAUrlCache::with_prefix(sentinels, &service_name, self.redis_key_prefix)?;
+                if let Some(policy) = ttl_policy {
+                    cache = cache.with_ttl_policy(policy);
+                }
+                layers = layers.layer(cache);
+            }
+            None => {}
+        }
+
+        let mut repository = CachedRepository::new(self.repository, layers.build()?)
+            .with_alias_policy(self.alias_policy);
+        if let Some((capacity, ttl)) = self.negative_cache {
+            repository = repository.with_exists_cache(
+                MokaExistenceCache::with_ttl(capacity, ttl).with_negative_ttl(ttl),
+            );
+        }
+        if let Some(limit) = self.inner_concurrency {
+            repository = repository.with_inner_concurrency(limit);
+        }
+        Ok(RedirectorService::new(repository))
+    }
+}
+
+fn check_positive<T: Default + PartialEq>(
+    what: &'static str,
+    value: Option<T>,
+) -> Result<(), BuildError> {
+    match value {
+        Some(value) if value == T::default() => Err(BuildError::NotPositive(what)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jiff::Timestamp;
+    use wormhole_core::{RedirectKind, ShortCode, UrlRecord};
+    use wormhole_storage::{InMemoryRepository, Repository};
+
+    fn record(url: &str) -> UrlRecord {
+        UrlRecord {
+            original_url: url.to_string(),
+            expire_at: None,
+            redirect_kind: RedirectKind::default(),
+            created_at: Timestamp::now(),
+            internal_only: false,
+            no_store: false,
+            owner_id: None,
+            metadata: Default::default(),
+        }
+    }
+
+    async fn repository() -> InMemoryRepository {
+        let repo = InMemoryRepository::new();
+        repo.insert(
+            &ShortCode::new_unchecked("abc123"),
+            record("https://example.com"),
+        )
+        .await
+        .unwrap();
+        repo
+    }
+
+    #[tokio::test]
+    async fn l1_only_service_resolves() {
+        let service = RedirectorBuilder::new(repository().await)
+            .with_l1(100)
+            .with_default_ttl(Duration::from_secs(60))
+            .with_negative_caching(100, Duration::from_secs(5))
+            .with_inner_concurrency(4)
+            .build()
+            .unwrap();
+
+        let found = service.resolve(&ShortCode::new_unchecked("abc123")).await;
+        assert_eq!(found.unwrap().unwrap().original_url, "https://example.com");
+        let missing = service.resolve(&ShortCode::new_unchecked("missing")).await;
+        assert!(missing.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn a_cache_layer_is_required() {
+        let err = RedirectorBuilder::new(repository().await)
+            .with_default_ttl(Duration::from_secs(60))
+            .build()
+            .unwrap_err();
+
+        assert!(matches!(err, BuildError::NoCacheLayer));
+    }
+
+    #[tokio::test]
+    async fn zero_sizes_and_durations_are_rejected() {
+        let err = RedirectorBuilder::new(repository().await)
+            .with_l1(0)
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, BuildError::NotPositive("L1 capacity")));
+
+        let err = RedirectorBuilder::new(repository().await)
+            .with_l1(100)
+            .with_default_ttl(Duration::ZERO)
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, BuildError::NotPositive("default TTL")));
+
+        let err = RedirectorBuilder::new(repository().await)
+            .with_l1(100)
+            .with_inner_concurrency(0)
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, BuildError::NotPositive("inner concurrency")));
+    }
+
+    #[tokio::test]
+    async fn sentinel_twice_conflicts() {
+        let err = RedirectorBuilder::new(repository().await)
+            .with_redis_sentinel(vec!["redis://127.0.0.1:26379"], "mymaster")
+            .with_redis_sentinel(vec!["redis://127.0.0.1:26380"], "mymaster")
+            .build()
+            .unwrap_err();
+
+        assert!(matches!(err, BuildError::ConflictingRedis));
+    }
+}
@@ -0,0 +1,239 @@
+//! Sampled estimate of how many distinct short codes are being resolved.
+//!
+//! A sudden jump in distinct codes usually means something upstream started
+//! generating cache keys it should not (e.g. codes with query strings glued
+//! on), which would churn every cache layer. [`KeyCardinality`] tracks the
+//! count per window with a HyperLogLog and publishes each finished window to
+//! the `cache_key_cardinality` gauge, so operators can alert on it.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+use tokio::time::Instant;
+use typed_builder::TypedBuilder;
+use wormhole_core::ShortCode;
+
+use crate::metrics;
+
+/// Settings for [`KeyCardinality`].
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct KeyCardinalityConfig {
+    /// Only one in `sample_rate` codes (chosen by hash, so a given code is
+    /// always in or out) is fed to the estimator; the estimate is scaled
+    /// back up. Higher values cut overhead at the cost of accuracy for
+    /// small counts.
+    #[builder(default = 8)]
+    pub sample_rate: u32,
+    /// Length of the window the gauge reports on.
+    #[builder(default = Duration::from_secs(300))]
+    pub window: Duration,
+    /// HyperLogLog precision: `2^precision` one-byte registers, with a
+    /// standard error of about `1.04 / sqrt(2^precision)`. Clamped to 4..=16.
+    #[builder(default = 12)]
+    pub precision: u8,
+}
+
+impl Default for KeyCardinalityConfig {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// Windowed, sampled distinct-count of resolved short codes.
+#[derive(Debug)]
+pub struct KeyCardinality {
+    sample_rate: u64,
+    window: Duration,
+    state: Mutex<Window>,
+}
+
+#[derive(Debug)]
+struct Window {
+    started: Instant,
+    hll: HyperLogLog,
+}
+
+impl KeyCardinality {
+    /// Creates an estimator; the first window starts now.
+    pub fn new(config: KeyCardinalityConfig) -> Self {
+        Self {
+            sample_rate: u64::from(config.sample_rate.max(1)),
+            window: config.window,
+            state: Mutex::new(Window {
+                started: Instant::now(),
+                hll: HyperLogLog::new(config.precision),
+            }),
+        }
+    }
+
+    /// Records that `code` was looked up.
+    ///
+    /// When the current window has run out, its estimate is published to the
+    /// gauge and a new window starts.
+    pub fn observe(&self, code: &ShortCode) {
+        let hash = hash_code(code);
+        let sampled = hash % self.sample_rate == 0;
+
+        let mut state = self.lock_state();
+        if state.started.elapsed() >= self.window {
+            metrics::set_cache_key_cardinality(self.scale(state.hll.estimate()));
+            state.hll.clear();
+            state.started = Instant::now();
+        }
+        if sampled {
+            state.hll.insert(hash);
+        }
+    }
+
+    /// Returns the estimated number of distinct codes in the current window.
+    pub fn estimate(&self) -> u64 {
+        self.scale(self.lock_state().hll.estimate())
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, Window> {
+        // A panic while holding the lock leaves at worst a partial window.
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn scale(&self, estimate: f64) -> u64 {
+        (estimate * self.sample_rate as f64).round() as u64
+    }
+}
+
+fn hash_code(code: &ShortCode) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    code.as_str().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A plain HyperLogLog over 64-bit hashes.
+#[derive(Debug)]
+struct HyperLogLog {
+    precision: u8,
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    fn new(precision: u8) -> Self {
+        let precision = precision.clamp(4, 16);
+        Self {
+            precision,
+            registers: vec![0; 1 << precision],
+        }
+    }
+
+    fn insert(&mut self, hash: u64) {
+        let index = (hash >> (64 - self.precision)) as usize;
+        // Position of the first set bit among the remaining bits, 1-based.
+        let rest = hash << self.precision;
+        let max_rank = 64 - u32::from(self.precision) + 1;
+        let rank = (rest.leading_zeros() + 1).min(max_rank) as u8;
+        if rank > self.registers[index] {
+            self.registers[index] = rank;
+        }
+    }
+
+    fn estimate(&self) -> f64 {
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let sum: f64 = self
+            .registers
+            .iter()
+            .map(|&r| 2f64.powi(-i32::from(r)))
+            .sum();
+        let raw = alpha * m * m / sum;
+
+        // Small-range correction: linear counting while registers are empty.
+        let zeros = self.registers.iter().filter(|&&r| r == 0).count();
+        if raw <= 2.5 * m && zeros > 0 {
+            m * (m / zeros as f64).ln()
+        } else {
+            raw
+        }
+    }
+
+    fn clear(&mut self) {
+        self.registers.fill(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes(n: usize) -> impl Iterator<Item = ShortCode> {
+        (0..n).map(|i| ShortCode::new_unchecked(format!("code{i}")))
+    }
+
+    fn assert_within(estimate: u64, actual: u64, tolerance: f64) {
+        let error = (estimate as f64 - actual as f64).abs() / actual as f64;
+        assert!(
+            error <= tolerance,
+            "estimate {estimate} is {:.1}% off {actual}",
+            error * 100.0
+        );
+    }
+
+    #[test]
+    fn unsampled_estimate_is_within_hll_error() {
+        let config = KeyCardinalityConfig::builder().sample_rate(1).build();
+        let estimator = KeyCardinality::new(config);
+
+        for code in codes(50_000) {
+            estimator.observe(&code);
+        }
+
+        // Standard error at precision 12 is ~1.6%; allow three of them.
+        assert_within(estimator.estimate(), 50_000, 0.05);
+    }
+
+    #[test]
+    fn repeated_codes_are_counted_once() {
+        let config = KeyCardinalityConfig::builder().sample_rate(1).build();
+        let estimator = KeyCardinality::new(config);
+
+        for _ in 0..10 {
+            for code in codes(1_000) {
+                estimator.observe(&code);
+            }
+        }
+
+        assert_within(estimator.estimate(), 1_000, 0.05);
+    }
+
+    #[test]
+    fn sampled_estimate_is_scaled_back_up() {
+        let estimator = KeyCardinality::new(KeyCardinalityConfig::default());
+
+        for code in codes(200_000) {
+            estimator.observe(&code);
+        }
+
+        // Sampling adds its own error on top of the HLL's.
+        assert_within(estimator.estimate(), 200_000, 0.1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn new_window_starts_from_zero() {
+        let config = KeyCardinalityConfig::builder()
+            .sample_rate(1)
+            .window(Duration::from_secs(60))
+            .build();
+        let estimator = KeyCardinality::new(config);
+
+        for code in codes(1_000) {
+            estimator.observe(&code);
+        }
+        tokio::time::advance(Duration::from_secs(61)).await;
+        estimator.observe(&ShortCode::new_unchecked("fresh"));
+
+        assert_eq!(estimator.estimate(), 1);
+    }
+}
@@ -1,5 +1,10 @@
+use crate::redirector::NotFoundReason;
+use prost::Message;
+use std::time::Duration;
 use thiserror::Error;
+use tonic::metadata::MetadataValue;
 use tonic::{Code, Status};
+use wormhole_proto_schema::v1 as proto;
 use wormhole_proto_schema::v1::ConversionError;
 use wormhole_storage::StorageError;
 
@@ -13,6 +18,14 @@ pub enum RedirectorError {
     ShortCodeMalformed(String),
     #[error("short code not found")]
     ShortCodeNotFound,
+    /// Like [`RedirectorError::ShortCodeNotFound`], with the reason attached
+    /// to the gRPC status details.
+    #[error("short code not found ({0:?})")]
+    ShortCodeUnresolved(NotFoundReason),
+    /// The service is in maintenance mode; clients should retry after the
+    /// given delay.
+    #[error("service is under maintenance")]
+    Maintenance { retry_after: Duration },
     #[error("storage operation failed: {0}")]
     Storage(
         #[from]
@@ -39,7 +52,36 @@ impl From<RedirectorError> for Status {
             RedirectorError::ShortCodeNotFound => {
                 Status::new(Code::NotFound, "short code not found")
             }
+            RedirectorError::ShortCodeUnresolved(reason) => {
+                let details = proto::ResolveFailure {
+                    reason: proto::ResolveFailureReason::from(reason) as i32,
+                };
+                Status::with_details(
+                    Code::NotFound,
+                    "short code not found",
+                    details.encode_to_vec().into(),
+                )
+            }
+            RedirectorError::Maintenance { retry_after } => {
+                let mut status = Status::new(Code::Unavailable, "service is under maintenance");
+                status.metadata_mut().insert(
+                    "retry-after",
+                    MetadataValue::from(retry_after.as_secs().max(1)),
+                );
+                status
+            }
             RedirectorError::Storage(source) => source.into(),
         }
     }
 }
+
+impl From<NotFoundReason> for proto::ResolveFailureReason {
+    fn from(reason: NotFoundReason) -> Self {
+        match reason {
+            NotFoundReason::Unknown => Self::Unspecified,
+            NotFoundReason::NeverExisted => Self::NeverExisted,
+            NotFoundReason::Expired => Self::Expired,
+            NotFoundReason::Deleted => Self::Deleted,
+        }
+    }
+}
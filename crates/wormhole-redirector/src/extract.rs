@@ -0,0 +1,154 @@
+//! An `axum` extractor for short codes in the request path.
+//!
+//! Enabled by the `axum` feature, for HTTP frontends built on the redirector.
+
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use percent_encoding::percent_decode_str;
+use thiserror::Error;
+use wormhole_core::{AliasPolicy, ShortCode};
+
+/// The short code in the last segment of the request path.
+///
+/// `Path<String>` percent-decodes the segment, so `/%61bc` and `/abc` would
+/// both resolve `abc`, and `/abc%2Fdef` would look up a code containing a
+/// slash. A valid short code never needs encoding, so this extractor
+/// decodes the raw segment and rejects it with [`ShortCodeRejection`]
+/// unless the result is a valid code byte-identical to what was sent.
+///
+/// The code must be the last segment of the route, e.g. `/{code}` or
+/// `/links/{code}`.
+///
+/// Lengths are checked against the [`AliasPolicy`] in the request
+/// extensions, e.g. added with `axum::Extension`, or the default bounds if
+/// there is none.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use axum::{routing::get, Router};
+/// use wormhole_redirector::extract::ShortCodePath;
+///
+/// async fn show(ShortCodePath(code): ShortCodePath) -> String {
+///     code.to_string()
+/// }
+///
+/// let app: Router = Router::new().route("/{code}", get(show));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShortCodePath(pub ShortCode);
+
+/// Rejection of [`ShortCodePath`]: `400 Bad Request` with the reason as a
+/// plain-text body.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{0}")]
+pub struct ShortCodeRejection(String);
+
+impl ShortCodeRejection {
+    /// Why the segment is not a valid short code.
+    pub fn message(&self) -> &str {
+        &self.0
+    }
+}
+
+impl IntoResponse for ShortCodeRejection {
+    fn into_response(self) -> Response {
+        (StatusCode::BAD_REQUEST, self.0).into_response()
+    }
+}
+
+impl ShortCodePath {
+    /// Parses a raw, still percent-encoded path segment.
+    pub fn parse(raw: &str) -> Result<Self, ShortCodeRejection> {
+        Self::parse_with(raw, &AliasPolicy::default())
+    }
+
+    /// Like [`ShortCodePath::parse`], but accepts aliases of the lengths
+    /// `policy` allows.
+    pub fn parse_with(raw: &str, policy: &AliasPolicy) -> Result<Self, ShortCodeRejection> {
+        let decoded = percent_decode_str(raw)
+            .decode_utf8()
+            .map_err(|_| ShortCodeRejection("short code is not valid UTF-8".to_string()))?;
+        let code = ShortCode::parse_with(decoded.into_owned(), policy)
+            .map_err(|e| ShortCodeRejection(e.to_string()))?;
+        if code.as_str() != raw {
+            return Err(ShortCodeRejection(
+                "short code must not be percent-encoded".to_string(),
+            ));
+        }
+        Ok(Self(code))
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for ShortCodePath {
+    type Rejection = ShortCodeRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let raw = parts.uri.path().rsplit('/').next().unwrap_or_default();
+        match parts.extensions.get::<AliasPolicy>() {
+            Some(policy) => Self::parse_with(raw, policy),
+            None => Self::parse(raw),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Request;
+
+    async fn extract(uri: &str) -> Result<ShortCodePath, ShortCodeRejection> {
+        let (mut parts, ()) = Request::builder().uri(uri).body(()).unwrap().into_parts();
+        ShortCodePath::from_request_parts(&mut parts, &()).await
+    }
+
+    #[tokio::test]
+    async fn valid_codes_are_extracted() {
+        let ShortCodePath(code) = extract("/links/My_code-9?ref=x").await.unwrap();
+        assert_eq!(code.as_str(), "My_code-9");
+    }
+
+    #[tokio::test]
+    async fn too_short_codes_are_rejected_with_400() {
+        let rejection = extract("/ab").await.unwrap_err();
+
+        assert!(rejection.message().contains("length"), "{rejection}");
+        assert_eq!(rejection.into_response().status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn alias_policy_in_extensions_sets_the_length_bounds() {
+        let policy = AliasPolicy::new().with_length(2, 32).unwrap();
+        let (mut parts, ()) = Request::builder()
+            .uri("/ab")
+            .extension(policy)
+            .body(())
+            .unwrap()
+            .into_parts();
+
+        let ShortCodePath(code) = ShortCodePath::from_request_parts(&mut parts, &())
+            .await
+            .unwrap();
+        assert_eq!(code.as_str(), "ab");
+    }
+
+    #[tokio::test]
+    async fn invalid_characters_are_rejected_with_400() {
+        for uri in [
+            "/abc.def",
+            "/abc%2Fdef",
+            "/abc%20def",
+            "/%FF%FE",
+            "/%61bc123",
+        ] {
+            let rejection = extract(uri).await.unwrap_err();
+            assert_eq!(
+                rejection.into_response().status(),
+                StatusCode::BAD_REQUEST,
+                "{uri}"
+            );
+        }
+    }
+}
@@ -1,18 +1,188 @@
 use crate::error::RedirectorError;
-use crate::redirector::Redirector;
+use crate::hit_dedup::{self, ClientIdentity};
+use crate::redirector::{CallerTrust, NotFoundReason, Redirector, Resolution};
 use proto::redirector_service_server::RedirectorService;
-use tonic::{Request, Response, Status};
-use wormhole_core::{ShortCode, UrlRecord};
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status, Streaming};
+use wormhole_core::{Clock, SharedClock, ShortCode, UrlRecord};
+use wormhole_generator::obfuscated::CreationTimeDecoder;
 use wormhole_proto_schema::v1 as proto;
 
+/// Responses a `ResolveStream` call buffers before it stops reading requests
+/// and waits for the client to catch up.
+const RESOLVE_STREAM_BUFFER: usize = 64;
+
 pub struct RedirectorGrpcServer<R: Redirector> {
-    redirector: R,
+    redirector: Arc<R>,
+    created_at_decoder: Option<CreationTimeDecoder>,
+    collapse_duplicate_slashes: bool,
+    trusted_callers: HashSet<IpAddr>,
+    client_identity: Option<Arc<dyn ClientIdentity>>,
+    clock: SharedClock,
 }
 
 impl<R: Redirector> RedirectorGrpcServer<R> {
     pub fn new(redirector: R) -> Self {
-        Self { redirector }
+        Self {
+            redirector: Arc::new(redirector),
+            created_at_decoder: None,
+            collapse_duplicate_slashes: false,
+            trusted_callers: HashSet::new(),
+            client_identity: None,
+            clock: SharedClock::default(),
+        }
+    }
+
+    /// Checks expiry at the API boundary against `clock` instead of the
+    /// system clock. Give the redirector the same clock.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = SharedClock::new(clock);
+        self
+    }
+
+    /// Populates `created_at` in resolve responses for generated short codes.
+    ///
+    /// The decoder must match the generator configuration the shortener uses,
+    /// so this is opt-in rather than guessed from defaults.
+    pub fn with_created_at_decoder(mut self, decoder: CreationTimeDecoder) -> Self {
+        self.created_at_decoder = Some(decoder);
+        self
+    }
+
+    /// Collapses repeated slashes in the path of resolved destinations, so
+    /// `https://x.com//a///b` is returned as `https://x.com/a/b`.
+    ///
+    /// The `://` after the scheme, the query and the fragment are left as is.
+    /// Disabled by default, since some destinations treat `//` as meaningful.
+    pub fn with_collapse_duplicate_slashes(mut self, enabled: bool) -> Self {
+        self.collapse_duplicate_slashes = enabled;
+        self
+    }
+
+    /// Treats requests from these peer addresses as trusted, allowing them to
+    /// resolve internal-only codes.
+    ///
+    /// Every other caller, including ones whose address is unknown, is
+    /// untrusted and gets `NOT_FOUND` for internal-only codes. Do not list
+    /// the address of a public-facing proxy such as the gateway here, or
+    /// internal-only codes become reachable through it.
+    pub fn with_trusted_callers(mut self, addrs: impl IntoIterator<Item = IpAddr>) -> Self {
+        self.trusted_callers = addrs.into_iter().collect();
+        self
+    }
+
+    /// Names the client of each resolve with `identity`, so a
+    /// [`DedupHitSink`](crate::hit_dedup::DedupHitSink) can drop repeated
+    /// hits from the same client.
+    pub fn with_client_identity(mut self, identity: impl ClientIdentity) -> Self {
+        self.client_identity = Some(Arc::new(identity));
+        self
+    }
+
+    fn caller_trust<T>(&self, request: &Request<T>) -> CallerTrust {
+        match request.remote_addr() {
+            Some(addr) if self.trusted_callers.contains(&addr.ip()) => CallerTrust::Trusted,
+            _ => CallerTrust::Untrusted,
+        }
+    }
+
+    fn client_id<T>(&self, request: &Request<T>) -> Option<String> {
+        self.client_identity
+            .as_ref()
+            .and_then(|identity| identity.identify(request.metadata(), request.remote_addr()))
+    }
+
+    /// Resolves one request on behalf of a caller with `trust`.
+    async fn resolve_one(
+        &self,
+        request: proto::ResolveRequest,
+        trust: CallerTrust,
+        client_id: Option<String>,
+    ) -> Result<proto::ResolveResponse, RedirectorError> {
+        let req: ResolveRequest = request.try_into()?;
+
+        let resolution = hit_dedup::with_client_id(
+            client_id,
+            self.redirector.resolve_detailed(&req.short_code, trust),
+        )
+        .await?;
+        let mut record = match resolution {
+            Resolution::Found(record) => record,
+            Resolution::NotFound(reason) => {
+                return Err(RedirectorError::ShortCodeUnresolved(reason));
+            }
+        };
+
+        if self.collapse_duplicate_slashes {
+            record.original_url = collapse_duplicate_slashes(&record.original_url);
+        }
+
+        let created_at = self
+            .created_at_decoder
+            .as_ref()
+            .and_then(|decoder| decoder.created_at(&req.short_code));
+
+        ResolveResponse {
+            short_code: req.short_code,
+            url_record: record,
+            created_at,
+            now: self.clock.now(),
+        }
+        .try_into()
+    }
+}
+
+// Not derived, which would require `R: Clone`.
+impl<R: Redirector> Clone for RedirectorGrpcServer<R> {
+    fn clone(&self) -> Self {
+        Self {
+            redirector: Arc::clone(&self.redirector),
+            created_at_decoder: self.created_at_decoder.clone(),
+            collapse_duplicate_slashes: self.collapse_duplicate_slashes,
+            trusted_callers: self.trusted_callers.clone(),
+            client_identity: self.client_identity.clone(),
+            clock: self.clock.clone(),
+        }
+    }
+}
+
+/// Reports `error` inside a `ResolveStream` response, with the code and
+/// message `Resolve` would have returned.
+fn stream_error(error: RedirectorError) -> proto::ResolveStreamError {
+    let reason = match &error {
+        RedirectorError::ShortCodeUnresolved(reason) => proto::ResolveFailureReason::from(*reason),
+        _ => proto::ResolveFailureReason::Unspecified,
+    };
+    let status = Status::from(error);
+    proto::ResolveStreamError {
+        code: status.code() as i32,
+        message: status.message().to_string(),
+        reason: reason as i32,
+    }
+}
+
+/// Collapses runs of `/` in the path of `url` into a single `/`.
+fn collapse_duplicate_slashes(url: &str) -> String {
+    let (scheme, rest) = match url.find("://") {
+        Some(idx) => url.split_at(idx + "://".len()),
+        None => ("", url),
+    };
+    let (path, tail) = rest.split_at(rest.find(['?', '#']).unwrap_or(rest.len()));
+
+    let mut collapsed = String::with_capacity(url.len());
+    collapsed.push_str(scheme);
+    for c in path.chars() {
+        if c == '/' && collapsed.ends_with('/') && collapsed.len() > scheme.len() {
+            continue;
+        }
+        collapsed.push(c);
     }
+    collapsed.push_str(tail);
+    collapsed
 }
 
 struct ResolveRequest {
@@ -38,7 +208,11 @@ impl TryFrom<proto::ResolveRequest> for ResolveRequest {
 }
 
 struct ResolveResponse {
+    short_code: ShortCode,
     url_record: UrlRecord,
+    created_at: Option<jiff::Timestamp>,
+    /// The time to check `url_record`'s expiry against.
+    now: jiff::Timestamp,
 }
 
 impl TryInto<proto::ResolveResponse> for ResolveResponse {
@@ -48,19 +222,28 @@ impl TryInto<proto::ResolveResponse> for ResolveResponse {
         let UrlRecord {
             original_url,
             expire_at,
+            redirect_kind,
+            internal_only,
+            ..
         } = self.url_record;
 
+        let kind = match self.short_code {
+            ShortCode::Generated(_) => proto::ShortCodeKind::Generated,
+            ShortCode::Custom(_) => proto::ShortCodeKind::Custom,
+        };
+
         // We keep this guard at the API boundary so stale cached entries cannot
         // leak expired records through gRPC responses.
         let expire_at = match expire_at {
-            Some(expire_at) if jiff::Timestamp::now() >= expire_at => {
-                return Err(RedirectorError::ShortCodeNotFound);
-            }
-            Some(expire_at) => {
-                let mut ts = prost_types::Timestamp::default();
-                ts.seconds = expire_at.as_second();
-                Some(ts)
+            Some(expire_at) if self.now >= expire_at => {
+                return Err(RedirectorError::ShortCodeUnresolved(
+                    NotFoundReason::Expired,
+                ));
             }
+            Some(expire_at) => Some(prost_types::Timestamp {
+                seconds: expire_at.as_second(),
+                nanos: 0,
+            }),
             None => None,
         };
 
@@ -68,6 +251,13 @@ impl TryInto<proto::ResolveResponse> for ResolveResponse {
             url_record: Some(proto::UrlRecord {
                 original_url,
                 expire_at,
+                redirect_kind: proto::RedirectKind::from(redirect_kind) as i32,
+                internal_only,
+            }),
+            kind: kind as i32,
+            created_at: self.created_at.map(|created_at| prost_types::Timestamp {
+                seconds: created_at.as_second(),
+                nanos: 0,
             }),
         })
     }
@@ -79,35 +269,183 @@ impl<R: Redirector> RedirectorService for RedirectorGrpcServer<R> {
         &self,
         request: Request<proto::ResolveRequest>,
     ) -> Result<Response<proto::ResolveResponse>, Status> {
-        let req: ResolveRequest = request.into_inner().try_into()?;
+        let trust = self.caller_trust(&request);
+        let client_id = self.client_id(&request);
+        let resp = self
+            .resolve_one(request.into_inner(), trust, client_id)
+            .await?;
 
-        let record = self
-            .redirector
-            .resolve(&req.short_code)
-            .await
-            .map_err(Status::from)?
-            .ok_or(RedirectorError::ShortCodeNotFound)?;
+        Ok(Response::new(resp))
+    }
 
-        let resp: proto::ResolveResponse = ResolveResponse { url_record: record }.try_into()?;
+    type ResolveStreamStream = ReceiverStream<Result<proto::ResolveStreamResponse, Status>>;
 
-        Ok(Response::new(resp))
+    async fn resolve_stream(
+        &self,
+        request: Request<Streaming<proto::ResolveStreamRequest>>,
+    ) -> Result<Response<Self::ResolveStreamStream>, Status> {
+        let trust = self.caller_trust(&request);
+        let client_id = self.client_id(&request);
+        let mut requests = request.into_inner();
+        let (tx, rx) = mpsc::channel(RESOLVE_STREAM_BUFFER);
+        let server = self.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let request = match requests.message().await {
+                    Ok(Some(request)) => request,
+                    Ok(None) => break,
+                    Err(status) => {
+                        let _ = tx.send(Err(status)).await;
+                        break;
+                    }
+                };
+                let short_code = request
+                    .short_code
+                    .as_ref()
+                    .map(|code| code.code.clone())
+                    .unwrap_or_default();
+                let request = proto::ResolveRequest {
+                    short_code: request.short_code,
+                };
+                let outcome = match server.resolve_one(request, trust, client_id.clone()).await {
+                    Ok(resolved) => proto::resolve_stream_response::Outcome::Resolved(resolved),
+                    Err(error) => {
+                        proto::resolve_stream_response::Outcome::Error(stream_error(error))
+                    }
+                };
+                let response = proto::ResolveStreamResponse {
+                    short_code,
+                    outcome: Some(outcome),
+                };
+                // Waits while the buffer is full; fails once the client is gone.
+                if tx.send(Ok(response)).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
     }
 }
 #[cfg(test)]
 mod tests {
     use super::*;
+    use async_trait::async_trait;
     use jiff::{SignedDuration, Timestamp};
     use tonic::Code;
+    use wormhole_core::RedirectKind;
+    use wormhole_generator::obfuscated::{ObfuscatedTinyFlake, Obfuscator};
+    use wormhole_generator::Generator;
+    use wormhole_tinyflake::TinyflakeSettings;
 
     fn resolve_response(expire_at: Option<Timestamp>) -> ResolveResponse {
         ResolveResponse {
+            short_code: ShortCode::new_unchecked("abc123"),
             url_record: UrlRecord {
                 original_url: "https://example.com".to_string(),
                 expire_at,
+                redirect_kind: RedirectKind::default(),
+                created_at: Timestamp::now(),
+                internal_only: false,
+                no_store: false,
+                owner_id: None,
+                metadata: Default::default(),
             },
+            created_at: None,
+            now: Timestamp::now(),
+        }
+    }
+
+    struct StaticRedirector(&'static str);
+
+    impl Default for StaticRedirector {
+        fn default() -> Self {
+            Self("https://example.com")
+        }
+    }
+
+    #[async_trait]
+    impl Redirector for StaticRedirector {
+        async fn resolve(&self, _code: &ShortCode) -> crate::Result<Option<UrlRecord>> {
+            Ok(Some(UrlRecord {
+                original_url: self.0.to_string(),
+                expire_at: None,
+                redirect_kind: RedirectKind::default(),
+                created_at: Timestamp::now(),
+                internal_only: false,
+                no_store: false,
+                owner_id: None,
+                metadata: Default::default(),
+            }))
+        }
+    }
+
+    struct UnresolvedRedirector(NotFoundReason);
+
+    #[async_trait]
+    impl Redirector for UnresolvedRedirector {
+        async fn resolve(&self, _code: &ShortCode) -> crate::Result<Option<UrlRecord>> {
+            Ok(None)
+        }
+
+        async fn resolve_detailed(
+            &self,
+            _code: &ShortCode,
+            _trust: CallerTrust,
+        ) -> crate::Result<Resolution> {
+            Ok(Resolution::NotFound(self.0))
+        }
+    }
+
+    fn failure_reason(status: &Status) -> proto::ResolveFailureReason {
+        use prost::Message;
+
+        proto::ResolveFailure::decode(status.details())
+            .expect("details should hold a ResolveFailure")
+            .reason()
+    }
+
+    struct InternalRedirector;
+
+    #[async_trait]
+    impl Redirector for InternalRedirector {
+        async fn resolve(&self, _code: &ShortCode) -> crate::Result<Option<UrlRecord>> {
+            Ok(Some(UrlRecord {
+                original_url: "https://intranet.example".to_string(),
+                expire_at: None,
+                redirect_kind: RedirectKind::default(),
+                created_at: Timestamp::now(),
+                internal_only: true,
+                no_store: false,
+                owner_id: None,
+                metadata: Default::default(),
+            }))
         }
     }
 
+    fn request_from<T>(mut request: Request<T>, peer: &str) -> Request<T> {
+        request
+            .extensions_mut()
+            .insert(tonic::transport::server::TcpConnectInfo {
+                local_addr: None,
+                remote_addr: Some(peer.parse().unwrap()),
+            });
+        request
+    }
+
+    fn resolve_request(code: &ShortCode) -> Request<proto::ResolveRequest> {
+        Request::new(proto::ResolveRequest {
+            short_code: Some(proto::ShortCode {
+                code: code.as_str().to_string(),
+                kind: match code {
+                    ShortCode::Generated(_) => proto::ShortCodeKind::Generated as i32,
+                    ShortCode::Custom(_) => proto::ShortCodeKind::Custom as i32,
+                },
+            }),
+        })
+    }
+
     #[test]
     fn resolve_response_try_into_converts_non_expiring_record() {
         let response: proto::ResolveResponse = resolve_response(None)
@@ -134,6 +472,18 @@ mod tests {
         assert_eq!(proto_expire_at.seconds, expire_at.as_second());
     }
 
+    #[test]
+    fn resolve_response_try_into_carries_redirect_kind() {
+        let mut response = resolve_response(None);
+        response.url_record.redirect_kind = RedirectKind::Permanent301;
+
+        let response: proto::ResolveResponse =
+            response.try_into().expect("response should convert");
+
+        let record = response.url_record.expect("record should be present");
+        assert_eq!(record.redirect_kind(), proto::RedirectKind::Permanent301);
+    }
+
     #[test]
     fn resolve_response_try_into_rejects_expired_records() {
         let expire_at = Timestamp::now() - SignedDuration::from_secs(1);
@@ -147,4 +497,270 @@ mod tests {
         assert_eq!(status.code(), Code::NotFound);
         assert_eq!(status.message(), "short code not found");
     }
+
+    #[tokio::test]
+    async fn resolve_reports_kind_and_creation_time_for_generated_codes() {
+        // Whole seconds keep the decoded time aligned with the generator's
+        // truncated elapsed-seconds counter.
+        let start_epoch = Timestamp::from_second(Timestamp::now().as_second() - 3600).unwrap();
+        let generator = ObfuscatedTinyFlake::new(
+            TinyflakeSettings::builder()
+                .start_epoch(start_epoch)
+                .node_id(0)
+                .build(),
+            Obfuscator::default(),
+        );
+
+        let before = Timestamp::now().as_second();
+        let code: ShortCode = generator.generate().into();
+        let after = Timestamp::now().as_second();
+
+        let server = RedirectorGrpcServer::new(StaticRedirector::default())
+            .with_created_at_decoder(
+                CreationTimeDecoder::builder()
+                    .start_epoch(start_epoch)
+                    .obfuscator(Obfuscator::default())
+                    .build(),
+            );
+
+        let response = server
+            .resolve(resolve_request(&code))
+            .await
+            .expect("resolve should succeed")
+            .into_inner();
+
+        assert_eq!(response.kind, proto::ShortCodeKind::Generated as i32);
+        let created_at = response.created_at.expect("created_at should be present");
+        assert!((before..=after).contains(&created_at.seconds));
+    }
+
+    #[tokio::test]
+    async fn resolve_omits_creation_time_for_custom_codes() {
+        let server = RedirectorGrpcServer::new(StaticRedirector::default())
+            .with_created_at_decoder(
+                CreationTimeDecoder::builder()
+                    .start_epoch(Timestamp::UNIX_EPOCH)
+                    .obfuscator(Obfuscator::default())
+                    .build(),
+            );
+
+        let code = ShortCode::custom("my-alias").unwrap();
+        let response = server
+            .resolve(resolve_request(&code))
+            .await
+            .expect("resolve should succeed")
+            .into_inner();
+
+        assert_eq!(response.kind, proto::ShortCodeKind::Custom as i32);
+        assert!(response.created_at.is_none());
+    }
+
+    #[test]
+    fn collapse_duplicate_slashes_keeps_scheme_query_and_fragment() {
+        assert_eq!(
+            collapse_duplicate_slashes("https://x.com//a///b/"),
+            "https://x.com/a/b/"
+        );
+        assert_eq!(
+            collapse_duplicate_slashes("https://x.com/a//b?next=https://y.com//c#//d"),
+            "https://x.com/a/b?next=https://y.com//c#//d"
+        );
+        assert_eq!(
+            collapse_duplicate_slashes("https://x.com/a/b"),
+            "https://x.com/a/b"
+        );
+    }
+
+    #[tokio::test]
+    async fn resolve_collapses_duplicate_slashes_when_enabled() {
+        let code = ShortCode::custom("slashes").unwrap();
+        let server = RedirectorGrpcServer::new(StaticRedirector("https://x.com//path//to"))
+            .with_collapse_duplicate_slashes(true);
+
+        let response = server
+            .resolve(resolve_request(&code))
+            .await
+            .expect("resolve should succeed")
+            .into_inner();
+
+        let record = response.url_record.expect("record should be present");
+        assert_eq!(record.original_url, "https://x.com/path/to");
+    }
+
+    #[tokio::test]
+    async fn resolve_leaves_duplicate_slashes_by_default() {
+        let code = ShortCode::custom("slashes").unwrap();
+        let server = RedirectorGrpcServer::new(StaticRedirector("https://x.com//path//to"));
+
+        let response = server
+            .resolve(resolve_request(&code))
+            .await
+            .expect("resolve should succeed")
+            .into_inner();
+
+        let record = response.url_record.expect("record should be present");
+        assert_eq!(record.original_url, "https://x.com//path//to");
+    }
+
+    #[tokio::test]
+    async fn resolve_internal_only_code_for_trusted_caller() {
+        let code = ShortCode::custom("internal").unwrap();
+        let server = RedirectorGrpcServer::new(InternalRedirector)
+            .with_trusted_callers(["10.0.0.1".parse().unwrap()]);
+
+        let response = server
+            .resolve(request_from(resolve_request(&code), "10.0.0.1:5000"))
+            .await
+            .expect("trusted caller should resolve internal-only code")
+            .into_inner();
+
+        let record = response.url_record.expect("record should be present");
+        assert_eq!(record.original_url, "https://intranet.example");
+        assert!(record.internal_only);
+    }
+
+    #[tokio::test]
+    async fn resolve_internal_only_code_for_untrusted_caller_is_not_found() {
+        let code = ShortCode::custom("internal").unwrap();
+        let server = RedirectorGrpcServer::new(InternalRedirector)
+            .with_trusted_callers(["10.0.0.1".parse().unwrap()]);
+
+        for request in [
+            request_from(resolve_request(&code), "203.0.113.7:5000"),
+            resolve_request(&code),
+        ] {
+            let status = server.resolve(request).await.unwrap_err();
+            assert_eq!(status.code(), Code::NotFound);
+        }
+    }
+
+    #[tokio::test]
+    async fn resolve_tags_hits_with_the_client_identity() {
+        use crate::hit_dedup::{DedupHitSink, HeaderIdentity};
+        use crate::hits::HitSink;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::time::Duration;
+        use wormhole_storage::{InMemoryRepository, Repository};
+
+        #[derive(Debug, Default)]
+        struct CountingSink(AtomicUsize);
+
+        impl HitSink for Arc<CountingSink> {
+            fn record_hit(&self, _code: &ShortCode) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let code = ShortCode::custom("counted").unwrap();
+        let repo = InMemoryRepository::new();
+        repo.insert(&code, resolve_response(None).url_record)
+            .await
+            .unwrap();
+        let hits = Arc::new(CountingSink::default());
+        let service = crate::RedirectorService::new(repo).with_hit_sink(DedupHitSink::new(
+            Arc::clone(&hits),
+            Duration::from_secs(60),
+            1_000,
+        ));
+        let server = RedirectorGrpcServer::new(service)
+            .with_client_identity(HeaderIdentity::new("x-client-id"));
+        let request_as = |client: &str| {
+            let mut request = resolve_request(&code);
+            request
+                .metadata_mut()
+                .insert("x-client-id", client.parse().unwrap());
+            request
+        };
+
+        for client in ["alice", "alice", "bob", "alice"] {
+            server.resolve(request_as(client)).await.unwrap();
+        }
+
+        assert_eq!(hits.0.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn resolve_public_code_for_untrusted_caller() {
+        let code = ShortCode::custom("public").unwrap();
+        let server = RedirectorGrpcServer::new(StaticRedirector::default())
+            .with_trusted_callers(["10.0.0.1".parse().unwrap()]);
+
+        let response = server
+            .resolve(request_from(resolve_request(&code), "203.0.113.7:5000"))
+            .await
+            .expect("public code should resolve for anyone")
+            .into_inner();
+
+        assert!(response.url_record.is_some());
+    }
+
+    #[tokio::test]
+    async fn resolve_not_found_carries_reason_in_status_details() {
+        let code = ShortCode::custom("missing").unwrap();
+        let cases = [
+            (
+                NotFoundReason::Unknown,
+                proto::ResolveFailureReason::Unspecified,
+            ),
+            (
+                NotFoundReason::NeverExisted,
+                proto::ResolveFailureReason::NeverExisted,
+            ),
+            (
+                NotFoundReason::Expired,
+                proto::ResolveFailureReason::Expired,
+            ),
+            (
+                NotFoundReason::Deleted,
+                proto::ResolveFailureReason::Deleted,
+            ),
+        ];
+
+        for (reason, expected) in cases {
+            let server = RedirectorGrpcServer::new(UnresolvedRedirector(reason));
+            let status = server.resolve(resolve_request(&code)).await.unwrap_err();
+
+            assert_eq!(status.code(), Code::NotFound);
+            assert_eq!(status.message(), "short code not found");
+            assert_eq!(failure_reason(&status), expected, "{reason:?}");
+        }
+    }
+
+    #[test]
+    fn resolve_response_try_into_reports_expired_reason() {
+        let expire_at = Timestamp::now() - SignedDuration::from_secs(1);
+
+        let result: Result<proto::ResolveResponse, RedirectorError> =
+            resolve_response(Some(expire_at)).try_into();
+        let status: Status = result.unwrap_err().into();
+
+        assert_eq!(
+            failure_reason(&status),
+            proto::ResolveFailureReason::Expired
+        );
+    }
+
+    #[tokio::test]
+    async fn resolve_checks_expiry_against_the_injected_clock() {
+        use wormhole_core::ManualClock;
+        use wormhole_storage::{InMemoryRepository, Repository};
+
+        let code = ShortCode::custom("clocked").unwrap();
+        let repo = InMemoryRepository::new();
+        repo.insert(
+            &code,
+            resolve_response(Some(Timestamp::now() + SignedDuration::from_hours(1))).url_record,
+        )
+        .await
+        .unwrap();
+        let clock = ManualClock::new(Timestamp::now());
+        let server = RedirectorGrpcServer::new(crate::RedirectorService::new(repo))
+            .with_clock(clock.clone());
+
+        server.resolve(resolve_request(&code)).await.unwrap();
+
+        clock.advance(SignedDuration::from_hours(2));
+        let status = server.resolve(resolve_request(&code)).await.unwrap_err();
+        assert_eq!(status.code(), Code::NotFound);
+    }
 }
@@ -0,0 +1,274 @@
+//! First-seen deduplication of hits.
+//!
+//! A visitor who refreshes a short link resolves it again and again; counting
+//! every one of those inflates the numbers and the writes to the analytics
+//! store. [`DedupHitSink`] sits in front of another [`HitSink`] and forwards
+//! only the first hit per `(code, client)` within a window.
+//!
+//! The client is named by a [`ClientIdentity`] on the gRPC server (see
+//! [`RedirectorGrpcServer::with_client_identity`]). The identity is carried
+//! to the sink in a task-local scope set up by [`with_client_id`], so the
+//! resolve path in between does not have to pass it along. Hits recorded
+//! outside such a scope, or for requests the identity cannot name, are never
+//! deduplicated.
+//!
+//! [`RedirectorGrpcServer::with_client_identity`]: crate::grpc::RedirectorGrpcServer::with_client_identity
+
+use std::fmt::Debug;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use tonic::metadata::MetadataMap;
+use wormhole_core::ShortCode;
+
+use crate::hits::HitSink;
+
+tokio::task_local! {
+    static CLIENT_ID: Option<String>;
+}
+
+/// Runs `future` with `client_id` as the client of every hit it records.
+pub async fn with_client_id<F: Future>(client_id: Option<String>, future: F) -> F::Output {
+    CLIENT_ID.scope(client_id, future).await
+}
+
+/// Returns the client set by the enclosing [`with_client_id`], if any.
+pub fn current_client_id() -> Option<String> {
+    CLIENT_ID.try_with(Clone::clone).ok().flatten()
+}
+
+/// Names the client behind a gRPC request, for hit deduplication.
+///
+/// The value only has to tell clients apart; it is never stored beyond the
+/// dedup window.
+pub trait ClientIdentity: Debug + Send + Sync + 'static {
+    /// Returns the client's identity, or `None` if the request carries none.
+    fn identify(&self, metadata: &MetadataMap, remote_addr: Option<SocketAddr>) -> Option<String>;
+}
+
+/// Identifies clients by the IP address of the connection.
+///
+/// Behind a proxy every request comes from the proxy's address; use a
+/// [`HeaderIdentity`] on the header the proxy sets instead.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PeerIpIdentity;
+
+impl ClientIdentity for PeerIpIdentity {
+    fn identify(&self, _metadata: &MetadataMap, remote_addr: Option<SocketAddr>) -> Option<String> {
+        remote_addr.map(|addr| addr.ip().to_string())
+    }
+}
+
+/// Identifies clients by the value of a request header, e.g. `x-client-id`
+/// or `x-forwarded-for`.
+#[derive(Debug, Clone)]
+pub struct HeaderIdentity {
+    name: String,
+}
+
+impl HeaderIdentity {
+    /// Reads the header `name`, which is matched case-insensitively.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into().to_ascii_lowercase(),
+        }
+    }
+}
+
+impl ClientIdentity for HeaderIdentity {
+    fn identify(&self, metadata: &MetadataMap, _remote_addr: Option<SocketAddr>) -> Option<String> {
+        let value = metadata.get(self.name.as_str())?.to_str().ok()?.trim();
+        (!value.is_empty()).then(|| value.to_string())
+    }
+}
+
+/// Identifies clients by the value of a cookie.
+#[derive(Debug, Clone)]
+pub struct CookieIdentity {
+    name: String,
+}
+
+impl CookieIdentity {
+    /// Reads the cookie `name` from the `cookie` header.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into() }
+    }
+}
+
+impl ClientIdentity for CookieIdentity {
+    fn identify(&self, metadata: &MetadataMap, _remote_addr: Option<SocketAddr>) -> Option<String> {
+        metadata
+            .get_all("cookie")
+            .iter()
+            .filter_map(|header| header.to_str().ok())
+            .flat_map(|header| header.split(';'))
+            .filter_map(|pair| pair.trim().split_once('='))
+            .find(|(name, value)| *name == self.name && !value.is_empty())
+            .map(|(_, value)| value.to_string())
+    }
+}
+
+/// A [`HitSink`] that forwards only the first hit per `(code, client)`
+/// within a window.
+///
+/// Hits without a client identity are always forwarded. Once `max_clients`
+/// pairs are tracked, the least recently seen ones are forgotten early, so
+/// their next hit counts again.
+#[derive(Debug)]
+pub struct DedupHitSink<S> {
+    inner: S,
+    seen: moka::sync::Cache<(ShortCode, String), ()>,
+    suppressed: AtomicU64,
+}
+
+impl<S: HitSink> DedupHitSink<S> {
+    /// Deduplicates hits within `window`, tracking at most `max_clients`
+    /// `(code, client)` pairs.
+    pub fn new(inner: S, window: Duration, max_clients: u64) -> Self {
+        Self {
+            inner,
+            seen: moka::sync::Cache::builder()
+                .max_capacity(max_clients)
+                .time_to_live(window)
+                .build(),
+            suppressed: AtomicU64::new(0),
+        }
+    }
+
+    /// Number of hits dropped as duplicates so far.
+    pub fn suppressed_hits(&self) -> u64 {
+        self.suppressed.load(Ordering::Relaxed)
+    }
+
+    fn first_seen(&self, code: &ShortCode, client_id: String) -> bool {
+        self.seen
+            .entry((code.clone(), client_id))
+            .or_insert(())
+            .is_fresh()
+    }
+}
+
+impl<S: HitSink> HitSink for DedupHitSink<S> {
+    fn record_hit(&self, code: &ShortCode) {
+        if let Some(client_id) = current_client_id() {
+            if !self.first_seen(code, client_id) {
+                self.suppressed.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+        }
+        self.inner.record_hit(code);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, Default)]
+    struct RecordingSink(Mutex<Vec<String>>);
+
+    impl HitSink for Arc<RecordingSink> {
+        fn record_hit(&self, code: &ShortCode) {
+            self.0.lock().unwrap().push(code.as_str().to_string());
+        }
+    }
+
+    impl RecordingSink {
+        fn count(&self) -> usize {
+            self.0.lock().unwrap().len()
+        }
+    }
+
+    fn code(s: &str) -> ShortCode {
+        ShortCode::new_unchecked(s)
+    }
+
+    async fn hit_as(sink: &impl HitSink, client: Option<&str>, code: &ShortCode) {
+        with_client_id(client.map(str::to_string), async { sink.record_hit(code) }).await;
+    }
+
+    #[tokio::test]
+    async fn duplicates_within_the_window_are_dropped() {
+        let recorded = Arc::new(RecordingSink::default());
+        let sink = DedupHitSink::new(Arc::clone(&recorded), Duration::from_secs(60), 1_000);
+
+        for _ in 0..5 {
+            hit_as(&sink, Some("alice"), &code("abc")).await;
+        }
+        // Other clients and other codes are counted on their own.
+        hit_as(&sink, Some("bob"), &code("abc")).await;
+        hit_as(&sink, Some("alice"), &code("def")).await;
+
+        assert_eq!(recorded.count(), 3);
+        assert_eq!(sink.suppressed_hits(), 4);
+    }
+
+    #[tokio::test]
+    async fn hits_count_again_after_the_window() {
+        let recorded = Arc::new(RecordingSink::default());
+        let sink = DedupHitSink::new(Arc::clone(&recorded), Duration::from_millis(100), 1_000);
+
+        hit_as(&sink, Some("alice"), &code("abc")).await;
+        hit_as(&sink, Some("alice"), &code("abc")).await;
+        assert_eq!(recorded.count(), 1);
+
+        // The cache runs on the wall clock, not on tokio's.
+        std::thread::sleep(Duration::from_millis(200));
+        hit_as(&sink, Some("alice"), &code("abc")).await;
+
+        assert_eq!(recorded.count(), 2);
+    }
+
+    #[tokio::test]
+    async fn anonymous_hits_are_never_deduplicated() {
+        let recorded = Arc::new(RecordingSink::default());
+        let sink = DedupHitSink::new(Arc::clone(&recorded), Duration::from_secs(60), 1_000);
+
+        hit_as(&sink, None, &code("abc")).await;
+        hit_as(&sink, None, &code("abc")).await;
+        // Outside of any scope.
+        sink.record_hit(&code("abc"));
+
+        assert_eq!(recorded.count(), 3);
+    }
+
+    #[test]
+    fn identities_read_the_request() {
+        let mut metadata = MetadataMap::new();
+        metadata.insert("x-client-id", "  device-42 ".parse().unwrap());
+        metadata.insert(
+            "cookie",
+            "theme=dark; wh_visitor=v-7; lang=en".parse().unwrap(),
+        );
+        let peer: SocketAddr = "203.0.113.9:5123".parse().unwrap();
+
+        assert_eq!(
+            PeerIpIdentity.identify(&metadata, Some(peer)).as_deref(),
+            Some("203.0.113.9")
+        );
+        assert_eq!(PeerIpIdentity.identify(&metadata, None), None);
+        assert_eq!(
+            HeaderIdentity::new("X-Client-Id")
+                .identify(&metadata, None)
+                .as_deref(),
+            Some("device-42")
+        );
+        assert_eq!(
+            HeaderIdentity::new("x-missing").identify(&metadata, None),
+            None
+        );
+        assert_eq!(
+            CookieIdentity::new("wh_visitor")
+                .identify(&metadata, None)
+                .as_deref(),
+            Some("v-7")
+        );
+        assert_eq!(
+            CookieIdentity::new("session").identify(&metadata, None),
+            None
+        );
+    }
+}
@@ -0,0 +1,451 @@
+//! Click counting for resolved short codes.
+//!
+//! The redirector reports each successful resolve to a [`HitSink`]. The sink
+//! provided here, [`BufferedHitCounter`], aggregates hits in memory and
+//! periodically flushes them to a [`HitCounter`] such as
+//! [`RedisHitCounter`] or [`RepositoryHitCounter`], so a slow or failing counter backend never sits on
+//! the redirect path.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use redis::AsyncCommands;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::warn;
+use typed_builder::TypedBuilder;
+use wormhole_core::ShortCode;
+use wormhole_storage::{AnalyticsRepository, Result, StorageError};
+
+/// Receives a notification for every successful resolve.
+///
+/// Implementations must return quickly and must not fail the redirect, so
+/// anything slow or fallible belongs behind a buffer.
+pub trait HitSink: Debug + Send + Sync + 'static {
+    /// Records one hit for `code`.
+    fn record_hit(&self, code: &ShortCode);
+}
+
+/// A durable per-code hit counter.
+#[async_trait]
+pub trait HitCounter: Send + Sync + 'static {
+    /// Adds `hits` to the counter for `code`.
+    async fn increment_by(&self, code: &ShortCode, hits: u64) -> Result<()>;
+}
+
+/// Counts hits in Redis with `INCRBY` on `{prefix}{code}`.
+#[derive(Debug, Clone)]
+pub struct RedisHitCounter {
+    conn: redis::aio::MultiplexedConnection,
+    key_prefix: String,
+}
+
+impl RedisHitCounter {
+    /// Creates a counter using the default key prefix `wh:hits:`.
+    pub fn new(conn: redis::aio::MultiplexedConnection) -> Self {
+        Self::with_prefix(conn, "wh:hits:")
+    }
+
+    /// Creates a counter using a custom key prefix.
+    pub fn with_prefix(
+        conn: redis::aio::MultiplexedConnection,
+        key_prefix: impl Into<String>,
+    ) -> Self {
+        Self {
+            conn,
+            key_prefix: key_prefix.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl HitCounter for RedisHitCounter {
+    async fn increment_by(&self, code: &ShortCode, hits: u64) -> Result<()> {
+        let key = format!("{}{}", self.key_prefix, code.as_str());
+        let mut conn = self.conn.clone();
+        conn.incr::<_, _, ()>(&key, hits).await.map_err(|e| {
+            StorageError::Unavailable(format!("failed to count hits for '{key}': {e}"))
+        })
+    }
+}
+
+/// Counts hits in the `hits` column of a repository, so the totals live next
+/// to the records and can be queried with [`AnalyticsRepository::hits`].
+#[derive(Debug, Clone)]
+pub struct RepositoryHitCounter<R> {
+    repository: R,
+}
+
+impl<R: AnalyticsRepository> RepositoryHitCounter<R> {
+    pub fn new(repository: R) -> Self {
+        Self { repository }
+    }
+}
+
+#[async_trait]
+impl<R: AnalyticsRepository> HitCounter for RepositoryHitCounter<R> {
+    async fn increment_by(&self, code: &ShortCode, hits: u64) -> Result<()> {
+        self.repository.increment_hits_by(code, hits).await?;
+        Ok(())
+    }
+}
+
+/// Settings for [`BufferedHitCounter`].
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct BufferedHitCounterConfig {
+    /// How often the background task flushes buffered hits.
+    #[builder(default = Duration::from_secs(1))]
+    pub flush_interval: Duration,
+    /// Maximum number of distinct codes held in the buffer.
+    ///
+    /// Hits for new codes are dropped once the buffer is full, which bounds
+    /// memory while the counter backend is down. Codes already buffered keep
+    /// accumulating.
+    #[builder(default = 100_000)]
+    pub max_pending_codes: usize,
+    /// Minimum time between two flush-failure log lines.
+    #[builder(default = Duration::from_secs(60))]
+    pub error_log_interval: Duration,
+}
+
+impl Default for BufferedHitCounterConfig {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// A [`HitSink`] that buffers hits locally and flushes them to a
+/// [`HitCounter`] in batches.
+///
+/// # Delivery semantics
+///
+/// Flushing is **at-least-once**: when an increment fails, its hits go back
+/// into the buffer and are retried on the next flush. If the backend applied
+/// the increment but the reply was lost, those hits are counted twice. In
+/// exchange, transient backend errors do not lose clicks. Hits still in the
+/// buffer when the process exits, and hits dropped because the buffer is
+/// full, are lost.
+///
+/// Flush failures are logged at most once per
+/// [`error_log_interval`](BufferedHitCounterConfig::error_log_interval), with
+/// the number of suppressed failures, so an outage does not flood the logs.
+pub struct BufferedHitCounter<C> {
+    inner: Arc<Inner<C>>,
+}
+
+// Not derived: that would require `C: Clone`, but clones only share `inner`.
+impl<C> Clone for BufferedHitCounter<C> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+struct Inner<C> {
+    counter: C,
+    config: BufferedHitCounterConfig,
+    pending: Mutex<HashMap<ShortCode, u64>>,
+    dropped: AtomicU64,
+    error_log: LogThrottle,
+}
+
+impl<C> Debug for BufferedHitCounter<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BufferedHitCounter")
+            .field("config", &self.inner.config)
+            .field("pending_codes", &self.inner.lock_pending().len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<C: HitCounter> BufferedHitCounter<C> {
+    /// Creates a buffered counter in front of `counter`.
+    ///
+    /// Nothing is flushed until [`BufferedHitCounter::spawn_flusher`] is
+    /// called or [`BufferedHitCounter::flush`] is invoked manually.
+    pub fn new(counter: C, config: BufferedHitCounterConfig) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                counter,
+                error_log: LogThrottle::new(config.error_log_interval),
+                config,
+                pending: Mutex::new(HashMap::new()),
+                dropped: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Returns the number of buffered hits not yet flushed.
+    pub fn pending_hits(&self) -> u64 {
+        self.inner.lock_pending().values().sum()
+    }
+
+    /// Returns the number of hits dropped because the buffer was full.
+    pub fn dropped_hits(&self) -> u64 {
+        self.inner.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Flushes all buffered hits once.
+    ///
+    /// Increments that fail are put back into the buffer. Returns the number
+    /// of hits successfully flushed.
+    pub async fn flush(&self) -> u64 {
+        let batch = std::mem::take(&mut *self.inner.lock_pending());
+
+        let mut flushed = 0;
+        let mut failed = 0;
+        let mut last_error = None;
+        for (code, hits) in batch {
+            match self.inner.counter.increment_by(&code, hits).await {
+                Ok(()) => flushed += hits,
+                Err(e) => {
+                    failed += hits;
+                    last_error = Some(e);
+                    self.inner.add(code, hits);
+                }
+            }
+        }
+
+        if let Some(error) = last_error {
+            if let Some(suppressed) = self.inner.error_log.check() {
+                warn!(
+                    error = %error,
+                    failed_hits = failed,
+                    suppressed_errors = suppressed,
+                    "Failed to flush hit counts, will retry"
+                );
+            }
+        }
+
+        flushed
+    }
+
+    /// Spawns a task that flushes every
+    /// [`flush_interval`](BufferedHitCounterConfig::flush_interval).
+    ///
+    /// Abort the returned handle and call [`BufferedHitCounter::flush`] once
+    /// more on shutdown to push out the remaining hits.
+    pub fn spawn_flusher(&self) -> JoinHandle<()> {
+        let this = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(this.inner.config.flush_interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                this.flush().await;
+            }
+        })
+    }
+}
+
+impl<C> Inner<C> {
+    fn lock_pending(&self) -> std::sync::MutexGuard<'_, HashMap<ShortCode, u64>> {
+        // A panic while holding the lock cannot leave the map inconsistent.
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn add(&self, code: ShortCode, hits: u64) {
+        let mut pending = self.lock_pending();
+        let at_capacity = pending.len() >= self.config.max_pending_codes;
+        match pending.get_mut(&code) {
+            Some(count) => *count = count.saturating_add(hits),
+            None if at_capacity => {
+                self.dropped.fetch_add(hits, Ordering::Relaxed);
+            }
+            None => {
+                pending.insert(code, hits);
+            }
+        }
+    }
+}
+
+impl<C: HitCounter> HitSink for BufferedHitCounter<C> {
+    fn record_hit(&self, code: &ShortCode) {
+        self.inner.add(code.clone(), 1);
+    }
+}
+
+/// Lets one event through per interval and counts the rest.
+struct LogThrottle {
+    interval: Duration,
+    state: Mutex<(Option<Instant>, u64)>,
+}
+
+impl LogThrottle {
+    fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            state: Mutex::new((None, 0)),
+        }
+    }
+
+    /// Returns `Some(suppressed)` if the caller should log now, where
+    /// `suppressed` is the number of events skipped since the last log.
+    fn check(&self) -> Option<u64> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let (last, suppressed) = &mut *state;
+        let now = Instant::now();
+        match last {
+            Some(at) if now.duration_since(*at) < self.interval => {
+                *suppressed += 1;
+                None
+            }
+            _ => {
+                *last = Some(now);
+                Some(std::mem::take(suppressed))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+
+    /// Fails every other call.
+    #[derive(Default)]
+    struct FlakyCounter {
+        fail_next: AtomicBool,
+        totals: Mutex<HashMap<String, u64>>,
+    }
+
+    impl FlakyCounter {
+        fn total(&self, code: &str) -> u64 {
+            self.totals
+                .lock()
+                .unwrap()
+                .get(code)
+                .copied()
+                .unwrap_or_default()
+        }
+    }
+
+    #[async_trait]
+    impl HitCounter for Arc<FlakyCounter> {
+        async fn increment_by(&self, code: &ShortCode, hits: u64) -> Result<()> {
+            if self.fail_next.fetch_xor(true, Ordering::SeqCst) {
+                return Err(StorageError::Unavailable("flaky".to_string()));
+            }
+            *self
+                .totals
+                .lock()
+                .unwrap()
+                .entry(code.as_str().to_string())
+                .or_default() += hits;
+            Ok(())
+        }
+    }
+
+    fn code(s: &str) -> ShortCode {
+        ShortCode::new_unchecked(s)
+    }
+
+    #[tokio::test]
+    async fn failed_flushes_keep_hits_until_they_succeed() {
+        let backend = Arc::new(FlakyCounter::default());
+        backend.fail_next.store(true, Ordering::SeqCst);
+        let counter = BufferedHitCounter::new(Arc::clone(&backend), Default::default());
+
+        for _ in 0..5 {
+            counter.record_hit(&code("abc"));
+        }
+        assert_eq!(counter.pending_hits(), 5);
+
+        // First attempt fails, hits stay buffered and keep accumulating.
+        assert_eq!(counter.flush().await, 0);
+        counter.record_hit(&code("abc"));
+        assert_eq!(counter.pending_hits(), 6);
+        assert_eq!(backend.total("abc"), 0);
+
+        // The retry succeeds with everything recorded so far.
+        assert_eq!(counter.flush().await, 6);
+        assert_eq!(counter.pending_hits(), 0);
+        assert_eq!(backend.total("abc"), 6);
+    }
+
+    #[tokio::test]
+    async fn full_buffer_drops_hits_for_new_codes() {
+        let backend = Arc::new(FlakyCounter::default());
+        let counter = BufferedHitCounter::new(
+            Arc::clone(&backend),
+            BufferedHitCounterConfig::builder()
+                .max_pending_codes(1)
+                .build(),
+        );
+
+        counter.record_hit(&code("abc"));
+        counter.record_hit(&code("abc"));
+        counter.record_hit(&code("def"));
+
+        assert_eq!(counter.pending_hits(), 2);
+        assert_eq!(counter.dropped_hits(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn flusher_eventually_delivers_hits() {
+        let backend = Arc::new(FlakyCounter::default());
+        backend.fail_next.store(true, Ordering::SeqCst);
+        let counter = BufferedHitCounter::new(Arc::clone(&backend), Default::default());
+        for _ in 0..3 {
+            counter.record_hit(&code("abc"));
+        }
+
+        let flusher = counter.spawn_flusher();
+        tokio::time::sleep(Duration::from_millis(2_500)).await;
+        flusher.abort();
+
+        assert_eq!(backend.total("abc"), 3);
+        assert_eq!(counter.pending_hits(), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn log_throttle_allows_one_event_per_interval() {
+        let throttle = LogThrottle::new(Duration::from_secs(60));
+
+        assert_eq!(throttle.check(), Some(0));
+        for _ in 0..10 {
+            assert_eq!(throttle.check(), None);
+        }
+
+        tokio::time::advance(Duration::from_secs(61)).await;
+        assert_eq!(throttle.check(), Some(10));
+        assert_eq!(throttle.check(), None);
+    }
+
+    #[tokio::test]
+    async fn repository_counter_accumulates_flushed_hits() {
+        use jiff::Timestamp;
+        use wormhole_core::{RedirectKind, UrlRecord};
+        use wormhole_storage::{InMemoryRepository, Repository};
+
+        let repo = InMemoryRepository::new();
+        let record = UrlRecord {
+            original_url: "https://example.com".to_string(),
+            expire_at: None,
+            redirect_kind: RedirectKind::default(),
+            created_at: Timestamp::now(),
+            internal_only: false,
+            no_store: false,
+            owner_id: None,
+            metadata: Default::default(),
+        };
+        repo.insert(&code("abc"), record).await.unwrap();
+        let counter =
+            BufferedHitCounter::new(RepositoryHitCounter::new(repo.clone()), Default::default());
+
+        for _ in 0..4 {
+            counter.record_hit(&code("abc"));
+        }
+        assert_eq!(counter.flush().await, 4);
+        counter.record_hit(&code("abc"));
+        assert_eq!(counter.flush().await, 1);
+
+        assert_eq!(repo.hits(&code("abc")).await.unwrap(), 5);
+    }
+}
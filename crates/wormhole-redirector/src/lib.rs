@@ -4,12 +4,25 @@
 //! to their original URLs. It uses the Repository decorator pattern to
 //! add transparent caching via either Redis or in-memory (Moka) caches.
 
+pub mod builder;
+pub mod cardinality;
 mod error;
+#[cfg(feature = "axum")]
+pub mod extract;
 pub mod grpc;
+pub mod hit_dedup;
+pub mod hits;
+pub mod maintenance;
+mod metrics;
 pub mod redirector;
 pub mod repository;
 pub mod service;
+pub mod tower_adapter;
 
+pub use builder::{BuildError, RedirectorBuilder};
 pub use error::{RedirectorError, Result};
+pub use maintenance::MaintenanceMode;
+pub use redirector::{CallerTrust, NotFoundReason, Resolution};
 pub use repository::CachedRepository;
-pub use service::RedirectorService;
+pub use service::{RedirectorService, ResolveOutcome};
+pub use tower_adapter::RedirectorTowerService;
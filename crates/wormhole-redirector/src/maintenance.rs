@@ -0,0 +1,51 @@
+//! Runtime switch for planned maintenance.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Shared maintenance flag for [`RedirectorService`](crate::RedirectorService).
+///
+/// While enabled, every resolve fails fast with
+/// [`RedirectorError::Maintenance`](crate::RedirectorError::Maintenance)
+/// without touching the cache or the database. Clones share the flag, so
+/// one handle can be given to the service and another to whatever flips it
+/// (an admin endpoint, a signal handler, ...).
+#[derive(Debug, Clone)]
+pub struct MaintenanceMode {
+    enabled: Arc<AtomicBool>,
+    retry_after: Duration,
+}
+
+impl MaintenanceMode {
+    /// Creates a disabled switch. `retry_after` is what clients are told to
+    /// wait before trying again.
+    pub fn new(retry_after: Duration) -> Self {
+        Self {
+            enabled: Arc::new(AtomicBool::new(false)),
+            retry_after,
+        }
+    }
+
+    pub fn enable(&self) {
+        self.enabled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn disable(&self) {
+        self.enabled.store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn retry_after(&self) -> Duration {
+        self.retry_after
+    }
+}
+
+impl Default for MaintenanceMode {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(60))
+    }
+}
@@ -0,0 +1,61 @@
+//! Hooks into `wormhole-metrics` that compile to nothing unless the `metrics`
+//! feature is enabled.
+
+use std::future::Future;
+
+#[cfg(feature = "metrics")]
+pub(crate) use wormhole_metrics::RedirectOutcome;
+
+/// Mirror of `wormhole_metrics::RedirectOutcome` so call sites need no `cfg`.
+#[cfg(not(feature = "metrics"))]
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum RedirectOutcome {
+    Hit,
+    Miss,
+    Expired,
+}
+
+#[cfg(feature = "metrics")]
+pub(crate) fn record_cache_lookup(layer: &str, hit: bool) {
+    wormhole_metrics::metrics().record_cache_lookup(layer, hit);
+}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) fn record_cache_lookup(_layer: &str, _hit: bool) {}
+
+#[cfg(feature = "metrics")]
+pub(crate) fn record_redirect(outcome: RedirectOutcome) {
+    wormhole_metrics::metrics().record_redirect(outcome);
+}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) fn record_redirect(_outcome: RedirectOutcome) {}
+
+#[cfg(feature = "metrics")]
+pub(crate) fn record_degraded_read() {
+    wormhole_metrics::metrics().record_degraded_read();
+}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) fn record_degraded_read() {}
+
+#[cfg(feature = "metrics")]
+pub(crate) fn set_cache_key_cardinality(estimate: u64) {
+    wormhole_metrics::metrics().set_cache_key_cardinality(estimate);
+}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) fn set_cache_key_cardinality(_estimate: u64) {}
+
+#[cfg(feature = "metrics")]
+pub(crate) async fn time_repository_fetch<T>(fetch: impl Future<Output = T>) -> T {
+    let start = std::time::Instant::now();
+    let output = fetch.await;
+    wormhole_metrics::metrics().observe_repository_fetch(start.elapsed());
+    output
+}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) async fn time_repository_fetch<T>(fetch: impl Future<Output = T>) -> T {
+    fetch.await
+}
@@ -2,9 +2,72 @@ use crate::Result;
 use async_trait::async_trait;
 use wormhole_core::{ShortCode, UrlRecord};
 
+/// Whether the caller of a resolve is inside the trust boundary.
+///
+/// Records marked [`UrlRecord::internal_only`] only resolve for trusted
+/// callers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CallerTrust {
+    /// An internal caller, allowed to resolve internal-only codes.
+    Trusted,
+    /// Any other caller.
+    #[default]
+    Untrusted,
+}
+
+/// Why a short code did not resolve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotFoundReason {
+    /// No further detail is available, or it must not be disclosed.
+    Unknown,
+    /// The code was never created.
+    NeverExisted,
+    /// The code existed but has expired.
+    Expired,
+    /// The code was deleted.
+    Deleted,
+}
+
+/// The outcome of [`Redirector::resolve_detailed`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Resolution {
+    /// The code resolved to this record.
+    Found(UrlRecord),
+    /// The code did not resolve.
+    NotFound(NotFoundReason),
+}
+
 #[async_trait]
 pub trait Redirector: Send + Sync + 'static {
     /// Resolves a short code to its stored URL record.
     /// Returns `None` if the code does not exist or has expired.
+    ///
+    /// This does not apply any caller restrictions; callers serving requests
+    /// from outside the trust boundary should use [`Redirector::resolve_for`].
     async fn resolve(&self, code: &ShortCode) -> Result<Option<UrlRecord>>;
+
+    /// Resolves a short code on behalf of a caller with the given trust.
+    ///
+    /// Internal-only records are reported as missing to untrusted callers, so
+    /// their existence is not revealed.
+    ///
+    /// The default implementation filters the result of
+    /// [`Redirector::resolve`], so any hit that records is counted before
+    /// the filter; implementations that count hits should override it.
+    async fn resolve_for(&self, code: &ShortCode, trust: CallerTrust) -> Result<Option<UrlRecord>> {
+        let record = self.resolve(code).await?;
+        Ok(record.filter(|record| !record.internal_only || trust == CallerTrust::Trusted))
+    }
+
+    /// Like [`Redirector::resolve_for`], but explains why a code did not
+    /// resolve.
+    ///
+    /// The default implementation cannot tell and reports
+    /// [`NotFoundReason::Unknown`].
+    async fn resolve_detailed(&self, code: &ShortCode, trust: CallerTrust) -> Result<Resolution> {
+        Ok(match self.resolve_for(code, trust).await? {
+            Some(record) => Resolution::Found(record),
+            None => Resolution::NotFound(NotFoundReason::Unknown),
+        })
+    }
 }
@@ -1,8 +1,13 @@
+use crate::metrics;
 use async_trait::async_trait;
-use tracing::{debug, trace};
-use wormhole_cache::{CacheError, UrlCache};
-use wormhole_core::{ShortCode, UrlRecord};
-use wormhole_storage::{ReadRepository, StorageError};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Semaphore;
+use tracing::field::Empty;
+use tracing::{debug, instrument, trace, warn, Instrument, Span};
+use wormhole_cache::{CacheError, MokaExistenceCache, UrlCache};
+use wormhole_core::{AliasPolicy, ShortCode, UrlRecord};
+use wormhole_storage::{CodeStatus, Lookup, ReadRepository, ScanCursor, ScanPage, StorageError};
 
 /// Type alias for repository results.
 pub type Result<T> = std::result::Result<T, StorageError>;
@@ -13,10 +18,31 @@ pub type Result<T> = std::result::Result<T, StorageError>;
 /// implementation to provide transparent caching. Read operations check the
 /// cache first, falling back to the inner repository. Successful reads from
 /// the inner repository are cached.
+///
+/// Codes that fail [`ShortCode::is_valid_with`] under the alias policy (see
+/// [`CachedRepository::with_alias_policy`]) are rejected up front, without
+/// touching the cache or the inner repository. Caches that remember misses
+/// (e.g. [`wormhole_cache::MokaUrlCache`], which keeps the `None` computed by
+/// `get_or_compute`) therefore only hold tombstones for well-formed codes,
+/// and a flood of garbage codes cannot push real entries out.
+///
+/// Records marked [`UrlRecord::no_store`] are never written to the cache:
+/// every read of such a code goes to the inner repository.
+///
+/// Existence checks can optionally be served from a separate
+/// [`MokaExistenceCache`] (see [`CachedRepository::with_exists_cache`]) so
+/// that conflict-check-heavy workloads do not compete with resolved records
+/// for space in the value cache.
+///
+/// The number of concurrent reads reaching the inner repository can be
+/// capped with [`CachedRepository::with_inner_concurrency`].
 #[derive(Debug, Clone)]
 pub struct CachedRepository<R, C> {
     inner: R,
     cache: C,
+    exists_cache: Option<MokaExistenceCache>,
+    inner_permits: Option<Arc<Semaphore>>,
+    alias_policy: AliasPolicy,
 }
 
 impl<R: ReadRepository, C: UrlCache> CachedRepository<R, C> {
@@ -42,7 +68,50 @@ impl<R: ReadRepository, C: UrlCache> CachedRepository<R, C> {
     /// # }
     /// ```
     pub fn new(inner: R, cache: C) -> Self {
-        Self { inner, cache }
+        Self {
+            inner,
+            cache,
+            exists_cache: None,
+            inner_permits: None,
+            alias_policy: AliasPolicy::default(),
+        }
+    }
+
+    /// Accepts codes of the lengths `policy` allows for aliases.
+    ///
+    /// Use the policy the shortener creates aliases with, or aliases
+    /// outside the default bounds are rejected as malformed.
+    pub fn with_alias_policy(mut self, policy: AliasPolicy) -> Self {
+        self.alias_policy = policy;
+        self
+    }
+
+    /// Serves [`ReadRepository::exists`] from a dedicated existence cache.
+    ///
+    /// With this enabled, existence checks no longer consult the value cache
+    /// at all: answers come from `exists_cache`, falling back to the inner
+    /// repository on a miss. Negative answers expire after the existence
+    /// cache's negative TTL, so codes created elsewhere are soon visible.
+    pub fn with_exists_cache(mut self, exists_cache: MokaExistenceCache) -> Self {
+        self.exists_cache = Some(exists_cache);
+        self
+    }
+
+    /// Allows at most `limit` concurrent `get`/`exists` calls on the inner
+    /// repository.
+    ///
+    /// Single-flight only merges fetches of the same code; a miss storm over
+    /// many distinct codes still fans out one inner read per code. With a
+    /// limit, reads beyond it queue until a permit frees up instead of
+    /// failing, so the backend sees bounded load and callers see latency.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `limit` is zero.
+    pub fn with_inner_concurrency(mut self, limit: usize) -> Self {
+        assert!(limit > 0, "inner concurrency limit must be positive");
+        self.inner_permits = Some(Arc::new(Semaphore::new(limit)));
+        self
     }
 
     /// Returns a reference to the inner repository.
@@ -55,66 +124,310 @@ impl<R: ReadRepository, C: UrlCache> CachedRepository<R, C> {
         &self.cache
     }
 
+    /// Returns a reference to the existence cache, if one is configured.
+    pub fn exists_cache(&self) -> Option<&MokaExistenceCache> {
+        self.exists_cache.as_ref()
+    }
+
+    /// Reads `code` from the inner repository, within the concurrency limit.
+    async fn inner_get(&self, code: &ShortCode) -> Result<Option<UrlRecord>> {
+        let _permit = self.acquire_inner_permit().await;
+        self.inner.get(code).await
+    }
+
+    /// Checks `code` in the inner repository, within the concurrency limit.
+    async fn inner_exists(&self, code: &ShortCode) -> Result<bool> {
+        let _permit = self.acquire_inner_permit().await;
+        self.inner.exists(code).await
+    }
+
+    /// Reads the status of `code` from the inner repository, within the
+    /// concurrency limit.
+    async fn inner_status(&self, code: &ShortCode) -> Result<CodeStatus> {
+        let _permit = self.acquire_inner_permit().await;
+        self.inner.status(code).await
+    }
+
+    async fn acquire_inner_permit(&self) -> Option<tokio::sync::SemaphorePermit<'_>> {
+        let permits = self.inner_permits.as_ref()?;
+        // The semaphore is never closed, so acquiring cannot fail.
+        permits.acquire().await.ok()
+    }
+
     /// Invalidate a cached entry.
     ///
     /// This is useful when the underlying data may have changed
     /// and you want to ensure the next read fetches fresh data.
     pub async fn invalidate(&self, code: &ShortCode) -> Result<()> {
         trace!(code = %code, "Invalidating cache entry");
+        if let Some(exists_cache) = &self.exists_cache {
+            exists_cache.invalidate(code).await;
+        }
         self.cache.del(code).await.map_err(StorageError::Cache)
     }
+
+    /// Invalidate several cached entries at once, e.g. after a bulk import.
+    ///
+    /// Uses [`UrlCache::del_many`], so caches that support it remove all
+    /// entries in one round trip. Empty input is a no-op.
+    pub async fn invalidate_many(&self, codes: &[ShortCode]) -> Result<()> {
+        if codes.is_empty() {
+            return Ok(());
+        }
+
+        trace!(count = codes.len(), "Invalidating cache entries");
+        if let Some(exists_cache) = &self.exists_cache {
+            for code in codes {
+                exists_cache.invalidate(code).await;
+            }
+        }
+        self.cache
+            .del_many(codes)
+            .await
+            .map_err(StorageError::Cache)
+    }
 }
 
 #[async_trait]
 impl<R: ReadRepository, C: UrlCache> ReadRepository for CachedRepository<R, C> {
     async fn get(&self, code: &ShortCode) -> Result<Option<UrlRecord>> {
+        Ok(self.lookup(code).await?.record)
+    }
+
+    /// Reads through the cache, falling back to the inner repository when
+    /// the cache fails.
+    ///
+    /// A cache error is logged and the record read from the inner repository
+    /// directly; the answer is then reported as degraded. Errors from the
+    /// inner repository itself are returned as they are.
+    ///
+    /// Runs in a `repository.lookup` span whose `outcome` is `hit`, `miss`,
+    /// `degraded`, `bypass` (a `no_store` record), `rejected` or `error`.
+    /// The cache layers add their own spans beneath it, and a read from the
+    /// inner repository shows up as a `repository.inner` span.
+    #[instrument(
+        name = "repository.lookup",
+        level = "debug",
+        skip_all,
+        fields(code = %code, outcome = Empty)
+    )]
+    async fn lookup(&self, code: &ShortCode) -> Result<Lookup> {
+        if !code.is_valid_with(&self.alias_policy) {
+            trace!(code = %code, "Rejecting malformed short code");
+            Span::current().record("outcome", "rejected");
+            return Ok(Lookup {
+                record: None,
+                degraded: false,
+            });
+        }
+
         trace!(code = %code, "Fetching URL record with cache");
 
+        // The cache only tells us about a miss by calling the fetch closure, so
+        // we note that here to report hit/miss metrics.
+        let missed = AtomicBool::new(false);
+        let missed_ref = &missed;
+        // The cache wraps fetch errors in its own type; keep the original so
+        // a failing inner repository is not mistaken for a failing cache.
+        let fetch_error = Mutex::new(None);
+        let fetch_error_ref = &fetch_error;
+
         // Use get_or_compute for single-flight semantics:
         // concurrent requests for the same key will coalesce into a single fetch
-        self.cache
+        let result = self
+            .cache
             .get_or_compute(code, move |c| {
                 let code = c.clone();
                 async move {
                     trace!(code = %code, "Cache miss, fetching from inner repository");
-                    self.inner
-                        .get(&code)
-                        .await
-                        .map_err(|e| CacheError::Operation(format!("repository fetch failed: {e}")))
+                    missed_ref.store(true, Ordering::Relaxed);
+                    let span = tracing::debug_span!(
+                        "repository.inner",
+                        layer = "inner",
+                        code = %code,
+                        outcome = Empty
+                    );
+                    let record = metrics::time_repository_fetch(self.inner_get(&code))
+                        .instrument(span.clone())
+                        .await;
+                    span.record(
+                        "outcome",
+                        match &record {
+                            Ok(Some(_)) => "hit",
+                            Ok(None) => "miss",
+                            Err(_) => "error",
+                        },
+                    );
+                    let record = record.map_err(|e| {
+                        let message = format!("repository fetch failed: {e}");
+                        *fetch_error_ref.lock().unwrap_or_else(|e| e.into_inner()) = Some(e);
+                        CacheError::Operation(message)
+                    })?;
+                    // `get_or_compute` caches whatever the fetch returns but
+                    // never an error, so this keeps the record out of it.
+                    match record {
+                        Some(record) if record.no_store => {
+                            Err(CacheError::Uncacheable(Box::new(record)))
+                        }
+                        record => Ok(record),
+                    }
                 }
             })
-            .await
-            .map_err(StorageError::Cache)
+            .await;
+
+        metrics::record_cache_lookup("repository", !missed.load(Ordering::Relaxed));
+        let record = match result {
+            Ok(record) => record,
+            Err(CacheError::Uncacheable(record)) => {
+                trace!(code = %code, "Serving no_store record without caching it");
+                Span::current().record("outcome", "bypass");
+                Some(*record)
+            }
+            Err(error) => {
+                if let Some(error) = fetch_error.into_inner().unwrap_or_else(|e| e.into_inner()) {
+                    Span::current().record("outcome", "error");
+                    return Err(error);
+                }
+                warn!(code = %code, error = %error, "Cache failed, reading from the inner repository");
+                Span::current().record("outcome", "degraded");
+                metrics::record_degraded_read();
+                return Ok(Lookup {
+                    record: self.inner_get(code).await?,
+                    degraded: true,
+                });
+            }
+        };
+
+        let outcome = if missed.load(Ordering::Relaxed) {
+            "miss"
+        } else {
+            "hit"
+        };
+        Span::current().record("outcome", outcome);
+        Ok(Lookup {
+            record,
+            degraded: false,
+        })
+    }
+
+    /// Reads the inner repository directly, leaving the cache untouched.
+    async fn peek(&self, code: &ShortCode) -> Result<Option<UrlRecord>> {
+        if !code.is_valid_with(&self.alias_policy) {
+            trace!(code = %code, "Rejecting malformed short code");
+            return Ok(None);
+        }
+        self.inner_get(code).await
     }
 
     async fn exists(&self, code: &ShortCode) -> Result<bool> {
-        trace!(code = %code, "Checking existence via get");
+        if !code.is_valid_with(&self.alias_policy) {
+            trace!(code = %code, "Rejecting malformed short code");
+            return Ok(false);
+        }
 
-        // Use get_url for existence check - if it returns Some, it exists
-        match self
-            .cache
-            .get_url(code)
-            .await
-            .map_err(StorageError::Cache)?
-        {
-            Some(_) => {
-                debug!(code = %code, "Cache hit indicates code exists");
-                return Ok(true);
-            }
-            None => {
-                trace!(code = %code, "Cache miss for existence check");
+        if let Some(exists_cache) = &self.exists_cache {
+            if let Some(exists) = exists_cache.get(code).await {
+                return Ok(exists);
             }
+
+            let exists = self.inner_exists(code).await?;
+            exists_cache.insert(code, exists).await;
+            return Ok(exists);
+        }
+
+        trace!(code = %code, "Checking existence in cache");
+
+        if self.cache.exists(code).await.map_err(StorageError::Cache)? {
+            debug!(code = %code, "Cache hit indicates code exists");
+            return Ok(true);
         }
+        trace!(code = %code, "Cache miss for existence check");
 
         // Fall back to inner repository
-        self.inner.exists(code).await
+        self.inner_exists(code).await
+    }
+
+    /// Answers from the cache when it holds the code: a cached record is
+    /// active and a cached tombstone is reported as not found. Only a real
+    /// cache miss asks the inner repository, which knows why a code is
+    /// inactive; an active record it returns is cached as [`get`] would.
+    ///
+    /// A cache error falls back to the inner repository, like [`lookup`].
+    ///
+    /// [`get`]: ReadRepository::get
+    /// [`lookup`]: ReadRepository::lookup
+    async fn status(&self, code: &ShortCode) -> Result<CodeStatus> {
+        if !code.is_valid_with(&self.alias_policy) {
+            return Ok(CodeStatus::NotFound);
+        }
+
+        // The fetch closure keeps the full status, so a miss costs one inner
+        // call and an inactive code is not looked up twice.
+        let fetched = Mutex::new(None);
+        let fetched_ref = &fetched;
+        let result = self
+            .cache
+            .get_or_compute(code, move |c| {
+                let code = c.clone();
+                async move {
+                    let status = self.inner_status(&code).await;
+                    let record = match &status {
+                        Ok(CodeStatus::Active(record)) if record.no_store => {
+                            Err(CacheError::Uncacheable(Box::new(record.clone())))
+                        }
+                        Ok(CodeStatus::Active(record)) => Ok(Some(record.clone())),
+                        Ok(_) => Ok(None),
+                        Err(e) => Err(CacheError::Operation(format!(
+                            "repository fetch failed: {e}"
+                        ))),
+                    };
+                    *fetched_ref.lock().unwrap_or_else(|e| e.into_inner()) = Some(status);
+                    record
+                }
+            })
+            .await;
+
+        if let Some(status) = fetched.into_inner().unwrap_or_else(|e| e.into_inner()) {
+            return status;
+        }
+        match result {
+            Ok(Some(record)) => Ok(CodeStatus::Active(record)),
+            Ok(None) => Ok(CodeStatus::NotFound),
+            Err(error) => {
+                warn!(code = %code, error = %error, "Cache failed, reading status from the inner repository");
+                metrics::record_degraded_read();
+                self.inner_status(code).await
+            }
+        }
+    }
+
+    /// Scans go straight to the inner repository: a scan wants the complete
+    /// set of codes, which the cache cannot provide.
+    async fn scan(&self, cursor: Option<ScanCursor>, limit: usize) -> Result<ScanPage> {
+        self.inner.scan(cursor, limit).await
+    }
+
+    async fn scan_owned_by(
+        &self,
+        owner_id: &str,
+        cursor: Option<ScanCursor>,
+        limit: usize,
+    ) -> Result<ScanPage> {
+        self.inner.scan_owned_by(owner_id, cursor, limit).await
+    }
+
+    /// Pings the inner repository only; the cache is not checked.
+    async fn ping(&self) -> Result<()> {
+        self.inner.ping().await
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use jiff::Timestamp;
     use wormhole_cache::MokaUrlCache;
+    use wormhole_core::RedirectKind;
     use wormhole_storage::{InMemoryRepository, Repository};
 
     fn code(s: &str) -> ShortCode {
@@ -125,6 +438,12 @@ mod tests {
         UrlRecord {
             original_url: url.to_string(),
             expire_at: None,
+            redirect_kind: RedirectKind::default(),
+            created_at: Timestamp::now(),
+            internal_only: false,
+            no_store: false,
+            owner_id: None,
+            metadata: Default::default(),
         }
     }
 
@@ -175,7 +494,7 @@ mod tests {
         // Pre-populate cache
         cache.set_url(&c, &record).await.unwrap();
 
-        // Should return true from cache via get_url
+        // Should return true from cache via the cache
         assert!(cached.exists(&c).await.unwrap());
     }
 
@@ -226,6 +545,153 @@ mod tests {
         assert!(cache.get_url(&c).await.unwrap().is_none());
     }
 
+    #[tokio::test]
+    async fn exists_cache_serves_repeated_checks() {
+        let (cached, _cache) = test_service();
+        let cached = cached.with_exists_cache(MokaExistenceCache::new());
+        let c = code("abc123");
+
+        cached
+            .inner()
+            .insert(&c, test_record("https://example.com"))
+            .await
+            .unwrap();
+        assert!(cached.exists(&c).await.unwrap());
+
+        // Remove from inner; the existence cache should still answer.
+        cached.inner().delete(&c).await.unwrap();
+        assert!(cached.exists(&c).await.unwrap());
+
+        // Invalidation clears the existence cache as well.
+        cached.invalidate(&c).await.unwrap();
+        assert!(!cached.exists(&c).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn heavy_exists_traffic_does_not_evict_cached_values() {
+        let inner = InMemoryRepository::new();
+        let cache = MokaUrlCache::with_capacity(16);
+        let cached = CachedRepository::new(inner, cache.clone())
+            .with_exists_cache(MokaExistenceCache::with_capacity(16));
+
+        let hot = code("hot-code");
+        let record = test_record("https://example.com");
+        cached.inner().insert(&hot, record.clone()).await.unwrap();
+        assert_eq!(cached.get(&hot).await.unwrap(), Some(record.clone()));
+
+        for i in 0..1_000 {
+            let probe = code(&format!("probe-{i}"));
+            cached
+                .inner()
+                .insert(&probe, test_record("https://example.com/probe"))
+                .await
+                .unwrap();
+            assert!(cached.exists(&probe).await.unwrap());
+        }
+
+        assert_eq!(cache.get_url(&hot).await.unwrap(), Some(record));
+    }
+
+    #[tokio::test]
+    async fn invalidate_many_removes_only_listed_entries() {
+        let (cached, cache) = test_service();
+        let codes: Vec<_> = (0..5).map(|i| code(&format!("bulk{i}"))).collect();
+        let keep = code("keep");
+
+        for c in codes.iter().chain([&keep]) {
+            cache
+                .set_url(c, &test_record("https://example.com"))
+                .await
+                .unwrap();
+        }
+
+        cached.invalidate_many(&codes).await.unwrap();
+
+        for c in &codes {
+            assert!(cache.get_url(c).await.unwrap().is_none());
+        }
+        assert!(cache.get_url(&keep).await.unwrap().is_some());
+
+        // Empty input is a no-op.
+        cached.invalidate_many(&[]).await.unwrap();
+        assert!(cache.get_url(&keep).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn malformed_codes_leave_no_tombstones() {
+        let (cached, cache) = test_service();
+
+        for i in 0..1_000 {
+            let garbage = code(&format!("bad code/{i}"));
+            assert_eq!(cached.get(&garbage).await.unwrap(), None);
+            assert!(!cached.exists(&garbage).await.unwrap());
+        }
+        assert_eq!(cached.get(&code("x")).await.unwrap(), None);
+
+        assert_eq!(cache.entry_count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn missing_valid_codes_are_cached_as_tombstones() {
+        let (cached, cache) = test_service();
+
+        for i in 0..10 {
+            assert_eq!(
+                cached.get(&code(&format!("missing{i}"))).await.unwrap(),
+                None
+            );
+        }
+
+        assert_eq!(cache.entry_count().await, 10);
+    }
+
+    #[tokio::test]
+    async fn short_aliases_resolve_under_the_alias_policy() {
+        let (cached, _cache) = test_service();
+        let c = code("ab");
+        cached
+            .inner()
+            .insert(&c, test_record("https://example.com"))
+            .await
+            .unwrap();
+
+        assert_eq!(cached.get(&c).await.unwrap(), None);
+
+        let cached = cached.with_alias_policy(AliasPolicy::new().with_length(2, 32).unwrap());
+        assert!(cached.get(&c).await.unwrap().is_some());
+        assert!(cached.exists(&c).await.unwrap());
+        assert!(cached.peek(&c).await.unwrap().is_some());
+        assert!(matches!(
+            cached.status(&c).await.unwrap(),
+            CodeStatus::Active(_)
+        ));
+        assert_eq!(cached.get(&code("a")).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn status_answers_from_the_cache_before_the_inner_repository() {
+        let (cached, cache) = test_service();
+        let c = code("abc123");
+
+        // A miss through `get` leaves a tombstone, which `status` honours
+        // even after the inner repository learns about the code.
+        assert_eq!(cached.get(&c).await.unwrap(), None);
+        cached
+            .inner()
+            .insert(&c, test_record("https://example.com"))
+            .await
+            .unwrap();
+        assert_eq!(cached.status(&c).await.unwrap(), CodeStatus::NotFound);
+
+        // A real miss asks the inner repository and caches the record.
+        cached.invalidate(&c).await.unwrap();
+        assert!(matches!(
+            cached.status(&c).await.unwrap(),
+            CodeStatus::Active(_)
+        ));
+        assert!(cache.get_url(&c).await.unwrap().is_some());
+    }
+
     #[tokio::test]
     async fn invalidate_is_idempotent() {
         let (cached, _cache) = test_service();
@@ -234,4 +700,142 @@ mod tests {
         // Invalidate non-existent key should not error
         cached.invalidate(&c).await.unwrap();
     }
+
+    #[tokio::test]
+    async fn no_store_records_are_never_cached() {
+        let repo = InMemoryRepository::new();
+        let token = code("onetime");
+        let normal = code("normal");
+        let mut token_record = test_record("https://example.com/token");
+        token_record.no_store = true;
+        repo.insert(&token, token_record.clone()).await.unwrap();
+        repo.insert(&normal, test_record("https://example.com"))
+            .await
+            .unwrap();
+
+        let cache = MokaUrlCache::new();
+        let cached_repo = CachedRepository::new(repo, cache.clone());
+
+        for _ in 0..3 {
+            assert_eq!(
+                cached_repo.get(&token).await.unwrap(),
+                Some(token_record.clone())
+            );
+            assert!(cache.get_url(&token).await.unwrap().is_none());
+        }
+
+        cached_repo.get(&normal).await.unwrap();
+        assert!(cache.get_url(&normal).await.unwrap().is_some());
+        assert_eq!(cache.entry_count().await, 1);
+    }
+
+    #[tokio::test]
+    async fn no_store_reads_do_not_trip_a_circuit_breaker() {
+        use wormhole_cache::{BreakerState, CircuitBreakerCache, CircuitBreakerConfig};
+
+        let repo = InMemoryRepository::new();
+        let token = code("onetime");
+        let mut token_record = test_record("https://example.com/token");
+        token_record.no_store = true;
+        repo.insert(&token, token_record.clone()).await.unwrap();
+
+        let cache = CircuitBreakerCache::new(
+            MokaUrlCache::new(),
+            CircuitBreakerConfig::builder().failure_threshold(2).build(),
+        );
+        let cached_repo = CachedRepository::new(repo, cache);
+
+        for _ in 0..5 {
+            let lookup = cached_repo.lookup(&token).await.unwrap();
+            assert_eq!(lookup.record, Some(token_record.clone()));
+            assert!(!lookup.degraded);
+        }
+        assert_eq!(cached_repo.cache().state(), BreakerState::Closed);
+    }
+
+    /// Tracks how many `get`/`exists` calls are in flight at once.
+    #[derive(Debug, Default)]
+    struct GaugedRepository {
+        inner: InMemoryRepository,
+        in_flight: std::sync::atomic::AtomicUsize,
+        peak: std::sync::atomic::AtomicUsize,
+    }
+
+    impl GaugedRepository {
+        async fn gauge<T>(&self, read: impl std::future::Future<Output = T>) -> T {
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            let result = read.await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            result
+        }
+    }
+
+    #[async_trait]
+    impl ReadRepository for GaugedRepository {
+        async fn get(&self, code: &ShortCode) -> Result<Option<UrlRecord>> {
+            self.gauge(self.inner.get(code)).await
+        }
+
+        async fn exists(&self, code: &ShortCode) -> Result<bool> {
+            self.gauge(self.inner.exists(code)).await
+        }
+
+        async fn status(&self, code: &ShortCode) -> Result<CodeStatus> {
+            self.inner.status(code).await
+        }
+
+        async fn scan(&self, cursor: Option<ScanCursor>, limit: usize) -> Result<ScanPage> {
+            self.inner.scan(cursor, limit).await
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn inner_concurrency_caps_parallel_inner_reads() {
+        let cached = Arc::new(
+            CachedRepository::new(GaugedRepository::default(), MokaUrlCache::new())
+                .with_inner_concurrency(3),
+        );
+
+        let handles: Vec<_> = (0..20)
+            .map(|i| {
+                let cached = Arc::clone(&cached);
+                tokio::spawn(async move {
+                    let c = code(&format!("storm{i}"));
+                    if i % 2 == 0 {
+                        cached.get(&c).await.unwrap();
+                    } else {
+                        cached.exists(&c).await.unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        assert_eq!(cached.inner().peak.load(Ordering::SeqCst), 3);
+        assert_eq!(cached.inner().in_flight.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn inner_reads_are_unbounded_by_default() {
+        let cached = Arc::new(CachedRepository::new(
+            GaugedRepository::default(),
+            MokaUrlCache::new(),
+        ));
+
+        let handles: Vec<_> = (0..20)
+            .map(|i| {
+                let cached = Arc::clone(&cached);
+                tokio::spawn(async move { cached.get(&code(&format!("storm{i}"))).await.unwrap() })
+            })
+            .collect();
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        assert_eq!(cached.inner().peak.load(Ordering::SeqCst), 20);
+    }
 }
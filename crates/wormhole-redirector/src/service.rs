@@ -1,11 +1,26 @@
 use std::sync::Arc;
 
-use crate::redirector::Redirector;
+use crate::cardinality::KeyCardinality;
+use crate::hits::HitSink;
+use crate::maintenance::MaintenanceMode;
+use crate::metrics::{self, RedirectOutcome};
+use crate::redirector::{CallerTrust, NotFoundReason, Redirector, Resolution};
 use async_trait::async_trait;
-use jiff::Timestamp;
-use tracing::{debug, trace};
-use wormhole_core::{ShortCode, UrlRecord};
-use wormhole_storage::ReadRepository;
+use tracing::field::Empty;
+use tracing::{debug, instrument, trace, Span};
+use wormhole_core::{Clock, SharedClock, ShortCode, UrlRecord};
+use wormhole_storage::{CodeStatus, ReadRepository};
+
+/// The result of [`RedirectorService::resolve_outcome`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolveOutcome {
+    /// The live record, or `None` if the code does not exist or has expired.
+    pub record: Option<UrlRecord>,
+    /// Whether the repository answered from a fallback path, e.g. because
+    /// the cache was failing. The record is still correct, but the service
+    /// is running without its cache.
+    pub served_degraded: bool,
+}
 
 /// Service for handling URL redirects.
 ///
@@ -13,6 +28,10 @@ use wormhole_storage::ReadRepository;
 #[derive(Debug, Clone)]
 pub struct RedirectorService<R> {
     repository: Arc<R>,
+    hit_sink: Option<Arc<dyn HitSink>>,
+    key_cardinality: Option<Arc<KeyCardinality>>,
+    maintenance: Option<MaintenanceMode>,
+    clock: SharedClock,
 }
 
 impl<R: ReadRepository> RedirectorService<R> {
@@ -20,6 +39,50 @@ impl<R: ReadRepository> RedirectorService<R> {
     pub fn new(repository: R) -> Self {
         Self {
             repository: Arc::new(repository),
+            hit_sink: None,
+            key_cardinality: None,
+            maintenance: None,
+            clock: SharedClock::default(),
+        }
+    }
+
+    /// Decides expiry by `clock` instead of the system clock, e.g. to let
+    /// tests move time.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = SharedClock::new(clock);
+        self
+    }
+
+    /// Reports every successful resolve to `sink`, e.g. for click counting.
+    pub fn with_hit_sink(mut self, sink: impl HitSink) -> Self {
+        self.hit_sink = Some(Arc::new(sink));
+        self
+    }
+
+    /// Feeds every looked-up code, found or not, to `estimator`.
+    pub fn with_key_cardinality(mut self, estimator: Arc<KeyCardinality>) -> Self {
+        self.key_cardinality = Some(estimator);
+        self
+    }
+
+    /// Refuses every resolve while `mode` is enabled.
+    pub fn with_maintenance(mut self, mode: MaintenanceMode) -> Self {
+        self.maintenance = Some(mode);
+        self
+    }
+
+    fn check_maintenance(&self) -> crate::Result<()> {
+        match &self.maintenance {
+            Some(mode) if mode.is_enabled() => Err(crate::RedirectorError::Maintenance {
+                retry_after: mode.retry_after(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn observe_key(&self, code: &ShortCode) {
+        if let Some(estimator) = &self.key_cardinality {
+            estimator.observe(code);
         }
     }
 
@@ -27,6 +90,10 @@ impl<R: ReadRepository> RedirectorService<R> {
     ///
     /// Returns `None` if the code doesn't exist or has expired.
     ///
+    /// Like [`Redirector::resolve`], this resolves internal-only codes too;
+    /// use [`Redirector::resolve_for`] for callers outside the trust
+    /// boundary.
+    ///
     /// # Arguments
     ///
     /// * `code` - The short code to resolve
@@ -39,44 +106,184 @@ impl<R: ReadRepository> RedirectorService<R> {
     pub async fn resolve(&self, code: &ShortCode) -> crate::Result<Option<UrlRecord>> {
         Redirector::resolve(self, code).await
     }
-}
 
-#[async_trait]
-impl<R: ReadRepository> Redirector for RedirectorService<R> {
-    async fn resolve(&self, code: &ShortCode) -> crate::Result<Option<UrlRecord>> {
+    /// Resolves a short code on behalf of a caller with the given trust,
+    /// also reporting whether the answer was served degraded, e.g. read from
+    /// the origin because the cache was failing.
+    ///
+    /// Internal-only records are reported as missing to untrusted callers,
+    /// and such a lookup counts as a miss, not a hit.
+    ///
+    /// Runs in a `redirector.resolve` span carrying the `code` and the
+    /// `outcome` (`hit`, `miss`, `expired`, `maintenance` or `error`), so
+    /// the cache and repository spans of one resolve nest under it. The
+    /// resolved URL is only logged at debug level.
+    #[instrument(name = "redirector.resolve", skip_all, fields(code = %code, outcome = Empty))]
+    pub async fn resolve_outcome(
+        &self,
+        code: &ShortCode,
+        trust: CallerTrust,
+    ) -> crate::Result<ResolveOutcome> {
         trace!(code = %code, "resolving short code");
+        if let Err(e) = self.check_maintenance() {
+            Span::current().record("outcome", "maintenance");
+            return Err(e);
+        }
+        self.observe_key(code);
 
-        match self
-            .repository
-            .get(code)
-            .await
-            .map_err(crate::RedirectorError::from)?
-        {
+        let lookup = match self.repository.lookup(code).await {
+            Ok(lookup) => lookup,
+            Err(e) => {
+                Span::current().record("outcome", "error");
+                return Err(e.into());
+            }
+        };
+        let served_degraded = lookup.degraded;
+
+        let record = match lookup.record {
+            // Do not reveal that an internal-only code exists.
+            Some(record) if record.internal_only && trust != CallerTrust::Trusted => {
+                trace!(code = %code, "Internal-only code requested by untrusted caller");
+                metrics::record_redirect(RedirectOutcome::Miss);
+                Span::current().record("outcome", "miss");
+                None
+            }
             Some(record) => {
                 // Check expiration
                 if let Some(expire_at) = record.expire_at {
-                    if Timestamp::now() >= expire_at {
+                    if self.clock.now() >= expire_at {
                         debug!(code = %code, "Record has expired");
-                        return Ok(None);
+                        metrics::record_redirect(RedirectOutcome::Expired);
+                        Span::current().record("outcome", "expired");
+                        return Ok(ResolveOutcome {
+                            record: None,
+                            served_degraded,
+                        });
                     }
                 }
 
                 debug!(code = %code, url = %record.original_url, "Resolved short code");
-                Ok(Some(record))
+                metrics::record_redirect(RedirectOutcome::Hit);
+                Span::current().record("outcome", "hit");
+                if let Some(sink) = &self.hit_sink {
+                    sink.record_hit(code);
+                }
+                Some(record)
             }
             None => {
                 trace!(code = %code, "Short code not found");
-                Ok(None)
+                metrics::record_redirect(RedirectOutcome::Miss);
+                Span::current().record("outcome", "miss");
+                None
             }
+        };
+
+        Ok(ResolveOutcome {
+            record,
+            served_degraded,
+        })
+    }
+
+    /// Looks up a short code without counting it as a visit, e.g. for admin
+    /// previews and link checkers.
+    ///
+    /// Unlike [`RedirectorService::resolve`], no hit is recorded, the code is
+    /// not fed to the key cardinality estimator, maintenance mode is ignored,
+    /// and the read goes through [`ReadRepository::peek`], so a cached
+    /// repository neither consults nor fills its cache. Expired records are
+    /// still reported as `None`.
+    pub async fn peek(&self, code: &ShortCode) -> crate::Result<Option<UrlRecord>> {
+        let record = self.repository.peek(code).await?;
+        Ok(record.filter(|record| !self.clock.is_expired(record.expire_at)))
+    }
+}
+
+#[async_trait]
+impl<R: ReadRepository> Redirector for RedirectorService<R> {
+    async fn resolve(&self, code: &ShortCode) -> crate::Result<Option<UrlRecord>> {
+        Ok(self
+            .resolve_outcome(code, CallerTrust::Trusted)
+            .await?
+            .record)
+    }
+
+    /// Checks trust before recording the hit, so an untrusted caller asking
+    /// for an internal-only code counts as a miss.
+    async fn resolve_for(
+        &self,
+        code: &ShortCode,
+        trust: CallerTrust,
+    ) -> crate::Result<Option<UrlRecord>> {
+        Ok(self.resolve_outcome(code, trust).await?.record)
+    }
+
+    /// Runs in the same `redirector.resolve` span as
+    /// [`RedirectorService::resolve_outcome`]. Degraded reads are reported by
+    /// the repository, e.g. [`crate::CachedRepository`] falling back to the
+    /// origin when its cache fails.
+    #[instrument(name = "redirector.resolve", skip_all, fields(code = %code, outcome = Empty))]
+    async fn resolve_detailed(
+        &self,
+        code: &ShortCode,
+        trust: CallerTrust,
+    ) -> crate::Result<Resolution> {
+        if let Err(e) = self.check_maintenance() {
+            Span::current().record("outcome", "maintenance");
+            return Err(e);
         }
+        self.observe_key(code);
+        let status = match self.repository.status(code).await {
+            Ok(status) => status,
+            Err(e) => {
+                Span::current().record("outcome", "error");
+                return Err(e.into());
+            }
+        };
+
+        let reason = match status {
+            // Checked before expiry: do not reveal that an internal-only code
+            // exists, not even that it expired.
+            CodeStatus::Active(record) if record.internal_only && trust != CallerTrust::Trusted => {
+                trace!(code = %code, "Internal-only code requested by untrusted caller");
+                metrics::record_redirect(RedirectOutcome::Miss);
+                Span::current().record("outcome", "miss");
+                return Ok(Resolution::NotFound(NotFoundReason::Unknown));
+            }
+            CodeStatus::Active(record) if self.clock.is_expired(record.expire_at) => {
+                NotFoundReason::Expired
+            }
+            CodeStatus::Active(record) => {
+                debug!(code = %code, url = %record.original_url, "Resolved short code");
+                metrics::record_redirect(RedirectOutcome::Hit);
+                Span::current().record("outcome", "hit");
+                if let Some(sink) = &self.hit_sink {
+                    sink.record_hit(code);
+                }
+                return Ok(Resolution::Found(record));
+            }
+            CodeStatus::Expired => NotFoundReason::Expired,
+            CodeStatus::Deleted => NotFoundReason::Deleted,
+            CodeStatus::NotFound => NotFoundReason::NeverExisted,
+        };
+
+        let (outcome, span_outcome) = match reason {
+            NotFoundReason::Expired => (RedirectOutcome::Expired, "expired"),
+            _ => (RedirectOutcome::Miss, "miss"),
+        };
+        metrics::record_redirect(outcome);
+        Span::current().record("outcome", span_outcome);
+        debug!(code = %code, reason = ?reason, "Short code did not resolve");
+        Ok(Resolution::NotFound(reason))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use jiff::SignedDuration;
-    use wormhole_core::UrlRecord;
+    use crate::CallerTrust;
+    use jiff::{SignedDuration, Timestamp};
+    use wormhole_cache::UrlCache;
+    use wormhole_core::{RedirectKind, UrlRecord};
     use wormhole_storage::{InMemoryRepository, Repository};
 
     fn code(s: &str) -> ShortCode {
@@ -87,6 +294,12 @@ mod tests {
         UrlRecord {
             original_url: url.to_string(),
             expire_at,
+            redirect_kind: RedirectKind::default(),
+            created_at: Timestamp::now(),
+            internal_only: false,
+            no_store: false,
+            owner_id: None,
+            metadata: Default::default(),
         }
     }
 
@@ -128,6 +341,31 @@ mod tests {
         assert!(result.is_none());
     }
 
+    #[tokio::test]
+    async fn resolve_follows_the_injected_clock() {
+        let c = code("ticking");
+        let start = Timestamp::now();
+        let clock = wormhole_core::ManualClock::new(start);
+        let expire_at = start + SignedDuration::from_mins(5);
+        let service = setup_with_record(&c, record("https://example.com", Some(expire_at)))
+            .await
+            .with_clock(clock.clone());
+
+        assert!(service.resolve(&c).await.unwrap().is_some());
+        assert!(service.peek(&c).await.unwrap().is_some());
+
+        clock.advance(SignedDuration::from_mins(5));
+        assert!(service.resolve(&c).await.unwrap().is_none());
+        assert!(service.peek(&c).await.unwrap().is_none());
+        assert_eq!(
+            service
+                .resolve_detailed(&c, CallerTrust::Untrusted)
+                .await
+                .unwrap(),
+            Resolution::NotFound(NotFoundReason::Expired)
+        );
+    }
+
     #[tokio::test]
     async fn resolve_not_yet_expired() {
         let c = code("valid");
@@ -138,4 +376,371 @@ mod tests {
         let result = result.expect("record should exist");
         assert_eq!(result.original_url, "https://example.com");
     }
+
+    #[tokio::test]
+    async fn internal_only_code_resolves_for_trusted_callers_only() {
+        let c = code("internal");
+        let mut internal = record("https://intranet.example", None);
+        internal.internal_only = true;
+        let service = setup_with_record(&c, internal).await;
+
+        let trusted = service.resolve_for(&c, CallerTrust::Trusted).await.unwrap();
+        assert_eq!(trusted.unwrap().original_url, "https://intranet.example");
+
+        let untrusted = service
+            .resolve_for(&c, CallerTrust::Untrusted)
+            .await
+            .unwrap();
+        assert!(untrusted.is_none());
+    }
+
+    #[tokio::test]
+    async fn public_code_resolves_for_all_callers() {
+        let c = code("public");
+        let service = setup_with_record(&c, record("https://example.com", None)).await;
+
+        for trust in [CallerTrust::Trusted, CallerTrust::Untrusted] {
+            let result = service.resolve_for(&c, trust).await.unwrap();
+            assert!(result.is_some(), "{trust:?}");
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct RecordingSink(std::sync::Mutex<Vec<String>>);
+
+    impl HitSink for Arc<RecordingSink> {
+        fn record_hit(&self, code: &ShortCode) {
+            self.0.lock().unwrap().push(code.to_string());
+        }
+    }
+
+    #[tokio::test]
+    async fn untrusted_lookups_of_internal_only_codes_record_no_hit() {
+        let c = code("internal");
+        let mut internal = record("https://intranet.example", None);
+        internal.internal_only = true;
+        let sink = Arc::new(RecordingSink::default());
+        let service = setup_with_record(&c, internal)
+            .await
+            .with_hit_sink(Arc::clone(&sink));
+
+        let outcome = service
+            .resolve_outcome(&c, CallerTrust::Untrusted)
+            .await
+            .unwrap();
+        assert!(outcome.record.is_none());
+        assert!(service
+            .resolve_for(&c, CallerTrust::Untrusted)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(sink.0.lock().unwrap().is_empty());
+
+        service.resolve_for(&c, CallerTrust::Trusted).await.unwrap();
+        assert_eq!(*sink.0.lock().unwrap(), vec!["internal".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn hit_sink_sees_only_successful_resolves() {
+        let c = code("abc123");
+        let sink = Arc::new(RecordingSink::default());
+        let service = setup_with_record(&c, record("https://example.com", None))
+            .await
+            .with_hit_sink(Arc::clone(&sink));
+
+        service.resolve(&c).await.unwrap();
+        service.resolve(&code("missing")).await.unwrap();
+
+        assert_eq!(*sink.0.lock().unwrap(), vec!["abc123".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn peek_records_no_hits() {
+        let c = code("abc123");
+        let sink = Arc::new(RecordingSink::default());
+        let service = setup_with_record(&c, record("https://example.com", None))
+            .await
+            .with_hit_sink(Arc::clone(&sink));
+
+        let peeked = service.peek(&c).await.unwrap();
+
+        assert_eq!(peeked.unwrap().original_url, "https://example.com");
+        assert!(sink.0.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn peek_leaves_the_cache_empty() {
+        let c = code("abc123");
+        let repo = InMemoryRepository::new();
+        repo.insert(&c, record("https://example.com", None))
+            .await
+            .unwrap();
+        let cache = wormhole_cache::MokaUrlCache::new();
+        let service = RedirectorService::new(crate::CachedRepository::new(repo, cache.clone()));
+
+        assert!(service.peek(&c).await.unwrap().is_some());
+        assert!(service.peek(&code("missing")).await.unwrap().is_none());
+
+        assert!(cache.get_url(&c).await.unwrap().is_none());
+        service.resolve(&c).await.unwrap();
+        assert!(cache.get_url(&c).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn peek_hides_expired_records() {
+        let c = code("expired");
+        let expired = Timestamp::now() - SignedDuration::from_secs(1);
+        let service = setup_with_record(&c, record("https://example.com", Some(expired))).await;
+
+        assert!(service.peek(&c).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn resolve_detailed_explains_missing_codes() {
+        let expired_code = code("expired");
+        let expired = Timestamp::now() - SignedDuration::from_secs(1);
+        let service =
+            setup_with_record(&expired_code, record("https://example.com", Some(expired))).await;
+
+        assert_eq!(
+            service
+                .resolve_detailed(&expired_code, CallerTrust::Untrusted)
+                .await
+                .unwrap(),
+            Resolution::NotFound(NotFoundReason::Expired)
+        );
+        assert_eq!(
+            service
+                .resolve_detailed(&code("never"), CallerTrust::Untrusted)
+                .await
+                .unwrap(),
+            Resolution::NotFound(NotFoundReason::NeverExisted)
+        );
+    }
+
+    #[tokio::test]
+    async fn resolve_detailed_hides_internal_only_codes() {
+        let c = code("internal");
+        let mut internal = record("https://intranet.example", None);
+        internal.internal_only = true;
+        let service = setup_with_record(&c, internal.clone()).await;
+
+        assert_eq!(
+            service
+                .resolve_detailed(&c, CallerTrust::Untrusted)
+                .await
+                .unwrap(),
+            Resolution::NotFound(NotFoundReason::Unknown)
+        );
+        assert_eq!(
+            service
+                .resolve_detailed(&c, CallerTrust::Trusted)
+                .await
+                .unwrap(),
+            Resolution::Found(internal)
+        );
+    }
+
+    #[tokio::test]
+    async fn resolve_detailed_hides_that_internal_only_codes_expired() {
+        let c = code("internal");
+        let start = Timestamp::now();
+        let clock = wormhole_core::ManualClock::new(start);
+        let mut internal = record(
+            "https://intranet.example",
+            Some(start + SignedDuration::from_mins(5)),
+        );
+        internal.internal_only = true;
+        // The repository keeps real time, so it still reports the record as
+        // active while the service's clock says it expired.
+        let service = setup_with_record(&c, internal)
+            .await
+            .with_clock(clock.clone());
+        clock.advance(SignedDuration::from_mins(5));
+
+        assert_eq!(
+            service
+                .resolve_detailed(&c, CallerTrust::Untrusted)
+                .await
+                .unwrap(),
+            Resolution::NotFound(NotFoundReason::Unknown)
+        );
+        assert_eq!(
+            service
+                .resolve_detailed(&c, CallerTrust::Trusted)
+                .await
+                .unwrap(),
+            Resolution::NotFound(NotFoundReason::Expired)
+        );
+    }
+
+    #[tokio::test]
+    async fn key_cardinality_counts_found_and_missing_codes() {
+        let c = code("abc123");
+        let estimator = Arc::new(KeyCardinality::new(
+            crate::cardinality::KeyCardinalityConfig::builder()
+                .sample_rate(1)
+                .build(),
+        ));
+        let service = setup_with_record(&c, record("https://example.com", None))
+            .await
+            .with_key_cardinality(Arc::clone(&estimator));
+
+        for _ in 0..3 {
+            service.resolve(&c).await.unwrap();
+        }
+        service.resolve(&code("missing")).await.unwrap();
+
+        assert_eq!(estimator.estimate(), 2);
+    }
+
+    #[derive(Debug, Default)]
+    struct CountingRepository {
+        inner: InMemoryRepository,
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    impl CountingRepository {
+        fn calls(&self) -> usize {
+            self.calls.load(std::sync::atomic::Ordering::SeqCst)
+        }
+
+        fn touch(&self) {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        }
+    }
+
+    #[async_trait]
+    impl ReadRepository for CountingRepository {
+        async fn get(&self, code: &ShortCode) -> wormhole_storage::Result<Option<UrlRecord>> {
+            self.touch();
+            self.inner.get(code).await
+        }
+
+        async fn exists(&self, code: &ShortCode) -> wormhole_storage::Result<bool> {
+            self.touch();
+            self.inner.exists(code).await
+        }
+
+        async fn status(&self, code: &ShortCode) -> wormhole_storage::Result<CodeStatus> {
+            self.touch();
+            self.inner.status(code).await
+        }
+
+        async fn scan(
+            &self,
+            cursor: Option<wormhole_storage::ScanCursor>,
+            limit: usize,
+        ) -> wormhole_storage::Result<wormhole_storage::ScanPage> {
+            self.touch();
+            self.inner.scan(cursor, limit).await
+        }
+    }
+
+    #[tokio::test]
+    async fn maintenance_mode_short_circuits_resolves() {
+        let c = code("abc123");
+        let repo = CountingRepository::default();
+        repo.inner
+            .insert(&c, record("https://example.com", None))
+            .await
+            .unwrap();
+        let mode = MaintenanceMode::new(std::time::Duration::from_secs(30));
+        let service = RedirectorService::new(repo).with_maintenance(mode.clone());
+
+        mode.enable();
+        let err = service.resolve(&c).await.unwrap_err();
+        assert!(matches!(
+            err,
+            crate::RedirectorError::Maintenance { retry_after } if retry_after.as_secs() == 30
+        ));
+        let err = service
+            .resolve_detailed(&c, CallerTrust::Trusted)
+            .await
+            .unwrap_err();
+        let status = tonic::Status::from(err);
+        assert_eq!(status.code(), tonic::Code::Unavailable);
+        assert_eq!(status.metadata().get("retry-after").unwrap(), "30");
+        assert_eq!(service.repository.calls(), 0);
+
+        mode.disable();
+        assert!(service.resolve(&c).await.unwrap().is_some());
+        assert_eq!(service.repository.calls(), 1);
+    }
+
+    /// A cache whose backend is down.
+    struct FailingCache;
+
+    #[async_trait]
+    impl wormhole_cache::UrlCache for FailingCache {
+        async fn get_url(&self, _code: &ShortCode) -> wormhole_cache::Result<Option<UrlRecord>> {
+            Err(wormhole_cache::CacheError::Unavailable(
+                "connection refused".to_string(),
+            ))
+        }
+
+        async fn set_url(
+            &self,
+            _code: &ShortCode,
+            _record: &UrlRecord,
+        ) -> wormhole_cache::Result<()> {
+            Err(wormhole_cache::CacheError::Unavailable(
+                "connection refused".to_string(),
+            ))
+        }
+
+        async fn del(&self, _code: &ShortCode) -> wormhole_cache::Result<()> {
+            Err(wormhole_cache::CacheError::Unavailable(
+                "connection refused".to_string(),
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn cache_failures_resolve_from_the_origin_as_degraded() {
+        let c = code("abc123");
+        let inner = InMemoryRepository::new();
+        inner
+            .insert(&c, record("https://example.com", None))
+            .await
+            .unwrap();
+        let service = RedirectorService::new(crate::CachedRepository::new(inner, FailingCache));
+
+        let outcome = service
+            .resolve_outcome(&c, CallerTrust::Untrusted)
+            .await
+            .unwrap();
+        assert!(outcome.served_degraded);
+        assert_eq!(outcome.record.unwrap().original_url, "https://example.com");
+
+        let outcome = service
+            .resolve_outcome(&code("missing"), CallerTrust::Untrusted)
+            .await
+            .unwrap();
+        assert!(outcome.served_degraded);
+        assert!(outcome.record.is_none());
+    }
+
+    #[tokio::test]
+    async fn healthy_cache_is_not_degraded() {
+        let c = code("abc123");
+        let inner = InMemoryRepository::new();
+        inner
+            .insert(&c, record("https://example.com", None))
+            .await
+            .unwrap();
+        let service = RedirectorService::new(crate::CachedRepository::new(
+            inner,
+            wormhole_cache::MokaUrlCache::new(),
+        ));
+
+        for _ in 0..2 {
+            let outcome = service
+                .resolve_outcome(&c, CallerTrust::Untrusted)
+                .await
+                .unwrap();
+            assert!(!outcome.served_degraded);
+            assert!(outcome.record.is_some());
+        }
+    }
 }
@@ -0,0 +1,247 @@
+//! A [`tower::Service`] view of [`RedirectorService`].
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use wormhole_core::{ShortCode, UrlRecord};
+use wormhole_storage::ReadRepository;
+
+use crate::redirector::Redirector;
+use crate::{CallerTrust, RedirectorError, RedirectorService};
+
+/// Adapts [`Redirector::resolve_for`] to [`tower::Service`], so resolves
+/// can run behind tower middleware such as `Timeout` or `ConcurrencyLimit`.
+///
+/// Every call resolves on behalf of a caller with the adapter's
+/// [`CallerTrust`], [`CallerTrust::Untrusted`] unless set with
+/// [`RedirectorTowerService::with_trust`], so internal-only codes stay
+/// hidden from stacks facing the outside.
+///
+/// The adapter is always ready; backpressure comes from the layers stacked
+/// over it. Those layers usually box errors, so a [`RedirectorError`] comes
+/// back out of the stack as a `tower::BoxError` that downcasts to it.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use tower::{ServiceBuilder, ServiceExt};
+/// use wormhole_core::ShortCode;
+/// use wormhole_redirector::{RedirectorService, RedirectorTowerService};
+/// use wormhole_storage::InMemoryRepository;
+///
+/// # async fn example() -> Result<(), tower::BoxError> {
+/// let service = RedirectorService::new(InMemoryRepository::new());
+/// let stack = ServiceBuilder::new()
+///     .concurrency_limit(64)
+///     .timeout(Duration::from_millis(250))
+///     .service(RedirectorTowerService::new(service));
+///
+/// let record = stack.oneshot(ShortCode::new_unchecked("abc123")).await?;
+/// # let _ = record;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct RedirectorTowerService<R> {
+    inner: Arc<RedirectorService<R>>,
+    trust: CallerTrust,
+}
+
+impl<R> RedirectorTowerService<R> {
+    pub fn new(service: RedirectorService<R>) -> Self {
+        Self {
+            inner: Arc::new(service),
+            trust: CallerTrust::default(),
+        }
+    }
+
+    /// Resolves on behalf of callers with `trust`, e.g.
+    /// [`CallerTrust::Trusted`] for a stack only reachable internally.
+    pub fn with_trust(mut self, trust: CallerTrust) -> Self {
+        self.trust = trust;
+        self
+    }
+
+    /// Returns the wrapped service.
+    pub fn service(&self) -> &RedirectorService<R> {
+        &self.inner
+    }
+}
+
+impl<R> Clone for RedirectorTowerService<R> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+            trust: self.trust,
+        }
+    }
+}
+
+impl<R: ReadRepository> tower::Service<ShortCode> for RedirectorTowerService<R> {
+    type Response = Option<UrlRecord>;
+    type Error = RedirectorError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, code: ShortCode) -> Self::Future {
+        let service = Arc::clone(&self.inner);
+        let trust = self.trust;
+        Box::pin(async move { service.resolve_for(&code, trust).await })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use jiff::Timestamp;
+    use std::time::Duration;
+    use tower::timeout::error::Elapsed;
+    use tower::{ServiceBuilder, ServiceExt};
+    use wormhole_core::RedirectKind;
+    use wormhole_storage::{
+        CodeStatus, InMemoryRepository, Repository, ScanCursor, ScanPage, StorageError,
+    };
+
+    fn code(value: &str) -> ShortCode {
+        ShortCode::new_unchecked(value)
+    }
+
+    fn record(url: &str) -> UrlRecord {
+        UrlRecord {
+            original_url: url.to_string(),
+            expire_at: None,
+            redirect_kind: RedirectKind::default(),
+            created_at: Timestamp::now(),
+            internal_only: false,
+            no_store: false,
+            owner_id: None,
+            metadata: Default::default(),
+        }
+    }
+
+    /// Answers every lookup after `delay`, or fails if `fail` is set.
+    #[derive(Debug, Default)]
+    struct SlowRepository {
+        inner: InMemoryRepository,
+        delay: Duration,
+        fail: bool,
+    }
+
+    impl SlowRepository {
+        async fn wait(&self) -> wormhole_storage::Result<()> {
+            tokio::time::sleep(self.delay).await;
+            if self.fail {
+                return Err(StorageError::Unavailable("database is down".to_string()));
+            }
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl ReadRepository for SlowRepository {
+        async fn get(&self, code: &ShortCode) -> wormhole_storage::Result<Option<UrlRecord>> {
+            self.wait().await?;
+            self.inner.get(code).await
+        }
+
+        async fn exists(&self, code: &ShortCode) -> wormhole_storage::Result<bool> {
+            self.wait().await?;
+            self.inner.exists(code).await
+        }
+
+        async fn status(&self, code: &ShortCode) -> wormhole_storage::Result<CodeStatus> {
+            self.wait().await?;
+            self.inner.status(code).await
+        }
+
+        async fn scan(
+            &self,
+            cursor: Option<ScanCursor>,
+            limit: usize,
+        ) -> wormhole_storage::Result<ScanPage> {
+            self.wait().await?;
+            self.inner.scan(cursor, limit).await
+        }
+    }
+
+    async fn adapter(repo: SlowRepository) -> RedirectorTowerService<SlowRepository> {
+        repo.inner
+            .insert(&code("abc123"), record("https://example.com"))
+            .await
+            .unwrap();
+        RedirectorTowerService::new(RedirectorService::new(repo))
+    }
+
+    #[tokio::test]
+    async fn resolves_through_the_service_interface() {
+        let service = adapter(SlowRepository::default()).await;
+
+        let found = service.clone().oneshot(code("abc123")).await.unwrap();
+        assert_eq!(found.unwrap().original_url, "https://example.com");
+        let missing = service.oneshot(code("missing")).await.unwrap();
+        assert!(missing.is_none());
+    }
+
+    #[tokio::test]
+    async fn internal_only_codes_need_a_trusted_adapter() {
+        let repo = SlowRepository::default();
+        let mut internal = record("https://intranet.example");
+        internal.internal_only = true;
+        repo.inner
+            .insert(&code("internal"), internal)
+            .await
+            .unwrap();
+        let service = adapter(repo).await;
+
+        let hidden = service.clone().oneshot(code("internal")).await.unwrap();
+        assert!(hidden.is_none());
+
+        let trusted = service
+            .with_trust(CallerTrust::Trusted)
+            .oneshot(code("internal"))
+            .await
+            .unwrap();
+        assert_eq!(trusted.unwrap().original_url, "https://intranet.example");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn timeout_layer_cuts_off_slow_resolves() {
+        let repo = SlowRepository {
+            delay: Duration::from_secs(5),
+            ..Default::default()
+        };
+        let stack = ServiceBuilder::new()
+            .timeout(Duration::from_millis(100))
+            .service(adapter(repo).await);
+
+        let err = stack.oneshot(code("abc123")).await.unwrap_err();
+
+        assert!(err.is::<Elapsed>(), "unexpected error: {err}");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn resolve_errors_pass_through_the_timeout_layer() {
+        let repo = SlowRepository {
+            delay: Duration::from_millis(10),
+            fail: true,
+            ..Default::default()
+        };
+        let stack = ServiceBuilder::new()
+            .timeout(Duration::from_secs(1))
+            .service(adapter(repo).await);
+
+        let err = stack.oneshot(code("abc123")).await.unwrap_err();
+
+        let err = err
+            .downcast::<RedirectorError>()
+            .expect("a RedirectorError");
+        assert!(matches!(*err, RedirectorError::Storage(_)));
+    }
+}
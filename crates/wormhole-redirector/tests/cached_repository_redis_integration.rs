@@ -0,0 +1,151 @@
+use jiff::Timestamp;
+use redis::AsyncCommands;
+use wormhole_cache::{CacheError, RedisUrlCache, UrlCache};
+use wormhole_core::{RedirectKind, ShortCode, UrlRecord};
+use wormhole_redirector::CachedRepository;
+use wormhole_storage::{InMemoryRepository, ReadRepository, Repository};
+use wormhole_test_infra::redis::RedisMaster;
+
+fn create_test_record(url: &str) -> UrlRecord {
+    UrlRecord {
+        original_url: url.to_string(),
+        expire_at: None,
+        redirect_kind: RedirectKind::default(),
+        created_at: Timestamp::now(),
+        internal_only: false,
+        no_store: false,
+        owner_id: None,
+        metadata: Default::default(),
+    }
+}
+
+async fn connect(redis: &RedisMaster) -> redis::aio::MultiplexedConnection {
+    let host = redis.host().await.expect("Failed to get Redis host");
+    let port = redis.port().await.expect("Failed to get Redis port");
+    let client = redis::Client::open(format!("redis://{host}:{port}"))
+        .expect("Failed to create Redis client");
+    client
+        .get_multiplexed_async_connection()
+        .await
+        .expect("Failed to get Redis connection")
+}
+
+#[tokio::test]
+async fn test_invalidate_many_with_redis_cache() {
+    let redis = RedisMaster::new()
+        .await
+        .expect("Failed to start Redis master");
+    let conn = connect(&redis).await;
+
+    let cache = RedisUrlCache::new(conn);
+    let cached = CachedRepository::new(InMemoryRepository::new(), cache.clone());
+
+    let codes: Vec<_> = (0..5)
+        .map(|i| ShortCode::custom(format!("import{i}")).unwrap())
+        .collect();
+    let keep = ShortCode::custom("untouched").unwrap();
+    for code in codes.iter().chain([&keep]) {
+        cache
+            .set_url(code, &create_test_record("https://example.com/import"))
+            .await
+            .unwrap();
+    }
+
+    cached.invalidate_many(&codes).await.unwrap();
+
+    for code in &codes {
+        assert!(cache.get_url(code).await.unwrap().is_none());
+    }
+    assert!(cache.get_url(&keep).await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn test_get_reads_through_and_fills_redis() {
+    let redis = RedisMaster::new()
+        .await
+        .expect("Failed to start Redis master");
+    let conn = connect(&redis).await;
+    let inner = InMemoryRepository::new();
+    let code = ShortCode::custom("readthru").unwrap();
+    inner
+        .insert(&code, create_test_record("https://example.com/page"))
+        .await
+        .unwrap();
+
+    let cache = RedisUrlCache::new(conn);
+    let cached = CachedRepository::new(inner.clone(), cache.clone());
+
+    let record = cached.get(&code).await.unwrap().unwrap();
+    assert_eq!(record.original_url, "https://example.com/page");
+    let stored = cache.get_url(&code).await.unwrap().unwrap();
+    assert_eq!(stored.original_url, "https://example.com/page");
+
+    // Served from Redis once the inner record is gone.
+    inner.delete(&code).await.unwrap();
+    assert!(cached.get(&code).await.unwrap().is_some());
+    assert!(cached.exists(&code).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_corrupted_redis_value_falls_back_to_inner_as_degraded() {
+    let redis = RedisMaster::new()
+        .await
+        .expect("Failed to start Redis master");
+    let mut conn = connect(&redis).await;
+    let inner = InMemoryRepository::new();
+    let code = ShortCode::custom("corrupt").unwrap();
+    inner
+        .insert(&code, create_test_record("https://example.com"))
+        .await
+        .unwrap();
+    conn.set::<_, _, ()>(format!("wh:url:{}", code.as_str()), "{not json")
+        .await
+        .unwrap();
+
+    let cache = RedisUrlCache::new(conn);
+    let cached = CachedRepository::new(inner, cache.clone());
+
+    // The corruption is not masked as a miss: the cache still reports it...
+    let err = cache.get_url(&code).await.unwrap_err();
+    assert!(matches!(err, CacheError::InvalidData(_)));
+
+    // ...while the repository serves the record from the origin, flagged.
+    let lookup = cached.lookup(&code).await.unwrap();
+    assert!(lookup.degraded);
+    assert_eq!(lookup.record.unwrap().original_url, "https://example.com");
+}
+
+#[tokio::test]
+async fn test_caches_with_different_prefixes_are_isolated() {
+    let redis = RedisMaster::new()
+        .await
+        .expect("Failed to start Redis master");
+    let conn = connect(&redis).await;
+    let code = ShortCode::custom("shared").unwrap();
+
+    let first_inner = InMemoryRepository::new();
+    first_inner
+        .insert(&code, create_test_record("https://first.example.com"))
+        .await
+        .unwrap();
+    let second_inner = InMemoryRepository::new();
+    second_inner
+        .insert(&code, create_test_record("https://second.example.com"))
+        .await
+        .unwrap();
+
+    let first_cache = RedisUrlCache::with_prefix(conn.clone(), "first:url:");
+    let second_cache = RedisUrlCache::with_prefix(conn, "second:url:");
+    let first = CachedRepository::new(first_inner, first_cache.clone());
+    let second = CachedRepository::new(second_inner, second_cache.clone());
+
+    let record = first.get(&code).await.unwrap().unwrap();
+    assert_eq!(record.original_url, "https://first.example.com");
+    assert!(second_cache.get_url(&code).await.unwrap().is_none());
+
+    let record = second.get(&code).await.unwrap().unwrap();
+    assert_eq!(record.original_url, "https://second.example.com");
+
+    second.invalidate(&code).await.unwrap();
+    assert!(first_cache.get_url(&code).await.unwrap().is_some());
+}
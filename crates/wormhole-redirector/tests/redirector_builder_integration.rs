@@ -0,0 +1,128 @@
+use std::time::Duration;
+
+use jiff::Timestamp;
+use redis::AsyncCommands;
+use wormhole_core::{RedirectKind, ShortCode, UrlRecord};
+use wormhole_redirector::builder::{BuildError, RedirectorBuilder};
+use wormhole_storage::{InMemoryRepository, Repository};
+use wormhole_test_infra::redis::{RedisHA, RedisHAConfig, RedisMaster};
+
+fn create_test_record(url: &str) -> UrlRecord {
+    UrlRecord {
+        original_url: url.to_string(),
+        expire_at: None,
+        redirect_kind: RedirectKind::default(),
+        created_at: Timestamp::now(),
+        internal_only: false,
+        no_store: false,
+        owner_id: None,
+        metadata: Default::default(),
+    }
+}
+
+async fn connect(redis: &RedisMaster) -> redis::aio::MultiplexedConnection {
+    let host = redis.host().await.expect("Failed to get Redis host");
+    let port = redis.port().await.expect("Failed to get Redis port");
+    let client = redis::Client::open(format!("redis://{host}:{port}"))
+        .expect("Failed to create Redis client");
+    client
+        .get_multiplexed_async_connection()
+        .await
+        .expect("Failed to get Redis connection")
+}
+
+async fn repository() -> InMemoryRepository {
+    let repo = InMemoryRepository::new();
+    repo.insert(
+        &ShortCode::new_unchecked("abc123"),
+        create_test_record("https://example.com"),
+    )
+    .await
+    .unwrap();
+    repo
+}
+
+#[tokio::test]
+async fn test_builder_moka_over_redis_resolves_and_fills_redis() {
+    let redis = RedisMaster::new()
+        .await
+        .expect("Failed to start Redis master");
+    let mut conn = connect(&redis).await;
+
+    let service = RedirectorBuilder::new(repository().await)
+        .with_l1(1_000)
+        .with_redis(conn.clone())
+        .with_redis_key_prefix("test:url:")
+        .with_default_ttl(Duration::from_secs(60))
+        .build()
+        .unwrap();
+
+    let code = ShortCode::new_unchecked("abc123");
+    let record = service.resolve(&code).await.unwrap().unwrap();
+    assert_eq!(record.original_url, "https://example.com");
+
+    // The L2 copy was written under the configured prefix, with the TTL.
+    let pttl: i64 = conn.pttl("test:url:abc123").await.unwrap();
+    assert!((55_000..=60_000).contains(&pttl), "PTTL {pttl}");
+}
+
+#[tokio::test]
+async fn test_builder_redis_only_resolves() {
+    let redis = RedisMaster::new()
+        .await
+        .expect("Failed to start Redis master");
+    let conn = connect(&redis).await;
+
+    let service = RedirectorBuilder::new(repository().await)
+        .with_redis(conn)
+        .build()
+        .unwrap();
+
+    let code = ShortCode::new_unchecked("abc123");
+    assert!(service.resolve(&code).await.unwrap().is_some());
+    assert!(service.resolve(&code).await.unwrap().is_some());
+    let missing = ShortCode::new_unchecked("missing");
+    assert!(service.resolve(&missing).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_builder_sentinel_resolves() {
+    let config = RedisHAConfig::default();
+    let service_name = config.service_name.clone();
+    let redis_ha = RedisHA::new(config)
+        .await
+        .expect("Failed to start Redis HA environment");
+    let sentinels = redis_ha.sentinel_addresses().await;
+    // Let the sentinels discover the topology.
+    tokio::time::sleep(Duration::from_secs(2)).await;
+
+    let service = RedirectorBuilder::new(repository().await)
+        .with_l1(1_000)
+        .with_redis_sentinel(sentinels, &service_name)
+        .build()
+        .unwrap();
+
+    let record = service
+        .resolve(&ShortCode::new_unchecked("abc123"))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(record.original_url, "https://example.com");
+}
+
+#[tokio::test]
+async fn test_builder_rejects_single_redis_with_sentinel() {
+    let redis = RedisMaster::new()
+        .await
+        .expect("Failed to start Redis master");
+    let conn = connect(&redis).await;
+
+    let err = RedirectorBuilder::new(repository().await)
+        .with_l1(1_000)
+        .with_redis(conn)
+        .with_redis_sentinel(vec!["redis://127.0.0.1:26379"], "mymaster")
+        .build()
+        .unwrap_err();
+
+    assert!(matches!(err, BuildError::ConflictingRedis));
+}
@@ -0,0 +1,174 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use jiff::Timestamp;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{Layer, Registry};
+use wormhole_cache::{LayeredCache, MokaUrlCache};
+use wormhole_core::{RedirectKind, ShortCode, UrlRecord};
+use wormhole_redirector::{CachedRepository, RedirectorService};
+use wormhole_storage::{InMemoryRepository, Repository};
+
+/// A span as the capture layer saw it.
+#[derive(Debug, Clone)]
+struct CapturedSpan {
+    id: Id,
+    name: &'static str,
+    parent: Option<Id>,
+    fields: BTreeMap<String, String>,
+}
+
+/// Records every span with its parent and fields.
+#[derive(Clone, Default)]
+struct SpanCapture {
+    spans: Arc<Mutex<Vec<CapturedSpan>>>,
+}
+
+struct FieldVisitor<'a>(&'a mut BTreeMap<String, String>);
+
+impl Visit for FieldVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{value:?}"));
+    }
+}
+
+impl<S> Layer<S> for SpanCapture
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let parent = ctx
+            .span(id)
+            .and_then(|span| span.parent())
+            .map(|parent| parent.id());
+        let mut fields = BTreeMap::new();
+        attrs.record(&mut FieldVisitor(&mut fields));
+        self.spans.lock().unwrap().push(CapturedSpan {
+            id: id.clone(),
+            name: attrs.metadata().name(),
+            parent,
+            fields,
+        });
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+        let mut spans = self.spans.lock().unwrap();
+        // Span ids are reused once a span closes, so the latest match is it.
+        if let Some(span) = spans.iter_mut().rev().find(|span| &span.id == id) {
+            values.record(&mut FieldVisitor(&mut span.fields));
+        }
+    }
+}
+
+impl SpanCapture {
+    fn take(&self) -> Vec<CapturedSpan> {
+        std::mem::take(&mut *self.spans.lock().unwrap())
+    }
+}
+
+/// Renders each span as `name[field=value,..] <- parent name`, in creation
+/// order.
+fn render(spans: &[CapturedSpan]) -> Vec<String> {
+    spans
+        .iter()
+        .map(|span| {
+            let fields: Vec<String> = span
+                .fields
+                .iter()
+                .map(|(key, value)| format!("{key}={value}"))
+                .collect();
+            let parent = span
+                .parent
+                .as_ref()
+                .and_then(|parent| spans.iter().rev().find(|s| &s.id == parent))
+                .map_or("-", |parent| parent.name);
+            format!("{}[{}] <- {parent}", span.name, fields.join(","))
+        })
+        .collect()
+}
+
+fn record(url: &str) -> UrlRecord {
+    UrlRecord {
+        original_url: url.to_string(),
+        expire_at: None,
+        redirect_kind: RedirectKind::default(),
+        created_at: Timestamp::now(),
+        internal_only: false,
+        no_store: false,
+        owner_id: None,
+        metadata: Default::default(),
+    }
+}
+
+#[tokio::test]
+async fn resolve_spans_follow_the_lookup_through_every_layer() {
+    let code = ShortCode::new_unchecked("abc123");
+    let repo = InMemoryRepository::new();
+    repo.insert(&code, record("https://example.com/secret-path"))
+        .await
+        .unwrap();
+    let cache = LayeredCache::new(MokaUrlCache::new(), MokaUrlCache::new());
+    let service = RedirectorService::new(CachedRepository::new(repo, cache));
+
+    let capture = SpanCapture::default();
+    let _guard = tracing::subscriber::set_default(Registry::default().with(capture.clone()));
+
+    // Cold: every layer misses and the inner repository answers.
+    service.resolve(&code).await.unwrap().unwrap();
+    let cold = capture.take();
+    assert_eq!(
+        render(&cold),
+        [
+            "redirector.resolve[code=abc123,outcome=hit] <- -",
+            "repository.lookup[code=abc123,outcome=miss] <- redirector.resolve",
+            "cache.layer[code=abc123,layer=l1,outcome=miss] <- repository.lookup",
+            "cache.layer[code=abc123,layer=l2,outcome=miss] <- cache.layer",
+            "repository.inner[code=abc123,layer=inner,outcome=hit] <- cache.layer",
+        ]
+    );
+    // The l2 span sits under the l1 span, and the inner read under l2.
+    assert_eq!(cold[3].parent.as_ref(), Some(&cold[2].id));
+    assert_eq!(cold[4].parent.as_ref(), Some(&cold[3].id));
+
+    // Warm: L1 answers on its own.
+    service.resolve(&code).await.unwrap().unwrap();
+    assert_eq!(
+        render(&capture.take()),
+        [
+            "redirector.resolve[code=abc123,outcome=hit] <- -",
+            "repository.lookup[code=abc123,outcome=hit] <- redirector.resolve",
+            "cache.layer[code=abc123,layer=l1,outcome=hit] <- repository.lookup",
+        ]
+    );
+
+    // A miss is reported as such, all the way down.
+    service
+        .resolve(&ShortCode::new_unchecked("missing"))
+        .await
+        .unwrap();
+    let miss = render(&capture.take());
+    assert_eq!(
+        miss[0],
+        "redirector.resolve[code=missing,outcome=miss] <- -"
+    );
+    assert_eq!(
+        miss[4],
+        "repository.inner[code=missing,layer=inner,outcome=miss] <- cache.layer"
+    );
+
+    // Span fields never carry the destination URL.
+    assert!(cold
+        .iter()
+        .flat_map(|span| span.fields.values())
+        .all(|value| !value.contains("example.com")));
+}
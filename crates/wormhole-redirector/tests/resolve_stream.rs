@@ -0,0 +1,120 @@
+use jiff::{SignedDuration, Timestamp};
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::Server;
+use tonic::Code;
+use wormhole_core::{RedirectKind, ShortCode, UrlRecord};
+use wormhole_proto_schema::v1 as proto;
+use wormhole_proto_schema::v1::redirector_service_client::RedirectorServiceClient;
+use wormhole_proto_schema::v1::redirector_service_server::RedirectorServiceServer;
+use wormhole_proto_schema::v1::resolve_stream_response::Outcome;
+use wormhole_redirector::grpc::RedirectorGrpcServer;
+use wormhole_redirector::RedirectorService;
+use wormhole_storage::{InMemoryRepository, Repository};
+
+fn record(url: &str, expire_at: Option<Timestamp>) -> UrlRecord {
+    UrlRecord {
+        original_url: url.to_string(),
+        expire_at,
+        redirect_kind: RedirectKind::default(),
+        created_at: Timestamp::now(),
+        internal_only: false,
+        no_store: false,
+        owner_id: None,
+        metadata: Default::default(),
+    }
+}
+
+fn request(code: &str) -> proto::ResolveStreamRequest {
+    proto::ResolveStreamRequest {
+        short_code: Some(proto::ShortCode {
+            code: code.to_string(),
+            kind: proto::ShortCodeKind::Custom as i32,
+        }),
+    }
+}
+
+async fn serve(repo: InMemoryRepository) -> RedirectorServiceClient<tonic::transport::Channel> {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let service = RedirectorGrpcServer::new(RedirectorService::new(repo));
+    tokio::spawn(async move {
+        Server::builder()
+            .add_service(RedirectorServiceServer::new(service))
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+            .unwrap();
+    });
+
+    RedirectorServiceClient::connect(format!("http://{addr}"))
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn resolve_stream_answers_every_code_in_order() {
+    let repo = InMemoryRepository::new();
+    repo.insert(
+        &ShortCode::new_unchecked("alive"),
+        record("https://example.com/alive", None),
+    )
+    .await
+    .unwrap();
+    repo.insert(
+        &ShortCode::new_unchecked("stale"),
+        record(
+            "https://example.com/stale",
+            Some(Timestamp::now() - SignedDuration::from_secs(1)),
+        ),
+    )
+    .await
+    .unwrap();
+    let mut client = serve(repo).await;
+
+    let requests = vec![
+        request("alive"),
+        request("missing"),
+        request("stale"),
+        request("a.b"),
+        proto::ResolveStreamRequest { short_code: None },
+        request("alive"),
+    ];
+    let mut responses = client
+        .resolve_stream(tokio_stream::iter(requests))
+        .await
+        .unwrap()
+        .into_inner();
+
+    let mut outcomes = Vec::new();
+    while let Some(response) = responses.message().await.unwrap() {
+        outcomes.push((response.short_code, response.outcome.unwrap()));
+    }
+
+    let summary: Vec<_> = outcomes
+        .iter()
+        .map(|(code, outcome)| match outcome {
+            Outcome::Resolved(resolved) => (
+                code.as_str(),
+                Code::Ok,
+                resolved.url_record.as_ref().unwrap().original_url.clone(),
+            ),
+            Outcome::Error(error) => (code.as_str(), Code::from(error.code), String::new()),
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("alive", Code::Ok, "https://example.com/alive".to_string()),
+            ("missing", Code::NotFound, String::new()),
+            ("stale", Code::NotFound, String::new()),
+            ("a.b", Code::InvalidArgument, String::new()),
+            ("", Code::InvalidArgument, String::new()),
+            ("alive", Code::Ok, "https://example.com/alive".to_string()),
+        ]
+    );
+
+    let Outcome::Error(expired) = &outcomes[2].1 else {
+        panic!("expired code should not resolve");
+    };
+    assert_eq!(expired.reason, proto::ResolveFailureReason::Expired as i32);
+}
@@ -9,7 +9,7 @@ use std::time::{Duration, Instant};
 use tokio::runtime::{Builder, Runtime};
 use wormhole_generator::seq::SeqGenerator;
 use wormhole_shortener::service::ShortenerService;
-use wormhole_shortener::shortener::{ExpirationPolicy, ShortenParams, Shortener};
+use wormhole_shortener::shortener::{ConflictPolicy, ExpirationPolicy, ShortenParams, Shortener};
 use wormhole_storage::MySqlRepository;
 use wormhole_test_infra::mysql::{MySqlServer, MysqlConfig};
 
@@ -100,6 +100,10 @@ fn build_shorten_params(batch_size: usize) -> Vec<ShortenParams> {
             original_url: format!("https://example{}.com", i),
             expiration: ExpirationPolicy::Never,
             custom_alias: None,
+            internal_only: false,
+            no_store: false,
+            idempotency_key: None,
+            conflict_policy: ConflictPolicy::Reject,
         })
         .collect()
 }
@@ -1,18 +1,26 @@
 mod cli;
 
-use crate::cli::{StorageBackendArg, CLI};
+use crate::cli::{RateLimitKeyArg, StorageBackendArg, CLI};
 use clap::Parser;
-use jiff::Timestamp;
+use std::time::Duration;
+use tonic::service::Interceptor;
 use tonic::transport::Server;
+use tonic::{Request, Status};
 use tracing::info;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
-use wormhole_generator::obfuscated::{ObfuscatedTinyFlake, Obfuscator};
-use wormhole_generator::Generator;
+use wormhole_core::AliasPolicy;
+use wormhole_generator::filtered::{Blocklist, FilteredGenerator};
+use wormhole_generator::obfuscated::{ObfuscatedTinyFlake, ObfuscatedTinyID, Obfuscator};
+use wormhole_generator::{check_code_width, Generator};
 use wormhole_proto_schema::v1::shortener_service_server::ShortenerServiceServer;
+use wormhole_shortener::auth::{ApiKeyAuth, InMemoryKeyStore};
 use wormhole_shortener::grpc::ShortenerGrpcServer;
+use wormhole_shortener::health::report_repository_health;
+use wormhole_shortener::lease::{NodeIdLease, NodeIdLeaseConfig};
+use wormhole_shortener::rate_limit::{KeyExtractor, RateLimitConfig, RateLimiter};
 use wormhole_storage::{InMemoryRepository, MySqlRepository, Repository};
-use wormhole_tinyflake::TinyflakeSettings;
+use wormhole_tinyflake::{FileStateStore, TinyflakeSettings};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -32,26 +40,128 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         "starting shortener gRPC server"
     );
 
-    let obfuscator = Obfuscator::builder().build();
-    // todo: make the start epoch configurable
-    let start_epoch: Timestamp = "2026-01-01T00:00:00+08[Asia/Shanghai]".parse()?;
+    // Keep the renewal task alive for as long as the server runs.
+    let _node_lease = match &config.node_lease_redis_url {
+        Some(redis_url) => {
+            let conn = redis::Client::open(redis_url.as_str())?
+                .get_multiplexed_async_connection()
+                .await?;
+            let lease =
+                NodeIdLease::acquire(conn, config.node_id, NodeIdLeaseConfig::default()).await?;
+            Some(lease.spawn_renewal())
+        }
+        None => None,
+    };
+
+    let mut obfuscator = Obfuscator::builder();
+    if let Some(prime) = config.obfuscator_prime {
+        obfuscator = obfuscator.prime(prime);
+    }
+    if let Some(mask) = config.obfuscator_mask {
+        obfuscator = obfuscator.mask(mask);
+    }
+    let obfuscator = obfuscator
+        .build()
+        .map_err(|e| format!("cannot start the code generator: {e}"))?;
 
     let tinyflake_settings = TinyflakeSettings::builder()
         .node_id(config.node_id)
-        .start_epoch(start_epoch)
+        .start_epoch(config.start_epoch)
+        .timestamp_bits(config.timestamp_bits)
+        .sequence_bits(config.sequence_bits)
+        .node_bits(config.node_bits)
         .build();
 
     info!(
         tinyflake.node_id = tinyflake_settings.node_id,
         tinyflake.start_epoch = tinyflake_settings.start_epoch.to_string(),
+        tinyflake.timestamp_bits = tinyflake_settings.timestamp_bits,
+        tinyflake.sequence_bits = tinyflake_settings.sequence_bits,
+        tinyflake.node_bits = tinyflake_settings.node_bits,
         "tinyflake settings"
     );
 
-    let generator = ObfuscatedTinyFlake::new(tinyflake_settings, obfuscator);
+    let generator = ObfuscatedTinyFlake::try_new(tinyflake_settings, obfuscator)
+        .map_err(|e| format!("cannot start the code generator: {e}"))?
+        .with_alphabet(config.code_alphabet);
+    let generator = match &config.generator_state_file {
+        Some(path) => {
+            generator.with_state_store(FileStateStore::new(path.clone()), Duration::from_secs(10))
+        }
+        None => generator,
+    };
+    let generator = FilteredGenerator::new(generator, Blocklist::new(&config.blocked_words));
+    let alias_policy = AliasPolicy::new()
+        .with_reserved(&config.reserved_aliases)
+        .with_case_insensitive(config.case_insensitive_aliases)
+        .with_length(config.alias_min_len.into(), config.alias_max_len.into())?;
+    let max_expiration = Duration::from_secs(u64::from(config.max_expiration_days) * 24 * 60 * 60);
+
+    let rate_limiter = match config.rate_limit_per_second {
+        Some(per_second) if per_second > 0.0 => {
+            let key = match config.rate_limit_key {
+                RateLimitKeyArg::PeerIp => KeyExtractor::PeerIp,
+                RateLimitKeyArg::ApiKey => {
+                    KeyExtractor::Header(config.rate_limit_header.to_ascii_lowercase())
+                }
+            };
+            let rate_config = RateLimitConfig::builder()
+                .burst(config.rate_limit_burst)
+                .refill_per_second(per_second)
+                .key(key)
+                .build();
+            info!(
+                per_second,
+                burst = config.rate_limit_burst,
+                "rate limiting shortener requests"
+            );
+            Some(RateLimiter::new(rate_config))
+        }
+        Some(_) => return Err("rate limit must be greater than zero".into()),
+        None => None,
+    };
+    // Keep the cleanup task alive for as long as the server runs.
+    let _rate_limit_cleanup = rate_limiter
+        .as_ref()
+        .map(|limiter| limiter.spawn_cleanup(Duration::from_secs(60)));
+
+    let mut api_keys = InMemoryKeyStore::new(config.api_keys.iter().map(|key| key.trim()));
+    if let Some(path) = &config.api_keys_file {
+        api_keys.extend(InMemoryKeyStore::from_file(path)?);
+    }
+    let mut auth = if api_keys.is_empty() {
+        info!("no api keys configured, shortener accepts unauthenticated requests");
+        None
+    } else {
+        info!(keys = api_keys.len(), "requiring api keys");
+        Some(ApiKeyAuth::new(api_keys))
+    };
+
+    let mut rate_limiter = rate_limiter;
+    // Authenticate first so rejected keys never get a rate limit bucket.
+    let interceptor = move |request: Request<()>| -> Result<Request<()>, Status> {
+        let request = match auth.as_mut() {
+            Some(auth) => auth.call(request)?,
+            None => request,
+        };
+        match rate_limiter.as_mut() {
+            Some(limiter) => limiter.call(request),
+            None => Ok(request),
+        }
+    };
 
     match config.storage {
         StorageBackendArg::InMemory => {
-            run_server(config.listen_addr, InMemoryRepository::new(), generator).await?;
+            run_server(
+                config.listen_addr,
+                InMemoryRepository::new(),
+                generator,
+                alias_policy,
+                max_expiration,
+                interceptor,
+                config.enable_reflection,
+            )
+            .await?;
         }
         StorageBackendArg::Mysql => {
             let mysql_dsn = config
@@ -60,25 +170,68 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             let repository = MySqlRepository::connect(&mysql_dsn).await?;
             // do the migration before starting the server
             repository.migrate().await?;
-            run_server(config.listen_addr, repository, generator).await?;
+            check_code_width(
+                ObfuscatedTinyID::max_code_len(),
+                repository.short_code_column_width().await?,
+            )?;
+            run_server(
+                config.listen_addr,
+                repository,
+                generator,
+                alias_policy,
+                max_expiration,
+                interceptor,
+                config.enable_reflection,
+            )
+            .await?;
         }
     }
 
     Ok(())
 }
 
-async fn run_server<R: Repository, G: Generator>(
+async fn run_server<R, G, I>(
     listen_addr: std::net::SocketAddr,
     repository: R,
     generator: G,
-) -> Result<(), tonic::transport::Error> {
-    let service = ShortenerGrpcServer::new(repository, generator);
+    alias_policy: AliasPolicy,
+    max_expiration: Duration,
+    interceptor: I,
+    enable_reflection: bool,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    R: Repository,
+    G: Generator,
+    I: Interceptor + Clone + Send + Sync + 'static,
+{
+    let (health_reporter, health_service) = tonic_health::server::health_reporter();
+    // A failed ping is logged and leaves the service NOT_SERVING, so
+    // readiness probes keep traffic away while the process stays up.
+    let _ = report_repository_health::<ShortenerServiceServer<ShortenerGrpcServer<R, G>>, _>(
+        &health_reporter,
+        &repository,
+    )
+    .await;
 
-    let (_, health_service) = tonic_health::server::health_reporter();
+    let service = ShortenerGrpcServer::new(repository, generator)
+        .with_alias_policy(alias_policy)
+        .with_max_expiration(max_expiration);
+
+    let reflection = if enable_reflection {
+        Some(wormhole_proto_schema::reflection_service()?)
+    } else {
+        None
+    };
 
     Server::builder()
         .add_service(health_service)
-        .add_service(ShortenerServiceServer::new(service))
+        .add_optional_service(reflection)
+        .add_service(ShortenerServiceServer::with_interceptor(
+            service,
+            interceptor,
+        ))
         .serve(listen_addr)
-        .await
+        .await?;
+
+    Ok(())
 }
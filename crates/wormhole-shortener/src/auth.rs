@@ -0,0 +1,170 @@
+//! API-key authentication for the shortener's gRPC service.
+//!
+//! [`ApiKeyAuth`] is a tonic [`Interceptor`] that admits a request only if
+//! its `x-api-key` metadata holds a key known to a [`KeyStore`]. Resolving
+//! stays public: the redirector never installs it.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+use tonic::service::Interceptor;
+use tonic::{Request, Status};
+use tracing::debug;
+
+/// The metadata header that carries the API key.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// The set of accepted API keys.
+pub trait KeyStore: Send + Sync + 'static {
+    /// Returns `true` if `key` may call the service.
+    fn contains(&self, key: &str) -> bool;
+}
+
+/// A fixed set of keys held in memory.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryKeyStore {
+    keys: HashSet<String>,
+}
+
+impl InMemoryKeyStore {
+    /// Creates a store accepting `keys`. Empty keys are ignored.
+    pub fn new<I, S>(keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let keys = keys
+            .into_iter()
+            .map(Into::into)
+            .filter(|key: &String| !key.is_empty())
+            .collect();
+        Self { keys }
+    }
+
+    /// Reads keys from a file, one per line. Surrounding whitespace is
+    /// trimmed, and blank lines and lines starting with `#` are skipped.
+    pub fn from_file(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        Ok(Self::new(
+            contents
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.starts_with('#')),
+        ))
+    }
+
+    /// Adds the keys of `other` to this store.
+    pub fn extend(&mut self, other: InMemoryKeyStore) {
+        self.keys.extend(other.keys);
+    }
+
+    /// Number of accepted keys.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Returns `true` if no key is accepted.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+impl KeyStore for InMemoryKeyStore {
+    fn contains(&self, key: &str) -> bool {
+        self.keys.contains(key)
+    }
+}
+
+/// Rejects requests without a valid API key with `UNAUTHENTICATED`.
+#[derive(Clone)]
+pub struct ApiKeyAuth {
+    store: Arc<dyn KeyStore>,
+}
+
+impl ApiKeyAuth {
+    pub fn new(store: impl KeyStore) -> Self {
+        Self {
+            store: Arc::new(store),
+        }
+    }
+}
+
+impl std::fmt::Debug for ApiKeyAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApiKeyAuth").finish_non_exhaustive()
+    }
+}
+
+impl Interceptor for ApiKeyAuth {
+    fn call(&mut self, request: Request<()>) -> Result<Request<()>, Status> {
+        let Some(key) = request.metadata().get(API_KEY_HEADER) else {
+            debug!("request without api key rejected");
+            return Err(Status::unauthenticated("missing api key"));
+        };
+
+        match key.to_str() {
+            Ok(key) if self.store.contains(key) => Ok(request),
+            _ => {
+                debug!("request with unknown api key rejected");
+                Err(Status::unauthenticated("invalid api key"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tonic::Code;
+
+    fn auth() -> ApiKeyAuth {
+        ApiKeyAuth::new(InMemoryKeyStore::new(["k-123", "k-456"]))
+    }
+
+    fn request(key: &str) -> Request<()> {
+        let mut request = Request::new(());
+        request
+            .metadata_mut()
+            .insert(API_KEY_HEADER, key.parse().unwrap());
+        request
+    }
+
+    #[test]
+    fn valid_key_is_admitted() {
+        assert!(auth().call(request("k-456")).is_ok());
+    }
+
+    #[test]
+    fn invalid_key_is_rejected() {
+        let status = auth().call(request("k-789")).unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+        assert_eq!(status.message(), "invalid api key");
+    }
+
+    #[test]
+    fn absent_key_is_rejected() {
+        let status = auth().call(Request::new(())).unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+        assert_eq!(status.message(), "missing api key");
+    }
+
+    #[test]
+    fn empty_key_is_never_accepted() {
+        let mut auth = ApiKeyAuth::new(InMemoryKeyStore::new([""]));
+        assert!(auth.call(request("")).is_err());
+    }
+
+    #[test]
+    fn keys_load_from_file() {
+        let path = std::env::temp_dir().join(format!("wormhole-api-keys-{}", std::process::id()));
+        std::fs::write(&path, "# partners\nk-123\n\n  k-456  \n").unwrap();
+
+        let store = InMemoryKeyStore::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(store.len(), 2);
+        assert!(store.contains("k-123"));
+        assert!(store.contains("k-456"));
+        assert!(!store.contains("# partners"));
+    }
+}
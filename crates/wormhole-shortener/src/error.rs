@@ -1,4 +1,5 @@
 use thiserror::Error;
+use tonic::{Code, Status};
 use wormhole_core::CoreError;
 
 #[derive(Debug, Clone, Error)]
@@ -9,14 +10,40 @@ pub enum ShortenerError {
     InvalidUrl(String),
     #[error("invalid short code: {0}")]
     InvalidShortCode(String),
+    #[error("alias is reserved: {0}")]
+    ReservedAlias(String),
+    #[error("idempotency key was already used for a different request: {0}")]
+    IdempotencyConflict(String),
+    #[error("invalid expiration: {0}")]
+    InvalidExpiration(String),
     #[error("storage error: {0}")]
     Storage(String),
+    #[error("failed to generate a short code: {0}")]
+    GenerationFailed(String),
 }
 
 impl From<CoreError> for ShortenerError {
     fn from(value: CoreError) -> Self {
         match value {
             CoreError::InvalidShortCode(message) => Self::InvalidShortCode(message),
+            CoreError::ReservedAlias(code) => Self::ReservedAlias(code),
+            CoreError::InvalidBaseUrl(message) => Self::InvalidUrl(message),
+            CoreError::InvalidAliasPolicy(message) => Self::InvalidShortCode(message),
         }
     }
 }
+
+impl From<ShortenerError> for Status {
+    fn from(error: ShortenerError) -> Self {
+        let code = match &error {
+            ShortenerError::AliasConflict(_) => Code::AlreadyExists,
+            ShortenerError::InvalidUrl(_)
+            | ShortenerError::InvalidShortCode(_)
+            | ShortenerError::ReservedAlias(_)
+            | ShortenerError::InvalidExpiration(_) => Code::InvalidArgument,
+            ShortenerError::IdempotencyConflict(_) => Code::FailedPrecondition,
+            ShortenerError::Storage(_) | ShortenerError::GenerationFailed(_) => Code::Internal,
+        };
+        Status::new(code, error.to_string())
+    }
+}
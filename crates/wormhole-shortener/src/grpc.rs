@@ -1,19 +1,61 @@
+use crate::error::ShortenerError;
+use crate::service::{reject_control_characters, reject_oversized_url};
+use crate::shortener::{ExpirationPolicy, DEFAULT_MAX_EXPIRATION, DEFAULT_MAX_URL_LEN};
+use std::time::Duration;
 use tonic::{Request, Response, Status};
-use wormhole_core::{ShortCode, UrlRecord};
+use wormhole_core::{AliasPolicy, BaseUrl, RedirectKind, ShortCode, UrlRecord};
 use wormhole_generator::Generator;
+use wormhole_proto_schema::shortener::v1 as shortener_proto;
 use wormhole_proto_schema::v1 as proto;
 use wormhole_proto_schema::v1::shortener_service_server::ShortenerService;
-use wormhole_proto_schema::v1::{ShortCode as ProtoShortCode, ShortCodeKind};
+use wormhole_proto_schema::v1::{ConversionError, ShortCode as ProtoShortCode, ShortCodeKind};
 use wormhole_storage::Repository;
 
 pub struct ShortenerGrpcServer<R: Repository, G: Generator> {
     storage: R,
     generator: G,
+    alias_policy: AliasPolicy,
+    max_expiration: Duration,
+    max_url_len: usize,
+    base_url: Option<BaseUrl>,
 }
 
 impl<R: Repository, G: Generator> ShortenerGrpcServer<R, G> {
     pub fn new(storage: R, generator: G) -> Self {
-        Self { storage, generator }
+        Self {
+            storage,
+            generator,
+            alias_policy: AliasPolicy::default(),
+            max_expiration: DEFAULT_MAX_EXPIRATION,
+            max_url_len: DEFAULT_MAX_URL_LEN,
+            base_url: None,
+        }
+    }
+
+    /// Builds `short_url` in responses on `base_url` unless the request
+    /// names its own base.
+    pub fn with_base_url(mut self, base_url: BaseUrl) -> Self {
+        self.base_url = Some(base_url);
+        self
+    }
+
+    /// Rejects expirations more than `max_expiration` in the future.
+    pub fn with_max_expiration(mut self, max_expiration: Duration) -> Self {
+        self.max_expiration = max_expiration;
+        self
+    }
+
+    /// Rejects URLs longer than `max_url_len` bytes. See
+    /// [`ShortenerService::with_max_url_len`](crate::service::ShortenerService::with_max_url_len).
+    pub fn with_max_url_len(mut self, max_url_len: usize) -> Self {
+        self.max_url_len = max_url_len;
+        self
+    }
+
+    /// Checks custom aliases against `policy` instead of the default rules.
+    pub fn with_alias_policy(mut self, alias_policy: AliasPolicy) -> Self {
+        self.alias_policy = alias_policy;
+        self
     }
 }
 
@@ -31,6 +73,11 @@ impl<R: Repository, G: Generator> ShortenerService for ShortenerGrpcServer<R, G>
             return Err(Status::invalid_argument("URL cannot be empty"));
         }
 
+        reject_oversized_url(&original_url, self.max_url_len)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        reject_control_characters(&original_url)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+
         // Check for valid scheme
         let parts: Vec<&str> = original_url.split("://").collect();
         if parts.len() < 2 || parts[0].is_empty() || parts[1].is_empty() {
@@ -43,7 +90,18 @@ impl<R: Repository, G: Generator> ShortenerService for ShortenerGrpcServer<R, G>
             return Err(Status::invalid_argument("URL scheme must be http or https"));
         }
 
+        // Validate the base up front so a bad one does not leave a stored
+        // code behind.
+        let base_url = req
+            .base_url
+            .as_deref()
+            .map(BaseUrl::parse)
+            .transpose()
+            .map_err(|e| Status::from(ShortenerError::from(e)))?
+            .or_else(|| self.base_url.clone());
+
         // Convert optional expiration timestamp
+        let now = jiff::Timestamp::now();
         let expire_at = req
             .expire_at
             .map(|ts| {
@@ -53,19 +111,29 @@ impl<R: Repository, G: Generator> ShortenerService for ShortenerGrpcServer<R, G>
                     .map_err(|_| Status::invalid_argument("invalid expiration timestamp"))
             })
             .transpose()?;
+        let expire_at = match expire_at {
+            Some(expire_at) => ExpirationPolicy::AtTimestamp(expire_at)
+                .expire_at(now, self.max_expiration)
+                .map_err(Status::from)?,
+            None => None,
+        };
 
         // Determine the short code to use
         let short_code = match req.custom_alias {
             Some(alias) => {
                 // Validate and create custom alias
-                let code = ShortCode::custom(&alias).map_err(|e| {
-                    Status::invalid_argument(format!("invalid custom alias: {}", e))
-                })?;
+                let code =
+                    ShortCode::custom_with_policy(alias, &self.alias_policy).map_err(|e| {
+                        Status::invalid_argument(format!("invalid custom alias: {}", e))
+                    })?;
                 code
             }
             None => {
                 // Generate new short code
-                self.generator.generate().into()
+                self.generator
+                    .try_generate()
+                    .map_err(|e| Status::from(ShortenerError::GenerationFailed(e.to_string())))?
+                    .into()
             }
         };
 
@@ -73,6 +141,12 @@ impl<R: Repository, G: Generator> ShortenerService for ShortenerGrpcServer<R, G>
         let record = UrlRecord {
             original_url,
             expire_at,
+            redirect_kind: RedirectKind::default(),
+            created_at: now,
+            internal_only: req.internal_only,
+            no_store: req.no_store,
+            owner_id: None,
+            metadata: Default::default(),
         };
 
         // Store in repository
@@ -92,10 +166,77 @@ impl<R: Repository, G: Generator> ShortenerService for ShortenerGrpcServer<R, G>
                 code: short_code.to_string(),
                 kind: kind as i32,
             }),
+            short_url: base_url
+                .map(|base_url| short_code.to_url(&base_url))
+                .unwrap_or_default(),
         };
 
         Ok(Response::new(response))
     }
+
+    async fn resolve(
+        &self,
+        request: Request<shortener_proto::ResolveRequest>,
+    ) -> Result<Response<shortener_proto::ResolveResponse>, Status> {
+        let short_code = required_short_code(request.into_inner().short_code)?;
+
+        let record = self
+            .storage
+            .get(&short_code)
+            .await
+            .map_err(Status::from)?
+            // Storage may hand back a record that expired since it was cached.
+            .filter(|record| {
+                record
+                    .expire_at
+                    .is_none_or(|expire_at| jiff::Timestamp::now() < expire_at)
+            })
+            .ok_or_else(|| Status::not_found("short code not found"))?;
+
+        let UrlRecord {
+            original_url,
+            expire_at,
+            redirect_kind,
+            internal_only,
+            ..
+        } = record;
+
+        Ok(Response::new(shortener_proto::ResolveResponse {
+            url_record: Some(proto::UrlRecord {
+                original_url,
+                expire_at: expire_at.map(|expire_at| prost_types::Timestamp {
+                    seconds: expire_at.as_second(),
+                    nanos: expire_at.subsec_nanosecond(),
+                }),
+                redirect_kind: proto::RedirectKind::from(redirect_kind) as i32,
+                internal_only,
+            }),
+        }))
+    }
+
+    async fn delete(
+        &self,
+        request: Request<shortener_proto::DeleteRequest>,
+    ) -> Result<Response<shortener_proto::DeleteResponse>, Status> {
+        let short_code = required_short_code(request.into_inner().short_code)?;
+
+        let existed = self
+            .storage
+            .delete(&short_code)
+            .await
+            .map_err(Status::from)?;
+
+        Ok(Response::new(shortener_proto::DeleteResponse { existed }))
+    }
+}
+
+/// Converts the short code of a request, which must be present.
+fn required_short_code(short_code: Option<ProtoShortCode>) -> Result<ShortCode, Status> {
+    let short_code =
+        short_code.ok_or_else(|| Status::invalid_argument("short code is required"))?;
+    short_code
+        .try_into()
+        .map_err(|e: ConversionError| ShortenerError::InvalidShortCode(e.to_string()).into())
 }
 
 #[cfg(test)]
@@ -105,10 +246,14 @@ mod tests {
     use prost_types::Timestamp;
     use tonic::Request;
     use wormhole_generator::seq::SeqGenerator;
+    use wormhole_proto_schema::shortener::v1 as shortener_proto;
     use wormhole_proto_schema::v1 as proto;
     use wormhole_proto_schema::v1::shortener_service_server::ShortenerService;
     use wormhole_proto_schema::v1::ShortCodeKind;
-    use wormhole_storage::{InMemoryRepository, ReadRepository, Repository, StorageError};
+    use wormhole_storage::{
+        CodeStatus, InMemoryRepository, ReadRepository, Repository, ScanCursor, ScanPage,
+        StorageError,
+    };
 
     #[derive(Debug, Clone, Default)]
     struct InsertOnlyConflictRepo;
@@ -127,6 +272,21 @@ mod tests {
                 "exists() must not be called".to_string(),
             ))
         }
+
+        async fn status(
+            &self,
+            _code: &wormhole_core::ShortCode,
+        ) -> wormhole_storage::Result<CodeStatus> {
+            Ok(CodeStatus::NotFound)
+        }
+
+        async fn scan(
+            &self,
+            _cursor: Option<ScanCursor>,
+            _limit: usize,
+        ) -> wormhole_storage::Result<ScanPage> {
+            Ok(ScanPage::default())
+        }
     }
 
     #[async_trait]
@@ -161,6 +321,9 @@ mod tests {
             original_url: original_url.into(),
             expire_at,
             custom_alias,
+            internal_only: false,
+            no_store: false,
+            base_url: None,
         }
     }
 
@@ -182,6 +345,106 @@ mod tests {
         assert_eq!(short_code.kind, ShortCodeKind::Custom as i32);
     }
 
+    #[tokio::test]
+    async fn create_builds_short_url_on_the_requested_base() {
+        async fn short_url(
+            server: &TestServer,
+            base_url: Option<&str>,
+            alias: &str,
+        ) -> Result<String, tonic::Status> {
+            let mut request = create_request("https://example.com", None, Some(alias.to_string()));
+            request.base_url = base_url.map(str::to_string);
+            let response = server.create(Request::new(request)).await?;
+            Ok(response.into_inner().short_url)
+        }
+
+        let server = test_server()
+            .with_base_url(wormhole_core::BaseUrl::parse("https://worm.hole/").unwrap());
+
+        assert_eq!(
+            short_url(&server, None, "brand-none").await.unwrap(),
+            "https://worm.hole/brand-none"
+        );
+        assert_eq!(
+            short_url(&server, Some("https://go.brand-a.com/"), "brand-a")
+                .await
+                .unwrap(),
+            "https://go.brand-a.com/brand-a"
+        );
+        assert_eq!(
+            short_url(&server, Some("https://l.brand-b.io/s"), "brand-b")
+                .await
+                .unwrap(),
+            "https://l.brand-b.io/s/brand-b"
+        );
+
+        let status = short_url(&server, Some("go.brand-c.com"), "brand-c")
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        let code = wormhole_core::ShortCode::new_unchecked("brand-c");
+        assert!(server.storage.get(&code).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn create_enforces_the_url_length_limit() {
+        let server = test_server().with_max_url_len(32);
+        let url = |len: usize| format!("https://example.com/{}", "a".repeat(len - 20));
+
+        server
+            .create(Request::new(create_request(url(32), None, None)))
+            .await
+            .unwrap();
+        let status = server
+            .create(Request::new(create_request(url(33), None, None)))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert!(status.message().contains("32"), "{}", status.message());
+    }
+
+    #[tokio::test]
+    async fn create_leaves_short_url_empty_without_a_base() {
+        let server = test_server();
+        let request = Request::new(create_request("https://example.com", None, None));
+
+        let response = server.create(request).await.unwrap().into_inner();
+
+        assert!(response.short_code.is_some());
+        assert!(response.short_url.is_empty());
+    }
+
+    #[tokio::test]
+    async fn create_rejects_reserved_aliases() {
+        let server = test_server()
+            .with_alias_policy(wormhole_core::AliasPolicy::new().with_reserved(["health"]));
+
+        for alias in ["health", "Health", "HEALTH"] {
+            let request = Request::new(create_request(
+                "https://example.com",
+                None,
+                Some(alias.to_string()),
+            ));
+            let status = server.create(request).await.unwrap_err();
+            assert_eq!(status.code(), tonic::Code::InvalidArgument, "{alias}");
+            assert!(status.message().contains("reserved"), "{alias}");
+        }
+    }
+
+    #[tokio::test]
+    async fn create_rejects_urls_with_control_characters() {
+        let server = test_server();
+
+        for url in [
+            "https://example.com/\r\nX-Injected: 1",
+            "https://example.com/\0",
+        ] {
+            let request = Request::new(create_request(url, None, None));
+            let status = server.create(request).await.unwrap_err();
+            assert_eq!(status.code(), tonic::Code::InvalidArgument, "{url:?}");
+        }
+    }
+
     #[tokio::test]
     async fn create_with_duplicate_alias_fails() {
         let server = test_server();
@@ -222,4 +485,129 @@ mod tests {
         let status = result.expect_err("create should fail with conflict");
         assert_eq!(status.code(), tonic::Code::AlreadyExists);
     }
+
+    async fn create_code(server: &TestServer, custom_alias: Option<&str>) -> proto::ShortCode {
+        let request = Request::new(create_request(
+            "https://example.com",
+            None,
+            custom_alias.map(str::to_string),
+        ));
+        server
+            .create(request)
+            .await
+            .unwrap()
+            .into_inner()
+            .short_code
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn resolve_returns_the_created_record() {
+        let server = test_server();
+
+        for alias in [None, Some("my-alias")] {
+            let short_code = create_code(&server, alias).await;
+            let request = Request::new(shortener_proto::ResolveRequest {
+                short_code: Some(short_code),
+            });
+            let record = server
+                .resolve(request)
+                .await
+                .unwrap()
+                .into_inner()
+                .url_record
+                .unwrap();
+
+            assert_eq!(record.original_url, "https://example.com");
+            assert_eq!(record.expire_at, None);
+        }
+    }
+
+    #[tokio::test]
+    async fn resolve_unknown_code_is_not_found() {
+        let server = test_server();
+
+        let request = Request::new(shortener_proto::ResolveRequest {
+            short_code: Some(proto::ShortCode {
+                code: "missing".to_string(),
+                kind: ShortCodeKind::Custom as i32,
+            }),
+        });
+        let status = server.resolve(request).await.unwrap_err();
+
+        assert_eq!(status.code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn resolve_and_delete_require_a_valid_short_code() {
+        let server = test_server();
+
+        let status = server
+            .resolve(Request::new(shortener_proto::ResolveRequest {
+                short_code: None,
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+        let status = server
+            .delete(Request::new(shortener_proto::DeleteRequest {
+                short_code: Some(proto::ShortCode {
+                    code: "a b".to_string(),
+                    kind: ShortCodeKind::Custom as i32,
+                }),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn delete_reports_whether_the_code_existed() {
+        let server = test_server();
+        let short_code = create_code(&server, Some("my-alias")).await;
+
+        let delete = |short_code: proto::ShortCode| {
+            server.delete(Request::new(shortener_proto::DeleteRequest {
+                short_code: Some(short_code),
+            }))
+        };
+        assert!(
+            delete(short_code.clone())
+                .await
+                .unwrap()
+                .into_inner()
+                .existed
+        );
+        assert!(
+            !delete(short_code.clone())
+                .await
+                .unwrap()
+                .into_inner()
+                .existed
+        );
+
+        let status = server
+            .resolve(Request::new(shortener_proto::ResolveRequest {
+                short_code: Some(short_code),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn create_rejects_past_expirations() {
+        let server = test_server();
+
+        let past = Timestamp {
+            seconds: jiff::Timestamp::now().as_second() - 60,
+            nanos: 0,
+        };
+        let request = Request::new(create_request("https://example.com", Some(past), None));
+        let status = server.create(request).await.unwrap_err();
+
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert!(status.message().contains("expiration"));
+    }
 }
@@ -0,0 +1,114 @@
+//! Startup health reporting for the gRPC health protocol.
+
+use tonic::server::NamedService;
+use tonic_health::server::HealthReporter;
+use tracing::{info, warn};
+use wormhole_storage::{ReadRepository, StorageError};
+
+/// Pings `repository` and reports service `S` as `SERVING` if it answers,
+/// or `NOT_SERVING` otherwise.
+///
+/// The ping error is returned so the caller can decide whether to keep
+/// running; probes see `NOT_SERVING` in the meantime.
+pub async fn report_repository_health<S, R>(
+    reporter: &HealthReporter,
+    repository: &R,
+) -> Result<(), StorageError>
+where
+    S: NamedService,
+    R: ReadRepository,
+{
+    match repository.ping().await {
+        Ok(()) => {
+            info!(service = S::NAME, "storage reachable, reporting SERVING");
+            reporter.set_serving::<S>().await;
+            Ok(())
+        }
+        Err(e) => {
+            warn!(service = S::NAME, error = %e, "storage ping failed, reporting NOT_SERVING");
+            reporter.set_not_serving::<S>().await;
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grpc::ShortenerGrpcServer;
+    use async_trait::async_trait;
+    use tonic::Request;
+    use tonic_health::pb::health_check_response::ServingStatus;
+    use tonic_health::pb::health_server::Health;
+    use tonic_health::pb::HealthCheckRequest;
+    use tonic_health::server::HealthService;
+    use wormhole_core::{ShortCode, UrlRecord};
+    use wormhole_generator::seq::SeqGenerator;
+    use wormhole_proto_schema::v1::shortener_service_server::ShortenerServiceServer;
+    use wormhole_storage::{CodeStatus, InMemoryRepository, ScanCursor, ScanPage};
+
+    type Server = ShortenerServiceServer<ShortenerGrpcServer<InMemoryRepository, SeqGenerator>>;
+
+    struct UnreachableRepo;
+
+    #[async_trait]
+    impl ReadRepository for UnreachableRepo {
+        async fn get(&self, _code: &ShortCode) -> wormhole_storage::Result<Option<UrlRecord>> {
+            unreachable!()
+        }
+
+        async fn exists(&self, _code: &ShortCode) -> wormhole_storage::Result<bool> {
+            unreachable!()
+        }
+
+        async fn status(&self, _code: &ShortCode) -> wormhole_storage::Result<CodeStatus> {
+            unreachable!()
+        }
+
+        async fn scan(
+            &self,
+            _cursor: Option<ScanCursor>,
+            _limit: usize,
+        ) -> wormhole_storage::Result<ScanPage> {
+            unreachable!()
+        }
+
+        async fn ping(&self) -> wormhole_storage::Result<()> {
+            Err(StorageError::Unavailable("connection refused".to_string()))
+        }
+    }
+
+    async fn status_of(reporter: &HealthReporter) -> ServingStatus {
+        let service = HealthService::from_health_reporter(reporter.clone());
+        let response = service
+            .check(Request::new(HealthCheckRequest {
+                service: <Server as NamedService>::NAME.to_string(),
+            }))
+            .await
+            .unwrap();
+        response.into_inner().status()
+    }
+
+    #[tokio::test]
+    async fn reports_serving_after_successful_ping() {
+        let (reporter, _) = tonic_health::server::health_reporter();
+
+        report_repository_health::<Server, _>(&reporter, &InMemoryRepository::new())
+            .await
+            .unwrap();
+
+        assert_eq!(status_of(&reporter).await, ServingStatus::Serving);
+    }
+
+    #[tokio::test]
+    async fn reports_not_serving_when_ping_fails() {
+        let (reporter, _) = tonic_health::server::health_reporter();
+
+        let err = report_repository_health::<Server, _>(&reporter, &UnreachableRepo)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, StorageError::Unavailable(_)));
+        assert_eq!(status_of(&reporter).await, ServingStatus::NotServing);
+    }
+}
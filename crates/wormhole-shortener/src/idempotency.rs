@@ -0,0 +1,53 @@
+//! Deduplication of retried `shorten` calls by idempotency key.
+
+use moka::future::Cache;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use wormhole_core::ShortCode;
+
+/// The outcome recorded for a key.
+#[derive(Debug, Clone)]
+pub(crate) struct Completed {
+    pub(crate) original_url: String,
+    pub(crate) short_code: ShortCode,
+}
+
+/// A key's slot. Requests for the same key hold its lock in turn, so a retry
+/// that races the original waits for it instead of minting a second code.
+pub(crate) type Slot = Arc<Mutex<Option<Completed>>>;
+
+/// Remembers which short code each idempotency key produced.
+///
+/// Entries live in memory for a fixed TTL, measured from the first request
+/// with that key, and are not shared between shortener instances. Retries
+/// must therefore reach the same instance within the TTL to be deduplicated.
+#[derive(Debug, Clone)]
+pub struct IdempotencyStore {
+    slots: Cache<String, Slot>,
+}
+
+impl IdempotencyStore {
+    /// Creates a store holding up to `max_keys` keys for `ttl` each.
+    pub fn new(max_keys: u64, ttl: Duration) -> Self {
+        let slots = Cache::builder()
+            .max_capacity(max_keys)
+            .time_to_live(ttl)
+            .build();
+        Self { slots }
+    }
+
+    /// Returns the slot for `key`, creating an empty one if needed.
+    pub(crate) async fn slot(&self, key: &str) -> Slot {
+        self.slots
+            .get_with_by_ref(key, async { Arc::new(Mutex::new(None)) })
+            .await
+    }
+}
+
+impl Default for IdempotencyStore {
+    /// Holds up to 100,000 keys for 24 hours.
+    fn default() -> Self {
+        Self::new(100_000, Duration::from_secs(24 * 60 * 60))
+    }
+}
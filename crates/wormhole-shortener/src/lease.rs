@@ -0,0 +1,201 @@
+//! Cluster-wide ownership of the generator node id.
+//!
+//! Tinyflake ids embed the node id, so two shorteners sharing one would
+//! hand out colliding codes. A [`NodeIdLease`] claims the id in Redis with
+//! `SET NX` and a TTL, and a background task keeps renewing it while the
+//! process runs. If the process dies, the lease simply expires.
+
+use redis::aio::MultiplexedConnection;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+use typed_builder::TypedBuilder;
+
+/// Extends the lease only if this process still holds it.
+const RENEW_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("PEXPIRE", KEYS[1], ARGV[2])
+else
+    return 0
+end
+"#;
+
+/// Deletes the lease only if this process still holds it.
+const RELEASE_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("DEL", KEYS[1])
+else
+    return 0
+end
+"#;
+
+#[derive(Debug, Error)]
+pub enum LeaseError {
+    #[error("node id {0} is already claimed by another process")]
+    AlreadyClaimed(u8),
+    #[error("redis error: {0}")]
+    Redis(#[from] redis::RedisError),
+}
+
+/// Settings for [`NodeIdLease`].
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct NodeIdLeaseConfig {
+    /// Prefix of the Redis key; the node id is appended.
+    #[builder(default = "wormhole:node:".to_string(), setter(into))]
+    pub key_prefix: String,
+    /// How long a claim survives without renewal.
+    #[builder(default = Duration::from_secs(30))]
+    pub ttl: Duration,
+    /// How often the renewal task extends the claim. Keep this well below
+    /// `ttl` so a slow renewal does not let the claim lapse.
+    #[builder(default = Duration::from_secs(10))]
+    pub renew_interval: Duration,
+}
+
+impl Default for NodeIdLeaseConfig {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// An exclusive claim on a generator node id.
+#[derive(Debug, Clone)]
+pub struct NodeIdLease {
+    conn: MultiplexedConnection,
+    node_id: u8,
+    key: String,
+    token: String,
+    config: NodeIdLeaseConfig,
+    held: Arc<AtomicBool>,
+}
+
+impl NodeIdLease {
+    /// Claims `node_id`, failing with [`LeaseError::AlreadyClaimed`] if
+    /// another process holds it.
+    ///
+    /// The claim lasts for [`NodeIdLeaseConfig::ttl`]; call
+    /// [`NodeIdLease::spawn_renewal`] to keep it.
+    pub async fn acquire(
+        conn: MultiplexedConnection,
+        node_id: u8,
+        config: NodeIdLeaseConfig,
+    ) -> Result<Self, LeaseError> {
+        let key = format!("{}{node_id}", config.key_prefix);
+        let token = lease_token();
+
+        let mut claim_conn = conn.clone();
+        let claimed: Option<String> = redis::cmd("SET")
+            .arg(&key)
+            .arg(&token)
+            .arg("NX")
+            .arg("PX")
+            .arg(ttl_millis(config.ttl))
+            .query_async(&mut claim_conn)
+            .await?;
+        if claimed.is_none() {
+            return Err(LeaseError::AlreadyClaimed(node_id));
+        }
+
+        info!(node_id, key = %key, "claimed generator node id");
+        Ok(Self {
+            conn,
+            node_id,
+            key,
+            token,
+            config,
+            held: Arc::new(AtomicBool::new(true)),
+        })
+    }
+
+    /// Returns the claimed node id.
+    pub fn node_id(&self) -> u8 {
+        self.node_id
+    }
+
+    /// Returns `false` once a renewal found the claim taken over, or after
+    /// [`NodeIdLease::release`].
+    pub fn is_held(&self) -> bool {
+        self.held.load(Ordering::Relaxed)
+    }
+
+    /// Extends the claim by another [`NodeIdLeaseConfig::ttl`].
+    ///
+    /// Returns `false` if the claim had already expired and been lost.
+    pub async fn renew(&self) -> Result<bool, LeaseError> {
+        let mut conn = self.conn.clone();
+        let renewed: i64 = redis::Script::new(RENEW_SCRIPT)
+            .key(&self.key)
+            .arg(&self.token)
+            .arg(ttl_millis(self.config.ttl))
+            .invoke_async(&mut conn)
+            .await?;
+
+        let renewed = renewed == 1;
+        if !renewed {
+            self.held.store(false, Ordering::Relaxed);
+        }
+        Ok(renewed)
+    }
+
+    /// Spawns a task that renews the claim every
+    /// [`renew_interval`](NodeIdLeaseConfig::renew_interval).
+    ///
+    /// The task stops once the claim is lost. Redis errors are logged and
+    /// retried on the next tick, since the claim may still be valid.
+    pub fn spawn_renewal(&self) -> JoinHandle<()> {
+        let lease = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(lease.config.renew_interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            // The first tick completes immediately and the claim is fresh.
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                match lease.renew().await {
+                    Ok(true) => debug!(node_id = lease.node_id, "renewed node id lease"),
+                    Ok(false) => {
+                        error!(
+                            node_id = lease.node_id,
+                            "lost node id lease, generated codes may collide"
+                        );
+                        return;
+                    }
+                    Err(e) => {
+                        warn!(node_id = lease.node_id, error = %e, "failed to renew node id lease")
+                    }
+                }
+            }
+        })
+    }
+
+    /// Gives up the claim so another process can take the node id at once.
+    ///
+    /// Returns `false` if the claim had already been lost.
+    pub async fn release(&self) -> Result<bool, LeaseError> {
+        self.held.store(false, Ordering::Relaxed);
+
+        let mut conn = self.conn.clone();
+        let released: i64 = redis::Script::new(RELEASE_SCRIPT)
+            .key(&self.key)
+            .arg(&self.token)
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(released == 1)
+    }
+}
+
+fn ttl_millis(ttl: Duration) -> u64 {
+    u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX).max(1)
+}
+
+/// Identifies this process as the lease holder.
+fn lease_token() -> String {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    format!("{}-{nanos}", std::process::id())
+}
@@ -3,8 +3,14 @@
 //! This crate provides the shortener service implementation and the
 //! code generator trait. Core types are re-exported from `wormhole_core`.
 
+pub mod auth;
 pub mod error;
 pub mod grpc;
+pub mod health;
+pub mod idempotency;
+pub mod lease;
+mod metrics;
+pub mod rate_limit;
 pub mod service;
 pub mod shortener;
 
@@ -0,0 +1,24 @@
+//! Hooks into `wormhole-metrics` that compile to nothing unless the `metrics`
+//! feature is enabled.
+
+use crate::ShortenerError;
+use wormhole_core::ShortCode;
+
+#[cfg(feature = "metrics")]
+pub(crate) fn record_shorten(result: &Result<ShortCode, ShortenerError>) {
+    let label = match result {
+        Ok(_) => "ok",
+        Err(ShortenerError::AliasConflict(_)) => "alias_conflict",
+        Err(ShortenerError::InvalidUrl(_)) => "invalid_url",
+        Err(ShortenerError::InvalidShortCode(_)) => "invalid_short_code",
+        Err(ShortenerError::ReservedAlias(_)) => "reserved_alias",
+        Err(ShortenerError::InvalidExpiration(_)) => "invalid_expiration",
+        Err(ShortenerError::IdempotencyConflict(_)) => "idempotency_conflict",
+        Err(ShortenerError::Storage(_)) => "storage_error",
+        Err(ShortenerError::GenerationFailed(_)) => "generation_failed",
+    };
+    wormhole_metrics::metrics().record_shorten(label);
+}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) fn record_shorten(_result: &Result<ShortCode, ShortenerError>) {}
@@ -0,0 +1,285 @@
+//! Per-client rate limiting for the shortener's gRPC service.
+//!
+//! [`RateLimiter`] is a tonic [`Interceptor`] that keeps one token bucket per
+//! client. Each request takes a token; an empty bucket rejects the request
+//! with `RESOURCE_EXHAUSTED` until it refills. Clients are told apart by a
+//! [`KeyExtractor`].
+
+use dashmap::DashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tonic::service::Interceptor;
+use tonic::{Request, Status};
+use tracing::{debug, warn};
+use typed_builder::TypedBuilder;
+
+/// The client a bucket belongs to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Key {
+    /// The peer address of the connection.
+    Ip(IpAddr),
+    /// The value of the API key header.
+    ApiKey(String),
+}
+
+/// How [`RateLimiter`] identifies the client behind a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyExtractor {
+    /// Limit by peer IP address.
+    PeerIp,
+    /// Limit by the value of the named metadata header, falling back to the
+    /// peer IP for requests that do not send it. The name must be
+    /// lowercase, as gRPC metadata keys are.
+    Header(String),
+}
+
+impl KeyExtractor {
+    /// Returns the key for `request`, or `None` if the request carries
+    /// neither the header nor a peer address.
+    pub fn extract<T>(&self, request: &Request<T>) -> Option<Key> {
+        if let Self::Header(name) = self {
+            let value = request
+                .metadata()
+                .get(name.as_str())
+                .and_then(|value| value.to_str().ok());
+            if let Some(value) = value {
+                return Some(Key::ApiKey(value.to_string()));
+            }
+        }
+        request.remote_addr().map(|addr| Key::Ip(addr.ip()))
+    }
+}
+
+/// Settings for [`RateLimiter`].
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct RateLimitConfig {
+    /// Requests a client may send back to back before being limited.
+    #[builder(default = 20)]
+    pub burst: u32,
+    /// Tokens added to each bucket per second.
+    #[builder(default = 1.0)]
+    pub refill_per_second: f64,
+    /// How clients are told apart.
+    #[builder(default = KeyExtractor::PeerIp)]
+    pub key: KeyExtractor,
+    /// Buckets unused for this long are dropped by the cleanup task. A
+    /// dropped bucket starts full again, so keep this at least as long as a
+    /// bucket takes to refill.
+    #[builder(default = Duration::from_secs(10 * 60))]
+    pub idle_timeout: Duration,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// A token bucket holding up to `burst` tokens.
+#[derive(Debug, Clone, Copy)]
+pub struct TokenBucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    /// Creates a full bucket.
+    pub fn full(burst: u32, now: Instant) -> Self {
+        Self {
+            tokens: f64::from(burst),
+            updated_at: now,
+        }
+    }
+
+    /// Refills the bucket for the time elapsed since its last use, then
+    /// takes one token if there is one.
+    pub fn try_acquire(&mut self, now: Instant, burst: u32, refill_per_second: f64) -> bool {
+        let elapsed = now.saturating_duration_since(self.updated_at);
+        self.tokens =
+            (self.tokens + elapsed.as_secs_f64() * refill_per_second).min(f64::from(burst));
+        self.updated_at = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// When the bucket was last used.
+    pub fn updated_at(&self) -> Instant {
+        self.updated_at
+    }
+}
+
+/// A token-bucket rate limiter shared by every clone.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    buckets: Arc<DashMap<Key, TokenBucket>>,
+    config: Arc<RateLimitConfig>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            buckets: Arc::new(DashMap::new()),
+            config: Arc::new(config),
+        }
+    }
+
+    /// Takes a token from `key`'s bucket, returning `false` if it is empty.
+    pub fn check_at(&self, key: Key, now: Instant) -> bool {
+        let RateLimitConfig {
+            burst,
+            refill_per_second,
+            ..
+        } = *self.config;
+        self.buckets
+            .entry(key)
+            .or_insert_with(|| TokenBucket::full(burst, now))
+            .try_acquire(now, burst, refill_per_second)
+    }
+
+    /// Drops buckets unused since `now - idle_timeout` and returns how many
+    /// were dropped.
+    pub fn evict_idle_at(&self, now: Instant) -> usize {
+        let before = self.buckets.len();
+        self.buckets.retain(|_, bucket| {
+            now.saturating_duration_since(bucket.updated_at()) < self.config.idle_timeout
+        });
+        before.saturating_sub(self.buckets.len())
+    }
+
+    /// Number of clients currently tracked.
+    pub fn tracked(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// Spawns a task that drops idle buckets every `interval`, so clients
+    /// that went away do not hold memory forever.
+    pub fn spawn_cleanup(&self, interval: Duration) -> JoinHandle<()> {
+        let limiter = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                let evicted = limiter.evict_idle_at(Instant::now());
+                if evicted > 0 {
+                    debug!(
+                        evicted,
+                        tracked = limiter.tracked(),
+                        "dropped idle rate limit buckets"
+                    );
+                }
+            }
+        })
+    }
+}
+
+impl Interceptor for RateLimiter {
+    fn call(&mut self, request: Request<()>) -> Result<Request<()>, Status> {
+        // Without a key there is nothing to limit by, e.g. over a Unix socket.
+        let Some(key) = self.config.key.extract(&request) else {
+            return Ok(request);
+        };
+
+        if self.check_at(key.clone(), Instant::now()) {
+            Ok(request)
+        } else {
+            warn!(?key, "rate limit exceeded");
+            Err(Status::resource_exhausted("rate limit exceeded"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tonic::Code;
+
+    fn limiter(burst: u32, refill_per_second: f64) -> RateLimiter {
+        RateLimiter::new(
+            RateLimitConfig::builder()
+                .burst(burst)
+                .refill_per_second(refill_per_second)
+                .key(KeyExtractor::Header("x-api-key".to_string()))
+                .idle_timeout(Duration::from_secs(60))
+                .build(),
+        )
+    }
+
+    fn request(api_key: &str) -> Request<()> {
+        let mut request = Request::new(());
+        request
+            .metadata_mut()
+            .insert("x-api-key", api_key.parse().unwrap());
+        request
+    }
+
+    #[test]
+    fn bucket_allows_burst_then_refills() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::full(2, start);
+
+        assert!(bucket.try_acquire(start, 2, 1.0));
+        assert!(bucket.try_acquire(start, 2, 1.0));
+        assert!(!bucket.try_acquire(start, 2, 1.0));
+
+        assert!(!bucket.try_acquire(start + Duration::from_millis(500), 2, 1.0));
+        assert!(bucket.try_acquire(start + Duration::from_secs(1), 2, 1.0));
+    }
+
+    #[test]
+    fn bucket_never_holds_more_than_burst() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::full(2, start);
+        let later = start + Duration::from_secs(3600);
+
+        assert!(bucket.try_acquire(later, 2, 1.0));
+        assert!(bucket.try_acquire(later, 2, 1.0));
+        assert!(!bucket.try_acquire(later, 2, 1.0));
+    }
+
+    #[test]
+    fn interceptor_rejects_once_the_bucket_is_empty() {
+        let mut limiter = limiter(3, 0.001);
+
+        for _ in 0..3 {
+            assert!(limiter.call(request("bot")).is_ok());
+        }
+        let status = limiter.call(request("bot")).unwrap_err();
+        assert_eq!(status.code(), Code::ResourceExhausted);
+
+        // Other clients have their own buckets.
+        assert!(limiter.call(request("human")).is_ok());
+    }
+
+    #[test]
+    fn requests_without_a_key_are_not_limited() {
+        let mut limiter = limiter(1, 0.001);
+
+        for _ in 0..5 {
+            assert!(limiter.call(Request::new(())).is_ok());
+        }
+        assert_eq!(limiter.tracked(), 0);
+    }
+
+    #[test]
+    fn idle_buckets_are_evicted() {
+        let limiter = limiter(1, 1.0);
+        let start = Instant::now();
+
+        limiter.check_at(Key::ApiKey("old".to_string()), start);
+        limiter.check_at(
+            Key::ApiKey("recent".to_string()),
+            start + Duration::from_secs(50),
+        );
+
+        assert_eq!(limiter.evict_idle_at(start + Duration::from_secs(70)), 1);
+        assert_eq!(limiter.tracked(), 1);
+    }
+}
@@ -1,9 +1,16 @@
-use crate::shortener::{ExpirationPolicy, ShortenParams, Shortener};
+use crate::idempotency::{Completed, IdempotencyStore};
+use crate::metrics;
+use crate::shortener::{
+    ConflictPolicy, ShortenParams, Shortener, DEFAULT_MAX_EXPIRATION, DEFAULT_MAX_URL_LEN,
+};
 use crate::ShortenerError;
 use async_trait::async_trait;
 use jiff::Timestamp;
 use std::sync::Arc;
-use wormhole_core::{ShortCode, UrlRecord};
+use std::time::Duration;
+use tracing::warn;
+use wormhole_cache::InvalidationPublisher;
+use wormhole_core::{AliasPolicy, RedirectKind, ShortCode, UrlRecord};
 use wormhole_generator::Generator;
 use wormhole_storage::{Repository, StorageError};
 
@@ -13,6 +20,9 @@ use wormhole_storage::{Repository, StorageError};
 /// - Short code generation (auto-generated or custom)
 /// - Expiration policy conversion
 /// - URL validation
+/// - Deduplication of retried requests by idempotency key
+/// - Announcing deletions to redirector caches, if an
+///   [`InvalidationPublisher`] is set
 ///
 /// Note: The `Generator` implementation is responsible for ensuring
 /// uniqueness of generated short codes. No collision retry is performed.
@@ -20,6 +30,11 @@ use wormhole_storage::{Repository, StorageError};
 pub struct ShortenerService<R, G> {
     repository: Arc<R>,
     generator: Arc<G>,
+    alias_policy: Arc<AliasPolicy>,
+    idempotency: IdempotencyStore,
+    max_expiration: Duration,
+    max_url_len: usize,
+    invalidation: Option<Arc<dyn InvalidationPublisher>>,
 }
 
 impl<R: Repository, G: Generator> ShortenerService<R, G> {
@@ -28,17 +43,65 @@ impl<R: Repository, G: Generator> ShortenerService<R, G> {
         Self {
             repository: Arc::new(repository),
             generator: Arc::new(generator),
+            alias_policy: Arc::new(AliasPolicy::default()),
+            idempotency: IdempotencyStore::default(),
+            max_expiration: DEFAULT_MAX_EXPIRATION,
+            max_url_len: DEFAULT_MAX_URL_LEN,
+            invalidation: None,
         }
     }
 
-    /// Validates that the URL has a valid format (has a scheme and host).
-    fn validate_url(url: &str) -> Result<(), ShortenerError> {
+    /// Rejects expirations more than `max_expiration` in the future. The
+    /// default is [`DEFAULT_MAX_EXPIRATION`].
+    pub fn with_max_expiration(mut self, max_expiration: Duration) -> Self {
+        self.max_expiration = max_expiration;
+        self
+    }
+
+    /// Rejects URLs longer than `max_url_len` bytes. The default is
+    /// [`DEFAULT_MAX_URL_LEN`].
+    ///
+    /// The MySQL schema stores `original_url` as `TEXT`, which holds at most
+    /// 65,535 bytes; widen the column (e.g. to `MEDIUMTEXT`) before raising
+    /// the limit past that.
+    pub fn with_max_url_len(mut self, max_url_len: usize) -> Self {
+        self.max_url_len = max_url_len;
+        self
+    }
+
+    /// Replaces the default store of idempotency keys, e.g. to change how
+    /// long keys are remembered.
+    pub fn with_idempotency_store(mut self, idempotency: IdempotencyStore) -> Self {
+        self.idempotency = idempotency;
+        self
+    }
+
+    /// Checks custom aliases against `policy`, e.g. to keep users from
+    /// claiming reserved words.
+    pub fn with_alias_policy(mut self, policy: AliasPolicy) -> Self {
+        self.alias_policy = Arc::new(policy);
+        self
+    }
+
+    /// Publishes an invalidation through `publisher` for every deleted code,
+    /// so redirector caches drop it instead of serving it until it expires.
+    pub fn with_invalidation_publisher(mut self, publisher: impl InvalidationPublisher) -> Self {
+        self.invalidation = Some(Arc::new(publisher));
+        self
+    }
+
+    /// Validates that the URL has a valid format (has a scheme and host) and
+    /// is within the length limit.
+    fn validate_url(&self, url: &str) -> Result<(), ShortenerError> {
         if url.is_empty() {
             return Err(ShortenerError::InvalidUrl(
                 "URL cannot be empty".to_string(),
             ));
         }
 
+        reject_oversized_url(url, self.max_url_len)?;
+        reject_control_characters(url)?;
+
         // Basic validation: check for scheme and host presence
         // A valid URL should have "://" and something after it
         let parts: Vec<&str> = url.split("://").collect();
@@ -63,57 +126,157 @@ impl<R: Repository, G: Generator> ShortenerService<R, G> {
 
     /// Generates a short code using the configured generator.
     /// The generator is responsible for ensuring uniqueness.
-    fn generate_code(&self) -> ShortCode {
-        self.generator.generate().into()
+    fn generate_code(&self) -> Result<ShortCode, ShortenerError> {
+        self.generator
+            .try_generate()
+            .map(Into::into)
+            .map_err(|e| ShortenerError::GenerationFailed(e.to_string()))
     }
-}
 
-#[async_trait]
-impl<R: Repository, G: Generator> Shortener for ShortenerService<R, G> {
-    async fn shorten(&self, params: ShortenParams) -> Result<ShortCode, ShortenerError> {
+    /// Validates the request, picks a short code and stores the record.
+    async fn store(&self, params: ShortenParams) -> Result<ShortCode, ShortenerError> {
         // Validate the URL
-        Self::validate_url(&params.original_url)?;
+        self.validate_url(&params.original_url)?;
 
         // Determine the short code to use
         let short_code = match params.custom_alias {
+            Some(ShortCode::Custom(alias)) => {
+                ShortCode::custom_with_policy(alias, &self.alias_policy)?
+            }
             Some(code) => code,
             // the generator can always produce a new code, so no need to check for conflicts here
-            None => self.generate_code(),
+            None => self.generate_code()?,
         };
 
         // Convert expiration policy to optional timestamp
-        let expire_at = match params.expiration {
-            ExpirationPolicy::Never => None,
-            ExpirationPolicy::AfterDuration(duration) => {
-                let future = Timestamp::now()
-                    + jiff::SignedDuration::try_from(duration).map_err(|e| {
-                        ShortenerError::InvalidUrl(format!("Invalid duration: {}", e))
-                    })?;
-                Some(future)
-            }
-            ExpirationPolicy::AtTimestamp(timestamp) => Some(timestamp),
-        };
+        let now = Timestamp::now();
+        let expire_at = params.expiration.expire_at(now, self.max_expiration)?;
 
         // Create the URL record
         let record = UrlRecord {
             original_url: params.original_url,
             expire_at,
+            redirect_kind: RedirectKind::default(),
+            created_at: now,
+            internal_only: params.internal_only,
+            no_store: params.no_store,
+            owner_id: None,
+            metadata: Default::default(),
         };
 
         // Store in repository
-        self.repository
-            .insert(&short_code, record)
-            .await
-            .map_err(storage_to_shortener_error)?;
+        match self.repository.insert(&short_code, record.clone()).await {
+            Ok(()) => Ok(short_code),
+            Err(StorageError::Conflict(_)) if matches!(short_code, ShortCode::Custom(_)) => {
+                match params.conflict_policy {
+                    ConflictPolicy::Reject => {
+                        Err(ShortenerError::AliasConflict(short_code.to_string()))
+                    }
+                    ConflictPolicy::Suggest { max_attempts } => {
+                        self.store_suggested(&short_code, record, max_attempts)
+                            .await
+                    }
+                }
+            }
+            Err(error) => Err(storage_to_shortener_error(error)),
+        }
+    }
+
+    /// Stores `record` under the first free `{alias}-N` for N from 2, trying
+    /// at most `max_attempts` candidates.
+    ///
+    /// Candidates that are known to be taken are skipped with an `exists`
+    /// probe; one taken between the probe and the insert is skipped too.
+    /// Candidates the alias policy rejects, e.g. for length, end the search.
+    async fn store_suggested(
+        &self,
+        alias: &ShortCode,
+        record: UrlRecord,
+        max_attempts: u32,
+    ) -> Result<ShortCode, ShortenerError> {
+        for n in (2..).take(max_attempts as usize) {
+            let Ok(candidate) =
+                ShortCode::custom_with_policy(format!("{alias}-{n}"), &self.alias_policy)
+            else {
+                break;
+            };
+            let taken = self
+                .repository
+                .exists(&candidate)
+                .await
+                .map_err(storage_to_shortener_error)?;
+            if taken {
+                continue;
+            }
+            match self.repository.insert(&candidate, record.clone()).await {
+                Ok(()) => return Ok(candidate),
+                Err(StorageError::Conflict(_)) => continue,
+                Err(error) => return Err(storage_to_shortener_error(error)),
+            }
+        }
+
+        Err(ShortenerError::AliasConflict(alias.to_string()))
+    }
+
+    /// Like [`ShortenerService::store`], but returns the code from an earlier
+    /// request with the same idempotency key instead of storing again.
+    ///
+    /// Failed requests record nothing, so they can be retried with the same
+    /// key.
+    async fn store_once(
+        &self,
+        key: String,
+        params: ShortenParams,
+    ) -> Result<ShortCode, ShortenerError> {
+        let slot = self.idempotency.slot(&key).await;
+        let mut completed = slot.lock().await;
+
+        if let Some(previous) = completed.as_ref() {
+            return if previous.original_url == params.original_url {
+                Ok(previous.short_code.clone())
+            } else {
+                Err(ShortenerError::IdempotencyConflict(key))
+            };
+        }
 
+        let original_url = params.original_url.clone();
+        let short_code = self.store(params).await?;
+        *completed = Some(Completed {
+            original_url,
+            short_code: short_code.clone(),
+        });
         Ok(short_code)
     }
+}
+
+#[async_trait]
+impl<R: Repository, G: Generator> Shortener for ShortenerService<R, G> {
+    async fn shorten(&self, params: ShortenParams) -> Result<ShortCode, ShortenerError> {
+        let result = match params.idempotency_key.clone() {
+            Some(key) => self.store_once(key, params).await,
+            None => self.store(params).await,
+        };
+        metrics::record_shorten(&result);
+        result
+    }
 
+    /// A failed invalidation is logged but does not fail the delete: the
+    /// record is already gone, and caches still drop it when it expires.
     async fn delete(&self, code: &ShortCode) -> Result<bool, ShortenerError> {
-        self.repository
+        let deleted = self
+            .repository
             .delete(code)
             .await
-            .map_err(storage_to_shortener_error)
+            .map_err(storage_to_shortener_error)?;
+
+        if deleted {
+            if let Some(publisher) = &self.invalidation {
+                if let Err(e) = publisher.publish_invalidation(code).await {
+                    warn!(code = %code, error = %e, "Failed to publish cache invalidation");
+                }
+            }
+        }
+        Ok(deleted)
     }
 }
 
@@ -125,12 +288,41 @@ fn storage_to_shortener_error(error: StorageError) -> ShortenerError {
     }
 }
 
+/// Rejects URLs longer than `max_len` bytes, so a client cannot bloat the
+/// store and every cache layer with huge records.
+pub(crate) fn reject_oversized_url(url: &str, max_len: usize) -> Result<(), ShortenerError> {
+    if url.len() > max_len {
+        return Err(ShortenerError::InvalidUrl(format!(
+            "URL is {} bytes long, the limit is {max_len}",
+            url.len()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Rejects URLs containing ASCII control characters.
+///
+/// The stored URL ends up verbatim in `Location` headers and log lines, so a
+/// raw `\r\n` or `\0` would allow header injection or log forging. Legitimate
+/// URLs percent-encode such bytes.
+pub(crate) fn reject_control_characters(url: &str) -> Result<(), ShortenerError> {
+    if let Some(position) = url.find(|c: char| c.is_ascii_control()) {
+        return Err(ShortenerError::InvalidUrl(format!(
+            "URL must not contain control characters (found at byte {position})"
+        )));
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::shortener::ExpirationPolicy;
     use async_trait::async_trait;
     use wormhole_generator::seq::SeqGenerator;
-    use wormhole_storage::{InMemoryRepository, ReadRepository};
+    use wormhole_storage::{CodeStatus, InMemoryRepository, ReadRepository, ScanCursor, ScanPage};
 
     #[derive(Debug, Clone, Default)]
     struct InsertConflictRepo;
@@ -146,6 +338,18 @@ mod tests {
                 "exists() must not be called".to_string(),
             ))
         }
+
+        async fn status(&self, _code: &ShortCode) -> wormhole_storage::Result<CodeStatus> {
+            Ok(CodeStatus::NotFound)
+        }
+
+        async fn scan(
+            &self,
+            _cursor: Option<ScanCursor>,
+            _limit: usize,
+        ) -> wormhole_storage::Result<ScanPage> {
+            Ok(ScanPage::default())
+        }
     }
 
     #[async_trait]
@@ -177,12 +381,45 @@ mod tests {
             original_url: "https://example.com".to_string(),
             expiration: ExpirationPolicy::Never,
             custom_alias: None,
+            internal_only: false,
+            no_store: false,
+            idempotency_key: None,
+            conflict_policy: ConflictPolicy::Reject,
         };
 
         let code = service.shorten(params).await.unwrap();
         assert_eq!(code.as_str().len(), 3); // "wh" + counter starting at 0
     }
 
+    #[tokio::test]
+    async fn exhausted_generator_fails_instead_of_panicking() {
+        // An 8-bit timestamp field runs out 256 seconds after the epoch.
+        let settings = wormhole_tinyflake::TinyflakeSettings::builder()
+            .node_id(0)
+            .start_epoch(Timestamp::now() - jiff::SignedDuration::from_secs(1_000))
+            .timestamp_bits(8)
+            .sequence_bits(24)
+            .node_bits(8)
+            .build();
+        let generator = wormhole_tinyflake::Tinyflake::new(settings).unwrap();
+        let service = ShortenerService::new(InMemoryRepository::new(), generator);
+
+        let err = service
+            .shorten(ShortenParams {
+                original_url: "https://example.com".to_string(),
+                expiration: ExpirationPolicy::Never,
+                custom_alias: None,
+                internal_only: false,
+                no_store: false,
+                idempotency_key: None,
+                conflict_policy: ConflictPolicy::Reject,
+            })
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, ShortenerError::GenerationFailed(_)));
+    }
+
     #[tokio::test]
     async fn shorten_with_custom_alias() {
         let service = test_service();
@@ -191,12 +428,92 @@ mod tests {
             original_url: "https://example.com".to_string(),
             expiration: ExpirationPolicy::Never,
             custom_alias: Some(ShortCode::custom("my-alias").unwrap()),
+            internal_only: false,
+            no_store: false,
+            idempotency_key: None,
+            conflict_policy: ConflictPolicy::Reject,
         };
 
         let code = service.shorten(params).await.unwrap();
         assert_eq!(code.as_str(), "my-alias");
     }
 
+    #[tokio::test]
+    async fn shorten_rejects_reserved_aliases_in_any_case() {
+        let service = test_service()
+            .with_alias_policy(AliasPolicy::new().with_reserved(["admin", "metrics"]));
+
+        for alias in ["admin", "ADMIN", "Metrics"] {
+            let params = ShortenParams {
+                original_url: "https://example.com".to_string(),
+                expiration: ExpirationPolicy::Never,
+                custom_alias: Some(ShortCode::custom(alias).unwrap()),
+                internal_only: false,
+                no_store: false,
+                idempotency_key: None,
+                conflict_policy: ConflictPolicy::Reject,
+            };
+
+            let err = service.shorten(params).await.unwrap_err();
+            assert!(
+                matches!(&err, ShortenerError::ReservedAlias(code) if code == alias),
+                "{alias}: {err:?}"
+            );
+        }
+
+        // Generated codes are not aliases, so the policy does not apply.
+        let params = ShortenParams {
+            original_url: "https://example.com".to_string(),
+            expiration: ExpirationPolicy::Never,
+            custom_alias: None,
+            internal_only: false,
+            no_store: false,
+            idempotency_key: None,
+            conflict_policy: ConflictPolicy::Reject,
+        };
+        assert!(service.shorten(params).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn case_insensitive_aliases_conflict_regardless_of_case() {
+        let service =
+            test_service().with_alias_policy(AliasPolicy::new().with_case_insensitive(true));
+        let params = |alias: &str| ShortenParams {
+            original_url: "https://example.com".to_string(),
+            expiration: ExpirationPolicy::Never,
+            custom_alias: Some(ShortCode::custom(alias).unwrap()),
+            internal_only: false,
+            no_store: false,
+            idempotency_key: None,
+            conflict_policy: ConflictPolicy::Reject,
+        };
+
+        let code = service.shorten(params("MyLink")).await.unwrap();
+        assert_eq!(code.as_str(), "mylink");
+
+        let err = service.shorten(params("mylink")).await.unwrap_err();
+        assert!(matches!(err, ShortenerError::AliasConflict(_)));
+        let err = service.shorten(params("MYLINK")).await.unwrap_err();
+        assert!(matches!(err, ShortenerError::AliasConflict(_)));
+    }
+
+    #[tokio::test]
+    async fn aliases_are_case_sensitive_by_default() {
+        let service = test_service();
+        let params = |alias: &str| ShortenParams {
+            original_url: "https://example.com".to_string(),
+            expiration: ExpirationPolicy::Never,
+            custom_alias: Some(ShortCode::custom(alias).unwrap()),
+            internal_only: false,
+            no_store: false,
+            idempotency_key: None,
+            conflict_policy: ConflictPolicy::Reject,
+        };
+
+        service.shorten(params("MyLink")).await.unwrap();
+        service.shorten(params("mylink")).await.unwrap();
+    }
+
     #[tokio::test]
     async fn shorten_with_duplicate_alias_fails() {
         let service = test_service();
@@ -205,12 +522,20 @@ mod tests {
             original_url: "https://example1.com".to_string(),
             expiration: ExpirationPolicy::Never,
             custom_alias: Some(ShortCode::custom("my-alias").unwrap()),
+            internal_only: false,
+            no_store: false,
+            idempotency_key: None,
+            conflict_policy: ConflictPolicy::Reject,
         };
 
         let params2 = ShortenParams {
             original_url: "https://example2.com".to_string(),
             expiration: ExpirationPolicy::Never,
             custom_alias: Some(ShortCode::custom("my-alias").unwrap()),
+            internal_only: false,
+            no_store: false,
+            idempotency_key: None,
+            conflict_policy: ConflictPolicy::Reject,
         };
 
         service.shorten(params1).await.unwrap();
@@ -218,6 +543,50 @@ mod tests {
         assert!(matches!(err, ShortenerError::AliasConflict(_)));
     }
 
+    fn suggesting(alias: &str, max_attempts: u32) -> ShortenParams {
+        ShortenParams {
+            original_url: "https://example.com".to_string(),
+            expiration: ExpirationPolicy::Never,
+            custom_alias: Some(ShortCode::custom(alias).unwrap()),
+            internal_only: false,
+            no_store: false,
+            idempotency_key: None,
+            conflict_policy: ConflictPolicy::Suggest { max_attempts },
+        }
+    }
+
+    #[tokio::test]
+    async fn taken_alias_gets_the_first_free_suggestion() {
+        let service = test_service();
+        service.shorten(suggesting("my-alias", 3)).await.unwrap();
+        service.shorten(suggesting("my-alias-2", 3)).await.unwrap();
+
+        let code = service.shorten(suggesting("my-alias", 3)).await.unwrap();
+
+        assert_eq!(code.as_str(), "my-alias-3");
+        assert!(service.repository.exists(&code).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn suggestions_give_up_once_the_budget_is_spent() {
+        let service = test_service();
+        for alias in ["my-alias", "my-alias-2", "my-alias-3"] {
+            service.shorten(suggesting(alias, 0)).await.unwrap();
+        }
+
+        let err = service
+            .shorten(suggesting("my-alias", 2))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(&err, ShortenerError::AliasConflict(code) if code == "my-alias"));
+        assert!(!service
+            .repository
+            .exists(&ShortCode::custom("my-alias-4").unwrap())
+            .await
+            .unwrap());
+    }
+
     #[tokio::test]
     async fn shorten_custom_alias_uses_insert_conflict_without_exists_precheck() {
         let service = ShortenerService::new(InsertConflictRepo, SeqGenerator::with_prefix("wh"));
@@ -226,6 +595,10 @@ mod tests {
             original_url: "https://example.com".to_string(),
             expiration: ExpirationPolicy::Never,
             custom_alias: Some(ShortCode::custom("my-alias").unwrap()),
+            internal_only: false,
+            no_store: false,
+            idempotency_key: None,
+            conflict_policy: ConflictPolicy::Reject,
         };
 
         let err = service.shorten(params).await.unwrap_err();
@@ -240,12 +613,107 @@ mod tests {
             original_url: "not-a-valid-url".to_string(),
             expiration: ExpirationPolicy::Never,
             custom_alias: None,
+            internal_only: false,
+            no_store: false,
+            idempotency_key: None,
+            conflict_policy: ConflictPolicy::Reject,
         };
 
         let err = service.shorten(params).await.unwrap_err();
         assert!(matches!(err, ShortenerError::InvalidUrl(_)));
     }
 
+    #[tokio::test]
+    async fn shorten_rejects_urls_with_control_characters() {
+        let service = test_service();
+
+        for url in [
+            "https://example.com/\r\nSet-Cookie: a=b",
+            "https://example.com/\npath",
+            "https://example.com/\0",
+        ] {
+            let params = ShortenParams {
+                original_url: url.to_string(),
+                expiration: ExpirationPolicy::Never,
+                custom_alias: None,
+                internal_only: false,
+                no_store: false,
+                idempotency_key: None,
+                conflict_policy: ConflictPolicy::Reject,
+            };
+
+            let err = service.shorten(params).await.unwrap_err();
+            assert!(matches!(err, ShortenerError::InvalidUrl(_)), "{url:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn shorten_enforces_the_url_length_limit() {
+        let service = test_service().with_max_url_len(64);
+        let url_of_len = |len: usize| {
+            let prefix = "https://example.com/";
+            format!("{prefix}{}", "a".repeat(len - prefix.len()))
+        };
+        let params = |url: String| ShortenParams {
+            original_url: url,
+            expiration: ExpirationPolicy::Never,
+            custom_alias: None,
+            internal_only: false,
+            no_store: false,
+            idempotency_key: None,
+            conflict_policy: ConflictPolicy::Reject,
+        };
+
+        service.shorten(params(url_of_len(64))).await.unwrap();
+
+        let err = service.shorten(params(url_of_len(65))).await.unwrap_err();
+        assert!(
+            matches!(&err, ShortenerError::InvalidUrl(message) if message.contains("64")),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn default_url_length_limit_is_2048_bytes() {
+        let service = test_service();
+        let url = |len: usize| format!("https://example.com/{}", "a".repeat(len - 20));
+        let params = |url: String| ShortenParams {
+            original_url: url,
+            expiration: ExpirationPolicy::Never,
+            custom_alias: None,
+            internal_only: false,
+            no_store: false,
+            idempotency_key: None,
+            conflict_policy: ConflictPolicy::Reject,
+        };
+
+        service
+            .shorten(params(url(DEFAULT_MAX_URL_LEN)))
+            .await
+            .unwrap();
+        assert!(service
+            .shorten(params(url(DEFAULT_MAX_URL_LEN + 1)))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn shorten_accepts_percent_encoded_characters() {
+        let service = test_service();
+
+        let params = ShortenParams {
+            original_url: "https://example.com/a%0D%0Ab?q=%00".to_string(),
+            expiration: ExpirationPolicy::Never,
+            custom_alias: None,
+            internal_only: false,
+            no_store: false,
+            idempotency_key: None,
+            conflict_policy: ConflictPolicy::Reject,
+        };
+
+        assert!(service.shorten(params).await.is_ok());
+    }
+
     #[tokio::test]
     async fn delete_existing_url() {
         let service = test_service();
@@ -254,6 +722,10 @@ mod tests {
             original_url: "https://example.com".to_string(),
             expiration: ExpirationPolicy::Never,
             custom_alias: Some(ShortCode::custom("abc123").unwrap()),
+            internal_only: false,
+            no_store: false,
+            idempotency_key: None,
+            conflict_policy: ConflictPolicy::Reject,
         };
 
         service.shorten(params).await.unwrap();
@@ -289,6 +761,10 @@ mod tests {
             original_url: "https://example.com".to_string(),
             expiration: ExpirationPolicy::Never,
             custom_alias: None,
+            internal_only: false,
+            no_store: false,
+            idempotency_key: None,
+            conflict_policy: ConflictPolicy::Reject,
         };
 
         let code1 = service.shorten(params.clone()).await.unwrap();
@@ -297,4 +773,232 @@ mod tests {
         assert_eq!(code1.as_str(), "wh0");
         assert_eq!(code2.as_str(), "wh1");
     }
+
+    #[tokio::test]
+    async fn shorten_persists_no_store() {
+        let service = test_service();
+
+        let params = ShortenParams {
+            original_url: "https://example.com/token".to_string(),
+            expiration: ExpirationPolicy::Never,
+            custom_alias: None,
+            internal_only: false,
+            no_store: true,
+            idempotency_key: None,
+            conflict_policy: ConflictPolicy::Reject,
+        };
+
+        let code = service.shorten(params).await.unwrap();
+        let record = service.repository.get(&code).await.unwrap().unwrap();
+        assert!(record.no_store);
+    }
+
+    fn keyed(url: &str, key: Option<&str>) -> ShortenParams {
+        ShortenParams {
+            original_url: url.to_string(),
+            expiration: ExpirationPolicy::Never,
+            custom_alias: None,
+            internal_only: false,
+            no_store: false,
+            idempotency_key: key.map(str::to_string),
+            conflict_policy: ConflictPolicy::Reject,
+        }
+    }
+
+    #[tokio::test]
+    async fn repeated_idempotency_key_returns_the_original_code() {
+        let service = test_service();
+
+        let first = service
+            .shorten(keyed("https://example.com", Some("retry-1")))
+            .await
+            .unwrap();
+        let again = service
+            .shorten(keyed("https://example.com", Some("retry-1")))
+            .await
+            .unwrap();
+
+        assert_eq!(first, again);
+        let next = service
+            .shorten(keyed("https://example.com", Some("retry-2")))
+            .await
+            .unwrap();
+        assert_ne!(first, next);
+    }
+
+    #[tokio::test]
+    async fn idempotency_key_reused_for_another_url_conflicts() {
+        let service = test_service();
+
+        service
+            .shorten(keyed("https://example.com", Some("retry-1")))
+            .await
+            .unwrap();
+        let err = service
+            .shorten(keyed("https://other.example", Some("retry-1")))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, ShortenerError::IdempotencyConflict(key) if key == "retry-1"));
+    }
+
+    #[tokio::test]
+    async fn requests_without_a_key_are_not_deduplicated() {
+        let service = test_service();
+
+        let first = service
+            .shorten(keyed("https://example.com", None))
+            .await
+            .unwrap();
+        let second = service
+            .shorten(keyed("https://example.com", None))
+            .await
+            .unwrap();
+
+        assert_ne!(first, second);
+    }
+
+    #[tokio::test]
+    async fn concurrent_retries_share_one_code() {
+        let service = Arc::new(test_service());
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let service = Arc::clone(&service);
+                tokio::spawn(async move {
+                    service
+                        .shorten(keyed("https://example.com", Some("retry-1")))
+                        .await
+                        .unwrap()
+                })
+            })
+            .collect();
+
+        let mut codes = Vec::new();
+        for handle in handles {
+            codes.push(handle.await.unwrap());
+        }
+        codes.dedup();
+        assert_eq!(codes.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn failed_requests_do_not_consume_the_key() {
+        let service = test_service();
+
+        let err = service
+            .shorten(keyed("not a url", Some("retry-1")))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ShortenerError::InvalidUrl(_)));
+
+        assert!(service
+            .shorten(keyed("https://example.com", Some("retry-1")))
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn shorten_rejects_invalid_expirations() {
+        let service =
+            ShortenerService::new(InMemoryRepository::new(), SeqGenerator::with_prefix("wh"))
+                .with_max_expiration(Duration::from_secs(60 * 60));
+
+        for expiration in [
+            ExpirationPolicy::AtTimestamp(Timestamp::now() - jiff::SignedDuration::from_secs(1)),
+            ExpirationPolicy::AfterDuration(Duration::ZERO),
+            ExpirationPolicy::AfterDuration(Duration::from_secs(2 * 60 * 60)),
+        ] {
+            let params = ShortenParams {
+                original_url: "https://example.com".to_string(),
+                expiration: expiration.clone(),
+                custom_alias: None,
+                internal_only: false,
+                no_store: false,
+                idempotency_key: None,
+                conflict_policy: ConflictPolicy::Reject,
+            };
+            let err = service.shorten(params).await.unwrap_err();
+            assert!(
+                matches!(err, ShortenerError::InvalidExpiration(_)),
+                "{expiration:?}: {err:?}"
+            );
+        }
+    }
+
+    /// Remembers every code it was asked to invalidate.
+    #[derive(Debug, Default, Clone)]
+    struct RecordingPublisher {
+        codes: Arc<std::sync::Mutex<Vec<String>>>,
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl InvalidationPublisher for RecordingPublisher {
+        async fn publish_invalidation(&self, code: &ShortCode) -> wormhole_cache::Result<()> {
+            self.codes.lock().unwrap().push(code.to_string());
+            if self.fail {
+                return Err(wormhole_cache::CacheError::Unavailable(
+                    "redis is down".to_string(),
+                ));
+            }
+            Ok(())
+        }
+    }
+
+    async fn shorten_alias(
+        service: &ShortenerService<InMemoryRepository, SeqGenerator>,
+        alias: &str,
+    ) {
+        service
+            .shorten(ShortenParams {
+                original_url: "https://example.com".to_string(),
+                expiration: ExpirationPolicy::Never,
+                custom_alias: Some(ShortCode::custom(alias).unwrap()),
+                internal_only: false,
+                no_store: false,
+                idempotency_key: None,
+                conflict_policy: ConflictPolicy::Reject,
+            })
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn delete_publishes_an_invalidation_only_for_deleted_codes() {
+        let publisher = RecordingPublisher::default();
+        let service = test_service().with_invalidation_publisher(publisher.clone());
+        shorten_alias(&service, "abc123").await;
+
+        assert!(service
+            .delete(&ShortCode::custom("abc123").unwrap())
+            .await
+            .unwrap());
+        assert!(!service
+            .delete(&ShortCode::custom("abc123").unwrap())
+            .await
+            .unwrap());
+        assert!(!service
+            .delete(&ShortCode::custom("missing").unwrap())
+            .await
+            .unwrap());
+
+        assert_eq!(*publisher.codes.lock().unwrap(), ["abc123"]);
+    }
+
+    #[tokio::test]
+    async fn failed_invalidation_does_not_fail_the_delete() {
+        let publisher = RecordingPublisher {
+            fail: true,
+            ..Default::default()
+        };
+        let service = test_service().with_invalidation_publisher(publisher.clone());
+        shorten_alias(&service, "abc123").await;
+
+        assert!(service
+            .delete(&ShortCode::custom("abc123").unwrap())
+            .await
+            .unwrap());
+        assert_eq!(publisher.codes.lock().unwrap().len(), 1);
+    }
 }
@@ -17,6 +17,68 @@ pub enum ExpirationPolicy {
     AtTimestamp(Timestamp),
 }
 
+/// How far in the future [`ExpirationPolicy`] may place an expiration by
+/// default: ten years.
+pub const DEFAULT_MAX_EXPIRATION: Duration = Duration::from_secs(10 * 365 * 24 * 60 * 60);
+
+/// Longest `original_url`, in bytes, accepted by default.
+pub const DEFAULT_MAX_URL_LEN: usize = 2048;
+
+impl ExpirationPolicy {
+    /// Returns the expiration time this policy sets for a record created at
+    /// `now`, or `None` if it never expires.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ShortenerError::InvalidExpiration`] if the expiration is not
+    /// after `now`, or is more than `max_horizon` after it.
+    pub fn expire_at(&self, now: Timestamp, max_horizon: Duration) -> Result<Option<Timestamp>> {
+        let horizon = jiff::SignedDuration::try_from(max_horizon)
+            .ok()
+            .and_then(|horizon| now.checked_add(horizon).ok())
+            .unwrap_or(Timestamp::MAX);
+
+        let expire_at = match self {
+            ExpirationPolicy::Never => return Ok(None),
+            ExpirationPolicy::AfterDuration(duration) => jiff::SignedDuration::try_from(*duration)
+                .ok()
+                .and_then(|duration| now.checked_add(duration).ok())
+                .filter(|expire_at| *expire_at <= horizon)
+                .ok_or_else(|| {
+                    ShortenerError::InvalidExpiration(format!(
+                        "duration {duration:?} exceeds the maximum of {max_horizon:?}"
+                    ))
+                })?,
+            ExpirationPolicy::AtTimestamp(timestamp) => *timestamp,
+        };
+
+        if expire_at <= now {
+            return Err(ShortenerError::InvalidExpiration(format!(
+                "expiration {expire_at} is not in the future"
+            )));
+        }
+        if expire_at > horizon {
+            return Err(ShortenerError::InvalidExpiration(format!(
+                "expiration {expire_at} is more than {max_horizon:?} away"
+            )));
+        }
+
+        Ok(Some(expire_at))
+    }
+}
+
+/// What to do when a custom alias is already taken.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Fail with [`ShortenerError::AliasConflict`].
+    #[default]
+    Reject,
+    /// Try `{alias}-2`, `{alias}-3`, ... up to `max_attempts` candidates and
+    /// use the first free one, failing with
+    /// [`ShortenerError::AliasConflict`] if all are taken.
+    Suggest { max_attempts: u32 },
+}
+
 /// Parameters for creating a shortened URL.
 #[derive(Debug, Clone)]
 pub struct ShortenParams {
@@ -26,6 +88,17 @@ pub struct ShortenParams {
     pub expiration: ExpirationPolicy,
     /// Optional custom alias for the shortened URL.
     pub custom_alias: Option<ShortCode>,
+    /// Whether the short code only resolves for trusted (internal) callers.
+    pub internal_only: bool,
+    /// Whether the record must never be cached.
+    pub no_store: bool,
+    /// Optional client-chosen key identifying this request across retries.
+    ///
+    /// A repeated key returns the code created by the first request instead
+    /// of creating another one.
+    pub idempotency_key: Option<String>,
+    /// What to do if `custom_alias` is taken. Ignored for generated codes.
+    pub conflict_policy: ConflictPolicy,
 }
 
 #[async_trait]
@@ -37,3 +110,74 @@ pub trait Shortener: Send + Sync + 'static {
     /// Returns `true` if the record existed and was removed.
     async fn delete(&self, code: &ShortCode) -> Result<bool>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jiff::SignedDuration;
+
+    const HOUR: Duration = Duration::from_secs(60 * 60);
+
+    #[test]
+    fn never_has_no_expiration() {
+        let now = Timestamp::now();
+        assert_eq!(ExpirationPolicy::Never.expire_at(now, HOUR).unwrap(), None);
+    }
+
+    #[test]
+    fn durations_within_the_horizon_are_accepted() {
+        let now = Timestamp::now();
+
+        let expire_at = ExpirationPolicy::AfterDuration(HOUR)
+            .expire_at(now, HOUR)
+            .unwrap();
+        assert_eq!(expire_at, Some(now + SignedDuration::from_hours(1)));
+    }
+
+    #[test]
+    fn past_timestamps_are_rejected() {
+        let now = Timestamp::now();
+
+        for timestamp in [now, now - SignedDuration::from_secs(1)] {
+            let err = ExpirationPolicy::AtTimestamp(timestamp)
+                .expire_at(now, HOUR)
+                .unwrap_err();
+            assert!(matches!(err, ShortenerError::InvalidExpiration(_)));
+        }
+    }
+
+    #[test]
+    fn zero_duration_is_rejected() {
+        let err = ExpirationPolicy::AfterDuration(Duration::ZERO)
+            .expire_at(Timestamp::now(), HOUR)
+            .unwrap_err();
+        assert!(matches!(err, ShortenerError::InvalidExpiration(_)));
+    }
+
+    #[test]
+    fn expirations_past_the_horizon_are_rejected() {
+        let now = Timestamp::now();
+
+        let policies = [
+            ExpirationPolicy::AfterDuration(HOUR + Duration::from_secs(1)),
+            ExpirationPolicy::AfterDuration(Duration::from_secs(100 * 365 * 24 * 60 * 60)),
+            ExpirationPolicy::AfterDuration(Duration::MAX),
+            ExpirationPolicy::AtTimestamp(now + SignedDuration::from_hours(2)),
+        ];
+        for policy in policies {
+            let err = policy.expire_at(now, HOUR).unwrap_err();
+            assert!(
+                matches!(err, ShortenerError::InvalidExpiration(_)),
+                "{policy:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn default_horizon_allows_ten_years() {
+        let now = Timestamp::now();
+
+        let ten_years = ExpirationPolicy::AfterDuration(DEFAULT_MAX_EXPIRATION);
+        assert!(ten_years.expire_at(now, DEFAULT_MAX_EXPIRATION).is_ok());
+    }
+}
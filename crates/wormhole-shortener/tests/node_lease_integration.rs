@@ -0,0 +1,113 @@
+use std::time::Duration;
+
+use redis::aio::MultiplexedConnection;
+use wormhole_shortener::lease::{LeaseError, NodeIdLease, NodeIdLeaseConfig};
+use wormhole_test_infra::redis::RedisMaster;
+
+/// Test fixture that manages a Redis container using test-infra.
+struct RedisFixture {
+    #[allow(dead_code)]
+    redis: RedisMaster,
+    redis_url: String,
+}
+
+impl RedisFixture {
+    async fn start() -> Self {
+        let redis = RedisMaster::new()
+            .await
+            .expect("Failed to start Redis master");
+        let host = redis.host().await.expect("Failed to get Redis host");
+        let port = redis.port().await.expect("Failed to get Redis port");
+        let redis_url = format!("redis://{}:{}", host, port);
+
+        Self { redis, redis_url }
+    }
+
+    /// Each call stands in for a separate shortener process.
+    async fn connection(&self) -> MultiplexedConnection {
+        redis::Client::open(self.redis_url.as_str())
+            .expect("Failed to create Redis client")
+            .get_multiplexed_async_connection()
+            .await
+            .expect("Failed to get Redis connection")
+    }
+}
+
+fn short_ttl() -> NodeIdLeaseConfig {
+    NodeIdLeaseConfig::builder()
+        .ttl(Duration::from_secs(1))
+        .renew_interval(Duration::from_millis(200))
+        .build()
+}
+
+#[tokio::test]
+async fn test_second_claim_of_same_node_id_is_rejected() {
+    let fixture = RedisFixture::start().await;
+
+    let first = NodeIdLease::acquire(fixture.connection().await, 1, short_ttl())
+        .await
+        .unwrap();
+    assert!(first.is_held());
+
+    let err = NodeIdLease::acquire(fixture.connection().await, 1, short_ttl())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, LeaseError::AlreadyClaimed(1)));
+
+    // Other node ids are unaffected.
+    NodeIdLease::acquire(fixture.connection().await, 2, short_ttl())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_released_node_id_is_claimable() {
+    let fixture = RedisFixture::start().await;
+
+    let first = NodeIdLease::acquire(fixture.connection().await, 3, short_ttl())
+        .await
+        .unwrap();
+    assert!(first.release().await.unwrap());
+    assert!(!first.is_held());
+
+    NodeIdLease::acquire(fixture.connection().await, 3, short_ttl())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_expired_node_id_is_claimable_and_old_holder_notices() {
+    let fixture = RedisFixture::start().await;
+
+    // No renewal task, so the claim lapses after the TTL.
+    let first = NodeIdLease::acquire(fixture.connection().await, 0, short_ttl())
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(1_500)).await;
+
+    let second = NodeIdLease::acquire(fixture.connection().await, 0, short_ttl())
+        .await
+        .unwrap();
+    assert!(!first.renew().await.unwrap());
+    assert!(!first.is_held());
+    assert!(second.is_held());
+}
+
+#[tokio::test]
+async fn test_renewal_keeps_the_claim_past_its_ttl() {
+    let fixture = RedisFixture::start().await;
+
+    let first = NodeIdLease::acquire(fixture.connection().await, 2, short_ttl())
+        .await
+        .unwrap();
+    let renewal = first.spawn_renewal();
+    tokio::time::sleep(Duration::from_millis(2_000)).await;
+
+    let err = NodeIdLease::acquire(fixture.connection().await, 2, short_ttl())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, LeaseError::AlreadyClaimed(2)));
+    assert!(first.is_held());
+
+    renewal.abort();
+}
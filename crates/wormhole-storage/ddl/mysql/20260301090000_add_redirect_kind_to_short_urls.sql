@@ -0,0 +1,4 @@
+-- Store the HTTP redirect status per short code. Existing rows keep the
+-- previous behaviour of a 302 Found redirect.
+ALTER TABLE short_urls
+    ADD COLUMN redirect_kind SMALLINT UNSIGNED NOT NULL DEFAULT 302 AFTER expire_at;
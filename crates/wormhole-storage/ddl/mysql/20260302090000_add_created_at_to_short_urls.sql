@@ -0,0 +1,4 @@
+-- Record when each short code was created. Rows inserted before this column
+-- existed are backfilled with 0 (the Unix epoch), meaning "unknown".
+ALTER TABLE short_urls
+    ADD COLUMN created_at BIGINT NOT NULL DEFAULT 0 AFTER redirect_kind;
@@ -0,0 +1,4 @@
+-- Mark short codes that only resolve for trusted (internal) callers.
+-- Existing rows stay public.
+ALTER TABLE short_urls
+    ADD COLUMN internal_only BOOLEAN NOT NULL DEFAULT FALSE AFTER created_at;
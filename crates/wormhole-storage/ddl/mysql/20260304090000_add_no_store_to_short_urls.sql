@@ -0,0 +1,4 @@
+-- Mark short codes whose records must never be cached (e.g. one-time
+-- tokens). Existing rows stay cacheable.
+ALTER TABLE short_urls
+    ADD COLUMN no_store BOOLEAN NOT NULL DEFAULT FALSE AFTER internal_only;
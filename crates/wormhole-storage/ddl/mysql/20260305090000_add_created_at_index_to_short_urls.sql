@@ -0,0 +1,3 @@
+-- Serve "most recently created" lookups, used to warm caches, from an index
+-- instead of a full table sort.
+CREATE INDEX idx_short_urls_created_at ON short_urls (created_at);
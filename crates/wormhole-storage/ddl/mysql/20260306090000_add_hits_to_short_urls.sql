@@ -0,0 +1,4 @@
+-- Durable click count per short code, bumped atomically by the redirector's
+-- hit counter. Existing rows start at zero.
+ALTER TABLE short_urls
+    ADD COLUMN hits BIGINT UNSIGNED NOT NULL DEFAULT 0 AFTER no_store;
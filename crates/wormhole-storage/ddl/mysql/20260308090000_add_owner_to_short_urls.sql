@@ -0,0 +1,7 @@
+-- Attach an optional owner and free-form metadata to each short code, and
+-- let owner-scoped listings walk an index in short code order. Existing rows
+-- have no owner and no metadata.
+ALTER TABLE short_urls
+    ADD COLUMN owner_id VARCHAR(255) NULL AFTER hits,
+    ADD COLUMN metadata JSON         NULL AFTER owner_id;
+CREATE INDEX idx_short_urls_owner_id ON short_urls (owner_id, short_code);
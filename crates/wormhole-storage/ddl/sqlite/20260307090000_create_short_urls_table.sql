@@ -0,0 +1,17 @@
+-- SQLite counterpart of the MySQL schema in ddl/mysql, folded into a single
+-- migration. BINARY collation keeps short codes case-sensitive, like
+-- ascii_bin does on MySQL.
+CREATE TABLE IF NOT EXISTS short_urls
+(
+    short_code    TEXT    NOT NULL PRIMARY KEY,
+    original_url  TEXT    NOT NULL,
+    expire_at     INTEGER NULL,
+    redirect_kind INTEGER NOT NULL DEFAULT 302,
+    created_at    INTEGER NOT NULL DEFAULT 0,
+    internal_only BOOLEAN NOT NULL DEFAULT FALSE,
+    no_store      BOOLEAN NOT NULL DEFAULT FALSE,
+    hits          INTEGER NOT NULL DEFAULT 0,
+    deleted_at    INTEGER NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_short_urls_created_at ON short_urls (created_at);
@@ -0,0 +1,6 @@
+-- Mirrors the MySQL migration of the same version: an optional owner and
+-- free-form JSON metadata per short code.
+ALTER TABLE short_urls ADD COLUMN owner_id TEXT NULL;
+ALTER TABLE short_urls ADD COLUMN metadata TEXT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_short_urls_owner_id ON short_urls (owner_id, short_code);
@@ -29,8 +29,12 @@ pub enum StorageError {
 impl From<StorageError> for Status {
     fn from(error: StorageError) -> Self {
         let (code, message) = match &error {
-            StorageError::Unavailable(_) => (Code::Unavailable, "storage backend unavailable"),
-            StorageError::Timeout(_) => (Code::DeadlineExceeded, "storage operation timed out"),
+            StorageError::Unavailable(_) | StorageError::Cache(CacheError::Unavailable(_)) => {
+                (Code::Unavailable, "storage backend unavailable")
+            }
+            StorageError::Timeout(_) | StorageError::Cache(CacheError::Timeout(_)) => {
+                (Code::DeadlineExceeded, "storage operation timed out")
+            }
             StorageError::Conflict(_) => (Code::AlreadyExists, "short code already exists"),
             StorageError::InvalidData(_)
             | StorageError::Unknown(_)
@@ -49,6 +53,7 @@ impl From<StorageError> for Status {
 mod tests {
     use super::StorageError;
     use tonic::{Code, Status};
+    use wormhole_cache::CacheError;
 
     fn assert_status(error: StorageError, expected_code: Code, expected_message: &str) {
         let status: Status = error.into();
@@ -74,6 +79,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn cache_connectivity_errors_are_not_masked() {
+        assert_status(
+            StorageError::Cache(CacheError::Timeout("GET wh:abc".to_string())),
+            Code::DeadlineExceeded,
+            "storage operation timed out",
+        );
+        assert_status(
+            StorageError::Cache(CacheError::Unavailable("connection refused".to_string())),
+            Code::Unavailable,
+            "storage backend unavailable",
+        );
+        assert_status(
+            StorageError::Cache(CacheError::InvalidData("not json".to_string())),
+            Code::Internal,
+            "storage operation failed",
+        );
+    }
+
     #[test]
     fn storage_error_conflict_maps_to_already_exists() {
         assert_status(
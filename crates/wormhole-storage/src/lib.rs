@@ -1,14 +1,31 @@
 pub mod error;
 pub mod memory;
 pub mod mysql;
+pub mod scan;
+mod sql;
+pub mod sqlite;
+pub mod status;
 
 pub use error::{Result, StorageError};
 pub use memory::InMemoryRepository;
-pub use mysql::MySqlRepository;
+pub use mysql::{MySqlPoolConfig, MySqlRepository};
+pub use scan::{ScanCursor, ScanPage};
+pub use sqlite::SqliteRepository;
+pub use status::CodeStatus;
 
 use async_trait::async_trait;
 use wormhole_core::{ShortCode, UrlRecord};
 
+/// The answer to [`ReadRepository::lookup`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lookup {
+    /// The record, or `None` if the code does not exist.
+    pub record: Option<UrlRecord>,
+    /// Whether the answer came from a fallback path, e.g. a read-through to
+    /// the backing store after the cache in front of it failed.
+    pub degraded: bool,
+}
+
 /// A read-only view of a repository.
 ///
 /// This trait provides only the read operations from [`Repository`],
@@ -21,6 +38,85 @@ pub trait ReadRepository: Send + Sync + 'static {
 
     /// Checks whether a short code already exists in the repository.
     async fn exists(&self, code: &ShortCode) -> Result<bool>;
+
+    /// Like [`ReadRepository::get`], also reporting whether the answer was
+    /// served degraded.
+    ///
+    /// Only decorators with a fallback path degrade, so the default reads
+    /// through [`ReadRepository::get`] and never does.
+    async fn lookup(&self, code: &ShortCode) -> Result<Lookup> {
+        Ok(Lookup {
+            record: self.get(code).await?,
+            degraded: false,
+        })
+    }
+
+    /// Reads a record without side effects such as filling a cache.
+    ///
+    /// Plain stores have none, so the default reads through
+    /// [`ReadRepository::get`]; decorators that write on read override it.
+    async fn peek(&self, code: &ShortCode) -> Result<Option<UrlRecord>> {
+        self.get(code).await
+    }
+
+    /// Reports whether a short code is active and, if not, why.
+    async fn status(&self, code: &ShortCode) -> Result<CodeStatus>;
+
+    /// Lists active records in short code order, `limit` at a time.
+    ///
+    /// Pass `None` to start from the beginning and the returned
+    /// [`ScanPage::next`] to continue. Deleted and expired records are
+    /// skipped. A `limit` of zero is treated as one.
+    async fn scan(&self, cursor: Option<ScanCursor>, limit: usize) -> Result<ScanPage>;
+
+    /// Like [`ReadRepository::scan`], limited to records owned by `owner_id`.
+    ///
+    /// The default pages through [`ReadRepository::scan`] and filters, which
+    /// reads every active record and may end on an empty page; backends that
+    /// can filter by owner override it.
+    async fn scan_owned_by(
+        &self,
+        owner_id: &str,
+        cursor: Option<ScanCursor>,
+        limit: usize,
+    ) -> Result<ScanPage> {
+        let limit = limit.max(1);
+        let mut cursor = cursor;
+        let mut items = Vec::new();
+        loop {
+            let page = self.scan(cursor, limit).await?;
+            let mut rest = page.items.into_iter();
+            for (code, record) in rest.by_ref() {
+                if record.owner_id.as_deref() == Some(owner_id) {
+                    items.push((code, record));
+                    if items.len() == limit {
+                        break;
+                    }
+                }
+            }
+
+            if items.len() == limit {
+                let next = match items.last() {
+                    Some((code, _)) if rest.len() > 0 || page.next.is_some() => {
+                        Some(ScanCursor::after(code))
+                    }
+                    _ => None,
+                };
+                return Ok(ScanPage { items, next });
+            }
+            match page.next {
+                Some(next) => cursor = Some(next),
+                None => return Ok(ScanPage { items, next: None }),
+            }
+        }
+    }
+
+    /// Checks that the backing store is reachable, e.g. for health checks.
+    ///
+    /// In-process repositories have nothing to check, which is the default.
+    async fn ping(&self) -> Result<()> {
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -32,3 +128,22 @@ pub trait Repository: ReadRepository {
     /// Returns `true` if the record existed and was removed.
     async fn delete(&self, code: &ShortCode) -> Result<bool>;
 }
+
+/// Durable per-code click counts.
+#[async_trait]
+pub trait AnalyticsRepository: Send + Sync + 'static {
+    /// Atomically adds `hits` to the click count of `code` and returns the
+    /// new count.
+    ///
+    /// Unknown and deleted codes are not counted and report zero.
+    async fn increment_hits_by(&self, code: &ShortCode, hits: u64) -> Result<u64>;
+
+    /// Adds one click to `code` and returns the new count.
+    async fn increment_hits(&self, code: &ShortCode) -> Result<u64> {
+        self.increment_hits_by(code, 1).await
+    }
+
+    /// Returns the click count of `code`, or zero for unknown and deleted
+    /// codes.
+    async fn hits(&self, code: &ShortCode) -> Result<u64>;
+}
@@ -1,28 +1,49 @@
 use async_trait::async_trait;
+use dashmap::mapref::entry::Entry as MapEntry;
 use dashmap::DashMap;
 use jiff::Timestamp;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use wormhole_core::{ShortCode, UrlRecord};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use wormhole_core::{Clock, RedirectKind, SharedClock, ShortCode, UrlRecord};
 
-use crate::{ReadRepository, Repository, Result, StorageError};
+use crate::{
+    AnalyticsRepository, CodeStatus, ReadRepository, Repository, Result, ScanCursor, ScanPage,
+    StorageError,
+};
 
 /// In-memory storage entry for a URL mapping.
 #[derive(Debug, Clone)]
 struct Entry {
     original_url: String,
     expire_at: Option<Timestamp>,
+    redirect_kind: RedirectKind,
+    created_at: Timestamp,
+    internal_only: bool,
+    no_store: bool,
+    owner_id: Option<String>,
+    metadata: BTreeMap<String, String>,
+    /// Shared by clones, so counting only needs a read guard on the shard.
+    hits: Arc<AtomicU64>,
 }
 
 impl Entry {
-    fn is_expired(&self) -> bool {
-        self.expire_at
-            .is_some_and(|expire_at| Timestamp::now() >= expire_at)
+    fn is_expired(&self, clock: &SharedClock) -> bool {
+        clock.is_expired(self.expire_at)
     }
 
     fn into_record(self) -> UrlRecord {
         UrlRecord {
             original_url: self.original_url,
             expire_at: self.expire_at,
+            redirect_kind: self.redirect_kind,
+            created_at: self.created_at,
+            internal_only: self.internal_only,
+            no_store: self.no_store,
+            owner_id: self.owner_id,
+            metadata: self.metadata,
         }
     }
 }
@@ -35,6 +56,7 @@ impl Entry {
 #[derive(Debug, Clone)]
 pub struct InMemoryRepository {
     storage: Arc<DashMap<String, Entry>>,
+    clock: SharedClock,
 }
 
 impl InMemoryRepository {
@@ -42,6 +64,7 @@ impl InMemoryRepository {
     pub fn new() -> Self {
         Self {
             storage: Arc::new(DashMap::new()),
+            clock: SharedClock::default(),
         }
     }
 
@@ -49,8 +72,84 @@ impl InMemoryRepository {
     pub fn with_capacity(capacity: usize) -> Self {
         Self {
             storage: Arc::new(DashMap::with_capacity(capacity)),
+            clock: SharedClock::default(),
         }
     }
+
+    /// Decides expiry by `clock` instead of the system clock, e.g. to let
+    /// tests move time.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = SharedClock::new(clock);
+        self
+    }
+
+    /// Removes every expired entry and returns how many were removed.
+    ///
+    /// Expired entries are otherwise only dropped when they are read, so
+    /// codes that are never looked up again stay in memory. The sweep locks
+    /// one shard at a time, and expiry is re-checked under the shard lock, so
+    /// an entry inserted concurrently is never removed.
+    pub fn purge_expired(&self) -> usize {
+        let mut removed = 0;
+        self.storage.retain(|_, entry| {
+            let expired = entry.is_expired(&self.clock);
+            removed += usize::from(expired);
+            !expired
+        });
+        removed
+    }
+
+    /// Spawns a task that calls [`InMemoryRepository::purge_expired`] every
+    /// `interval`.
+    ///
+    /// The task runs until the returned handle is aborted.
+    pub fn spawn_eviction(&self, interval: Duration) -> JoinHandle<()> {
+        let this = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                this.purge_expired();
+            }
+        })
+    }
+
+    /// Lists active records after `cursor`, optionally only those owned by
+    /// `owner_id`.
+    fn scan_page(
+        &self,
+        owner_id: Option<&str>,
+        cursor: Option<ScanCursor>,
+        limit: usize,
+    ) -> ScanPage {
+        let limit = limit.max(1);
+        let after = cursor.as_ref().map_or("", ScanCursor::as_str);
+
+        // DashMap iteration order is arbitrary, so sort to give cursors a
+        // stable meaning across calls.
+        let mut entries: Vec<(String, Entry)> = self
+            .storage
+            .iter()
+            .filter(|entry| entry.key().as_str() > after && !entry.value().is_expired(&self.clock))
+            .filter(|entry| owner_id.is_none_or(|owner| entry.owner_id.as_deref() == Some(owner)))
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect();
+        entries.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+
+        let has_more = entries.len() > limit;
+        let items: Vec<_> = entries
+            .into_iter()
+            .take(limit)
+            .map(|(code, entry)| (ShortCode::new_unchecked(code), entry.into_record()))
+            .collect();
+        let next = match items.last() {
+            Some((code, _)) if has_more => Some(ScanCursor::after(code)),
+            _ => None,
+        };
+
+        ScanPage { items, next }
+    }
 }
 
 impl Default for InMemoryRepository {
@@ -68,7 +167,7 @@ impl ReadRepository for InMemoryRepository {
             return Ok(None);
         };
 
-        if entry.is_expired() {
+        if entry.is_expired(&self.clock) {
             drop(entry);
             self.storage.remove(key);
             return Ok(None);
@@ -84,7 +183,7 @@ impl ReadRepository for InMemoryRepository {
             return Ok(false);
         };
 
-        if entry.is_expired() {
+        if entry.is_expired(&self.clock) {
             drop(entry);
             self.storage.remove(key);
             return Ok(false);
@@ -92,6 +191,33 @@ impl ReadRepository for InMemoryRepository {
 
         Ok(true)
     }
+
+    /// Deleted entries are removed outright, so they report
+    /// [`CodeStatus::NotFound`] rather than [`CodeStatus::Deleted`].
+    async fn status(&self, code: &ShortCode) -> Result<CodeStatus> {
+        let Some(entry) = self.storage.get(code.as_str()) else {
+            return Ok(CodeStatus::NotFound);
+        };
+
+        if entry.is_expired(&self.clock) {
+            return Ok(CodeStatus::Expired);
+        }
+
+        Ok(CodeStatus::Active(entry.clone().into_record()))
+    }
+
+    async fn scan(&self, cursor: Option<ScanCursor>, limit: usize) -> Result<ScanPage> {
+        Ok(self.scan_page(None, cursor, limit))
+    }
+
+    async fn scan_owned_by(
+        &self,
+        owner_id: &str,
+        cursor: Option<ScanCursor>,
+        limit: usize,
+    ) -> Result<ScanPage> {
+        Ok(self.scan_page(Some(owner_id), cursor, limit))
+    }
 }
 
 #[async_trait]
@@ -101,20 +227,31 @@ impl Repository for InMemoryRepository {
         let entry = Entry {
             original_url: record.original_url,
             expire_at: record.expire_at,
+            redirect_kind: record.redirect_kind,
+            created_at: record.created_at,
+            internal_only: record.internal_only,
+            no_store: record.no_store,
+            owner_id: record.owner_id,
+            metadata: record.metadata,
+            hits: Arc::new(AtomicU64::new(0)),
         };
 
-        // Check-and-insert: reject if the code is already taken (and not expired).
-        let existing = self.storage.get(&key);
-        if let Some(ref e) = existing {
-            if !e.is_expired() {
-                return Err(StorageError::Conflict(code.to_string()));
+        // Check-and-insert under the shard's write lock, so concurrent
+        // inserts of the same code cannot both pass the conflict check.
+        match self.storage.entry(key) {
+            MapEntry::Occupied(existing) if !existing.get().is_expired(&self.clock) => {
+                Err(StorageError::Conflict(code.to_string()))
+            }
+            // An expired entry may be replaced.
+            MapEntry::Occupied(mut existing) => {
+                existing.insert(entry);
+                Ok(())
+            }
+            MapEntry::Vacant(vacant) => {
+                vacant.insert(entry);
+                Ok(())
             }
-            // Expired entry — drop the read guard, then remove & re-insert below.
-            drop(existing);
         }
-
-        self.storage.insert(key, entry);
-        Ok(())
     }
 
     async fn delete(&self, code: &ShortCode) -> Result<bool> {
@@ -122,6 +259,23 @@ impl Repository for InMemoryRepository {
     }
 }
 
+#[async_trait]
+impl AnalyticsRepository for InMemoryRepository {
+    async fn increment_hits_by(&self, code: &ShortCode, hits: u64) -> Result<u64> {
+        let Some(entry) = self.storage.get(code.as_str()) else {
+            return Ok(0);
+        };
+        Ok(entry.hits.fetch_add(hits, Ordering::Relaxed) + hits)
+    }
+
+    async fn hits(&self, code: &ShortCode) -> Result<u64> {
+        Ok(self
+            .storage
+            .get(code.as_str())
+            .map_or(0, |entry| entry.hits.load(Ordering::Relaxed)))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -135,6 +289,12 @@ mod tests {
         UrlRecord {
             original_url: url.to_string(),
             expire_at,
+            redirect_kind: RedirectKind::default(),
+            created_at: Timestamp::now(),
+            internal_only: false,
+            no_store: false,
+            owner_id: None,
+            metadata: Default::default(),
         }
     }
 
@@ -209,6 +369,37 @@ mod tests {
         assert!(result.is_none());
     }
 
+    #[tokio::test]
+    async fn expiry_follows_the_injected_clock() {
+        let start = Timestamp::now();
+        let clock = wormhole_core::ManualClock::new(start);
+        let repo = InMemoryRepository::new().with_clock(clock.clone());
+        let c = code("abc123");
+        repo.insert(
+            &c,
+            record(
+                "https://example.com",
+                Some(start + SignedDuration::from_hours(1)),
+            ),
+        )
+        .await
+        .unwrap();
+
+        assert!(repo.get(&c).await.unwrap().is_some());
+        assert!(matches!(
+            repo.status(&c).await.unwrap(),
+            CodeStatus::Active(_)
+        ));
+
+        clock.advance(SignedDuration::from_hours(1));
+        assert!(matches!(
+            repo.status(&c).await.unwrap(),
+            CodeStatus::Expired
+        ));
+        assert!(repo.get(&c).await.unwrap().is_none());
+        assert!(!repo.exists(&c).await.unwrap());
+    }
+
     #[tokio::test]
     async fn not_expired_entry() {
         let repo = InMemoryRepository::new();
@@ -283,6 +474,12 @@ mod tests {
                 let r = UrlRecord {
                     original_url: format!("https://example{}.com", i),
                     expire_at: None,
+                    redirect_kind: RedirectKind::default(),
+                    created_at: Timestamp::now(),
+                    internal_only: false,
+                    no_store: false,
+                    owner_id: None,
+                    metadata: Default::default(),
                 };
                 repo.insert(&c, r).await.unwrap();
             });
@@ -308,4 +505,298 @@ mod tests {
             assert_eq!(result.original_url, format!("https://example{}.com", i));
         }
     }
+
+    #[tokio::test]
+    async fn scan_pages_through_all_active_records() {
+        let repo = InMemoryRepository::new();
+        for i in 0..25 {
+            repo.insert(
+                &code(&format!("code-{i:02}")),
+                record("https://example.com", None),
+            )
+            .await
+            .unwrap();
+        }
+        let expired = Timestamp::now() - SignedDuration::from_secs(1);
+        repo.insert(
+            &code("code-expired"),
+            record("https://example.com", Some(expired)),
+        )
+        .await
+        .unwrap();
+
+        let mut seen = Vec::new();
+        let mut cursor = None;
+        let mut pages = 0;
+        loop {
+            let page = repo.scan(cursor, 10).await.unwrap();
+            assert!(page.items.len() <= 10);
+            seen.extend(page.items.into_iter().map(|(code, _)| code.to_string()));
+            pages += 1;
+            match page.next {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+
+        let expected: Vec<_> = (0..25).map(|i| format!("code-{i:02}")).collect();
+        assert_eq!(seen, expected);
+        assert_eq!(pages, 3);
+    }
+
+    #[tokio::test]
+    async fn scan_of_empty_repository_has_no_next_page() {
+        let repo = InMemoryRepository::new();
+
+        let page = repo.scan(None, 10).await.unwrap();
+        assert!(page.items.is_empty());
+        assert!(page.next.is_none());
+    }
+
+    #[tokio::test]
+    async fn status_distinguishes_active_expired_and_missing() {
+        let repo = InMemoryRepository::new();
+        let expired = Timestamp::now() - SignedDuration::from_secs(1);
+        repo.insert(&code("active"), record("https://example.com", None))
+            .await
+            .unwrap();
+        repo.insert(
+            &code("expired"),
+            record("https://example.com", Some(expired)),
+        )
+        .await
+        .unwrap();
+
+        assert!(matches!(
+            repo.status(&code("active")).await.unwrap(),
+            CodeStatus::Active(record) if record.original_url == "https://example.com"
+        ));
+        assert_eq!(
+            repo.status(&code("expired")).await.unwrap(),
+            CodeStatus::Expired
+        );
+        assert_eq!(
+            repo.status(&code("missing")).await.unwrap(),
+            CodeStatus::NotFound
+        );
+    }
+
+    #[tokio::test]
+    async fn purge_expired_keeps_live_entries() {
+        let repo = InMemoryRepository::new();
+        let past = Timestamp::now() - SignedDuration::from_secs(1);
+
+        repo.insert(
+            &code("expired"),
+            record("https://example.com/old", Some(past)),
+        )
+        .await
+        .unwrap();
+        repo.insert(&code("live"), record("https://example.com", None))
+            .await
+            .unwrap();
+
+        assert_eq!(repo.purge_expired(), 1);
+        assert_eq!(repo.storage.len(), 1);
+        assert!(repo.exists(&code("live")).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn spawn_eviction_sweeps_unread_expired_entries() {
+        let clock = wormhole_core::ManualClock::new(Timestamp::now());
+        let repo = InMemoryRepository::new().with_clock(clock.clone());
+        let soon = Timestamp::now() + SignedDuration::from_secs(60);
+
+        for i in 0..100 {
+            repo.insert(
+                &code(&format!("short{i}")),
+                record("https://example.com", Some(soon)),
+            )
+            .await
+            .unwrap();
+        }
+        repo.insert(&code("keep"), record("https://example.com", None))
+            .await
+            .unwrap();
+        assert_eq!(repo.storage.len(), 101);
+        clock.advance(SignedDuration::from_secs(61));
+
+        let eviction = repo.spawn_eviction(Duration::from_millis(10));
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        eviction.abort();
+
+        assert_eq!(repo.storage.len(), 1);
+        assert!(repo.exists(&code("keep")).await.unwrap());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_hit_increments_are_serialized() {
+        let repo = InMemoryRepository::new();
+        repo.insert(&code("abc123"), record("https://example.com", None))
+            .await
+            .unwrap();
+
+        let handles: Vec<_> = (0..100)
+            .map(|_| {
+                let repo = repo.clone();
+                tokio::spawn(async move { repo.increment_hits(&code("abc123")).await.unwrap() })
+            })
+            .collect();
+        let mut counts = Vec::new();
+        for handle in handles {
+            counts.push(handle.await.unwrap());
+        }
+        counts.sort_unstable();
+
+        // Every increment observed a distinct count, so none were lost.
+        assert_eq!(counts, (1..=100).collect::<Vec<u64>>());
+        assert_eq!(repo.hits(&code("abc123")).await.unwrap(), 100);
+    }
+
+    #[tokio::test]
+    async fn unknown_codes_are_not_counted() {
+        let repo = InMemoryRepository::new();
+
+        assert_eq!(repo.increment_hits(&code("nope")).await.unwrap(), 0);
+        assert_eq!(repo.hits(&code("nope")).await.unwrap(), 0);
+    }
+
+    fn owned(url: &str, owner_id: &str) -> UrlRecord {
+        let mut record = record(url, None);
+        record.owner_id = Some(owner_id.to_string());
+        record
+    }
+
+    /// Pages through [`ReadRepository::scan_owned_by`] and returns the codes.
+    async fn owned_codes<R: ReadRepository>(repo: &R, owner_id: &str, limit: usize) -> Vec<String> {
+        let mut seen = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = repo.scan_owned_by(owner_id, cursor, limit).await.unwrap();
+            assert!(page.items.len() <= limit);
+            for (code, record) in page.items {
+                assert_eq!(record.owner_id.as_deref(), Some(owner_id));
+                seen.push(code.to_string());
+            }
+            match page.next {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        seen
+    }
+
+    async fn insert_owned_records(repo: &InMemoryRepository) {
+        for i in 0..10 {
+            let owner = if i % 3 == 0 { "tenant-a" } else { "tenant-b" };
+            repo.insert(
+                &code(&format!("code-{i:02}")),
+                owned("https://example.com", owner),
+            )
+            .await
+            .unwrap();
+        }
+        repo.insert(&code("code-unowned"), record("https://example.com", None))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn owner_and_metadata_are_stored() {
+        let repo = InMemoryRepository::new();
+        let mut record = owned("https://example.com", "tenant-a");
+        record
+            .metadata
+            .insert("campaign".to_string(), "spring".to_string());
+        repo.insert(&code("tagged"), record.clone()).await.unwrap();
+
+        assert_eq!(repo.get(&code("tagged")).await.unwrap(), Some(record));
+    }
+
+    #[tokio::test]
+    async fn scan_owned_by_only_returns_the_owners_records() {
+        let repo = InMemoryRepository::new();
+        insert_owned_records(&repo).await;
+
+        assert_eq!(
+            owned_codes(&repo, "tenant-a", 2).await,
+            ["code-00", "code-03", "code-06", "code-09"]
+        );
+        assert_eq!(owned_codes(&repo, "tenant-b", 100).await.len(), 6);
+        assert!(owned_codes(&repo, "nobody", 10).await.is_empty());
+    }
+
+    /// Only implements the required methods, to exercise the default
+    /// [`ReadRepository::scan_owned_by`].
+    struct ScanOnly(InMemoryRepository);
+
+    #[async_trait]
+    impl ReadRepository for ScanOnly {
+        async fn get(&self, code: &ShortCode) -> Result<Option<UrlRecord>> {
+            self.0.get(code).await
+        }
+
+        async fn exists(&self, code: &ShortCode) -> Result<bool> {
+            self.0.exists(code).await
+        }
+
+        async fn status(&self, code: &ShortCode) -> Result<CodeStatus> {
+            self.0.status(code).await
+        }
+
+        async fn scan(&self, cursor: Option<ScanCursor>, limit: usize) -> Result<ScanPage> {
+            self.0.scan(cursor, limit).await
+        }
+    }
+
+    #[tokio::test]
+    async fn default_scan_owned_by_filters_scan_pages() {
+        let repo = InMemoryRepository::new();
+        insert_owned_records(&repo).await;
+        let repo = ScanOnly(repo);
+
+        assert_eq!(
+            owned_codes(&repo, "tenant-a", 2).await,
+            ["code-00", "code-03", "code-06", "code-09"]
+        );
+        assert_eq!(owned_codes(&repo, "tenant-b", 4).await.len(), 6);
+        assert!(owned_codes(&repo, "nobody", 3).await.is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_inserts_of_the_same_code_admit_exactly_one() {
+        let repo = Arc::new(InMemoryRepository::new());
+        let barrier = Arc::new(tokio::sync::Barrier::new(64));
+
+        let handles: Vec<_> = (0..64)
+            .map(|i| {
+                let repo = Arc::clone(&repo);
+                let barrier = Arc::clone(&barrier);
+                tokio::spawn(async move {
+                    barrier.wait().await;
+                    repo.insert(
+                        &code("contested"),
+                        record(&format!("https://example{i}.com"), None),
+                    )
+                    .await
+                    .map(|()| i)
+                })
+            })
+            .collect();
+
+        let mut winners = Vec::new();
+        for handle in handles {
+            match handle.await.unwrap() {
+                Ok(i) => winners.push(i),
+                Err(err) => assert!(matches!(err, StorageError::Conflict(_)), "{err:?}"),
+            }
+        }
+
+        assert_eq!(winners.len(), 1, "winners: {winners:?}");
+        let stored = repo.get(&code("contested")).await.unwrap().unwrap();
+        assert_eq!(
+            stored.original_url,
+            format!("https://example{}.com", winners[0])
+        );
+    }
 }
@@ -1,9 +1,47 @@
 use async_trait::async_trait;
-use jiff::Timestamp;
+use sqlx::mysql::{MySqlPoolOptions, MySqlRow};
+use sqlx::types::Json;
 use sqlx::{MySqlPool, Row};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use typed_builder::TypedBuilder;
 use wormhole_core::{ShortCode, UrlRecord};
 
-use crate::{ReadRepository, Repository, Result, StorageError};
+use crate::sql::{
+    is_unique_violation, map_sqlx_error, now_unix_seconds, parse_created_at, parse_expire_at,
+    parse_redirect_kind,
+};
+use crate::{
+    AnalyticsRepository, CodeStatus, ReadRepository, Repository, Result, ScanCursor, ScanPage,
+    StorageError,
+};
+
+/// Connection pool settings for [`MySqlRepository::connect_with`].
+///
+/// The defaults match sqlx's own.
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct MySqlPoolConfig {
+    /// Upper bound on open connections.
+    #[builder(default = 10)]
+    pub max_connections: u32,
+    /// Connections kept open even when idle.
+    #[builder(default = 0)]
+    pub min_connections: u32,
+    /// How long a query waits for a free connection before failing with
+    /// [`StorageError::Timeout`]. Keep it short to fail over quickly.
+    #[builder(default = Duration::from_secs(30))]
+    pub acquire_timeout: Duration,
+    /// Idle connections above `min_connections` are closed after this long;
+    /// `None` keeps them open.
+    #[builder(default = Some(Duration::from_secs(600)))]
+    pub idle_timeout: Option<Duration>,
+}
+
+impl Default for MySqlPoolConfig {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
 
 /// MySQL implementation of the repository contract.
 ///
@@ -11,76 +49,250 @@ use crate::{ReadRepository, Repository, Result, StorageError};
 /// records (`deleted_at IS NULL` and not expired). Inserts never reuse an
 /// existing short code, including soft-deleted rows, to preserve analytics
 /// history with a single-row-per-code model.
+///
+/// Reads (`get`, `exists`) and writes (`insert`, `delete`) may go to separate
+/// pools, see [`MySqlRepository::with_pools`].
+///
+/// Clones share the same pools. Dropping a repository does not close them;
+/// processes that create repositories on the fly, e.g. one per tenant,
+/// should call [`MySqlRepository::close`] when done with one.
 #[derive(Debug, Clone)]
 pub struct MySqlRepository {
-    pool: MySqlPool,
+    write_pool: MySqlPool,
+    read_pool: MySqlPool,
 }
 
 impl MySqlRepository {
-    /// Creates a repository from an existing MySQL connection pool.
+    /// Creates a repository from an existing MySQL connection pool, used for
+    /// both reads and writes.
     pub fn new(pool: MySqlPool) -> Self {
-        Self { pool }
+        Self::with_pools(pool.clone(), pool)
     }
 
-    /// Creates a repository by opening a new MySQL connection pool.
+    /// Creates a repository that sends writes to `write_pool` (the primary)
+    /// and reads to `read_pool` (typically a replica).
+    ///
+    /// Replicas apply writes asynchronously, so a read issued right after a
+    /// write may not observe it: a freshly inserted code can briefly look
+    /// missing and a deleted one can still resolve. `exists` is subject to the
+    /// same lag, so it must not be relied on to detect alias conflicts; the
+    /// unique constraint enforced by `insert` on the primary remains the
+    /// source of truth. Callers that need read-after-write consistency should
+    /// use a repository built with [`MySqlRepository::new`] on the primary.
+    pub fn with_pools(write_pool: MySqlPool, read_pool: MySqlPool) -> Self {
+        Self {
+            write_pool,
+            read_pool,
+        }
+    }
+
+    /// Creates a repository by opening a new MySQL connection pool with
+    /// default settings.
     pub async fn connect(database_url: &str) -> Result<Self> {
-        let pool = MySqlPool::connect(database_url)
+        Self::connect_with(database_url, MySqlPoolConfig::default()).await
+    }
+
+    /// Creates a repository by opening a new MySQL connection pool tuned by
+    /// `config`.
+    pub async fn connect_with(database_url: &str, config: MySqlPoolConfig) -> Result<Self> {
+        let pool = MySqlPoolOptions::new()
+            .max_connections(config.max_connections)
+            .min_connections(config.min_connections)
+            .acquire_timeout(config.acquire_timeout)
+            .idle_timeout(config.idle_timeout)
+            .connect(database_url)
             .await
             .map_err(map_sqlx_error)?;
         Ok(Self::new(pool))
     }
 
+    /// Closes the pools, waiting for connections in use to be returned.
+    ///
+    /// Clones share the pools, so they are closed too: any further query
+    /// through one fails with [`StorageError::Unavailable`].
+    pub async fn close(self) {
+        self.write_pool.close().await;
+        self.read_pool.close().await;
+    }
+
+    /// Brings the schema up to date on the write pool.
+    ///
+    /// Creates `short_urls` on a fresh database and applies every migration
+    /// under `ddl/mysql` that has not run yet, in version order. Applied
+    /// versions are recorded in sqlx's `_sqlx_migrations` table, so calling
+    /// this on every startup is safe; it fails if an applied migration was
+    /// edited afterwards.
     pub async fn migrate(&self) -> Result<()> {
         sqlx::migrate!("ddl/mysql")
-            .run(&self.pool)
+            .run(&self.write_pool)
             .await
             .map_err(|e| StorageError::Unknown(format!("failed to run migrations: {e}")))?;
 
         Ok(())
     }
 
-    /// Returns a reference to the underlying pool.
+    /// Returns the width of the `short_code` column, in characters.
+    ///
+    /// Compare it with the longest code the generator can produce at startup;
+    /// MySQL would otherwise reject (or, outside strict mode, truncate)
+    /// longer codes on insert.
+    pub async fn short_code_column_width(&self) -> Result<usize> {
+        let width: Option<Option<i64>> = sqlx::query_scalar(
+            "SELECT CAST(CHARACTER_MAXIMUM_LENGTH AS SIGNED) FROM information_schema.COLUMNS \
+             WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = 'short_urls' \
+             AND COLUMN_NAME = 'short_code'",
+        )
+        .fetch_optional(&self.write_pool)
+        .await
+        .map_err(map_sqlx_error)?;
+
+        width
+            .flatten()
+            .and_then(|width| usize::try_from(width).ok())
+            .ok_or_else(|| {
+                StorageError::InvalidData("short_urls.short_code column not found".to_string())
+            })
+    }
+
+    /// Returns up to `limit` active short codes, newest first.
+    ///
+    /// Recently created links are likely to be visited soon, so this is a
+    /// good source for warming caches, e.g. through `BloomFilter::warm_from`.
+    /// Rows created before `created_at` was tracked sort last.
+    pub async fn recent(&self, limit: usize) -> Result<Vec<ShortCode>> {
+        let codes: Vec<String> = sqlx::query_scalar(
+            r#"
+            SELECT short_code
+            FROM short_urls
+            WHERE deleted_at IS NULL
+              AND (expire_at IS NULL OR expire_at > ?)
+            ORDER BY created_at DESC, short_code
+            LIMIT ?
+            "#,
+        )
+        .bind(now_unix_seconds())
+        .bind(limit as u64)
+        .fetch_all(&self.read_pool)
+        .await
+        .map_err(map_sqlx_error)?;
+
+        Ok(codes.into_iter().map(ShortCode::new_unchecked).collect())
+    }
+
+    /// Removes the row for `code` outright, whether or not it was already
+    /// soft-deleted. Returns `false` if there was no such row.
+    ///
+    /// Meant for erasure requests that must not leave the URL behind.
+    /// Unlike [`Repository::delete`], this gives up the no-reuse policy:
+    /// `exists` reports `false` afterwards, so the code may be handed out
+    /// again, and its click count is lost with the row.
+    pub async fn hard_delete(&self, code: &ShortCode) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM short_urls WHERE short_code = ?")
+            .bind(code.as_str())
+            .execute(&self.write_pool)
+            .await
+            .map_err(map_sqlx_error)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Returns a reference to the pool used for writes.
     pub fn pool(&self) -> &MySqlPool {
-        &self.pool
+        &self.write_pool
     }
-}
 
-fn now_unix_seconds() -> i64 {
-    Timestamp::now().as_second()
-}
+    /// Returns a reference to the pool used for reads.
+    ///
+    /// This is the same pool as [`MySqlRepository::pool`] unless the
+    /// repository was built with [`MySqlRepository::with_pools`].
+    pub fn read_pool(&self) -> &MySqlPool {
+        &self.read_pool
+    }
+
+    /// Lists active records after `cursor`, optionally only those owned by
+    /// `owner_id`.
+    async fn scan_page(
+        &self,
+        owner_id: Option<&str>,
+        cursor: Option<ScanCursor>,
+        limit: usize,
+    ) -> Result<ScanPage> {
+        let limit = limit.max(1);
+        let owner_filter = if owner_id.is_some() {
+            "AND owner_id = ?"
+        } else {
+            ""
+        };
+        let sql = format!(
+            r#"
+            SELECT short_code, original_url, expire_at, redirect_kind, created_at, internal_only,
+                   no_store, owner_id, metadata
+            FROM short_urls
+            WHERE short_code > ?
+              AND deleted_at IS NULL
+              AND (expire_at IS NULL OR expire_at > ?)
+              {owner_filter}
+            ORDER BY short_code
+            LIMIT ?
+            "#
+        );
+
+        // Keyset pagination on the primary key; fetch one extra row to learn
+        // whether another page follows.
+        let mut query = sqlx::query(&sql)
+            .bind(cursor.as_ref().map_or("", ScanCursor::as_str))
+            .bind(now_unix_seconds());
+        if let Some(owner_id) = owner_id {
+            query = query.bind(owner_id);
+        }
+        let rows = query
+            .bind((limit + 1) as u64)
+            .fetch_all(&self.read_pool)
+            .await
+            .map_err(map_sqlx_error)?;
 
-fn parse_expire_at(seconds: Option<i64>) -> Result<Option<Timestamp>> {
-    seconds
-        .map(|value| {
-            Timestamp::from_second(value).map_err(|e| {
-                StorageError::InvalidData(format!("invalid expire_at timestamp '{}': {e}", value))
+        let has_more = rows.len() > limit;
+        let items = rows
+            .iter()
+            .take(limit)
+            .map(|row| {
+                let code: String = row.try_get("short_code").map_err(map_sqlx_error)?;
+                Ok((ShortCode::new_unchecked(code), record_from_row(row)?))
             })
-        })
-        .transpose()
-}
+            .collect::<Result<Vec<_>>>()?;
+        let next = match items.last() {
+            Some((code, _)) if has_more => Some(ScanCursor::after(code)),
+            _ => None,
+        };
 
-fn is_unique_violation(err: &sqlx::Error) -> bool {
-    err.as_database_error()
-        .is_some_and(sqlx::error::DatabaseError::is_unique_violation)
+        Ok(ScanPage { items, next })
+    }
 }
 
-fn map_sqlx_error(err: sqlx::Error) -> StorageError {
-    let message = err.to_string();
-
-    match err {
-        sqlx::Error::PoolTimedOut => StorageError::Timeout(message),
-        sqlx::Error::PoolClosed
-        | sqlx::Error::WorkerCrashed
-        | sqlx::Error::Io(_)
-        | sqlx::Error::Tls(_) => StorageError::Unavailable(message),
-        sqlx::Error::ColumnIndexOutOfBounds { .. }
-        | sqlx::Error::ColumnNotFound(_)
-        | sqlx::Error::ColumnDecode { .. }
-        | sqlx::Error::TypeNotFound { .. }
-        | sqlx::Error::Decode(_)
-        | sqlx::Error::RowNotFound => StorageError::InvalidData(message),
-        _ => StorageError::Query(message),
-    }
+fn record_from_row(row: &MySqlRow) -> Result<UrlRecord> {
+    let original_url: String = row.try_get("original_url").map_err(map_sqlx_error)?;
+    let expire_at_raw: Option<i64> = row.try_get("expire_at").map_err(map_sqlx_error)?;
+    let expire_at = parse_expire_at(expire_at_raw)?;
+    let redirect_kind_raw: u16 = row.try_get("redirect_kind").map_err(map_sqlx_error)?;
+    let redirect_kind = parse_redirect_kind(redirect_kind_raw)?;
+    let created_at_raw: i64 = row.try_get("created_at").map_err(map_sqlx_error)?;
+    let created_at = parse_created_at(created_at_raw)?;
+    let internal_only: bool = row.try_get("internal_only").map_err(map_sqlx_error)?;
+    let no_store: bool = row.try_get("no_store").map_err(map_sqlx_error)?;
+    let owner_id: Option<String> = row.try_get("owner_id").map_err(map_sqlx_error)?;
+    let metadata: Option<Json<BTreeMap<String, String>>> =
+        row.try_get("metadata").map_err(map_sqlx_error)?;
+
+    Ok(UrlRecord {
+        original_url,
+        expire_at,
+        redirect_kind,
+        created_at,
+        internal_only,
+        no_store,
+        owner_id,
+        metadata: metadata.map(|Json(metadata)| metadata).unwrap_or_default(),
+    })
 }
 
 #[async_trait]
@@ -90,7 +302,8 @@ impl ReadRepository for MySqlRepository {
 
         let row = sqlx::query(
             r#"
-            SELECT original_url, expire_at
+            SELECT original_url, expire_at, redirect_kind, created_at, internal_only, no_store,
+                   owner_id, metadata
             FROM short_urls
             WHERE short_code = ?
               AND deleted_at IS NULL
@@ -100,22 +313,11 @@ impl ReadRepository for MySqlRepository {
         )
         .bind(code.as_str())
         .bind(now)
-        .fetch_optional(&self.pool)
+        .fetch_optional(&self.read_pool)
         .await
         .map_err(map_sqlx_error)?;
 
-        let Some(row) = row else {
-            return Ok(None);
-        };
-
-        let original_url: String = row.try_get("original_url").map_err(map_sqlx_error)?;
-        let expire_at_raw: Option<i64> = row.try_get("expire_at").map_err(map_sqlx_error)?;
-        let expire_at = parse_expire_at(expire_at_raw)?;
-
-        Ok(Some(UrlRecord {
-            original_url,
-            expire_at,
-        }))
+        row.as_ref().map(record_from_row).transpose()
     }
 
     async fn exists(&self, code: &ShortCode) -> Result<bool> {
@@ -128,13 +330,72 @@ impl ReadRepository for MySqlRepository {
             "#,
         )
         .bind(code.as_str())
-        .fetch_optional(&self.pool)
+        .fetch_optional(&self.read_pool)
         .await
         .map_err(map_sqlx_error)?
         .is_some();
 
         Ok(exists)
     }
+
+    async fn status(&self, code: &ShortCode) -> Result<CodeStatus> {
+        let row = sqlx::query(
+            r#"
+            SELECT original_url, expire_at, redirect_kind, created_at, internal_only, no_store,
+                   owner_id, metadata, deleted_at
+            FROM short_urls
+            WHERE short_code = ?
+            LIMIT 1
+            "#,
+        )
+        .bind(code.as_str())
+        .fetch_optional(&self.read_pool)
+        .await
+        .map_err(map_sqlx_error)?;
+
+        let Some(row) = row else {
+            return Ok(CodeStatus::NotFound);
+        };
+
+        let deleted_at: Option<i64> = row.try_get("deleted_at").map_err(map_sqlx_error)?;
+        if deleted_at.is_some() {
+            return Ok(CodeStatus::Deleted);
+        }
+
+        let record = record_from_row(&row)?;
+        if record
+            .expire_at
+            .is_some_and(|expire_at| expire_at.as_second() <= now_unix_seconds())
+        {
+            return Ok(CodeStatus::Expired);
+        }
+
+        Ok(CodeStatus::Active(record))
+    }
+
+    async fn scan(&self, cursor: Option<ScanCursor>, limit: usize) -> Result<ScanPage> {
+        self.scan_page(None, cursor, limit).await
+    }
+
+    async fn scan_owned_by(
+        &self,
+        owner_id: &str,
+        cursor: Option<ScanCursor>,
+        limit: usize,
+    ) -> Result<ScanPage> {
+        self.scan_page(Some(owner_id), cursor, limit).await
+    }
+
+    /// Runs `SELECT 1` on both the write and the read pool.
+    async fn ping(&self) -> Result<()> {
+        for pool in [&self.write_pool, &self.read_pool] {
+            sqlx::query("SELECT 1")
+                .execute(pool)
+                .await
+                .map_err(map_sqlx_error)?;
+        }
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -144,14 +405,23 @@ impl Repository for MySqlRepository {
 
         let result = sqlx::query(
             r#"
-            INSERT INTO short_urls (short_code, original_url, expire_at, deleted_at)
-            VALUES (?, ?, ?, NULL)
+            INSERT INTO short_urls (
+                short_code, original_url, expire_at, redirect_kind, created_at, internal_only,
+                no_store, owner_id, metadata, deleted_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, NULL)
             "#,
         )
         .bind(code.as_str())
         .bind(record.original_url)
         .bind(expire_at)
-        .execute(&self.pool)
+        .bind(record.redirect_kind.status_code())
+        .bind(record.created_at.as_second())
+        .bind(record.internal_only)
+        .bind(record.no_store)
+        .bind(record.owner_id)
+        .bind((!record.metadata.is_empty()).then_some(Json(record.metadata)))
+        .execute(&self.write_pool)
         .await;
 
         match result {
@@ -174,10 +444,64 @@ impl Repository for MySqlRepository {
         )
         .bind(now)
         .bind(code.as_str())
-        .execute(&self.pool)
+        .execute(&self.write_pool)
         .await
         .map_err(map_sqlx_error)?;
 
         Ok(result.rows_affected() > 0)
     }
 }
+
+#[async_trait]
+impl AnalyticsRepository for MySqlRepository {
+    /// Runs `UPDATE ... SET hits = hits + ?` and reads the count back in the
+    /// same transaction, so the row lock taken by the update keeps concurrent
+    /// increments from interleaving.
+    async fn increment_hits_by(&self, code: &ShortCode, hits: u64) -> Result<u64> {
+        let mut tx = self.write_pool.begin().await.map_err(map_sqlx_error)?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE short_urls
+            SET hits = hits + ?
+            WHERE short_code = ?
+              AND deleted_at IS NULL
+            "#,
+        )
+        .bind(hits)
+        .bind(code.as_str())
+        .execute(&mut *tx)
+        .await
+        .map_err(map_sqlx_error)?;
+
+        if result.rows_affected() == 0 {
+            return Ok(0);
+        }
+
+        let count: u64 = sqlx::query_scalar("SELECT hits FROM short_urls WHERE short_code = ?")
+            .bind(code.as_str())
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(map_sqlx_error)?;
+        tx.commit().await.map_err(map_sqlx_error)?;
+
+        Ok(count)
+    }
+
+    async fn hits(&self, code: &ShortCode) -> Result<u64> {
+        let count: Option<u64> = sqlx::query_scalar(
+            r#"
+            SELECT hits
+            FROM short_urls
+            WHERE short_code = ?
+              AND deleted_at IS NULL
+            "#,
+        )
+        .bind(code.as_str())
+        .fetch_optional(&self.read_pool)
+        .await
+        .map_err(map_sqlx_error)?;
+
+        Ok(count.unwrap_or(0))
+    }
+}
@@ -0,0 +1,38 @@
+use wormhole_core::{ShortCode, UrlRecord};
+
+/// Position to resume a [`scan`](crate::ReadRepository::scan) from.
+///
+/// Cursors are keyed on the last short code returned, so they stay valid
+/// while records are inserted or deleted between pages.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ScanCursor(String);
+
+impl ScanCursor {
+    /// Creates a cursor that resumes after `code`.
+    pub fn after(code: &ShortCode) -> Self {
+        Self(code.as_str().to_string())
+    }
+
+    /// Returns the short code the next page starts after.
+    ///
+    /// Useful for handing the cursor to a client and rebuilding it with
+    /// [`ScanCursor::from`] on the next request.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for ScanCursor {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+/// One page of a repository scan, ordered by short code.
+#[derive(Debug, Clone, Default)]
+pub struct ScanPage {
+    /// Active records on this page.
+    pub items: Vec<(ShortCode, UrlRecord)>,
+    /// Cursor for the next page, or `None` if this is the last one.
+    pub next: Option<ScanCursor>,
+}
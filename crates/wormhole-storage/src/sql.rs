@@ -0,0 +1,55 @@
+//! Helpers shared by the sqlx-backed repositories.
+
+use jiff::Timestamp;
+use wormhole_core::RedirectKind;
+
+use crate::{Result, StorageError};
+
+pub(crate) fn now_unix_seconds() -> i64 {
+    Timestamp::now().as_second()
+}
+
+pub(crate) fn parse_expire_at(seconds: Option<i64>) -> Result<Option<Timestamp>> {
+    seconds
+        .map(|value| {
+            Timestamp::from_second(value).map_err(|e| {
+                StorageError::InvalidData(format!("invalid expire_at timestamp '{}': {e}", value))
+            })
+        })
+        .transpose()
+}
+
+pub(crate) fn parse_created_at(seconds: i64) -> Result<Timestamp> {
+    Timestamp::from_second(seconds).map_err(|e| {
+        StorageError::InvalidData(format!("invalid created_at timestamp '{}': {e}", seconds))
+    })
+}
+
+pub(crate) fn parse_redirect_kind(status: u16) -> Result<RedirectKind> {
+    RedirectKind::from_status_code(status)
+        .ok_or_else(|| StorageError::InvalidData(format!("invalid redirect_kind '{}'", status)))
+}
+
+pub(crate) fn is_unique_violation(err: &sqlx::Error) -> bool {
+    err.as_database_error()
+        .is_some_and(sqlx::error::DatabaseError::is_unique_violation)
+}
+
+pub(crate) fn map_sqlx_error(err: sqlx::Error) -> StorageError {
+    let message = err.to_string();
+
+    match err {
+        sqlx::Error::PoolTimedOut => StorageError::Timeout(message),
+        sqlx::Error::PoolClosed
+        | sqlx::Error::WorkerCrashed
+        | sqlx::Error::Io(_)
+        | sqlx::Error::Tls(_) => StorageError::Unavailable(message),
+        sqlx::Error::ColumnIndexOutOfBounds { .. }
+        | sqlx::Error::ColumnNotFound(_)
+        | sqlx::Error::ColumnDecode { .. }
+        | sqlx::Error::TypeNotFound { .. }
+        | sqlx::Error::Decode(_)
+        | sqlx::Error::RowNotFound => StorageError::InvalidData(message),
+        _ => StorageError::Query(message),
+    }
+}
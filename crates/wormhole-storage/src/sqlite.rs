@@ -0,0 +1,354 @@
+use async_trait::async_trait;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteRow};
+use sqlx::types::Json;
+use sqlx::{Row, SqlitePool};
+use std::collections::BTreeMap;
+use wormhole_core::{ShortCode, UrlRecord};
+
+use crate::sql::{
+    is_unique_violation, map_sqlx_error, now_unix_seconds, parse_created_at, parse_expire_at,
+    parse_redirect_kind,
+};
+use crate::{
+    AnalyticsRepository, CodeStatus, ReadRepository, Repository, Result, ScanCursor, ScanPage,
+    StorageError,
+};
+
+/// The path [`SqliteRepository::connect`] treats as a private in-memory
+/// database.
+pub const IN_MEMORY: &str = ":memory:";
+
+/// SQLite implementation of the repository contract, for single-node and
+/// embedded deployments.
+///
+/// Semantics match [`MySqlRepository`](crate::MySqlRepository): soft delete
+/// through `deleted_at`, reads only return active records, and inserts never
+/// reuse a short code, including soft-deleted ones.
+#[derive(Debug, Clone)]
+pub struct SqliteRepository {
+    pool: SqlitePool,
+}
+
+impl SqliteRepository {
+    /// Creates a repository from an existing SQLite connection pool.
+    ///
+    /// The schema is not touched; call [`SqliteRepository::migrate`] if the
+    /// database may be new.
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Opens the database at `path`, creating the file and the schema if
+    /// they do not exist yet.
+    ///
+    /// Pass [`IN_MEMORY`] for a database that lives as long as the
+    /// repository (and its clones). It is served by a single connection,
+    /// since every SQLite connection to `:memory:` opens a database of its
+    /// own.
+    pub async fn connect(path: &str) -> Result<Self> {
+        let pool = if path == IN_MEMORY {
+            SqlitePoolOptions::new()
+                .max_connections(1)
+                .idle_timeout(None)
+                .max_lifetime(None)
+                .connect_with(SqliteConnectOptions::new().in_memory(true))
+                .await
+        } else {
+            let options = SqliteConnectOptions::new()
+                .filename(path)
+                .create_if_missing(true)
+                .journal_mode(SqliteJournalMode::Wal);
+            SqlitePoolOptions::new().connect_with(options).await
+        }
+        .map_err(map_sqlx_error)?;
+
+        let repo = Self::new(pool);
+        repo.migrate().await?;
+        Ok(repo)
+    }
+
+    pub async fn migrate(&self) -> Result<()> {
+        sqlx::migrate!("ddl/sqlite")
+            .run(&self.pool)
+            .await
+            .map_err(|e| StorageError::Unknown(format!("failed to run migrations: {e}")))?;
+
+        Ok(())
+    }
+
+    /// Returns a reference to the underlying connection pool.
+    pub fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+
+    /// Lists active records after `cursor`, optionally only those owned by
+    /// `owner_id`.
+    async fn scan_page(
+        &self,
+        owner_id: Option<&str>,
+        cursor: Option<ScanCursor>,
+        limit: usize,
+    ) -> Result<ScanPage> {
+        let limit = limit.max(1);
+        let owner_filter = if owner_id.is_some() {
+            "AND owner_id = ?"
+        } else {
+            ""
+        };
+        let sql = format!(
+            r#"
+            SELECT short_code, original_url, expire_at, redirect_kind, created_at, internal_only,
+                   no_store, owner_id, metadata
+            FROM short_urls
+            WHERE short_code > ?
+              AND deleted_at IS NULL
+              AND (expire_at IS NULL OR expire_at > ?)
+              {owner_filter}
+            ORDER BY short_code
+            LIMIT ?
+            "#
+        );
+
+        let mut query = sqlx::query(&sql)
+            .bind(cursor.as_ref().map_or("", ScanCursor::as_str))
+            .bind(now_unix_seconds());
+        if let Some(owner_id) = owner_id {
+            query = query.bind(owner_id);
+        }
+        let rows = query
+            .bind(i64::try_from(limit + 1).unwrap_or(i64::MAX))
+            .fetch_all(&self.pool)
+            .await
+            .map_err(map_sqlx_error)?;
+
+        let has_more = rows.len() > limit;
+        let items = rows
+            .iter()
+            .take(limit)
+            .map(|row| {
+                let code: String = row.try_get("short_code").map_err(map_sqlx_error)?;
+                Ok((ShortCode::new_unchecked(code), record_from_row(row)?))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let next = match items.last() {
+            Some((code, _)) if has_more => Some(ScanCursor::after(code)),
+            _ => None,
+        };
+
+        Ok(ScanPage { items, next })
+    }
+}
+
+fn record_from_row(row: &SqliteRow) -> Result<UrlRecord> {
+    let original_url: String = row.try_get("original_url").map_err(map_sqlx_error)?;
+    let expire_at_raw: Option<i64> = row.try_get("expire_at").map_err(map_sqlx_error)?;
+    let expire_at = parse_expire_at(expire_at_raw)?;
+    let redirect_kind_raw: i64 = row.try_get("redirect_kind").map_err(map_sqlx_error)?;
+    let redirect_kind = u16::try_from(redirect_kind_raw)
+        .map_err(|_| {
+            StorageError::InvalidData(format!("invalid redirect_kind '{redirect_kind_raw}'"))
+        })
+        .and_then(parse_redirect_kind)?;
+    let created_at_raw: i64 = row.try_get("created_at").map_err(map_sqlx_error)?;
+    let created_at = parse_created_at(created_at_raw)?;
+    let internal_only: bool = row.try_get("internal_only").map_err(map_sqlx_error)?;
+    let no_store: bool = row.try_get("no_store").map_err(map_sqlx_error)?;
+    let owner_id: Option<String> = row.try_get("owner_id").map_err(map_sqlx_error)?;
+    let metadata: Option<Json<BTreeMap<String, String>>> =
+        row.try_get("metadata").map_err(map_sqlx_error)?;
+
+    Ok(UrlRecord {
+        original_url,
+        expire_at,
+        redirect_kind,
+        created_at,
+        internal_only,
+        no_store,
+        owner_id,
+        metadata: metadata.map(|Json(metadata)| metadata).unwrap_or_default(),
+    })
+}
+
+#[async_trait]
+impl ReadRepository for SqliteRepository {
+    async fn get(&self, code: &ShortCode) -> Result<Option<UrlRecord>> {
+        let row = sqlx::query(
+            r#"
+            SELECT original_url, expire_at, redirect_kind, created_at, internal_only, no_store,
+                   owner_id, metadata
+            FROM short_urls
+            WHERE short_code = ?
+              AND deleted_at IS NULL
+              AND (expire_at IS NULL OR expire_at > ?)
+            LIMIT 1
+            "#,
+        )
+        .bind(code.as_str())
+        .bind(now_unix_seconds())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(map_sqlx_error)?;
+
+        row.as_ref().map(record_from_row).transpose()
+    }
+
+    async fn exists(&self, code: &ShortCode) -> Result<bool> {
+        let exists = sqlx::query("SELECT 1 FROM short_urls WHERE short_code = ? LIMIT 1")
+            .bind(code.as_str())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(map_sqlx_error)?
+            .is_some();
+
+        Ok(exists)
+    }
+
+    async fn status(&self, code: &ShortCode) -> Result<CodeStatus> {
+        let row = sqlx::query(
+            r#"
+            SELECT original_url, expire_at, redirect_kind, created_at, internal_only, no_store,
+                   owner_id, metadata, deleted_at
+            FROM short_urls
+            WHERE short_code = ?
+            LIMIT 1
+            "#,
+        )
+        .bind(code.as_str())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(map_sqlx_error)?;
+
+        let Some(row) = row else {
+            return Ok(CodeStatus::NotFound);
+        };
+
+        let deleted_at: Option<i64> = row.try_get("deleted_at").map_err(map_sqlx_error)?;
+        if deleted_at.is_some() {
+            return Ok(CodeStatus::Deleted);
+        }
+
+        let record = record_from_row(&row)?;
+        if record
+            .expire_at
+            .is_some_and(|expire_at| expire_at.as_second() <= now_unix_seconds())
+        {
+            return Ok(CodeStatus::Expired);
+        }
+
+        Ok(CodeStatus::Active(record))
+    }
+
+    async fn scan(&self, cursor: Option<ScanCursor>, limit: usize) -> Result<ScanPage> {
+        self.scan_page(None, cursor, limit).await
+    }
+
+    async fn scan_owned_by(
+        &self,
+        owner_id: &str,
+        cursor: Option<ScanCursor>,
+        limit: usize,
+    ) -> Result<ScanPage> {
+        self.scan_page(Some(owner_id), cursor, limit).await
+    }
+
+    async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1")
+            .execute(&self.pool)
+            .await
+            .map_err(map_sqlx_error)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Repository for SqliteRepository {
+    async fn insert(&self, code: &ShortCode, record: UrlRecord) -> Result<()> {
+        let expire_at = record.expire_at.map(|ts| ts.as_second());
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO short_urls (
+                short_code, original_url, expire_at, redirect_kind, created_at, internal_only,
+                no_store, owner_id, metadata, deleted_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, NULL)
+            "#,
+        )
+        .bind(code.as_str())
+        .bind(record.original_url)
+        .bind(expire_at)
+        .bind(i64::from(record.redirect_kind.status_code()))
+        .bind(record.created_at.as_second())
+        .bind(record.internal_only)
+        .bind(record.no_store)
+        .bind(record.owner_id)
+        .bind((!record.metadata.is_empty()).then_some(Json(record.metadata)))
+        .execute(&self.pool)
+        .await;
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(err) if is_unique_violation(&err) => Err(StorageError::Conflict(code.to_string())),
+            Err(err) => Err(map_sqlx_error(err)),
+        }
+    }
+
+    async fn delete(&self, code: &ShortCode) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE short_urls
+            SET deleted_at = ?
+            WHERE short_code = ?
+              AND deleted_at IS NULL
+            "#,
+        )
+        .bind(now_unix_seconds())
+        .bind(code.as_str())
+        .execute(&self.pool)
+        .await
+        .map_err(map_sqlx_error)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[async_trait]
+impl AnalyticsRepository for SqliteRepository {
+    /// SQLite serializes writers, so a single `UPDATE ... RETURNING` is
+    /// enough to keep concurrent increments from interleaving.
+    async fn increment_hits_by(&self, code: &ShortCode, hits: u64) -> Result<u64> {
+        let count: Option<i64> = sqlx::query_scalar(
+            r#"
+            UPDATE short_urls
+            SET hits = hits + ?
+            WHERE short_code = ?
+              AND deleted_at IS NULL
+            RETURNING hits
+            "#,
+        )
+        .bind(i64::try_from(hits).unwrap_or(i64::MAX))
+        .bind(code.as_str())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(map_sqlx_error)?;
+
+        Ok(count.map_or(0, |count| u64::try_from(count).unwrap_or(0)))
+    }
+
+    async fn hits(&self, code: &ShortCode) -> Result<u64> {
+        let count: Option<i64> = sqlx::query_scalar(
+            r#"
+            SELECT hits
+            FROM short_urls
+            WHERE short_code = ?
+              AND deleted_at IS NULL
+            "#,
+        )
+        .bind(code.as_str())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(map_sqlx_error)?;
+
+        Ok(count.map_or(0, |count| u64::try_from(count).unwrap_or(0)))
+    }
+}
@@ -0,0 +1,18 @@
+use wormhole_core::UrlRecord;
+
+/// Where a short code is in its lifecycle.
+///
+/// Unlike [`ReadRepository::get`](crate::ReadRepository::get), which folds
+/// every inactive state into `None`, this tells callers why a code does not
+/// resolve.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CodeStatus {
+    /// The code resolves to this record.
+    Active(UrlRecord),
+    /// The code exists but its expiration time has passed.
+    Expired,
+    /// The code was deleted.
+    Deleted,
+    /// The code was never stored, or the repository keeps no trace of it.
+    NotFound,
+}
@@ -2,12 +2,15 @@ use std::time::Duration;
 
 use jiff::{SignedDuration, Timestamp};
 use sqlx::mysql::MySqlPoolOptions;
-use wormhole_core::{ShortCode, UrlRecord};
-use wormhole_storage::{MySqlRepository, ReadRepository, Repository, StorageError};
+use wormhole_core::{RedirectKind, ShortCode, UrlRecord};
+use wormhole_storage::{
+    AnalyticsRepository, CodeStatus, MySqlPoolConfig, MySqlRepository, ReadRepository, Repository,
+    ScanCursor, StorageError,
+};
 use wormhole_test_infra::mysql::{MySqlServer, MysqlConfig};
 
 struct Fixture {
-    _mysql: MySqlServer,
+    mysql: MySqlServer,
     repo: MySqlRepository,
 }
 
@@ -17,17 +20,19 @@ impl Fixture {
             .await
             .expect("start mysql");
         let url = mysql.database_url().await.expect("mysql url");
-        let pool = connect_with_retry(&url).await;
+        let repo = MySqlRepository::new(connect_with_retry(&url).await);
 
-        sqlx::migrate!("ddl/mysql")
-            .run(&pool)
+        repo.migrate()
             .await
             .expect("migrations should run successfully");
 
-        Self {
-            _mysql: mysql,
-            repo: MySqlRepository::new(pool),
-        }
+        Self { mysql, repo }
+    }
+
+    /// Opens an additional pool to the same database.
+    async fn extra_pool(&self) -> sqlx::MySqlPool {
+        let url = self.mysql.database_url().await.expect("mysql url");
+        connect_with_retry(&url).await
     }
 }
 
@@ -59,6 +64,12 @@ fn record(url: &str, expire_at: Option<Timestamp>) -> UrlRecord {
     UrlRecord {
         original_url: url.to_string(),
         expire_at,
+        redirect_kind: RedirectKind::default(),
+        created_at: Timestamp::now(),
+        internal_only: false,
+        no_store: false,
+        owner_id: None,
+        metadata: Default::default(),
     }
 }
 
@@ -78,6 +89,45 @@ async fn insert_and_get_active_record() {
     assert_eq!(got.expire_at, None);
 }
 
+#[tokio::test]
+async fn insert_and_get_preserves_redirect_kind() {
+    let fixture = Fixture::start().await;
+    let short_code = code("permanent");
+    let mut permanent = record("https://example.com", None);
+    permanent.redirect_kind = RedirectKind::Permanent301;
+
+    fixture.repo.insert(&short_code, permanent).await.unwrap();
+
+    let got = fixture.repo.get(&short_code).await.unwrap().unwrap();
+    assert_eq!(got.redirect_kind, RedirectKind::Permanent301);
+}
+
+#[tokio::test]
+async fn insert_and_get_preserves_internal_only() {
+    let fixture = Fixture::start().await;
+    let short_code = code("internal");
+    let mut internal = record("https://intranet.example", None);
+    internal.internal_only = true;
+
+    fixture.repo.insert(&short_code, internal).await.unwrap();
+
+    let got = fixture.repo.get(&short_code).await.unwrap().unwrap();
+    assert!(got.internal_only);
+}
+
+#[tokio::test]
+async fn insert_and_get_preserves_no_store() {
+    let fixture = Fixture::start().await;
+    let short_code = code("onetime");
+    let mut token = record("https://example.com/token", None);
+    token.no_store = true;
+
+    fixture.repo.insert(&short_code, token).await.unwrap();
+
+    let got = fixture.repo.get(&short_code).await.unwrap().unwrap();
+    assert!(got.no_store);
+}
+
 #[tokio::test]
 async fn insert_conflicts_when_code_already_exists() {
     let fixture = Fixture::start().await;
@@ -144,3 +194,451 @@ async fn exists_tracks_historical_codes_for_no_reuse_policy() {
 
     assert!(fixture.repo.exists(&short_code).await.unwrap());
 }
+
+#[tokio::test]
+async fn hard_delete_removes_the_row_and_frees_the_code() {
+    let fixture = Fixture::start().await;
+    let short_code = code("erase-me");
+
+    fixture
+        .repo
+        .insert(&short_code, record("https://example.com", None))
+        .await
+        .unwrap();
+    fixture.repo.delete(&short_code).await.unwrap();
+
+    // Soft-deleted rows are removed too.
+    assert!(fixture.repo.hard_delete(&short_code).await.unwrap());
+    let rows: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM short_urls WHERE short_code = ?")
+        .bind(short_code.as_str())
+        .fetch_one(fixture.repo.pool())
+        .await
+        .unwrap();
+    assert_eq!(rows, 0);
+    assert!(!fixture.repo.exists(&short_code).await.unwrap());
+    assert_eq!(
+        fixture.repo.status(&short_code).await.unwrap(),
+        CodeStatus::NotFound
+    );
+    assert!(!fixture.repo.hard_delete(&short_code).await.unwrap());
+
+    // The code can be claimed again.
+    fixture
+        .repo
+        .insert(&short_code, record("https://reused.example", None))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn exhausted_pool_fails_with_timeout() {
+    let fixture = Fixture::start().await;
+    let url = fixture.mysql.database_url().await.expect("mysql url");
+    let config = MySqlPoolConfig::builder()
+        .max_connections(1)
+        .acquire_timeout(Duration::from_millis(200))
+        .build();
+    let repo = MySqlRepository::connect_with(&url, config).await.unwrap();
+    let short_code = code("tiny-pool");
+    repo.insert(&short_code, record("https://example.com", None))
+        .await
+        .unwrap();
+
+    // Hold the only connection so the next query cannot get one.
+    let held = repo.pool().acquire().await.unwrap();
+    let err = repo.get(&short_code).await.unwrap_err();
+    assert!(matches!(err, StorageError::Timeout(_)));
+
+    drop(held);
+    assert!(repo.get(&short_code).await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn migrate_creates_the_schema_on_a_fresh_database() {
+    let mysql = MySqlServer::new(MysqlConfig::builder().build())
+        .await
+        .expect("start mysql");
+    let url = mysql.database_url().await.expect("mysql url");
+    let repo = MySqlRepository::new(connect_with_retry(&url).await);
+
+    repo.migrate().await.unwrap();
+
+    let columns: Vec<String> = sqlx::query_scalar(
+        "SELECT CAST(COLUMN_NAME AS CHAR) FROM information_schema.COLUMNS \
+         WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = 'short_urls'",
+    )
+    .fetch_all(repo.pool())
+    .await
+    .unwrap();
+    for column in [
+        "redirect_kind",
+        "created_at",
+        "internal_only",
+        "no_store",
+        "hits",
+    ] {
+        assert!(columns.iter().any(|c| c == column), "missing {column}");
+    }
+    let short_code = code("fresh");
+    repo.insert(&short_code, record("https://example.com", None))
+        .await
+        .unwrap();
+    assert!(repo.get(&short_code).await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn migrate_is_idempotent() {
+    let fixture = Fixture::start().await;
+    let short_code = code("survivor");
+    fixture
+        .repo
+        .insert(&short_code, record("https://example.com", None))
+        .await
+        .unwrap();
+
+    // The fixture already migrated; running again applies nothing new.
+    fixture.repo.migrate().await.unwrap();
+    fixture.repo.migrate().await.unwrap();
+
+    let applied: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM _sqlx_migrations")
+        .fetch_one(fixture.repo.pool())
+        .await
+        .unwrap();
+    assert_eq!(applied, sqlx::migrate!("ddl/mysql").iter().count() as i64);
+    assert!(fixture.repo.get(&short_code).await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn split_pools_route_reads_to_read_pool() {
+    let fixture = Fixture::start().await;
+    let write_pool = fixture.extra_pool().await;
+    let read_pool = fixture.extra_pool().await;
+    let repo = MySqlRepository::with_pools(write_pool.clone(), read_pool.clone());
+    let short_code = code("replica");
+
+    repo.insert(&short_code, record("https://example.com", None))
+        .await
+        .unwrap();
+
+    // With the write pool gone, reads keep working through the read pool.
+    write_pool.close().await;
+    let got = repo.get(&short_code).await.unwrap().unwrap();
+    assert_eq!(got.original_url, "https://example.com");
+    assert!(repo.exists(&short_code).await.unwrap());
+
+    let err = repo
+        .insert(&code("after-close"), record("https://example.com", None))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, StorageError::Unavailable(_)));
+}
+
+#[tokio::test]
+async fn split_pools_route_writes_to_write_pool() {
+    let fixture = Fixture::start().await;
+    let write_pool = fixture.extra_pool().await;
+    let read_pool = fixture.extra_pool().await;
+    let repo = MySqlRepository::with_pools(write_pool, read_pool.clone());
+    let short_code = code("primary");
+
+    // With the read pool gone, writes keep working through the write pool.
+    read_pool.close().await;
+    repo.insert(&short_code, record("https://example.com", None))
+        .await
+        .unwrap();
+    assert!(repo.delete(&short_code).await.unwrap());
+
+    let err = repo.get(&short_code).await.unwrap_err();
+    assert!(matches!(err, StorageError::Unavailable(_)));
+    let err = repo.exists(&short_code).await.unwrap_err();
+    assert!(matches!(err, StorageError::Unavailable(_)));
+
+    // The writes are visible to the fixture's single-pool repository.
+    assert!(fixture.repo.exists(&short_code).await.unwrap());
+    assert!(fixture.repo.get(&short_code).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn scan_pages_through_active_records_without_gaps_or_duplicates() {
+    let fixture = Fixture::start().await;
+    for i in 0..25 {
+        fixture
+            .repo
+            .insert(
+                &code(&format!("code-{i:02}")),
+                record("https://example.com", None),
+            )
+            .await
+            .unwrap();
+    }
+    let expired = Timestamp::now() - SignedDuration::from_secs(1);
+    fixture
+        .repo
+        .insert(
+            &code("code-expired"),
+            record("https://example.com", Some(expired)),
+        )
+        .await
+        .unwrap();
+    fixture
+        .repo
+        .insert(&code("code-deleted"), record("https://example.com", None))
+        .await
+        .unwrap();
+    fixture.repo.delete(&code("code-deleted")).await.unwrap();
+
+    let mut seen = Vec::new();
+    let mut cursor: Option<ScanCursor> = None;
+    loop {
+        let page = fixture.repo.scan(cursor, 10).await.unwrap();
+        assert!(page.items.len() <= 10);
+        seen.extend(page.items.into_iter().map(|(code, _)| code.to_string()));
+        match page.next {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+
+    let expected: Vec<_> = (0..25).map(|i| format!("code-{i:02}")).collect();
+    assert_eq!(seen, expected);
+}
+
+#[tokio::test]
+async fn status_reports_why_a_code_does_not_resolve() {
+    let fixture = Fixture::start().await;
+    let expired = Timestamp::now() - SignedDuration::from_secs(1);
+    fixture
+        .repo
+        .insert(&code("active"), record("https://example.com", None))
+        .await
+        .unwrap();
+    fixture
+        .repo
+        .insert(
+            &code("expired"),
+            record("https://example.com", Some(expired)),
+        )
+        .await
+        .unwrap();
+    fixture
+        .repo
+        .insert(&code("deleted"), record("https://example.com", None))
+        .await
+        .unwrap();
+    fixture.repo.delete(&code("deleted")).await.unwrap();
+
+    assert!(matches!(
+        fixture.repo.status(&code("active")).await.unwrap(),
+        CodeStatus::Active(_)
+    ));
+    assert_eq!(
+        fixture.repo.status(&code("expired")).await.unwrap(),
+        CodeStatus::Expired
+    );
+    assert_eq!(
+        fixture.repo.status(&code("deleted")).await.unwrap(),
+        CodeStatus::Deleted
+    );
+    assert_eq!(
+        fixture.repo.status(&code("missing")).await.unwrap(),
+        CodeStatus::NotFound
+    );
+}
+
+#[tokio::test]
+async fn short_code_column_width_matches_the_ddl() {
+    let fixture = Fixture::start().await;
+
+    assert_eq!(fixture.repo.short_code_column_width().await.unwrap(), 32);
+}
+
+#[tokio::test]
+async fn recent_returns_active_codes_newest_first() {
+    let fixture = Fixture::start().await;
+    let now = Timestamp::now();
+    for i in 0..5 {
+        let mut record = record("https://example.com", None);
+        record.created_at = now - SignedDuration::from_mins(10 - i);
+        fixture
+            .repo
+            .insert(&code(&format!("code-{i}")), record)
+            .await
+            .unwrap();
+    }
+
+    // Newer than every active row, but not active.
+    let mut expired = record(
+        "https://example.com",
+        Some(now - SignedDuration::from_secs(1)),
+    );
+    expired.created_at = now;
+    fixture
+        .repo
+        .insert(&code("code-expired"), expired)
+        .await
+        .unwrap();
+    let mut deleted = record("https://example.com", None);
+    deleted.created_at = now;
+    fixture
+        .repo
+        .insert(&code("code-deleted"), deleted)
+        .await
+        .unwrap();
+    fixture.repo.delete(&code("code-deleted")).await.unwrap();
+
+    let recent = fixture.repo.recent(3).await.unwrap();
+    let recent: Vec<_> = recent.iter().map(ShortCode::to_string).collect();
+    assert_eq!(recent, ["code-4", "code-3", "code-2"]);
+
+    let all = fixture.repo.recent(100).await.unwrap();
+    assert_eq!(all.len(), 5);
+    assert!(fixture.repo.recent(0).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn closed_repository_fails_fast() {
+    let fixture = Fixture::start().await;
+    let url = fixture.mysql.database_url().await.expect("mysql url");
+    let repo = MySqlRepository::connect(&url).await.unwrap();
+    let short_code = code("closing");
+    repo.insert(&short_code, record("https://example.com", None))
+        .await
+        .unwrap();
+    let clone = repo.clone();
+
+    repo.close().await;
+
+    let result = tokio::time::timeout(Duration::from_secs(5), clone.get(&short_code))
+        .await
+        .expect("a closed pool should not hang");
+    assert!(matches!(result, Err(StorageError::Unavailable(_))));
+    assert!(clone.ping().await.is_err());
+}
+
+#[tokio::test]
+async fn ping_succeeds_against_a_live_server() {
+    let fixture = Fixture::start().await;
+
+    fixture.repo.ping().await.unwrap();
+}
+
+#[tokio::test]
+async fn concurrent_hit_increments_are_serialized() {
+    let fixture = Fixture::start().await;
+    let short_code = code("popular");
+    fixture
+        .repo
+        .insert(&short_code, record("https://example.com", None))
+        .await
+        .unwrap();
+
+    let handles: Vec<_> = (0..20)
+        .map(|_| {
+            let repo = fixture.repo.clone();
+            tokio::spawn(async move { repo.increment_hits(&code("popular")).await.unwrap() })
+        })
+        .collect();
+    let mut counts = Vec::new();
+    for handle in handles {
+        counts.push(handle.await.unwrap());
+    }
+    counts.sort_unstable();
+
+    assert_eq!(counts, (1..=20).collect::<Vec<u64>>());
+    assert_eq!(fixture.repo.hits(&short_code).await.unwrap(), 20);
+}
+
+#[tokio::test]
+async fn deleted_codes_are_not_counted() {
+    let fixture = Fixture::start().await;
+    let short_code = code("gone");
+    fixture
+        .repo
+        .insert(&short_code, record("https://example.com", None))
+        .await
+        .unwrap();
+    fixture
+        .repo
+        .increment_hits_by(&short_code, 3)
+        .await
+        .unwrap();
+    fixture.repo.delete(&short_code).await.unwrap();
+
+    assert_eq!(fixture.repo.increment_hits(&short_code).await.unwrap(), 0);
+    assert_eq!(fixture.repo.hits(&short_code).await.unwrap(), 0);
+    assert_eq!(fixture.repo.hits(&code("missing")).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn owner_and_metadata_round_trip() {
+    let fixture = Fixture::start().await;
+    let short_code = code("tagged");
+    let mut tagged = record("https://example.com", None);
+    tagged.owner_id = Some("tenant-a".to_string());
+    tagged
+        .metadata
+        .insert("campaign".to_string(), "spring".to_string());
+
+    fixture
+        .repo
+        .insert(&short_code, tagged.clone())
+        .await
+        .unwrap();
+
+    assert_eq!(fixture.repo.get(&short_code).await.unwrap(), Some(tagged));
+    fixture
+        .repo
+        .insert(&code("plain"), record("https://example.com", None))
+        .await
+        .unwrap();
+    let plain = fixture.repo.get(&code("plain")).await.unwrap().unwrap();
+    assert_eq!(plain.owner_id, None);
+    assert!(plain.metadata.is_empty());
+}
+
+#[tokio::test]
+async fn scan_owned_by_only_returns_the_owners_records() {
+    let fixture = Fixture::start().await;
+    for i in 0..10 {
+        let mut owned = record("https://example.com", None);
+        owned.owner_id = Some(if i % 3 == 0 { "tenant-a" } else { "tenant-b" }.to_string());
+        fixture
+            .repo
+            .insert(&code(&format!("code-{i:02}")), owned)
+            .await
+            .unwrap();
+    }
+    fixture
+        .repo
+        .insert(&code("code-unowned"), record("https://example.com", None))
+        .await
+        .unwrap();
+    fixture.repo.delete(&code("code-09")).await.unwrap();
+
+    let mut seen = Vec::new();
+    let mut cursor: Option<ScanCursor> = None;
+    loop {
+        let page = fixture
+            .repo
+            .scan_owned_by("tenant-a", cursor, 2)
+            .await
+            .unwrap();
+        assert!(page.items.len() <= 2);
+        for (code, record) in page.items {
+            assert_eq!(record.owner_id.as_deref(), Some("tenant-a"));
+            seen.push(code.to_string());
+        }
+        match page.next {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+
+    assert_eq!(seen, ["code-00", "code-03", "code-06"]);
+    let page = fixture
+        .repo
+        .scan_owned_by("nobody", None, 10)
+        .await
+        .unwrap();
+    assert!(page.items.is_empty() && page.next.is_none());
+}
@@ -0,0 +1,338 @@
+use jiff::{SignedDuration, Timestamp};
+use wormhole_core::{RedirectKind, ShortCode, UrlRecord};
+use wormhole_storage::sqlite::IN_MEMORY;
+use wormhole_storage::{
+    AnalyticsRepository, CodeStatus, ReadRepository, Repository, ScanCursor, SqliteRepository,
+    StorageError,
+};
+
+async fn repo() -> SqliteRepository {
+    SqliteRepository::connect(IN_MEMORY)
+        .await
+        .expect("open in-memory sqlite")
+}
+
+fn code(value: &str) -> ShortCode {
+    ShortCode::new_unchecked(value)
+}
+
+fn record(url: &str, expire_at: Option<Timestamp>) -> UrlRecord {
+    UrlRecord {
+        original_url: url.to_string(),
+        expire_at,
+        redirect_kind: RedirectKind::default(),
+        created_at: Timestamp::now(),
+        internal_only: false,
+        no_store: false,
+        owner_id: None,
+        metadata: Default::default(),
+    }
+}
+
+#[tokio::test]
+async fn insert_and_get_active_record() {
+    let repo = repo().await;
+    let short_code = code("abc123");
+
+    repo.insert(&short_code, record("https://example.com", None))
+        .await
+        .unwrap();
+
+    let got = repo.get(&short_code).await.unwrap().unwrap();
+    assert_eq!(got.original_url, "https://example.com");
+    assert_eq!(got.expire_at, None);
+}
+
+#[tokio::test]
+async fn insert_and_get_preserves_record_flags() {
+    let repo = repo().await;
+    let short_code = code("flags");
+    let mut flagged = record("https://intranet.example", None);
+    flagged.redirect_kind = RedirectKind::Permanent301;
+    flagged.internal_only = true;
+    flagged.no_store = true;
+
+    repo.insert(&short_code, flagged).await.unwrap();
+
+    let got = repo.get(&short_code).await.unwrap().unwrap();
+    assert_eq!(got.redirect_kind, RedirectKind::Permanent301);
+    assert!(got.internal_only);
+    assert!(got.no_store);
+}
+
+#[tokio::test]
+async fn insert_conflicts_when_code_already_exists() {
+    let repo = repo().await;
+    let short_code = code("abc123");
+
+    repo.insert(&short_code, record("https://one.example", None))
+        .await
+        .unwrap();
+
+    let err = repo
+        .insert(&short_code, record("https://two.example", None))
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, StorageError::Conflict(_)));
+}
+
+#[tokio::test]
+async fn codes_are_case_sensitive() {
+    let repo = repo().await;
+
+    repo.insert(&code("AbC"), record("https://upper.example", None))
+        .await
+        .unwrap();
+    repo.insert(&code("abc"), record("https://lower.example", None))
+        .await
+        .unwrap();
+
+    let got = repo.get(&code("abc")).await.unwrap().unwrap();
+    assert_eq!(got.original_url, "https://lower.example");
+}
+
+#[tokio::test]
+async fn get_returns_none_for_expired_record() {
+    let repo = repo().await;
+    let short_code = code("expired");
+    let expired = Timestamp::now() - SignedDuration::from_secs(1);
+
+    repo.insert(&short_code, record("https://example.com", Some(expired)))
+        .await
+        .unwrap();
+
+    assert!(repo.get(&short_code).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn delete_marks_record_as_soft_deleted() {
+    let repo = repo().await;
+    let short_code = code("to-delete");
+
+    repo.insert(&short_code, record("https://example.com", None))
+        .await
+        .unwrap();
+
+    assert!(repo.delete(&short_code).await.unwrap());
+    assert!(repo.get(&short_code).await.unwrap().is_none());
+    assert!(!repo.delete(&short_code).await.unwrap());
+}
+
+#[tokio::test]
+async fn deleted_codes_are_never_reused() {
+    let repo = repo().await;
+    let short_code = code("history");
+
+    repo.insert(&short_code, record("https://example.com", None))
+        .await
+        .unwrap();
+    repo.delete(&short_code).await.unwrap();
+
+    assert!(repo.exists(&short_code).await.unwrap());
+    let err = repo
+        .insert(&short_code, record("https://other.example", None))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, StorageError::Conflict(_)));
+}
+
+#[tokio::test]
+async fn scan_pages_through_active_records_without_gaps_or_duplicates() {
+    let repo = repo().await;
+    for i in 0..25 {
+        repo.insert(
+            &code(&format!("code-{i:02}")),
+            record("https://example.com", None),
+        )
+        .await
+        .unwrap();
+    }
+    let expired = Timestamp::now() - SignedDuration::from_secs(1);
+    repo.insert(
+        &code("code-expired"),
+        record("https://example.com", Some(expired)),
+    )
+    .await
+    .unwrap();
+    repo.insert(&code("code-deleted"), record("https://example.com", None))
+        .await
+        .unwrap();
+    repo.delete(&code("code-deleted")).await.unwrap();
+
+    let mut seen = Vec::new();
+    let mut cursor: Option<ScanCursor> = None;
+    loop {
+        let page = repo.scan(cursor, 10).await.unwrap();
+        assert!(page.items.len() <= 10);
+        seen.extend(page.items.into_iter().map(|(code, _)| code.to_string()));
+        match page.next {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+
+    let expected: Vec<_> = (0..25).map(|i| format!("code-{i:02}")).collect();
+    assert_eq!(seen, expected);
+}
+
+#[tokio::test]
+async fn status_reports_why_a_code_does_not_resolve() {
+    let repo = repo().await;
+    let expired = Timestamp::now() - SignedDuration::from_secs(1);
+    repo.insert(&code("active"), record("https://example.com", None))
+        .await
+        .unwrap();
+    repo.insert(
+        &code("expired"),
+        record("https://example.com", Some(expired)),
+    )
+    .await
+    .unwrap();
+    repo.insert(&code("deleted"), record("https://example.com", None))
+        .await
+        .unwrap();
+    repo.delete(&code("deleted")).await.unwrap();
+
+    assert!(matches!(
+        repo.status(&code("active")).await.unwrap(),
+        CodeStatus::Active(_)
+    ));
+    assert_eq!(
+        repo.status(&code("expired")).await.unwrap(),
+        CodeStatus::Expired
+    );
+    assert_eq!(
+        repo.status(&code("deleted")).await.unwrap(),
+        CodeStatus::Deleted
+    );
+    assert_eq!(
+        repo.status(&code("missing")).await.unwrap(),
+        CodeStatus::NotFound
+    );
+}
+
+#[tokio::test]
+async fn concurrent_hit_increments_are_serialized() {
+    let repo = repo().await;
+    let short_code = code("popular");
+    repo.insert(&short_code, record("https://example.com", None))
+        .await
+        .unwrap();
+
+    let handles: Vec<_> = (0..20)
+        .map(|_| {
+            let repo = repo.clone();
+            tokio::spawn(async move { repo.increment_hits(&code("popular")).await.unwrap() })
+        })
+        .collect();
+    let mut counts = Vec::new();
+    for handle in handles {
+        counts.push(handle.await.unwrap());
+    }
+    counts.sort_unstable();
+
+    assert_eq!(counts, (1..=20).collect::<Vec<u64>>());
+    assert_eq!(repo.hits(&short_code).await.unwrap(), 20);
+}
+
+#[tokio::test]
+async fn deleted_codes_are_not_counted() {
+    let repo = repo().await;
+    let short_code = code("gone");
+    repo.insert(&short_code, record("https://example.com", None))
+        .await
+        .unwrap();
+    repo.increment_hits_by(&short_code, 3).await.unwrap();
+    repo.delete(&short_code).await.unwrap();
+
+    assert_eq!(repo.increment_hits(&short_code).await.unwrap(), 0);
+    assert_eq!(repo.hits(&short_code).await.unwrap(), 0);
+    assert_eq!(repo.hits(&code("missing")).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn file_database_persists_across_connections() {
+    let path = std::env::temp_dir().join(format!(
+        "wormhole-sqlite-{}-{}.db",
+        std::process::id(),
+        Timestamp::now().as_nanosecond()
+    ));
+    let path = path.to_str().unwrap();
+
+    let repo = SqliteRepository::connect(path).await.unwrap();
+    repo.insert(&code("durable"), record("https://example.com", None))
+        .await
+        .unwrap();
+    repo.pool().close().await;
+
+    // Reconnecting finds the schema in place and the row still there.
+    let reopened = SqliteRepository::connect(path).await.unwrap();
+    let got = reopened.get(&code("durable")).await.unwrap().unwrap();
+    assert_eq!(got.original_url, "https://example.com");
+    reopened.ping().await.unwrap();
+    reopened.pool().close().await;
+
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{path}{suffix}"));
+    }
+}
+
+#[tokio::test]
+async fn owner_and_metadata_round_trip() {
+    let repo = repo().await;
+    let short_code = code("tagged");
+    let mut tagged = record("https://example.com", None);
+    tagged.owner_id = Some("tenant-a".to_string());
+    tagged
+        .metadata
+        .insert("campaign".to_string(), "spring".to_string());
+
+    repo.insert(&short_code, tagged.clone()).await.unwrap();
+
+    // Only the new columns: SQLite stores created_at in whole seconds.
+    let got = repo.get(&short_code).await.unwrap().unwrap();
+    assert_eq!(got.owner_id, tagged.owner_id);
+    assert_eq!(got.metadata, tagged.metadata);
+    repo.insert(&code("plain"), record("https://example.com", None))
+        .await
+        .unwrap();
+    let plain = repo.get(&code("plain")).await.unwrap().unwrap();
+    assert_eq!(plain.owner_id, None);
+    assert!(plain.metadata.is_empty());
+}
+
+#[tokio::test]
+async fn scan_owned_by_only_returns_the_owners_records() {
+    let repo = repo().await;
+    for i in 0..10 {
+        let mut owned = record("https://example.com", None);
+        owned.owner_id = Some(if i % 3 == 0 { "tenant-a" } else { "tenant-b" }.to_string());
+        repo.insert(&code(&format!("code-{i:02}")), owned)
+            .await
+            .unwrap();
+    }
+    repo.insert(&code("code-unowned"), record("https://example.com", None))
+        .await
+        .unwrap();
+    repo.delete(&code("code-09")).await.unwrap();
+
+    let mut seen = Vec::new();
+    let mut cursor: Option<ScanCursor> = None;
+    loop {
+        let page = repo.scan_owned_by("tenant-a", cursor, 2).await.unwrap();
+        assert!(page.items.len() <= 2);
+        for (code, record) in page.items {
+            assert_eq!(record.owner_id.as_deref(), Some("tenant-a"));
+            seen.push(code.to_string());
+        }
+        match page.next {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+
+    assert_eq!(seen, ["code-00", "code-03", "code-06"]);
+    let page = repo.scan_owned_by("nobody", None, 10).await.unwrap();
+    assert!(page.items.is_empty() && page.next.is_none());
+}
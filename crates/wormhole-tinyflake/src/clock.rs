@@ -52,6 +52,14 @@ pub(crate) mod test_clock {
                 inner: Arc::new(Mutex::new(TestClockState { now })),
             }
         }
+
+        /// Moves the clock to `now`, which may be in the past.
+        pub(crate) fn set(&self, now: Timestamp) {
+            self.inner
+                .lock()
+                .expect("test clock lock should not be poisoned")
+                .now = now;
+        }
     }
 
     impl Clock for TestClock {
@@ -6,10 +6,21 @@ use thiserror::Error;
 pub enum Error {
     #[error("invalid node id {node_id}; expected 0..={max_node_id}")]
     InvalidNodeId { node_id: u8, max_node_id: u8 },
+    #[error(
+        "invalid bit layout {timestamp_bits}/{sequence_bits}/{node_bits}; \
+         widths must sum to 40, with 1..=32 sequence bits and 1..=8 node bits"
+    )]
+    InvalidLayout {
+        timestamp_bits: u8,
+        sequence_bits: u8,
+        node_bits: u8,
+    },
     #[error("epoch is ahead of current clock time: epoch={epoch}, now={now}")]
     EpochAhead { epoch: Timestamp, now: Timestamp },
     #[error("overtime limit")]
     OverTimeLimit,
     #[error("generator state lock is poisoned")]
     StatePoisoned,
+    #[error("failed to persist generator state: {0}")]
+    StateStore(String),
 }
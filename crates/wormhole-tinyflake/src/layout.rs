@@ -0,0 +1,162 @@
+use crate::error::Error;
+use crate::TinyId;
+
+/// How the 40 bits of a [`TinyId`] are split between its fields.
+///
+/// From the least significant bit up, an id holds the timestamp, then the
+/// sequence, then the node id. The default layout of 30, 8 and 2 bits is the
+/// one [`TinyId`]'s own accessors read; other layouts trade timestamp range or
+/// per-second throughput for more nodes and must be read back through the
+/// layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BitLayout {
+    timestamp_bits: u8,
+    sequence_bits: u8,
+    node_bits: u8,
+}
+
+impl BitLayout {
+    /// Bits in a [`TinyId`].
+    pub const TOTAL_BITS: u8 = 40;
+
+    /// Creates a layout, checking that the widths sum to
+    /// [`TOTAL_BITS`](Self::TOTAL_BITS), that none is zero, and that the
+    /// sequence and node id fit the types that carry them (`u32` and `u8`).
+    pub fn new(timestamp_bits: u8, sequence_bits: u8, node_bits: u8) -> Result<Self, Error> {
+        let sum = u16::from(timestamp_bits) + u16::from(sequence_bits) + u16::from(node_bits);
+        let valid = sum == u16::from(Self::TOTAL_BITS)
+            && timestamp_bits > 0
+            && (1..=32).contains(&sequence_bits)
+            && (1..=8).contains(&node_bits);
+        if !valid {
+            return Err(Error::InvalidLayout {
+                timestamp_bits,
+                sequence_bits,
+                node_bits,
+            });
+        }
+
+        Ok(Self {
+            timestamp_bits,
+            sequence_bits,
+            node_bits,
+        })
+    }
+
+    pub fn timestamp_bits(&self) -> u8 {
+        self.timestamp_bits
+    }
+
+    pub fn sequence_bits(&self) -> u8 {
+        self.sequence_bits
+    }
+
+    pub fn node_bits(&self) -> u8 {
+        self.node_bits
+    }
+
+    /// Largest timestamp, in seconds since the epoch, an id can hold.
+    pub fn max_timestamp(&self) -> u64 {
+        mask(self.timestamp_bits)
+    }
+
+    /// Largest sequence number, i.e. one less than the ids a node can issue
+    /// per second.
+    pub fn max_sequence(&self) -> u32 {
+        mask(self.sequence_bits) as u32
+    }
+
+    /// Largest node id.
+    pub fn max_node_id(&self) -> u8 {
+        mask(self.node_bits) as u8
+    }
+
+    /// Packs the fields into an id. Each value is truncated to its width.
+    pub fn compose(&self, timestamp: u64, sequence: u32, node_id: u8) -> TinyId {
+        let timestamp = timestamp & self.max_timestamp();
+        let sequence = (u64::from(sequence) & mask(self.sequence_bits)) << self.timestamp_bits;
+        let node_id = (u64::from(node_id) & mask(self.node_bits))
+            << (self.timestamp_bits + self.sequence_bits);
+        let raw = timestamp | sequence | node_id;
+
+        let bytes = raw.to_le_bytes();
+        TinyId::from_bytes([bytes[0], bytes[1], bytes[2], bytes[3], bytes[4]])
+    }
+
+    /// Reads the timestamp of `id`.
+    pub fn timestamp(&self, id: &TinyId) -> u64 {
+        raw(id) & self.max_timestamp()
+    }
+
+    /// Reads the sequence number of `id`.
+    pub fn sequence(&self, id: &TinyId) -> u32 {
+        ((raw(id) >> self.timestamp_bits) & mask(self.sequence_bits)) as u32
+    }
+
+    /// Reads the node id of `id`.
+    pub fn node_id(&self, id: &TinyId) -> u8 {
+        ((raw(id) >> (self.timestamp_bits + self.sequence_bits)) & mask(self.node_bits)) as u8
+    }
+}
+
+impl Default for BitLayout {
+    /// 30 timestamp bits (about 34 years), 8 sequence bits (256 ids per
+    /// second) and 2 node bits (4 nodes).
+    fn default() -> Self {
+        Self {
+            timestamp_bits: 30,
+            sequence_bits: 8,
+            node_bits: 2,
+        }
+    }
+}
+
+fn mask(bits: u8) -> u64 {
+    (1_u64 << bits) - 1
+}
+
+fn raw(id: &TinyId) -> u64 {
+    let [b0, b1, b2, b3, b4] = id.into_bytes();
+    u64::from_le_bytes([b0, b1, b2, b3, b4, 0, 0, 0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_layout_matches_the_bitfield() {
+        let layout = BitLayout::default();
+        let id = TinyId::new()
+            .with_timestamp(0x2345_6789)
+            .with_sequence(0xAB)
+            .with_node_id(0b10);
+
+        assert_eq!(layout.compose(0x2345_6789, 0xAB, 0b10), id);
+        assert_eq!(layout.timestamp(&id), 0x2345_6789);
+        assert_eq!(layout.sequence(&id), 0xAB);
+        assert_eq!(layout.node_id(&id), 0b10);
+    }
+
+    #[test]
+    fn wide_node_layout_round_trips() {
+        let layout = BitLayout::new(30, 2, 8).unwrap();
+        assert_eq!(layout.max_node_id(), 255);
+        assert_eq!(layout.max_sequence(), 3);
+
+        let id = layout.compose(layout.max_timestamp(), 3, 255);
+        assert_eq!(layout.timestamp(&id), layout.max_timestamp());
+        assert_eq!(layout.sequence(&id), 3);
+        assert_eq!(layout.node_id(&id), 255);
+    }
+
+    #[test]
+    fn widths_must_sum_to_forty_bits() {
+        assert!(BitLayout::new(30, 8, 1).is_err());
+        assert!(BitLayout::new(30, 8, 3).is_err());
+        assert!(BitLayout::new(40, 0, 0).is_err());
+        assert!(BitLayout::new(0, 32, 8).is_err());
+        assert!(BitLayout::new(28, 2, 10).is_err());
+        assert!(BitLayout::new(29, 9, 2).is_ok());
+    }
+}
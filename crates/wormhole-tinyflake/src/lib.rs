@@ -1,9 +1,14 @@
 mod clock;
 pub mod error;
+mod layout;
+mod metrics;
+mod state;
 mod tiny_id;
 mod tinyflake;
 
 pub use clock::{Clock, SystemClock};
 pub use error::Error;
+pub use layout::BitLayout;
+pub use state::{FileStateStore, NoopStateStore, StateStore};
 pub use tiny_id::TinyId;
-pub use tinyflake::{Tinyflake, TinyflakeSettings};
+pub use tinyflake::{Tinyflake, TinyflakeSettings, TinyflakeStats};
@@ -0,0 +1,12 @@
+//! Hooks into `wormhole-metrics` that compile to nothing unless the `metrics`
+//! feature is enabled.
+
+use std::time::Duration;
+
+#[cfg(feature = "metrics")]
+pub(crate) fn observe_wait(reason: &str, elapsed: Duration) {
+    wormhole_metrics::metrics().observe_generator_wait(reason, elapsed);
+}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) fn observe_wait(_reason: &str, _elapsed: Duration) {}
@@ -0,0 +1,75 @@
+use crate::error::Error;
+use jiff::Timestamp;
+use std::path::{Path, PathBuf};
+
+/// Persists a [`Tinyflake`](crate::Tinyflake)'s high-water timestamp across
+/// restarts.
+///
+/// The generator only issues ids at timestamps below the last value it saved,
+/// and after a restart waits for the clock to reach the loaded value before
+/// issuing again. A clock that jumped backward while the process was down
+/// therefore cannot reuse a timestamp.
+pub trait StateStore: Send + Sync + 'static {
+    /// Returns the last saved timestamp, or `None` if nothing was saved.
+    fn load(&self) -> Option<Timestamp>;
+
+    /// Replaces the saved timestamp.
+    fn save(&self, high_water: Timestamp) -> Result<(), Error>;
+}
+
+/// A [`StateStore`] that remembers nothing; the default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopStateStore;
+
+impl StateStore for NoopStateStore {
+    fn load(&self) -> Option<Timestamp> {
+        None
+    }
+
+    fn save(&self, _high_water: Timestamp) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// A [`StateStore`] that keeps the timestamp in a file.
+///
+/// Saves write a sibling temporary file and rename it over the original, so
+/// a crash mid-save leaves the previous value intact.
+#[derive(Debug, Clone)]
+pub struct FileStateStore {
+    path: PathBuf,
+}
+
+impl FileStateStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl StateStore for FileStateStore {
+    /// Returns `None` if the file is missing or does not hold a timestamp.
+    fn load(&self) -> Option<Timestamp> {
+        std::fs::read_to_string(&self.path)
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    }
+
+    fn save(&self, high_water: Timestamp) -> Result<(), Error> {
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+
+        let write = || -> std::io::Result<()> {
+            let file = std::fs::File::create(&tmp)?;
+            std::io::Write::write_all(&mut &file, high_water.to_string().as_bytes())?;
+            file.sync_all()?;
+            std::fs::rename(&tmp, &self.path)
+        };
+        write().map_err(|e| Error::StateStore(format!("{}: {e}", self.path.display())))
+    }
+}
@@ -1,6 +1,10 @@
 use modular_bitfield::prelude::*;
 use std::fmt;
 
+/// A 40-bit id.
+///
+/// The accessors read the default [`BitLayout`](crate::BitLayout); ids from a
+/// generator with another layout must be read through that layout.
 #[bitfield]
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct TinyId {
@@ -1,42 +1,77 @@
 use crate::{
     clock::{Clock, SystemClock},
     error::Error,
-    TinyId,
+    metrics, BitLayout, NoopStateStore, StateStore, TinyId,
 };
 use jiff::Timestamp;
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
 use typed_builder::TypedBuilder;
 
-const MAX_TIMESTAMP_SECONDS: u64 = (1_u64 << 30) - 1;
-const MAX_NODE_ID: u8 = 0b11;
-const MAX_SEQUENCE: u8 = u8::MAX;
-
 /// Configures a Tinyflake generator instance.
 #[derive(Debug, Clone, Copy, TypedBuilder)]
 pub struct TinyflakeSettings {
-    /// A unique node index in the range `[0, 3]`.
+    /// A unique node index, below `2^node_bits`.
     #[builder]
     pub node_id: u8,
-    /// Custom epoch used as the zero point for the 30-bit timestamp field.
+    /// Custom epoch used as the zero point for the timestamp field.
     ///
     /// Tinyflake math runs at whole-second precision (`Timestamp::as_second`).
-    /// Sub-second detail is intentionally not modeled in the 30-bit timestamp.
+    /// Sub-second detail is intentionally not modeled in the timestamp.
     #[builder]
     pub start_epoch: Timestamp,
+    /// Width of the timestamp field; caps the generator's lifetime at
+    /// `2^timestamp_bits` seconds after the epoch.
+    #[builder(default = 30)]
+    pub timestamp_bits: u8,
+    /// Width of the sequence field; caps each node at `2^sequence_bits` ids
+    /// per second.
+    #[builder(default = 8)]
+    pub sequence_bits: u8,
+    /// Width of the node id field; caps a cluster at `2^node_bits` nodes.
+    ///
+    /// The three widths must sum to 40. Every node of a cluster, and every
+    /// reader decoding its ids, must use the same widths.
+    #[builder(default = 2)]
+    pub node_bits: u8,
+}
+
+impl TinyflakeSettings {
+    /// Returns the bit layout these settings describe.
+    pub fn layout(&self) -> Result<BitLayout, Error> {
+        BitLayout::new(self.timestamp_bits, self.sequence_bits, self.node_bits)
+    }
 }
 
 #[derive(Debug, Default)]
 struct GeneratorState {
     last_elapsed_timestamp: Option<Timestamp>,
-    sequence: u8,
+    sequence: u32,
+    /// Second up to which the state store allows issuing ids, exclusive.
+    reserved_until: Option<i64>,
+}
+
+/// Cumulative counters describing how often a [`Tinyflake`] had to block.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TinyflakeStats {
+    /// Number of `next_id` calls that waited, for any reason.
+    pub waits: u64,
+    /// Total time spent waiting, as measured by the generator's clock.
+    pub total_wait: Duration,
 }
 
 /// Tinyflake ID generator with Sonyflake-style wait-on-overflow semantics.
 pub struct Tinyflake<C: Clock> {
     start_time: Timestamp,
     node_id: u8,
+    layout: BitLayout,
     clock: C,
     state: Mutex<GeneratorState>,
+    state_store: Box<dyn StateStore>,
+    persist_interval: Duration,
+    waits: AtomicU64,
+    wait_nanos: AtomicU64,
 }
 
 impl Tinyflake<SystemClock> {
@@ -48,10 +83,11 @@ impl Tinyflake<SystemClock> {
 
 impl<C: Clock> Tinyflake<C> {
     fn with_clock(settings: TinyflakeSettings, clock: C) -> Result<Self, Error> {
-        if settings.node_id > MAX_NODE_ID {
+        let layout = settings.layout()?;
+        if settings.node_id > layout.max_node_id() {
             return Err(Error::InvalidNodeId {
                 node_id: settings.node_id,
-                max_node_id: MAX_NODE_ID,
+                max_node_id: layout.max_node_id(),
             });
         }
 
@@ -66,11 +102,65 @@ impl<C: Clock> Tinyflake<C> {
         Ok(Self {
             start_time: settings.start_epoch,
             node_id: settings.node_id,
+            layout,
             clock,
             state: Mutex::new(GeneratorState::default()),
+            state_store: Box::new(NoopStateStore),
+            persist_interval: Duration::from_secs(10),
+            waits: AtomicU64::new(0),
+            wait_nanos: AtomicU64::new(0),
         })
     }
 
+    /// Persists the high-water timestamp in `store` so ids stay unique when
+    /// the clock moves backward across a restart.
+    ///
+    /// If `store` holds a timestamp, the first `next_id` waits until the
+    /// clock reaches it. Each save reserves the next `interval` of timestamps,
+    /// so the store is written about once per `interval` rather than once per
+    /// id, and a restart may skip up to `interval` of timestamps.
+    pub fn with_state_store(mut self, store: impl StateStore, interval: Duration) -> Self {
+        let state = self.state.get_mut().unwrap_or_else(PoisonError::into_inner);
+        if let Some(high_water) = store.load() {
+            state.last_elapsed_timestamp = Some(high_water);
+            state.sequence = 0;
+        }
+        state.reserved_until = None;
+
+        self.state_store = Box::new(store);
+        self.persist_interval = interval.max(Duration::from_secs(1));
+        self
+    }
+
+    /// Returns how often and how long `next_id` has blocked so far.
+    ///
+    /// Frequent waits point at a node exhausting its per-second sequence or
+    /// at an unstable clock.
+    pub fn stats(&self) -> TinyflakeStats {
+        TinyflakeStats {
+            waits: self.waits.load(Ordering::Relaxed),
+            total_wait: Duration::from_nanos(self.wait_nanos.load(Ordering::Relaxed)),
+        }
+    }
+
+    /// Blocks until `target` and returns the clock's time afterwards,
+    /// recording the wait under `reason`.
+    fn wait_until(&self, target: Timestamp, reason: &str) -> Timestamp {
+        let before = self.clock.now();
+        self.clock.wait_until(target);
+        let after = self.clock.now();
+
+        let elapsed = Duration::try_from(after.duration_since(before)).unwrap_or_default();
+        self.waits.fetch_add(1, Ordering::Relaxed);
+        self.wait_nanos.fetch_add(
+            u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX),
+            Ordering::Relaxed,
+        );
+        metrics::observe_wait(reason, elapsed);
+
+        after
+    }
+
     /// Generates the next unique TinyId.
     ///
     /// Correctness strategy (matching Sonyflake behavior):
@@ -78,7 +168,21 @@ impl<C: Clock> Tinyflake<C> {
     /// - if clock moves backward, wait until clock catches up
     pub fn next_id(&self) -> Result<TinyId, Error> {
         let mut state = self.state.lock().map_err(|_| Error::StatePoisoned)?;
+        self.next_id_locked(&mut state)
+    }
+
+    /// Generates `n` ids under a single lock acquisition.
+    ///
+    /// The ids are exactly those `n` consecutive `next_id` calls would
+    /// return: unique and increasing, waiting for the next second whenever
+    /// the sequence runs out. Other callers block until the whole batch is
+    /// done. On error, the ids generated so far are discarded.
+    pub fn next_ids(&self, n: usize) -> Result<Vec<TinyId>, Error> {
+        let mut state = self.state.lock().map_err(|_| Error::StatePoisoned)?;
+        (0..n).map(|_| self.next_id_locked(&mut state)).collect()
+    }
 
+    fn next_id_locked(&self, state: &mut GeneratorState) -> Result<TinyId, Error> {
         let mut now = self.clock.now();
 
         match state.last_elapsed_timestamp {
@@ -91,20 +195,18 @@ impl<C: Clock> Tinyflake<C> {
                     // Clock moved backward — block until we've caught up to the
                     // last timestamp used. Without this, two calls could produce
                     // the same (timestamp, sequence, node_id) triple.
-                    self.clock.wait_until(last);
-                    now = self.clock.now();
+                    now = self.wait_until(last, "clock_rollback");
                 }
 
                 if now.as_second() == last.as_second() {
-                    if state.sequence < MAX_SEQUENCE {
+                    if state.sequence < self.layout.max_sequence() {
                         state.sequence += 1;
                     } else {
                         // Per-second sequence exhausted: wait for the next
                         // second boundary, then reset so we start fresh.
                         let next_second = Timestamp::from_second(last.as_second() + 1)
                             .expect("next second is a valid timestamp");
-                        self.clock.wait_until(next_second);
-                        now = self.clock.now();
+                        now = self.wait_until(next_second, "sequence_exhausted");
                         state.sequence = 0;
                     }
                 } else {
@@ -116,14 +218,27 @@ impl<C: Clock> Tinyflake<C> {
 
         // Seconds elapsed since the custom epoch, used as the timestamp field.
         let elapsed = now.as_second() - self.start_time.as_second();
-        if elapsed as u64 > MAX_TIMESTAMP_SECONDS {
+        if elapsed as u64 > self.layout.max_timestamp() {
             return Err(Error::OverTimeLimit);
         }
 
-        let id = TinyId::new()
-            .with_timestamp(elapsed as u32)
-            .with_sequence(state.sequence)
-            .with_node_id(self.node_id);
+        // Save before issuing past the reservation, so a restart never
+        // resumes below a timestamp that was already used.
+        if state
+            .reserved_until
+            .is_none_or(|until| now.as_second() >= until)
+        {
+            let until = now.as_second() + self.persist_interval.as_secs() as i64;
+            let high_water = Timestamp::from_second(until).map_err(|e| {
+                Error::StateStore(format!("high-water timestamp out of range: {e}"))
+            })?;
+            self.state_store.save(high_water)?;
+            state.reserved_until = Some(until);
+        }
+
+        let id = self
+            .layout
+            .compose(elapsed as u64, state.sequence, self.node_id);
 
         state.last_elapsed_timestamp = Some(now);
 
@@ -135,6 +250,7 @@ impl<C: Clock> Tinyflake<C> {
 mod tests {
     use super::*;
     use crate::clock::test_clock::TestClock;
+    use crate::FileStateStore;
 
     fn make_generator(node_id: u8, clock_second: i64) -> Tinyflake<TestClock> {
         let epoch = Timestamp::from_second(0).unwrap();
@@ -177,6 +293,41 @@ mod tests {
         assert_eq!(id.timestamp(), 101); // elapsed = 101s - epoch(0s)
     }
 
+    #[test]
+    fn sequence_overflow_records_wait_time() {
+        let gen = make_generator(0, 100);
+        for _ in 0..=255 {
+            gen.next_id().unwrap();
+        }
+        assert_eq!(gen.stats(), TinyflakeStats::default());
+
+        gen.next_id().unwrap();
+
+        let stats = gen.stats();
+        assert_eq!(stats.waits, 1);
+        assert!(stats.total_wait > Duration::ZERO);
+        assert!(stats.total_wait <= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn clock_rollback_records_wait_time() {
+        let epoch = Timestamp::from_second(0).unwrap();
+        let settings = TinyflakeSettings::builder()
+            .node_id(0)
+            .start_epoch(epoch)
+            .build();
+        let clock = TestClock::new(Timestamp::from_second(100).unwrap());
+        let gen = Tinyflake::with_clock(settings, clock.clone()).unwrap();
+
+        gen.next_id().unwrap();
+        clock.set(Timestamp::from_second(95).unwrap());
+        gen.next_id().unwrap();
+
+        let stats = gen.stats();
+        assert_eq!(stats.waits, 1);
+        assert_eq!(stats.total_wait, Duration::from_secs(5));
+    }
+
     #[test]
     fn node_id_is_embedded() {
         let gen = make_generator(3, 100);
@@ -200,9 +351,192 @@ mod tests {
             .start_epoch(epoch)
             .build();
         // Place the clock one second past the 30-bit timestamp limit.
-        let over_limit = MAX_TIMESTAMP_SECONDS as i64 + 1;
+        let over_limit = BitLayout::default().max_timestamp() as i64 + 1;
         let clock = TestClock::new(Timestamp::from_second(over_limit).unwrap());
         let gen = Tinyflake::with_clock(settings, clock).unwrap();
         assert_eq!(gen.next_id(), Err(Error::OverTimeLimit));
     }
+
+    fn make_layout_generator(
+        node_id: u8,
+        timestamp_bits: u8,
+        sequence_bits: u8,
+        node_bits: u8,
+    ) -> Result<Tinyflake<TestClock>, Error> {
+        let settings = TinyflakeSettings::builder()
+            .node_id(node_id)
+            .start_epoch(Timestamp::from_second(0).unwrap())
+            .timestamp_bits(timestamp_bits)
+            .sequence_bits(sequence_bits)
+            .node_bits(node_bits)
+            .build();
+        let clock = TestClock::new(Timestamp::from_second(100).unwrap());
+        Tinyflake::with_clock(settings, clock)
+    }
+
+    #[test]
+    fn four_node_layout_caps_node_ids_at_three() {
+        assert!(make_layout_generator(3, 30, 8, 2).is_ok());
+        assert_eq!(
+            make_layout_generator(4, 30, 8, 2).err(),
+            Some(Error::InvalidNodeId {
+                node_id: 4,
+                max_node_id: 3,
+            })
+        );
+    }
+
+    #[test]
+    fn wide_node_layout_embeds_large_node_ids() {
+        let layout = BitLayout::new(28, 4, 8).unwrap();
+        let gen = make_layout_generator(255, 28, 4, 8).unwrap();
+
+        // 16 ids fit in second 100; the 17th waits for second 101.
+        let ids: Vec<_> = (0..17).map(|_| gen.next_id().unwrap()).collect();
+        for (sequence, id) in ids[..16].iter().enumerate() {
+            assert_eq!(layout.node_id(id), 255);
+            assert_eq!(layout.sequence(id), sequence as u32);
+            assert_eq!(layout.timestamp(id), 100);
+        }
+        assert_eq!(layout.sequence(&ids[16]), 0);
+        assert_eq!(layout.timestamp(&ids[16]), 101);
+        assert_eq!(gen.stats().waits, 1);
+    }
+
+    #[test]
+    fn layout_must_sum_to_forty_bits() {
+        assert!(matches!(
+            make_layout_generator(0, 30, 8, 8).err(),
+            Some(Error::InvalidLayout { .. })
+        ));
+    }
+
+    #[test]
+    fn narrow_timestamp_shortens_the_lifetime() {
+        let settings = TinyflakeSettings::builder()
+            .node_id(0)
+            .start_epoch(Timestamp::from_second(0).unwrap())
+            .timestamp_bits(6)
+            .sequence_bits(32)
+            .node_bits(2)
+            .build();
+        // 2^6 - 1 = 63 is the last second the timestamp field can hold.
+        let clock = TestClock::new(Timestamp::from_second(64).unwrap());
+        let gen = Tinyflake::with_clock(settings, clock).unwrap();
+
+        assert_eq!(gen.next_id(), Err(Error::OverTimeLimit));
+    }
+
+    #[test]
+    fn file_state_store_survives_a_backward_clock_jump() {
+        let path = std::env::temp_dir().join(format!(
+            "wormhole-tinyflake-state-{}-{:?}",
+            std::process::id(),
+            std::thread::current().id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let epoch = Timestamp::from_second(0).unwrap();
+        let settings = TinyflakeSettings::builder()
+            .node_id(0)
+            .start_epoch(epoch)
+            .build();
+
+        let clock = TestClock::new(Timestamp::from_second(1_000).unwrap());
+        let first = Tinyflake::with_clock(settings, clock.clone())
+            .unwrap()
+            .with_state_store(FileStateStore::new(&path), Duration::from_secs(10));
+        let mut issued = Vec::new();
+        for second in [1_000, 1_005, 1_012] {
+            clock.set(Timestamp::from_second(second).unwrap());
+            issued.push(first.next_id().unwrap());
+        }
+        drop(first);
+
+        // The process restarts with the clock a minute behind.
+        let clock = TestClock::new(Timestamp::from_second(950).unwrap());
+        let second = Tinyflake::with_clock(settings, clock.clone())
+            .unwrap()
+            .with_state_store(FileStateStore::new(&path), Duration::from_secs(10));
+        let resumed = second.next_id().unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(issued.iter().all(|id| id.timestamp() < resumed.timestamp()));
+        assert_eq!(resumed.timestamp(), 1_022);
+        assert_eq!(second.stats().waits, 1);
+    }
+
+    #[test]
+    fn state_is_saved_once_per_interval() {
+        #[derive(Clone, Default)]
+        struct CountingStore(std::sync::Arc<std::sync::Mutex<Vec<Timestamp>>>);
+
+        impl StateStore for CountingStore {
+            fn load(&self) -> Option<Timestamp> {
+                None
+            }
+
+            fn save(&self, high_water: Timestamp) -> Result<(), Error> {
+                self.0.lock().unwrap().push(high_water);
+                Ok(())
+            }
+        }
+
+        let store = CountingStore::default();
+        let clock = TestClock::new(Timestamp::from_second(100).unwrap());
+        let settings = TinyflakeSettings::builder()
+            .node_id(0)
+            .start_epoch(Timestamp::from_second(0).unwrap())
+            .build();
+        let gen = Tinyflake::with_clock(settings, clock.clone())
+            .unwrap()
+            .with_state_store(store.clone(), Duration::from_secs(30));
+
+        for second in 100..=130 {
+            clock.set(Timestamp::from_second(second).unwrap());
+            gen.next_id().unwrap();
+            gen.next_id().unwrap();
+        }
+
+        let saved: Vec<i64> = store
+            .0
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|t| t.as_second())
+            .collect();
+        assert_eq!(saved, vec![130, 160]);
+    }
+
+    #[test]
+    fn next_ids_spans_seconds_without_duplicates() {
+        let gen = make_generator(1, 100);
+
+        let ids = gen.next_ids(600).unwrap();
+
+        assert_eq!(ids.len(), 600);
+        let distinct: std::collections::HashSet<_> = ids.iter().copied().collect();
+        assert_eq!(distinct.len(), 600);
+        // 256 ids per second: seconds 100 and 101 fill up, 102 gets the rest.
+        assert_eq!(ids[0].timestamp(), 100);
+        assert_eq!(ids[599].timestamp(), 102);
+        assert_eq!(ids[599].sequence(), 87);
+        assert!(ids
+            .windows(2)
+            .all(|pair| (pair[0].timestamp(), pair[0].sequence())
+                < (pair[1].timestamp(), pair[1].sequence())));
+        assert_eq!(gen.stats().waits, 2);
+    }
+
+    #[test]
+    fn next_ids_continues_where_next_id_left_off() {
+        let gen = make_generator(0, 100);
+
+        let single = gen.next_id().unwrap();
+        let batch = gen.next_ids(2).unwrap();
+
+        assert_eq!(single.sequence(), 0);
+        assert_eq!(batch[0].sequence(), 1);
+        assert_eq!(batch[1].sequence(), 2);
+        assert!(gen.next_ids(0).unwrap().is_empty());
+    }
 }